@@ -0,0 +1,12 @@
+use greek_meander::{GreekKeySpiralConfig, SpiralKind, VisualOptions, spiral};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = GreekKeySpiralConfig::new(SpiralKind::Archimedean, 3.0, 280.0, 50.0, 7.0, 8, 2.0)?;
+    let mut visual = VisualOptions::new("#7C3B2E", 0.85);
+    visual.fill_color = Some("#F2DED1".into());
+    visual.background_color = Some("#1E251D".into());
+
+    spiral::generate_pattern_svg(&config, &visual, "meander_spiral")?;
+    println!("Generated meander_spiral.svg and meander_spiral.png");
+    Ok(())
+}
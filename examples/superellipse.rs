@@ -0,0 +1,12 @@
+use greek_meander::{GreekKeySuperellipseConfig, VisualOptions, superellipse};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = GreekKeySuperellipseConfig::new(200.0, 140.0, 4.0, 30, 8, 3.0)?;
+    let mut visual = VisualOptions::new("#1F5B73", 0.9);
+    visual.fill_color = Some("#DCEFF4".into());
+    visual.background_color = Some("#182026".into());
+
+    superellipse::generate_pattern_svg(&config, &visual, "meander_superellipse")?;
+    println!("Generated meander_superellipse.svg and meander_superellipse.png");
+    Ok(())
+}
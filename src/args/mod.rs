@@ -1,31 +1,273 @@
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
+use greek_meander::{Effect, FrameShape, LineCap, LineJoin, Paint, SpiralKind, TextRail, Theme};
 
-#[derive(Parser, Debug)]
+use crate::batch::Shape;
+use crate::file_config::ConfigFormat;
+use crate::preset::Preset;
+use crate::sweep::Sweep;
+
+/// Parses colour flags with gradient syntax; clap would otherwise prefer
+/// `Paint::from`, which treats every string as a solid colour.
+fn parse_paint(s: &str) -> Result<Paint, String> {
+    s.parse()
+}
+
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
     #[arg(long)]
     pub config: Option<PathBuf>,
-    #[arg(long, help = "Stroke width [default: 6]")]
+    #[arg(
+        long,
+        help = "Syntax of the --config file [default: from its extension, .json, .yaml or .yml, else toml]"
+    )]
+    pub config_format: Option<ConfigFormat>,
+    #[arg(
+        long,
+        help = "Fail on --config keys that name no option, instead of warning about them"
+    )]
+    pub strict: bool,
+    #[arg(
+        long,
+        help = "Lay the [profile.<NAME>] section of each config file over the rest of it"
+    )]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        help = "Built-in config that the --config file and other options override; the presets reproduce the sample images"
+    )]
+    pub preset: Option<Preset>,
+    #[arg(
+        long,
+        help = "Ignore the user config, the project config and GREEK_MEANDER_* variables"
+    )]
+    pub isolated: bool,
+    #[arg(
+        long,
+        help = "Keep running and render again whenever the --config file changes"
+    )]
+    pub watch: bool,
+    #[arg(
+        long,
+        help = "Built-in look: attic-red-figure, gold-leaf, marble or monochrome; other options override it"
+    )]
+    pub theme: Option<Theme>,
+    #[arg(
+        long,
+        help = "Stroke width; not allowed with --inlay-gap, which sets it [default: 6]"
+    )]
     pub stroke_width: Option<f32>,
-    #[arg(long, help = "Stroke color [default: #AB8E0E]")]
-    pub stroke_color: Option<String>,
+    #[arg(
+        long,
+        value_parser = parse_paint,
+        help = "Stroke color or gradient, e.g. \"linear-gradient(90deg, #8B6914, #FFF1A8)\" [default: #AB8E0E]"
+    )]
+    pub stroke_color: Option<Paint>,
     #[arg(long, help = "Stroke opacity [default: 0.7]")]
     pub stroke_opacity: Option<f32>,
-    #[arg(long, help = "Fill color for pattern interior [default: none]")]
-    pub fill_color: Option<String>,
-    #[arg(long, help = "Background color for the SVG canvas [default: none]")]
-    pub background_color: Option<String>,
+    #[arg(
+        long,
+        value_parser = parse_paint,
+        help = "Fill color or gradient for pattern interior [default: none]"
+    )]
+    pub fill_color: Option<Paint>,
+    #[arg(
+        long,
+        value_parser = parse_paint,
+        help = "Background color or gradient for the SVG canvas [default: none]"
+    )]
+    pub background_color: Option<Paint>,
     #[arg(
         long,
         help = "SVG stroke-dasharray value, e.g. \"5,3\" [default: solid]"
     )]
     pub stroke_dash: Option<String>,
+    #[arg(
+        long,
+        help = "Stroke corner style: miter, round or bevel [default: miter]"
+    )]
+    pub line_join: Option<LineJoin>,
+    #[arg(long, help = "Stroke end style: butt, round or square [default: butt]")]
+    pub line_cap: Option<LineCap>,
+    #[arg(
+        long,
+        help = "Longest miter, in stroke widths, before a corner is beveled [default: 4]"
+    )]
+    pub miter_limit: Option<f64>,
+    #[arg(long, help = "Keep the stroke width fixed when the SVG is scaled")]
+    pub non_scaling_stroke: bool,
+    #[arg(
+        long = "effect",
+        help = "Filter effect for the border: shadow, emboss, engrave or inner-glow; repeat to combine"
+    )]
+    pub effects: Vec<Effect>,
+    #[arg(
+        long,
+        help = "Fill the inside of the border with a key lattice of this spacing [default: none]"
+    )]
+    pub field_spacing: Option<f64>,
+    #[arg(
+        long,
+        help = "Draw circle and ellipse key runs as line segments within this many pixels of the true arc [default: true arcs]"
+    )]
+    pub arc_tolerance: Option<f64>,
+    #[arg(
+        long,
+        help = "Maximum decimal places for coordinates and lengths [default: full precision]"
+    )]
+    pub precision: Option<u8>,
+    #[arg(long, help = "Put shared stroke and fill attributes on one <g>")]
+    pub shared_styles: bool,
+    #[arg(long, help = "Write path data with relative commands")]
+    pub relative_paths: bool,
+    #[arg(
+        long,
+        help = "Compact SVG output, about 1.6 to 4 times smaller: shared styles, relative paths, 2 decimal places unless --precision is set"
+    )]
+    pub minify: bool,
+    #[arg(
+        long,
+        help = "Start element ids with this instead of gm, so SVGs inlined in one page do not clash"
+    )]
+    pub id_prefix: Option<String>,
+    #[arg(
+        long,
+        value_parser = parse_paint,
+        help = "Stroke color or gradient for the key pattern [default: --stroke-color]"
+    )]
+    pub pattern_color: Option<Paint>,
+    #[arg(
+        long,
+        help = "Stroke opacity for the key pattern [default: --stroke-opacity]"
+    )]
+    pub pattern_opacity: Option<f32>,
+    #[arg(long, help = "Leave out the key pattern")]
+    pub hide_pattern: bool,
+    #[arg(
+        long,
+        value_parser = parse_paint,
+        help = "Stroke color or gradient for the inner frame [default: --stroke-color]"
+    )]
+    pub inner_frame_color: Option<Paint>,
+    #[arg(
+        long,
+        help = "Stroke opacity for the inner frame [default: --stroke-opacity]"
+    )]
+    pub inner_frame_opacity: Option<f32>,
+    #[arg(long, help = "Leave out the inner frame")]
+    pub hide_inner_frame: bool,
+    #[arg(
+        long,
+        value_parser = parse_paint,
+        help = "Stroke color or gradient for the outer frame [default: --stroke-color]"
+    )]
+    pub outer_frame_color: Option<Paint>,
+    #[arg(
+        long,
+        help = "Stroke opacity for the outer frame [default: --stroke-opacity]"
+    )]
+    pub outer_frame_opacity: Option<f32>,
+    #[arg(long, help = "Leave out the outer frame")]
+    pub hide_outer_frame: bool,
+    #[arg(
+        long = "key-color",
+        value_parser = parse_paint,
+        help = "Stroke color or gradient for successive keys; repeat to alternate, e.g. --key-color \"#A3341F\" --key-color \"#111\""
+    )]
+    pub key_colors: Vec<Paint>,
+    #[arg(
+        long,
+        help = "Draw every line as two thin lines this many pixels apart, replacing --stroke-width [default: single lines]"
+    )]
+    pub inlay_gap: Option<f64>,
+    #[arg(
+        long,
+        help = "Stroke width of each of the two inlay lines [default: 2]"
+    )]
+    pub inlay_line_width: Option<f64>,
+    #[arg(
+        long,
+        value_parser = parse_paint,
+        help = "Color or gradient of the inlay lines [default: the part's stroke color]"
+    )]
+    pub inlay_line_color: Option<Paint>,
+    #[arg(
+        long,
+        value_parser = parse_paint,
+        help = "Color or gradient between the inlay lines [default: none]"
+    )]
+    pub inlay_color: Option<Paint>,
+    #[arg(
+        long = "title",
+        help = "Title line centred inside the border; repeat for more lines"
+    )]
+    pub title: Vec<String>,
+    #[arg(long, help = "Text running around a circle or ellipse border")]
+    pub ring_text: Option<String>,
+    #[arg(
+        long,
+        help = "Frame the ring text follows: inner or outer [default: inner]"
+    )]
+    pub ring_text_rail: Option<TextRail>,
+    #[arg(
+        long,
+        help = "CSS font family for text, e.g. \"Cinzel, serif\" [default: serif]"
+    )]
+    pub font_family: Option<String>,
+    #[arg(long, help = "Font size in pixels [default: fit the space]")]
+    pub font_size: Option<f64>,
+    #[arg(
+        long,
+        value_parser = parse_paint,
+        help = "Color or gradient for text [default: --stroke-color]"
+    )]
+    pub text_color: Option<Paint>,
+    #[arg(
+        long,
+        help = "TrueType or OpenType font to embed, so the SVG and PNG draw the same glyphs"
+    )]
+    pub font_file: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Accessible name stored as the SVG <title> [default: the --title lines]"
+    )]
+    pub svg_title: Option<String>,
+    #[arg(long, help = "Description stored as the SVG <desc>")]
+    pub description: Option<String>,
+    #[arg(long, help = "Author recorded in the SVG metadata")]
+    pub author: Option<String>,
+    #[arg(
+        long,
+        help = "Licence URL or rights statement recorded in the SVG metadata"
+    )]
+    pub license: Option<String>,
     #[arg(long, help = "Border margin in pixels [default: 1]")]
     pub border_margin: Option<i32>,
-    #[arg(long, help = "Base name of the output file [default: meander]")]
+    #[arg(
+        long,
+        help = "Base name of the output file; {shape} and {<option>}, e.g. {pattern_count} or {inlay.gap}, take the design's values [default: meander]"
+    )]
     pub file: Option<String>,
+    #[arg(
+        long,
+        help = "Directory to write the outputs to, created if missing [default: the current directory]"
+    )]
+    pub out_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        overrides_with = "force",
+        help = "Fail instead of overwriting existing outputs"
+    )]
+    pub no_clobber: bool,
+    #[arg(
+        long,
+        overrides_with = "no_clobber",
+        help = "Overwrite existing outputs, undoing an earlier --no-clobber"
+    )]
+    pub force: bool,
     #[arg(long)]
     pub stdout: bool,
     #[arg(long)]
@@ -38,14 +280,24 @@ pub struct Args {
     pub command: Commands,
 }
 
-#[derive(Subcommand, Debug)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum Commands {
     Rect(RectArgs),
     Circle(CircleArgs),
     Ellipse(EllipseArgs),
+    Superellipse(SuperellipseArgs),
+    Spiral(SpiralArgs),
+    Field(FieldArgs),
+    Frame(FrameArgs),
+    Reproduce(ReproduceArgs),
+    Batch(BatchArgs),
+    Sweep(SweepArgs),
+    Serve(ServeArgs),
+    Config(ConfigArgs),
+    Validate(ValidateArgs),
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone, Default)]
 pub struct RectArgs {
     #[arg(long, help = "Key unit length in pixels [default: 25]")]
     pub size: Option<i32>,
@@ -55,7 +307,7 @@ pub struct RectArgs {
     pub height: Option<i32>,
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone, Default)]
 pub struct CircleArgs {
     #[arg(long, help = "Number of patterns around the circle [default: 30]")]
     pub pattern_count: Option<i32>,
@@ -63,7 +315,7 @@ pub struct CircleArgs {
     pub radius: Option<f64>,
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone, Default)]
 pub struct EllipseArgs {
     #[arg(long, help = "Number of patterns around the ellipse [default: 30]")]
     pub pattern_count: Option<i32>,
@@ -72,3 +324,215 @@ pub struct EllipseArgs {
     #[arg(long, help = "Vertical outer semi-axis in pixels [default: 200]")]
     pub ry: Option<f64>,
 }
+
+#[derive(Parser, Debug, Clone, Default)]
+pub struct SuperellipseArgs {
+    #[arg(
+        long,
+        help = "Number of patterns around the superellipse [default: 30]"
+    )]
+    pub pattern_count: Option<i32>,
+    #[arg(long, help = "Horizontal outer semi-axis in pixels [default: 300]")]
+    pub rx: Option<f64>,
+    #[arg(long, help = "Vertical outer semi-axis in pixels [default: 200]")]
+    pub ry: Option<f64>,
+    #[arg(
+        long,
+        help = "Superellipse exponent; 2 is an ellipse, 4 a squircle [default: 4]"
+    )]
+    pub exponent: Option<f64>,
+}
+
+#[derive(Parser, Debug, Clone, Default)]
+pub struct SpiralArgs {
+    #[arg(
+        long,
+        help = "Spiral kind: archimedean or logarithmic [default: archimedean]"
+    )]
+    pub kind: Option<SpiralKind>,
+    #[arg(long, help = "Number of full turns [default: 3]")]
+    pub turns: Option<f64>,
+    #[arg(long, help = "Radius where the band starts, at the top [default: 300]")]
+    pub start_radius: Option<f64>,
+    #[arg(long, help = "Radius where the band ends [default: 60]")]
+    pub end_radius: Option<f64>,
+    #[arg(long, help = "Key unit length in pixels [default: 8]")]
+    pub size: Option<f64>,
+}
+
+#[derive(Parser, Debug, Clone, Default)]
+pub struct FieldArgs {
+    #[arg(long, help = "Width of the filled area in pixels [default: 600]")]
+    pub width: Option<f64>,
+    #[arg(long, help = "Height of the filled area in pixels [default: 400]")]
+    pub height: Option<f64>,
+    #[arg(long, help = "Key unit length of the lattice in pixels [default: 10]")]
+    pub size: Option<f64>,
+}
+
+#[derive(Parser, Debug, Clone, Default)]
+pub struct FrameArgs {
+    #[arg(help = "PNG, JPEG or SVG image to put inside the border")]
+    pub input: Option<PathBuf>,
+    #[arg(long, help = "Border shape: rect, circle or ellipse [default: rect]")]
+    pub shape: Option<FrameShape>,
+    #[arg(
+        long,
+        help = "Key unit length in pixels for a rect border [default: 10]"
+    )]
+    pub size: Option<i32>,
+    #[arg(
+        long,
+        help = "Number of patterns around a circle or ellipse border [default: 30]"
+    )]
+    pub pattern_count: Option<i32>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ReproduceArgs {
+    #[arg(help = "SVG written by greek-meander")]
+    pub input: PathBuf,
+    #[arg(
+        long,
+        help = "Write the recovered config, with any overrides, to this TOML file instead of rendering"
+    )]
+    pub export: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct BatchArgs {
+    #[arg(help = "TOML, JSON or YAML manifest listing the designs to render")]
+    pub manifest: PathBuf,
+    #[arg(
+        long,
+        help = "Number of designs to render at once [default: available CPUs]"
+    )]
+    pub jobs: Option<NonZeroUsize>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct SweepArgs {
+    #[arg(help = "Shape to render")]
+    pub shape: Shape,
+    #[arg(
+        long,
+        required = true,
+        help = "Config option and the values it takes, as a list or an inclusive range with an optional step, e.g. stroke_width=2,4,8 or circle.pattern_count=20..40:5; repeat to render every combination"
+    )]
+    pub vary: Vec<Sweep>,
+    #[arg(
+        long,
+        help = "Designs per row of the contact sheet [default: values of the last --vary, or a square grid for one]"
+    )]
+    pub columns: Option<NonZeroUsize>,
+    #[arg(
+        long,
+        help = "Size in pixels of each design on the contact sheet [default: 240]"
+    )]
+    pub cell_size: Option<f64>,
+    #[arg(
+        long,
+        help = "Number of designs to render at once [default: available CPUs]"
+    )]
+    pub jobs: Option<NonZeroUsize>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ServeArgs {
+    #[arg(long, help = "Address to listen on [default: 127.0.0.1]")]
+    pub host: Option<String>,
+    #[arg(long, help = "Port to listen on; 0 picks a free one [default: 8080]")]
+    pub port: Option<u16>,
+    #[arg(
+        long,
+        help = "Number of renders kept in memory; 0 renders every request [default: 128]"
+    )]
+    pub cache_size: Option<usize>,
+    #[arg(
+        long,
+        help = "Number of requests answered at once [default: available CPUs]"
+    )]
+    pub jobs: Option<NonZeroUsize>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ValidateArgs {
+    #[arg(help = "TOML, JSON or YAML config file to check")]
+    pub config: PathBuf,
+    #[arg(
+        long = "shape",
+        help = "Shape to check the options for; repeat for more [default: the shapes the file has sections for, or rect]"
+    )]
+    pub shapes: Vec<Shape>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigCommand {
+    /// Print the JSON Schema for config files, for editors to complete and check them
+    Schema,
+    /// Print the options set by the config files and GREEK_MEANDER_* variables, and where each came from
+    Show(ShowArgs),
+    /// Write a config file listing every option with its default, commented out
+    Init(InitArgs),
+    /// Print the config a shape command would render with, to save as a config file
+    Dump(DumpArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ShowArgs {
+    #[arg(
+        long,
+        help = "Print every option a render would use, with the command line, theme and defaults laid in"
+    )]
+    pub resolved: bool,
+    #[arg(
+        help = "Shape to resolve the options for [default: the first shape the configs have a section for, or rect]"
+    )]
+    pub shape: Option<Shape>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct InitArgs {
+    #[arg(help = "File to write, or - for stdout [default: greek-meander.toml]")]
+    pub path: Option<PathBuf>,
+    #[arg(long, help = "Overwrite the file if it exists")]
+    pub force: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct DumpArgs {
+    #[command(subcommand)]
+    pub command: ShapeCommand,
+}
+
+/// A shape command and its options, as `config dump` takes them.
+#[derive(Subcommand, Debug, Clone)]
+pub enum ShapeCommand {
+    Rect(RectArgs),
+    Circle(CircleArgs),
+    Ellipse(EllipseArgs),
+    Superellipse(SuperellipseArgs),
+    Spiral(SpiralArgs),
+    Field(FieldArgs),
+    Frame(FrameArgs),
+}
+
+impl From<ShapeCommand> for Commands {
+    fn from(command: ShapeCommand) -> Self {
+        match command {
+            ShapeCommand::Rect(args) => Commands::Rect(args),
+            ShapeCommand::Circle(args) => Commands::Circle(args),
+            ShapeCommand::Ellipse(args) => Commands::Ellipse(args),
+            ShapeCommand::Superellipse(args) => Commands::Superellipse(args),
+            ShapeCommand::Spiral(args) => Commands::Spiral(args),
+            ShapeCommand::Field(args) => Commands::Field(args),
+            ShapeCommand::Frame(args) => Commands::Frame(args),
+        }
+    }
+}
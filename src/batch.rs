@@ -0,0 +1,300 @@
+use std::error::Error;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::Deserialize;
+
+use crate::args::{
+    CircleArgs, Commands, EllipseArgs, FieldArgs, FrameArgs, RectArgs, SpiralArgs, SuperellipseArgs,
+};
+use crate::file_config::{self, ConfigFormat, FileConfig};
+
+/// The shape a batch job renders.
+#[derive(Deserialize, clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Shape {
+    Rect,
+    Circle,
+    Ellipse,
+    Superellipse,
+    Spiral,
+    Field,
+    Frame,
+}
+
+impl Shape {
+    /// The shape command, with every option left to the job's config.
+    pub fn command(self) -> Commands {
+        match self {
+            Shape::Rect => Commands::Rect(RectArgs::default()),
+            Shape::Circle => Commands::Circle(CircleArgs::default()),
+            Shape::Ellipse => Commands::Ellipse(EllipseArgs::default()),
+            Shape::Superellipse => Commands::Superellipse(SuperellipseArgs::default()),
+            Shape::Spiral => Commands::Spiral(SpiralArgs::default()),
+            Shape::Field => Commands::Field(FieldArgs::default()),
+            Shape::Frame => Commands::Frame(FrameArgs::default()),
+        }
+    }
+
+    /// The config section holding the shape's own options.
+    pub fn section(self) -> &'static str {
+        match self {
+            Shape::Rect => "rect",
+            Shape::Circle => "circle",
+            Shape::Ellipse => "ellipse",
+            Shape::Superellipse => "superellipse",
+            Shape::Spiral => "spiral",
+            Shape::Field => "field",
+            Shape::Frame => "frame",
+        }
+    }
+}
+
+/// One design in a manifest.
+#[derive(Debug)]
+pub struct Job {
+    /// Shown in the summary; the output path unless the job names itself.
+    pub name: String,
+    pub shape: Shape,
+    /// Output file name without extension, as for `--file`.
+    pub output: String,
+    /// The manifest defaults with the job's own options laid over them.
+    pub config: FileConfig,
+}
+
+/// How one job went, with what it produced.
+#[derive(Debug)]
+pub struct Outcome<T> {
+    pub name: String,
+    pub result: Result<T, String>,
+}
+
+/// Manifest layout. Sections are kept as tables until each job's options
+/// are merged over the defaults.
+#[derive(Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    defaults: toml::Table,
+    #[serde(default)]
+    jobs: Vec<toml::Table>,
+}
+
+/// Loads a batch manifest in the format its extension names, as for config
+/// files.
+pub fn load(path: &Path) -> Result<Vec<Job>, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read manifest '{}': {}", path.display(), e))?;
+    let manifest: Manifest = ConfigFormat::detect(path)
+        .parse(&content)
+        .map_err(|e| format!("invalid manifest '{}': {}", path.display(), e))?;
+    if manifest.jobs.is_empty() {
+        return Err(format!("manifest '{}' lists no jobs", path.display()).into());
+    }
+    manifest
+        .jobs
+        .into_iter()
+        .enumerate()
+        .map(|(index, job)| {
+            parse_job(&manifest.defaults, job).map_err(|e| {
+                format!("job {} in manifest '{}': {}", index + 1, path.display(), e).into()
+            })
+        })
+        .collect()
+}
+
+fn parse_job(defaults: &toml::Table, mut job: toml::Table) -> Result<Job, Box<dyn Error>> {
+    let shape: Shape = job
+        .remove("shape")
+        .ok_or("missing 'shape'")?
+        .try_into()
+        .map_err(|e| format!("invalid 'shape': {}", e))?;
+    let output: String = job
+        .remove("output")
+        .ok_or("missing 'output'")?
+        .try_into()
+        .map_err(|e| format!("invalid 'output': {}", e))?;
+    let name = match job.remove("name") {
+        Some(name) => name
+            .try_into()
+            .map_err(|e| format!("invalid 'name': {}", e))?,
+        None => output.clone(),
+    };
+    let mut config = defaults.clone();
+    file_config::merge(&mut config, job);
+    Ok(Job {
+        name,
+        shape,
+        output,
+        config: toml::Value::Table(config).try_into()?,
+    })
+}
+
+/// Renders the jobs on up to `threads` threads and returns their outcomes in
+/// manifest order. A job that panics fails without stopping the others.
+pub fn run<T, F>(jobs: Vec<Job>, threads: usize, render: F) -> Vec<Outcome<T>>
+where
+    T: Send,
+    F: Fn(Job) -> Result<T, String> + Sync,
+{
+    let names: Vec<String> = jobs.iter().map(|job| job.name.clone()).collect();
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    let results = Mutex::new((0..names.len()).map(|_| None).collect::<Vec<_>>());
+    std::thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, names.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let Some((index, job)) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    let result = catch_unwind(AssertUnwindSafe(|| render(job)))
+                        .unwrap_or_else(|_| Err("rendering panicked".to_string()));
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            });
+        }
+    });
+    names
+        .into_iter()
+        .zip(results.into_inner().unwrap())
+        .map(|(name, result)| Outcome {
+            name,
+            result: result.expect("every job runs"),
+        })
+        .collect()
+}
+
+/// Prints a line per job and fails if any job did.
+pub fn report<T>(outcomes: &[Outcome<T>]) -> Result<(), Box<dyn Error>> {
+    for outcome in outcomes {
+        match &outcome.result {
+            Ok(_) => println!("ok      {}", outcome.name),
+            Err(e) => println!("FAILED  {}: {}", outcome.name, e),
+        }
+    }
+    let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+    println!(
+        "{} of {} jobs rendered",
+        outcomes.len() - failed,
+        outcomes.len()
+    );
+    if failed > 0 {
+        return Err(format!("{} of {} jobs failed", failed, outcomes.len()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jobs(content: &str, extension: &str) -> Result<Vec<Job>, Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!(
+            "gm_batch_{}_{}.{}",
+            std::process::id(),
+            content.len(),
+            extension
+        ));
+        std::fs::write(&path, content).unwrap();
+        let jobs = load(&path);
+        let _ = std::fs::remove_file(&path);
+        jobs
+    }
+
+    #[test]
+    fn jobs_inherit_and_override_defaults() {
+        let jobs = jobs(
+            r##"
+            [defaults]
+            stroke_width = 4.0
+            stroke_color = "#112233"
+            [defaults.circle]
+            radius = 200.0
+            pattern_count = 20
+
+            [[jobs]]
+            shape = "circle"
+            output = "out/small"
+            [jobs.circle]
+            radius = 100.0
+
+            [[jobs]]
+            name = "red rect"
+            shape = "rect"
+            output = "out/rect"
+            stroke_color = "#ff0000"
+            "##,
+            "toml",
+        )
+        .unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].name, "out/small");
+        assert_eq!(jobs[0].shape, Shape::Circle);
+        let circle = jobs[0].config.circle.as_ref().unwrap();
+        assert_eq!(circle.radius, Some(100.0));
+        assert_eq!(circle.pattern_count, Some(20));
+        assert_eq!(jobs[0].config.stroke_width, Some(4.0));
+        assert_eq!(jobs[1].name, "red rect");
+        assert_eq!(jobs[1].output, "out/rect");
+        assert_eq!(
+            jobs[1].config.stroke_color.as_ref().unwrap().to_string(),
+            "#ff0000"
+        );
+    }
+
+    #[test]
+    fn json_manifests_load() {
+        let jobs = jobs(
+            r#"{
+                "defaults": { "stroke_opacity": 0.5 },
+                "jobs": [{ "shape": "spiral", "output": "s", "spiral": { "turns": 4 } }]
+            }"#,
+            "json",
+        )
+        .unwrap();
+        assert_eq!(jobs[0].shape, Shape::Spiral);
+        assert_eq!(jobs[0].config.stroke_opacity, Some(0.5));
+        assert_eq!(jobs[0].config.spiral.as_ref().unwrap().turns, Some(4.0));
+    }
+
+    #[test]
+    fn bad_jobs_name_their_position() {
+        let err = jobs(
+            "[[jobs]]\nshape = \"rect\"\noutput = \"a\"\n[[jobs]]\noutput = \"b\"\n",
+            "toml",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.starts_with("job 2 in manifest"), "{err}");
+        assert!(err.ends_with("missing 'shape'"), "{err}");
+        let err = jobs("[[jobs]]\nshape = \"hexagon\"\noutput = \"a\"\n", "toml")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("invalid 'shape'"), "{err}");
+        let err = jobs("[defaults]\n", "toml").unwrap_err().to_string();
+        assert!(err.ends_with("lists no jobs"), "{err}");
+    }
+
+    #[test]
+    fn outcomes_keep_manifest_order() {
+        let jobs = (0..8)
+            .map(|i| Job {
+                name: i.to_string(),
+                shape: Shape::Rect,
+                output: i.to_string(),
+                config: FileConfig::default(),
+            })
+            .collect();
+        let outcomes = run(jobs, 3, |job| match job.name.as_str() {
+            "2" => Err("bad size".to_string()),
+            "5" => panic!("boom"),
+            _ => Ok(()),
+        });
+        let names: Vec<_> = outcomes.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, ["0", "1", "2", "3", "4", "5", "6", "7"]);
+        assert_eq!(outcomes[2].result, Err("bad size".to_string()));
+        assert_eq!(outcomes[5].result, Err("rendering panicked".to_string()));
+        assert!(report(&outcomes).is_err());
+        assert!(report(&outcomes[..2]).is_ok());
+    }
+}
@@ -1,40 +1,83 @@
 use svg::Document;
 use svg::node::element::path::Data;
-use svg::node::element::{Circle, Path as SvgPath, Rectangle};
+use svg::node::element::{Circle, Rectangle};
 
 #[cfg(feature = "native")]
 use crate::common::save_and_convert_svg;
-use crate::config::{GreekKeyCircleConfig, VisualOptions};
+use crate::common::{Point, add_double_line, add_pattern, arc_to, ellipse_lines};
+use crate::compact;
+use crate::config::{CirclePatternPoints, Element, GreekKeyCircleConfig, VisualOptions};
+use crate::field::{FieldClip, add_field};
+use crate::inlay::INLAY_ARC_TOLERANCE;
+use crate::metadata;
+use crate::paint::add_paint_defs;
+use crate::text::{TextLayout, add_text};
 
-fn draw_greek_key_patterns(config: &GreekKeyCircleConfig) -> Data {
-    let (mut points_a, mut points_b, mut points_c, mut points_d, mut points_e) =
-        config.get_coords_for_patterns();
-
-    let mut data = Data::new().move_to((points_a[0].x, points_a[0].y));
+// Draws the pattern as one closed path, or with `split` as one open path per key.
+fn draw_greek_key_patterns(
+    config: &GreekKeyCircleConfig,
+    arc_tolerance: Option<f64>,
+    split: bool,
+) -> Vec<Data> {
+    let centre = config.get_centre();
+    let r = &config.radii;
+    let (r_a, r_b, r_c, r_d, r_e) = (
+        (r.r_a, r.r_a),
+        (r.r_b, r.r_b),
+        (r.r_c, r.r_c),
+        (r.r_d, r.r_d),
+        (r.r_e, r.r_e),
+    );
+    // Radial runs are straight; tangential runs follow their ring as true arcs.
+    let arc = |data: Data, (rx, ry): (f64, f64), from: Point, to: Point| {
+        arc_to(data, centre, rx, ry, from, to, arc_tolerance)
+    };
+    let n = config.pattern_count as usize;
+    let mut keys: Vec<CirclePatternPoints> = Vec::with_capacity(n);
+    let mut points = config.get_coords_for_patterns();
+    for _ in 0..n {
+        keys.push(points);
+        let (a, b, c, d, e) = points;
+        points = config.get_coords_for_patterns_by_p0(a[5], b[5], c[5], d[5], e[5]);
+    }
 
-    for _ in 0..config.pattern_count {
-        data = data
-            .line_to((points_e[0].x, points_e[0].y))
-            .line_to((points_e[4].x, points_e[4].y))
-            .line_to((points_b[4].x, points_b[4].y))
-            .line_to((points_b[2].x, points_b[2].y))
-            .line_to((points_c[2].x, points_c[2].y))
-            .line_to((points_c[3].x, points_c[3].y))
-            .line_to((points_d[3].x, points_d[3].y))
-            .line_to((points_d[1].x, points_d[1].y))
-            .line_to((points_a[1].x, points_a[1].y))
-            .line_to((points_a[5].x, points_a[5].y));
+    // Draws one key from a0 round to a1, short of the run along ring a that
+    // joins it to the next key.
+    let body = |data: Data, (a, b, c, d, e): &CirclePatternPoints| {
+        let data = data.line_to((e[0].x, e[0].y));
+        let data = arc(data, r_e, e[0], e[4]);
+        let data = data.line_to((b[4].x, b[4].y));
+        let data = arc(data, r_b, b[4], b[2]);
+        let data = data.line_to((c[2].x, c[2].y));
+        let data = arc(data, r_c, c[2], c[3]);
+        let data = data.line_to((d[3].x, d[3].y));
+        let data = arc(data, r_d, d[3], d[1]);
+        data.line_to((a[1].x, a[1].y))
+    };
 
-        (points_a, points_b, points_c, points_d, points_e) = config.get_coords_for_patterns_by_p0(
-            points_a[5],
-            points_b[5],
-            points_c[5],
-            points_d[5],
-            points_e[5],
-        );
+    if !split {
+        let start = keys[0].0[0];
+        let mut data = Data::new().move_to((start.x, start.y));
+        for key in &keys {
+            data = body(data, key);
+            data = arc(data, r_a, key.0[1], key.0[5]);
+        }
+        return vec![data.close()];
     }
 
-    data.close()
+    // Each piece runs from the middle of the ring a run before its key to the
+    // middle of the one after, where the arcs of neighbouring pieces meet
+    // tangentially.
+    (0..n)
+        .map(|i| {
+            let before = &keys[(i + n - 1) % n].0;
+            let after = &keys[i].0;
+            let data = Data::new().move_to((before[3].x, before[3].y));
+            let data = arc(data, r_a, before[3], before[5]);
+            let data = body(data, &keys[i]);
+            arc(data, r_a, after[1], after[3])
+        })
+        .collect()
 }
 
 fn draw_frame(
@@ -62,61 +105,78 @@ fn apply_dash(circle: Circle, dash: Option<&str>) -> Circle {
     }
 }
 
-fn build_document(config: &GreekKeyCircleConfig, visual: &VisualOptions) -> Document {
+pub(crate) fn build_document(config: &GreekKeyCircleConfig, visual: &VisualOptions) -> Document {
     let stroke_width = config.stroke_width;
-    let stroke_color = visual.stroke_color.as_str();
-    let stroke_opacity = visual.stroke_opacity;
     let dash = visual.stroke_dash.as_deref();
     let (width, height) = config.get_canvas_size();
     let mut document = Document::new().set("viewBox", (0, 0, width, height));
+    document = add_paint_defs(document, visual, width, height);
 
-    if let Some(bg) = &visual.background_color {
+    if let Some(bg) = visual.background_paint() {
         document = document.add(
             Rectangle::new()
                 .set("width", width)
                 .set("height", height)
-                .set("fill", bg.as_str()),
+                .set("fill", bg),
         );
     }
 
-    let path_data = draw_greek_key_patterns(config);
-    let path = SvgPath::new()
-        .set("fill", visual.fill_color.as_deref().unwrap_or("none"))
-        .set("stroke", stroke_color)
-        .set("stroke-width", stroke_width)
-        .set("stroke-opacity", stroke_opacity)
-        .set("d", path_data);
-    let path = match dash {
-        Some(d) => path.set("stroke-dasharray", d),
-        None => path,
+    // The inlay's offset lines are built from straight segments.
+    let tolerance = match visual.inlay {
+        Some(_) => Some(visual.arc_tolerance.unwrap_or(INLAY_ARC_TOLERANCE)),
+        None => visual.arc_tolerance,
     };
-    document = document.add(path);
+    document = add_pattern(
+        document,
+        visual,
+        draw_greek_key_patterns(config, tolerance, false).remove(0),
+        || draw_greek_key_patterns(config, tolerance, true),
+        &visual.fill_paint(),
+        stroke_width,
+    );
 
     let centre = config.get_centre();
-    document = document.add(apply_dash(
-        draw_frame(
-            centre.x,
-            centre.y,
-            config.radii.r_i,
-            stroke_color,
-            stroke_width,
-            stroke_opacity,
-        ),
-        dash,
-    ));
-    document = document.add(apply_dash(
-        draw_frame(
-            centre.x,
-            centre.y,
-            config.radii.r_o,
-            stroke_color,
-            stroke_width,
-            stroke_opacity,
-        ),
-        dash,
-    ));
+    if let Some(spacing) = visual.field_spacing {
+        let clip = FieldClip::Circle {
+            centre,
+            r: config.radii.r_i,
+        };
+        document = add_field(document, clip, spacing, stroke_width, visual);
+    }
+    for (element, r) in [
+        (Element::InnerFrame, config.radii.r_i),
+        (Element::OuterFrame, config.radii.r_o),
+    ] {
+        if visual.is_hidden(element) {
+            continue;
+        }
+        document = match (&visual.inlay, tolerance) {
+            (Some(inlay), Some(tolerance)) => {
+                let data = ellipse_lines(centre, r, r, tolerance);
+                add_double_line(document, visual, inlay, element, data)
+            }
+            _ => document.add(apply_dash(
+                draw_frame(
+                    centre.x,
+                    centre.y,
+                    r,
+                    &visual.element_stroke(element),
+                    stroke_width,
+                    visual.element_opacity(element),
+                ),
+                dash,
+            )),
+        };
+    }
 
-    document
+    let (r_i, r_o) = (config.radii.r_i, config.radii.r_o);
+    let layout = TextLayout {
+        centre,
+        inner: (r_i, r_i),
+        rings: Some([(r_i, r_i), (r_o, r_o)]),
+        stroke_width,
+    };
+    add_text(document, visual, &layout)
 }
 
 /// Returns the circle Greek Key pattern as an SVG string.
@@ -124,7 +184,17 @@ fn build_document(config: &GreekKeyCircleConfig, visual: &VisualOptions) -> Docu
 /// Available on all targets including WASM. For file output, use
 /// [`generate_pattern_svg`] (requires the `native` feature).
 pub fn generate_svg_string(config: &GreekKeyCircleConfig, visual: &VisualOptions) -> String {
-    build_document(config, visual).to_string()
+    let visual = metadata::stamped(
+        visual,
+        config.stroke_width,
+        config.border_margin,
+        "circle",
+        vec![
+            ("pattern_count", config.pattern_count.into()),
+            ("radius", config.r_o.into()),
+        ],
+    );
+    compact::to_string(build_document(config, &visual), &visual)
 }
 
 /// Generates a circle Greek Key pattern and writes `<filename>.svg` and `<filename>.png`.
@@ -137,5 +207,5 @@ pub fn generate_pattern_svg(
     visual: &VisualOptions,
     filename: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    save_and_convert_svg(build_document(config, visual), filename)
+    save_and_convert_svg(&generate_svg_string(config, visual), filename)
 }
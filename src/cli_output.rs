@@ -1,14 +1,23 @@
 use std::error::Error;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use greek_meander::text::render_options;
 use resvg::render;
 use resvg::usvg::Tree;
 
+use crate::file_config;
+use crate::schema;
+
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct OutputOptions {
     pub(crate) write_svg: bool,
     pub(crate) write_png: bool,
     pub(crate) write_stdout: bool,
     pub(crate) png_scale: f32,
+    /// Fail rather than replace an output that exists.
+    pub(crate) no_clobber: bool,
 }
 
 impl OutputOptions {
@@ -23,6 +32,7 @@ impl OutputOptions {
             write_png,
             write_stdout,
             png_scale,
+            no_clobber: false,
         };
         options.validate()?;
         Ok(options)
@@ -42,11 +52,37 @@ impl OutputOptions {
     }
 }
 
+/// Writes the outputs `options` asks for. The PNG is rendered and both files
+/// are written to temporary files before either is moved into place, and an
+/// SVG already moved is removed if the PNG then cannot be, so a failure never
+/// leaves a new SVG beside a stale or partial PNG.
 pub(crate) fn write_outputs(
     svg_content: &[u8],
     filename: &str,
     options: &OutputOptions,
 ) -> Result<(), Box<dyn Error>> {
+    let svg_path = format!("{}.svg", filename);
+    let png_path = format!("{}.png", filename);
+    if options.no_clobber {
+        // Checked again as each file is moved into place; this only saves
+        // rendering a PNG that could not be written.
+        let paths = [
+            (options.write_svg, &svg_path),
+            (options.write_png, &png_path),
+        ];
+        if let Some((_, path)) = paths
+            .iter()
+            .find(|(written, path)| *written && Path::new(path).exists())
+        {
+            return Err(already_exists(Path::new(path)).into());
+        }
+    }
+    let png = if options.write_png {
+        Some(png_bytes(svg_content, options.png_scale)?)
+    } else {
+        None
+    };
+
     if options.write_stdout {
         use std::io::Write;
         let stdout = std::io::stdout();
@@ -55,19 +91,168 @@ pub(crate) fn write_outputs(
         handle.write_all(b"\n")?;
     }
 
-    if options.write_svg {
-        std::fs::write(format!("{}.svg", filename), svg_content)?;
+    let svg = if options.write_svg {
+        Some(Staged::write(Path::new(&svg_path), svg_content)?)
+    } else {
+        None
+    };
+    let png = match png {
+        Some(png) => Some(Staged::write(Path::new(&png_path), &png)?),
+        None => None,
+    };
+    commit_all(svg.into_iter().chain(png), options.no_clobber)
+}
+
+// Moves each staged file into place. If one cannot be moved, those already
+// moved are removed again, so no output is left beside a stale one.
+fn commit_all(
+    staged: impl Iterator<Item = Staged>,
+    no_clobber: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut committed = Vec::new();
+    for staged in staged {
+        let path = staged.path.clone();
+        if let Err(e) = staged.commit(no_clobber) {
+            for path in committed {
+                let _ = std::fs::remove_file(path);
+            }
+            return Err(e);
+        }
+        committed.push(path);
     }
+    Ok(())
+}
+
+/// Writes `content` to `path` whole or not at all, creating the directory if
+/// needed. With `no_clobber`, fails if `path` exists, even if another process
+/// creates it while this one writes.
+pub(crate) fn write_atomically(
+    path: &Path,
+    content: &[u8],
+    no_clobber: bool,
+) -> Result<(), Box<dyn Error>> {
+    Staged::write(path, content)?.commit(no_clobber)
+}
+
+fn already_exists(path: &Path) -> String {
+    format!(
+        "'{}' already exists; pass --force to overwrite it",
+        path.display()
+    )
+}
+
+// A temporary file beside `path`, waiting to be moved into place. Readers see
+// the old file or the new one, never a partial one. Dropping it removes the
+// temporary file.
+struct Staged {
+    temp: PathBuf,
+    path: PathBuf,
+}
 
-    if options.write_png {
-        write_png(svg_content, filename, options.png_scale)?;
+impl Staged {
+    fn write(path: &Path, content: &[u8]) -> Result<Self, Box<dyn Error>> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let fail = |e: std::io::Error| format!("could not write '{}': {}", path.display(), e);
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(fail)?;
+        }
+        let name = path
+            .file_name()
+            .ok_or_else(|| format!("could not write '{}': not a file name", path.display()))?;
+        let staged = Self {
+            temp: path.with_file_name(format!(
+                ".{}.{}-{}.tmp",
+                name.to_string_lossy(),
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            )),
+            path: path.to_path_buf(),
+        };
+        std::fs::write(&staged.temp, content).map_err(fail)?;
+        Ok(staged)
     }
 
-    Ok(())
+    // Moves the file into place. Without `no_clobber` a rename replaces any
+    // file at `path`; with it, a hard link creates `path` only if it is free,
+    // checking and writing in one step.
+    fn commit(self, no_clobber: bool) -> Result<(), Box<dyn Error>> {
+        let moved = if no_clobber {
+            std::fs::hard_link(&self.temp, &self.path)
+        } else {
+            std::fs::rename(&self.temp, &self.path)
+        };
+        match moved {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                Err(already_exists(&self.path).into())
+            }
+            Err(e) => Err(format!("could not write '{}': {}", self.path.display(), e).into()),
+        }
+    }
+}
+
+impl Drop for Staged {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.temp);
+    }
 }
 
-fn write_png(svg_content: &[u8], filename: &str, scale: f32) -> Result<(), Box<dyn Error>> {
-    let tree = Tree::from_data(svg_content, &resvg::usvg::Options::default())?;
+/// Fills the placeholders of an output name: `{shape}` with the shape's name,
+/// and `{<option>}` with the option's value in the resolved `config` of the
+/// design, or `none` if it is unset. As with `--vary`, an option that is not
+/// top-level is looked up in the shape's section, so `{pattern_count}` reads
+/// `circle.pattern_count` for a circle.
+pub(crate) fn output_name(
+    template: &str,
+    shape: &str,
+    config: &toml::Table,
+) -> Result<String, Box<dyn Error>> {
+    let fail = |problem: String| format!("output name '{template}': {problem}");
+    let options = schema::option_keys();
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| fail("'{' is not closed by '}'".into()))?;
+        let key = rest[start + 1..start + end].trim();
+        let value = if key == "shape" {
+            shape.to_string()
+        } else {
+            let key = [key.to_string(), format!("{shape}.{key}")]
+                .into_iter()
+                .find(|key| options.contains(key))
+                .ok_or_else(|| fail(format!("'{key}' names no option")))?;
+            file_config::get(config, &key).map_or_else(|| "none".to_string(), shown)
+        };
+        name.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+    Ok(name)
+}
+
+// An option value as it reads in a file name: numbers as written, lists
+// joined by commas, and characters that separate paths replaced.
+fn shown(value: &toml::Value) -> String {
+    let text = match value {
+        toml::Value::String(text) => text.clone(),
+        toml::Value::Float(float) => float.to_string(),
+        toml::Value::Array(items) => items.iter().map(shown).collect::<Vec<_>>().join(","),
+        value => value.to_string(),
+    };
+    text.replace(
+        |c: char| {
+            matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control()
+        },
+        "_",
+    )
+}
+
+/// Rasterizes `svg_content` to PNG data at `scale`.
+pub(crate) fn png_bytes(svg_content: &[u8], scale: f32) -> Result<Vec<u8>, Box<dyn Error>> {
+    let tree = Tree::from_data(svg_content, &render_options(svg_content))?;
     let pixmap_size = tree
         .size()
         .to_int_size()
@@ -80,14 +265,12 @@ fn write_png(svg_content: &[u8], filename: &str, scale: f32) -> Result<(), Box<d
         resvg::tiny_skia::Transform::from_scale(scale, scale),
         &mut pixmap.as_mut(),
     );
-    pixmap.save_png(format!("{}.png", filename))?;
-
-    Ok(())
+    Ok(pixmap.encode_png()?)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::OutputOptions;
+    use super::{OutputOptions, Staged, commit_all, output_name};
 
     #[test]
     fn stdout_only_output_is_valid() {
@@ -110,4 +293,68 @@ mod tests {
             assert!(err.to_string().contains("--scale"));
         }
     }
+
+    #[test]
+    fn no_clobber_keeps_a_file_that_appears_while_writing() {
+        let dir = std::env::temp_dir().join(format!("gm_no_clobber_{}", std::process::id()));
+        let path = dir.join("out.svg");
+        let staged = Staged::write(&path, b"new").unwrap();
+        // Another job writes the same output first.
+        std::fs::write(&path, b"theirs").unwrap();
+        let err = staged.commit(true).unwrap_err();
+        let left = std::fs::read_dir(&dir).unwrap().count();
+        let content = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(err.to_string().contains("already exists"), "{err}");
+        assert_eq!(content, b"theirs");
+        assert_eq!(left, 1, "the temporary file is removed");
+    }
+
+    #[test]
+    fn a_failed_commit_removes_the_outputs_already_moved() {
+        let dir = std::env::temp_dir().join(format!("gm_commit_all_{}", std::process::id()));
+        let svg = Staged::write(&dir.join("out.svg"), b"svg").unwrap();
+        let png = Staged::write(&dir.join("out.png"), b"png").unwrap();
+        // Another job writes the PNG after the pre-check.
+        std::fs::write(dir.join("out.png"), b"theirs").unwrap();
+        let err = commit_all([svg, png].into_iter(), true).unwrap_err();
+        let mut left: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        left.sort();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(err.to_string().contains("out.png' already exists"), "{err}");
+        assert_eq!(left, ["out.png"]);
+    }
+
+    #[test]
+    fn output_names_take_option_values() {
+        let config: toml::Table = toml::from_str(
+            "stroke_color = \"#AB8E0E\"\nstroke_opacity = 0.7\nkey_colors = [\"#111111\", \"#222222\"]\n[circle]\npattern_count = 30\nradius = 300.0\n[text]\nfont_file = \"fonts/a.ttf\"\n",
+        )
+        .unwrap();
+        let name = |template: &str| output_name(template, "circle", &config);
+        assert_eq!(
+            name("out/{shape}-{pattern_count}-{stroke_color}").unwrap(),
+            "out/circle-30-#AB8E0E"
+        );
+        assert_eq!(name("{circle.radius}_{stroke_opacity}").unwrap(), "300_0.7");
+        assert_eq!(name("{key_colors}").unwrap(), "#111111,#222222");
+        assert_eq!(
+            name("{text.font_file}-{fill_color}").unwrap(),
+            "fonts_a.ttf-none"
+        );
+        assert_eq!(name("meander").unwrap(), "meander");
+        assert_eq!(
+            name("m-{size}").unwrap_err().to_string(),
+            "output name 'm-{size}': 'size' names no option"
+        );
+        assert!(
+            name("m-{shape")
+                .unwrap_err()
+                .to_string()
+                .contains("not closed")
+        );
+    }
 }
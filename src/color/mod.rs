@@ -0,0 +1,286 @@
+/// Keywords accepted in place of a colour: no paint, fully transparent, and
+/// the inherited `color` property.
+const KEYWORDS: [&str; 3] = ["none", "transparent", "currentcolor"];
+
+/// The CSS Color Module Level 4 named colours.
+const NAMED_COLORS: [&str; 148] = [
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blue",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkgrey",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkslategrey",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dimgrey",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "green",
+    "greenyellow",
+    "grey",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgray",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightslategrey",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "rebeccapurple",
+    "red",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "slategrey",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+];
+
+/// Checks that `color` is a CSS colour SVG renderers understand: `#RGB`,
+/// `#RGBA`, `#RRGGBB` or `#RRGGBBAA` hex, `rgb()`/`rgba()`, `hsl()`/`hsla()`,
+/// a named colour, or `none`, `transparent` or `currentColor`.
+///
+/// Returns a description of the problem for the caller to prefix with the
+/// option name.
+pub(crate) fn check_color(color: &str) -> Result<(), String> {
+    let color = color.trim();
+    if color.is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    let lower = color.to_ascii_lowercase();
+    let valid = if let Some(hex) = lower.strip_prefix('#') {
+        matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    } else if let Some(args) = function_args(&lower, &["rgb", "rgba"]) {
+        check_rgb(&args)
+    } else if let Some(args) = function_args(&lower, &["hsl", "hsla"]) {
+        check_hsl(&args)
+    } else {
+        KEYWORDS.contains(&lower.as_str()) || NAMED_COLORS.contains(&lower.as_str())
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{color}' is not a colour; expected #RRGGBB, rgb(), hsl() or a CSS colour name"
+        ))
+    }
+}
+
+/// Returns the arguments of `name(...)` for any of `names`, split on commas,
+/// whitespace and the `/` before an alpha value.
+fn function_args(s: &str, names: &[&str]) -> Option<Vec<String>> {
+    let open = s.find('(')?;
+    if !names.contains(&s[..open].trim_end()) {
+        return None;
+    }
+    let inner = s[open + 1..].strip_suffix(')')?;
+    Some(
+        inner
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|a| !a.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+fn number(arg: &str) -> Option<f64> {
+    arg.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+fn percentage(arg: &str) -> Option<f64> {
+    arg.strip_suffix('%').and_then(number)
+}
+
+fn check_alpha(arg: &str) -> bool {
+    percentage(arg).or_else(|| number(arg)).is_some()
+}
+
+fn check_rgb(args: &[String]) -> bool {
+    matches!(args.len(), 3 | 4)
+        && args[..3]
+            .iter()
+            .all(|a| percentage(a).or_else(|| number(a)).is_some())
+        && args.get(3).is_none_or(|a| check_alpha(a))
+}
+
+fn check_hsl(args: &[String]) -> bool {
+    let hue = |a: &str| {
+        ["deg", "grad", "rad", "turn"]
+            .iter()
+            .find_map(|unit| a.strip_suffix(unit))
+            .or(Some(a))
+            .and_then(number)
+            .is_some()
+    };
+    matches!(args.len(), 3 | 4)
+        && hue(&args[0])
+        && percentage(&args[1]).is_some()
+        && percentage(&args[2]).is_some()
+        && args.get(3).is_none_or(|a| check_alpha(a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_css_colour_forms() {
+        for color in [
+            "#AB8E0E",
+            "#abc",
+            "#AB8E0E80",
+            "#fff8",
+            "gold",
+            "RebeccaPurple",
+            "none",
+            "currentColor",
+            "rgb(171, 142, 14)",
+            "rgba(171,142,14,0.5)",
+            "rgb(67% 56% 5% / 50%)",
+            "hsl(49, 85%, 36%)",
+            "hsla(49deg 85% 36% / 0.4)",
+        ] {
+            assert!(check_color(color).is_ok(), "{color}");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_colours() {
+        for color in [
+            "#GG0000",
+            "#12345",
+            "goldd",
+            "rgb(1, 2)",
+            "rgb(1, 2, x)",
+            "hsl(10, 20, 30)",
+            "rgb(1, 2, 3",
+            "",
+        ] {
+            assert!(check_color(color).is_err(), "{color}");
+        }
+    }
+
+    #[test]
+    fn error_names_the_colour() {
+        let e = check_color("#GG0000").unwrap_err();
+        assert!(e.contains("'#GG0000'"));
+    }
+}
@@ -2,8 +2,18 @@
 use resvg::render;
 #[cfg(feature = "native")]
 use resvg::usvg::Tree;
-#[cfg(feature = "native")]
 use svg::Document;
+use svg::node::Node;
+use svg::node::element::Path as SvgPath;
+use svg::node::element::path::Data;
+
+use crate::config::{Element, Inlay, VisualOptions};
+use crate::inlay::inlay_paths;
+#[cfg(feature = "native")]
+use crate::text::render_options;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
 
 #[derive(Debug, Clone, Copy)]
 pub struct Point {
@@ -13,13 +23,11 @@ pub struct Point {
 
 #[cfg(feature = "native")]
 pub(crate) fn save_and_convert_svg(
-    document: Document,
+    svg_content: &str,
     filename: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Serialize once; write to .svg and reuse the bytes for rasterization.
-    let mut svg_content = Vec::new();
-    svg::write(&mut svg_content, &document)?;
-    write_default_outputs(&svg_content, filename)
+    // Serialized once by the caller; write to .svg and reuse the bytes for rasterization.
+    write_default_outputs(svg_content.as_bytes(), filename)
 }
 
 #[cfg(feature = "native")]
@@ -29,7 +37,7 @@ fn write_default_outputs(
 ) -> Result<(), Box<dyn std::error::Error>> {
     std::fs::write(format!("{}.svg", filename), svg_content)?;
 
-    let tree = Tree::from_data(svg_content, &resvg::usvg::Options::default())?;
+    let tree = Tree::from_data(svg_content, &render_options(svg_content))?;
     let pixmap_size = tree.size().to_int_size();
     let mut pixmap = resvg::tiny_skia::Pixmap::new(pixmap_size.width(), pixmap_size.height())
         .ok_or("canvas has zero dimensions")?;
@@ -42,3 +50,454 @@ fn write_default_outputs(
 
     Ok(())
 }
+
+/// Appends the arc from `from` to `to` along the axis-aligned ellipse centred on `centre`.
+///
+/// Both points must lie on the ellipse and be less than half a turn apart; the
+/// shorter way round is taken. Without a `tolerance` this emits a single SVG
+/// arc (`A`) command. With one, the arc is flattened into line segments that
+/// stay within `tolerance` pixels of the true curve, for backends that only
+/// understand straight lines; a tolerance of at least the larger semi-axis
+/// draws the whole arc as one chord.
+pub(crate) fn arc_to(
+    data: Data,
+    centre: Point,
+    rx: f64,
+    ry: f64,
+    from: Point,
+    to: Point,
+    tolerance: Option<f64>,
+) -> Data {
+    let angle_of = |p: Point| ((p.y - centre.y) / ry).atan2((p.x - centre.x) / rx);
+    let start = angle_of(from);
+    let mut sweep = angle_of(to) - start;
+    if sweep > std::f64::consts::PI {
+        sweep -= std::f64::consts::TAU;
+    } else if sweep <= -std::f64::consts::PI {
+        sweep += std::f64::consts::TAU;
+    }
+
+    let Some(tolerance) = tolerance else {
+        // Positive parametric sweep runs clockwise on screen (y points down).
+        let sweep_flag = if sweep > 0.0 { 1 } else { 0 };
+        return data.elliptical_arc_to((rx, ry, 0, 0, sweep_flag, to.x, to.y));
+    };
+
+    // A chord spanning angle `a` on a circle of radius r sags r(1 - cos(a/2))
+    // below the arc; the larger semi-axis bounds the sag on an ellipse.
+    let r = rx.max(ry);
+    let segments = if tolerance >= r {
+        1
+    } else {
+        let max_step = 2.0 * (1.0 - tolerance / r).acos();
+        ((sweep.abs() / max_step).ceil() as usize).max(1)
+    };
+    let mut data = data;
+    for i in 1..segments {
+        let angle = start + sweep * i as f64 / segments as f64;
+        data = data.line_to((centre.x + rx * angle.cos(), centre.y + ry * angle.sin()));
+    }
+    data.line_to((to.x, to.y))
+}
+
+/// Splits a polyline into one path per key, cutting the segment that starts at
+/// each index in `cuts` (ascending) at its midpoint.
+///
+/// Cuts fall on straight runs, so the butt ends of neighbouring pieces meet
+/// flush. A closed polyline wraps round, giving one piece per cut; an open one
+/// keeps its ends on the first and last pieces.
+pub(crate) fn split_polyline(points: &[Point], cuts: &[usize], closed: bool) -> Vec<Data> {
+    let n = points.len();
+    let at = |i: usize| points[i % n];
+    let mid = |i: usize| {
+        let (p, q) = (at(i), at(i + 1));
+        ((p.x + q.x) / 2.0, (p.y + q.y) / 2.0)
+    };
+    let piece = |start: (f64, f64), from: usize, to: usize, end: Option<(f64, f64)>| {
+        let mut data = Data::new().move_to(start);
+        for i in from..to {
+            data = data.line_to((at(i).x, at(i).y));
+        }
+        match end {
+            Some(end) => data.line_to(end),
+            None => data,
+        }
+    };
+
+    let mut pieces = Vec::new();
+    if closed {
+        for (j, &cut) in cuts.iter().enumerate() {
+            let next = cuts.get(j + 1).copied().unwrap_or(cuts[0] + n);
+            pieces.push(piece(mid(cut), cut + 1, next + 1, Some(mid(next))));
+        }
+    } else {
+        let mut start = ((points[0].x, points[0].y), 1);
+        for &cut in cuts {
+            pieces.push(piece(start.0, start.1, cut + 1, Some(mid(cut))));
+            start = (mid(cut), cut + 1);
+        }
+        pieces.push(piece(start.0, start.1, n, None));
+    }
+    pieces
+}
+
+/// Adds the key pattern in the pattern style, unless it is hidden.
+///
+/// With `key_colors` set, the stroke is drawn as the paths from `keys`, one per
+/// key, cycling through the key colours. With an inlay, the pattern becomes a
+/// double line and the key colours, if any, fill the band between its lines.
+/// Either way `fill` goes on an unstroked copy of `path` underneath, since the
+/// open pieces cannot carry it.
+pub(crate) fn add_pattern(
+    document: Document,
+    visual: &VisualOptions,
+    path: Data,
+    keys: impl FnOnce() -> Vec<Data>,
+    fill: &str,
+    stroke_width: f32,
+) -> Document {
+    if visual.is_hidden(Element::Pattern) {
+        return document;
+    }
+    let stroked = |stroke: &str, fill: &str, data: Data| {
+        let path = SvgPath::new()
+            .set("fill", fill)
+            .set("stroke", stroke)
+            .set("stroke-width", stroke_width)
+            .set("stroke-opacity", visual.element_opacity(Element::Pattern))
+            .set("d", data);
+        match visual.stroke_dash.as_deref() {
+            Some(d) => path.set("stroke-dasharray", d),
+            None => path,
+        }
+    };
+
+    let paints = visual.key_paints();
+    if paints.is_empty() && visual.inlay.is_none() {
+        return document.add(stroked(
+            &visual.element_stroke(Element::Pattern),
+            fill,
+            path,
+        ));
+    }
+    let mut document = document;
+    if fill != "none" {
+        document = document.add(
+            SvgPath::new()
+                .set("fill", fill)
+                .set("stroke", "none")
+                .set("d", path.clone()),
+        );
+    }
+    if let Some(inlay) = &visual.inlay {
+        let bands = if paints.is_empty() {
+            visual
+                .inlay_paint()
+                .map(|paint| vec![(path.clone(), paint)])
+                .unwrap_or_default()
+        } else {
+            keys().into_iter().zip(paints.into_iter().cycle()).collect()
+        };
+        for element in inlay_paths(
+            inlay,
+            &path,
+            bands,
+            &visual.inlay_line_paint(Element::Pattern),
+            visual.element_opacity(Element::Pattern),
+            visual.stroke_dash.as_deref(),
+        ) {
+            document = document.add(element);
+        }
+        return document;
+    }
+    for (i, data) in keys().into_iter().enumerate() {
+        document = document.add(stroked(&paints[i % paints.len()], "none", data));
+    }
+    document
+}
+
+/// Adds `data` as a double line in `element`'s style, with the inlay colour
+/// between the lines. `data` must hold only straight segments.
+pub(crate) fn add_double_line(
+    document: Document,
+    visual: &VisualOptions,
+    inlay: &Inlay,
+    element: Element,
+    data: Data,
+) -> Document {
+    let bands = visual
+        .inlay_paint()
+        .map(|paint| vec![(data.clone(), paint)])
+        .unwrap_or_default();
+    let mut document = document;
+    for path in inlay_paths(
+        inlay,
+        &data,
+        bands,
+        &visual.inlay_line_paint(element),
+        visual.element_opacity(element),
+        visual.stroke_dash.as_deref(),
+    ) {
+        document = document.add(path);
+    }
+    document
+}
+
+/// Encodes `data` as standard padded base64, for `data:` URIs.
+pub(crate) fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes standard base64, ignoring whitespace. Returns `None` on any other
+/// character outside the alphabet.
+#[cfg(feature = "native")]
+pub(crate) fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        if c == b'=' {
+            break;
+        }
+        let value = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+        bits = bits << 6 | value;
+        count += 1;
+        if count == 4 {
+            out.extend_from_slice(&bits.to_be_bytes()[1..]);
+            bits = 0;
+            count = 0;
+        }
+    }
+    match count {
+        2 => out.push((bits >> 4) as u8),
+        3 => out.extend_from_slice(&((bits >> 2) as u16).to_be_bytes()),
+        _ => {}
+    }
+    Some(out)
+}
+
+/// Returns whether `node` is, or contains, a stroked element outside `<defs>`:
+/// part of the drawn border rather than the background.
+pub(crate) fn has_stroke(node: &dyn Node) -> bool {
+    node.get_name() != "defs"
+        && (node
+            .get_attributes()
+            .is_some_and(|a| a.contains_key("stroke"))
+            || node
+                .get_children()
+                .into_iter()
+                .flatten()
+                .any(|child| has_stroke(child.as_ref())))
+}
+
+/// Returns the closed axis-aligned ellipse centred on `centre` as line
+/// segments within `tolerance` pixels of the curve.
+pub(crate) fn ellipse_lines(centre: Point, rx: f64, ry: f64, tolerance: f64) -> Data {
+    let quadrants =
+        [(0.0, -ry), (rx, 0.0), (0.0, ry), (-rx, 0.0), (0.0, -ry)].map(|(x, y)| Point {
+            x: centre.x + x,
+            y: centre.y + y,
+        });
+    let mut data = Data::new().move_to((quadrants[0].x, quadrants[0].y));
+    for pair in quadrants.windows(2) {
+        data = arc_to(data, centre, rx, ry, pair[0], pair[1], Some(tolerance));
+    }
+    data.close()
+}
+
+/// A polyline approximation of a smooth curve, indexed by arc length.
+///
+/// Used by shapes without a closed-form SVG primitive (superellipse, spiral)
+/// to place keys at equal distances along the curve and to offset rails along
+/// the curve normal.
+#[derive(Debug, Clone)]
+pub(crate) struct SampledCurve {
+    points: Vec<Point>,
+    lengths: Vec<f64>,
+    closed: bool,
+}
+
+impl SampledCurve {
+    pub(crate) fn new(mut points: Vec<Point>, closed: bool) -> Self {
+        if closed {
+            points.push(points[0]);
+        }
+        let mut lengths = Vec::with_capacity(points.len());
+        let mut total = 0.0;
+        lengths.push(total);
+        for pair in points.windows(2) {
+            total += distance(pair[0], pair[1]);
+            lengths.push(total);
+        }
+        Self {
+            points,
+            lengths,
+            closed,
+        }
+    }
+
+    pub(crate) fn length(&self) -> f64 {
+        self.lengths[self.lengths.len() - 1]
+    }
+
+    fn wrap(&self, s: f64) -> f64 {
+        if self.closed {
+            s.rem_euclid(self.length())
+        } else {
+            s.clamp(0.0, self.length())
+        }
+    }
+
+    /// Returns the point at arc length `s` from the start of the curve.
+    pub(crate) fn point_at(&self, s: f64) -> Point {
+        let s = self.wrap(s);
+        let i = match self.lengths.binary_search_by(|l| l.total_cmp(&s)) {
+            Ok(i) => i.min(self.points.len() - 2),
+            Err(i) => i.saturating_sub(1).min(self.points.len() - 2),
+        };
+        let span = self.lengths[i + 1] - self.lengths[i];
+        let t = if span > 0.0 {
+            (s - self.lengths[i]) / span
+        } else {
+            0.0
+        };
+        let (p, q) = (self.points[i], self.points[i + 1]);
+        Point {
+            x: p.x + (q.x - p.x) * t,
+            y: p.y + (q.y - p.y) * t,
+        }
+    }
+
+    /// Returns the unit normal at arc length `s`, pointing to the right of the
+    /// direction of travel in SVG coordinates (inwards for a clockwise curve).
+    pub(crate) fn normal_at(&self, s: f64) -> Point {
+        let delta = self.length() * 1e-4;
+        let (before, after) = if self.closed {
+            (s - delta, s + delta)
+        } else {
+            let s = self.wrap(s);
+            ((s - delta).max(0.0), (s + delta).min(self.length()))
+        };
+        let p = self.point_at(before);
+        let q = self.point_at(after);
+        let len = distance(p, q);
+        Point {
+            x: -(q.y - p.y) / len,
+            y: (q.x - p.x) / len,
+        }
+    }
+
+    /// Returns the point at arc length `s`, moved `offset` along the normal.
+    pub(crate) fn offset_point(&self, s: f64, offset: f64) -> Point {
+        let p = self.point_at(s);
+        let n = self.normal_at(s);
+        Point {
+            x: p.x + offset * n.x,
+            y: p.y + offset * n.y,
+        }
+    }
+
+    /// Returns the smallest radius of curvature found between neighbouring samples.
+    ///
+    /// Offsetting a curve by more than this folds the offset curve over itself.
+    pub(crate) fn min_radius_of_curvature(&self) -> f64 {
+        self.points
+            .windows(3)
+            .map(|w| circumradius(w[0], w[1], w[2]))
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+fn distance(p: Point, q: Point) -> f64 {
+    (q.x - p.x).hypot(q.y - p.y)
+}
+
+fn circumradius(a: Point, b: Point, c: Point) -> f64 {
+    let cross = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+    if cross.abs() < f64::EPSILON {
+        return f64::INFINITY;
+    }
+    distance(a, b) * distance(b, c) * distance(c, a) / (2.0 * cross.abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Vec<Point> {
+        [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]
+            .into_iter()
+            .map(|(x, y)| Point { x, y })
+            .collect()
+    }
+
+    #[test]
+    fn a_tolerance_of_the_radius_draws_one_chord() {
+        let centre = Point { x: 0.0, y: 0.0 };
+        let from = Point { x: 100.0, y: 0.0 };
+        let to = Point { x: 0.0, y: 100.0 };
+        let commands = |tolerance: f64| {
+            let start = Data::new().move_to((from.x, from.y));
+            arc_to(start, centre, 100.0, 100.0, from, to, Some(tolerance)).len()
+        };
+        assert_eq!(commands(100.0), 2);
+        assert_eq!(commands(1000.0), 2);
+        assert!(commands(10.0) > 2);
+    }
+
+    #[test]
+    fn closed_polyline_splits_at_segment_midpoints() {
+        let pieces = split_polyline(&square(), &[0, 2], true);
+        let d: Vec<String> = pieces
+            .into_iter()
+            .map(|data| svg::node::element::Path::new().set("d", data).to_string())
+            .collect();
+        assert_eq!(d.len(), 2);
+        assert!(d[0].contains("M5,0 L10,0 L10,10 L5,10"), "{}", d[0]);
+        assert!(d[1].contains("M5,10 L0,10 L0,0 L5,0"), "{}", d[1]);
+    }
+
+    #[test]
+    fn open_polyline_keeps_its_ends() {
+        let pieces = split_polyline(&square(), &[1], false);
+        let d: Vec<String> = pieces
+            .into_iter()
+            .map(|data| svg::node::element::Path::new().set("d", data).to_string())
+            .collect();
+        assert_eq!(d.len(), 2);
+        assert!(d[0].contains("M0,0 L10,0 L10,5"), "{}", d[0]);
+        assert!(d[1].contains("M10,5 L10,10 L0,10"), "{}", d[1]);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn base64_round_trips_with_padding() {
+        for (data, text) in [
+            (&b"Man"[..], "TWFu"),
+            (b"Ma", "TWE="),
+            (b"M", "TQ=="),
+            (b"", ""),
+        ] {
+            assert_eq!(base64_encode(data), text);
+            assert_eq!(base64_decode(text).as_deref(), Some(data));
+        }
+        assert_eq!(base64_decode("TW\nFu").as_deref(), Some(&b"Man"[..]));
+        assert_eq!(base64_decode("TW*u"), None);
+    }
+}
@@ -0,0 +1,842 @@
+use std::collections::HashMap;
+
+use svg::Document;
+use svg::node::element::Element;
+use svg::node::{Node, Value};
+
+use crate::config::VisualOptions;
+use crate::effects::apply_effects;
+use crate::metadata::add_metadata;
+
+/// Decimal places used by `minify` when no explicit precision is set.
+const MINIFY_PRECISION: u8 = 2;
+
+/// Attributes holding plain numbers or number lists, rounded by `precision`.
+const NUMERIC_ATTRIBUTES: [&str; 14] = [
+    "viewBox",
+    "x",
+    "y",
+    "width",
+    "height",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "points",
+    "stroke-width",
+    "stroke-miterlimit",
+    "font-size",
+];
+
+/// Presentation attributes that `shared_styles` hoists onto a wrapping `<g>`.
+const STYLE_ATTRIBUTES: [&str; 9] = [
+    "fill",
+    "stroke",
+    "stroke-width",
+    "stroke-opacity",
+    "stroke-dasharray",
+    "stroke-linejoin",
+    "stroke-linecap",
+    "stroke-miterlimit",
+    "vector-effect",
+];
+
+/// Elements that draw geometry and so take part in style sharing.
+const SHAPE_ELEMENTS: [&str; 6] = ["path", "circle", "ellipse", "rect", "polygon", "polyline"];
+
+/// Serializes `document`, applying the stroke geometry, effects and output
+/// size options in `visual`.
+pub(crate) fn to_string(mut document: Document, visual: &VisualOptions) -> String {
+    add_stroke_geometry(&mut document, visual);
+    apply_effects(&mut document, visual);
+    let precision = visual
+        .precision
+        .or(visual.minify.then_some(MINIFY_PRECISION));
+    let format = NumberFormat {
+        precision,
+        relative: visual.relative_paths || visual.minify,
+        tight: visual.minify,
+    };
+    if format.precision.is_some() || format.relative {
+        rewrite_numbers(&mut document, &format);
+    }
+    if visual.shared_styles || visual.minify {
+        share_styles(&mut document);
+    }
+    add_metadata(&mut document, visual);
+    if let Some(prefix) = &visual.id_prefix {
+        prefix_ids(&mut document, prefix);
+    }
+
+    let svg = document.to_string();
+    if visual.minify {
+        svg.replace(">\n<", "><")
+    } else {
+        svg
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct NumberFormat {
+    precision: Option<u8>,
+    relative: bool,
+    /// Drop separators and leading zeros wherever SVG allows it.
+    tight: bool,
+}
+
+impl NumberFormat {
+    fn round(&self, value: f64) -> f64 {
+        match self.precision {
+            Some(p) => {
+                let scale = 10f64.powi(p as i32);
+                (value * scale).round() / scale
+            }
+            None => value,
+        }
+    }
+
+    fn format(&self, value: f64) -> String {
+        let mut s = match self.precision {
+            Some(p) => {
+                let s = format!("{:.*}", p as usize, value);
+                if s.contains('.') {
+                    s.trim_end_matches('0').trim_end_matches('.').to_string()
+                } else {
+                    s
+                }
+            }
+            // Path data is written from f32, so that is all the precision there is.
+            None => (value as f32).to_string(),
+        };
+        if s == "-0" {
+            s = "0".to_string();
+        }
+        if self.tight {
+            if let Some(rest) = s.strip_prefix("0.") {
+                s = format!(".{rest}");
+            } else if let Some(rest) = s.strip_prefix("-0.") {
+                s = format!("-.{rest}");
+            }
+        }
+        s
+    }
+}
+
+fn rewrite_numbers(node: &mut dyn Node, format: &NumberFormat) {
+    if let Some(attributes) = node.get_attributes_mut() {
+        for (name, value) in attributes.iter_mut() {
+            if name == "d" {
+                *value = Value::from(rewrite_path(value, format));
+            } else if format.precision.is_some() && NUMERIC_ATTRIBUTES.contains(&name.as_str()) {
+                *value = Value::from(round_numbers(value, format));
+            }
+        }
+    }
+    if let Some(children) = node.get_children_mut() {
+        for child in children.iter_mut() {
+            rewrite_numbers(child.as_mut(), format);
+        }
+    }
+}
+
+/// Rounds every number in a plain numeric attribute, keeping the separators.
+fn round_numbers(value: &str, format: &NumberFormat) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while !rest.is_empty() {
+        let len = number_len(rest);
+        if len > 0 {
+            match rest[..len].parse::<f64>() {
+                Ok(n) => out.push_str(&format.format(format.round(n))),
+                Err(_) => out.push_str(&rest[..len]),
+            }
+            rest = &rest[len..];
+        } else {
+            let c = rest.chars().next().unwrap_or_default();
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+/// Length of the number at the start of `s`, or 0 if it does not start with one.
+fn number_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        i += 1;
+    }
+    let digits_start = i;
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+    }
+    if i < bytes.len() && bytes[i] == b'.' {
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+    }
+    if i == digits_start || (i == digits_start + 1 && bytes[digits_start] == b'.') {
+        return 0;
+    }
+    if i < bytes.len() && matches!(bytes[i], b'e' | b'E') {
+        let mut j = i + 1;
+        if j < bytes.len() && matches!(bytes[j], b'+' | b'-') {
+            j += 1;
+        }
+        let exponent_start = j;
+        while j < bytes.len() && bytes[j].is_ascii_digit() {
+            j += 1;
+        }
+        if j > exponent_start {
+            i = j;
+        }
+    }
+    i
+}
+
+/// Splits path data into commands and their numeric arguments.
+fn parse_path(d: &str) -> Option<Vec<(char, Vec<f64>)>> {
+    let mut segments: Vec<(char, Vec<f64>)> = Vec::new();
+    let mut rest = d;
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_alphabetic() {
+            segments.push((c, Vec::new()));
+            rest = &rest[1..];
+        } else if c.is_ascii_whitespace() || c == ',' {
+            rest = &rest[1..];
+        } else {
+            let len = number_len(rest);
+            if len == 0 {
+                return None;
+            }
+            segments.last_mut()?.1.push(rest[..len].parse().ok()?);
+            rest = &rest[len..];
+        }
+    }
+    Some(segments)
+}
+
+fn argument_count(command: char) -> Option<usize> {
+    match command.to_ascii_uppercase() {
+        'Z' => Some(0),
+        'H' | 'V' => Some(1),
+        'M' | 'L' | 'T' => Some(2),
+        'S' | 'Q' => Some(4),
+        'C' => Some(6),
+        'A' => Some(7),
+        _ => None,
+    }
+}
+
+/// Converts parsed path data to absolute commands, expanding `H`/`V` and the
+/// implicit line-tos after a move into `L`.
+fn absolutize(segments: Vec<(char, Vec<f64>)>) -> Option<Vec<(char, Vec<f64>)>> {
+    let mut out = Vec::with_capacity(segments.len());
+    let (mut current, mut start) = ((0.0, 0.0), (0.0, 0.0));
+    for (command, args) in segments {
+        let count = argument_count(command)?;
+        let upper = command.to_ascii_uppercase();
+        if upper == 'Z' {
+            out.push(('Z', Vec::new()));
+            current = start;
+            continue;
+        }
+        if args.is_empty() || args.len() % count != 0 {
+            return None;
+        }
+        for (i, group) in args.chunks(count).enumerate() {
+            let origin = if command.is_ascii_lowercase() {
+                current
+            } else {
+                (0.0, 0.0)
+            };
+            let (upper, absolute) = match upper {
+                'H' => ('L', vec![origin.0 + group[0], current.1]),
+                'V' => ('L', vec![current.0, origin.1 + group[0]]),
+                'A' => {
+                    let mut a = group[..5].to_vec();
+                    a.extend([origin.0 + group[5], origin.1 + group[6]]);
+                    ('A', a)
+                }
+                _ => {
+                    let a = group
+                        .chunks(2)
+                        .flat_map(|pair| [origin.0 + pair[0], origin.1 + pair[1]])
+                        .collect();
+                    // Implicit repeats after a move are line-tos.
+                    (if upper == 'M' && i > 0 { 'L' } else { upper }, a)
+                }
+            };
+            current = (absolute[absolute.len() - 2], absolute[absolute.len() - 1]);
+            if upper == 'M' {
+                start = current;
+            }
+            out.push((upper, absolute));
+        }
+    }
+    Some(out)
+}
+
+/// Drops line-to points that lie within `tolerance` of the straight line
+/// between the points kept either side of them.
+///
+/// Curves sampled into short chords (superellipse rails, spirals) collapse to
+/// far fewer points without any visible change at the written precision.
+fn simplify_lines(segments: Vec<(char, Vec<f64>)>, tolerance: f64) -> Vec<(char, Vec<f64>)> {
+    let mut out: Vec<(char, Vec<f64>)> = Vec::with_capacity(segments.len());
+    let mut run: Vec<(f64, f64)> = Vec::new();
+    let mut anchor = (0.0, 0.0);
+
+    let flush = |out: &mut Vec<(char, Vec<f64>)>, run: &mut Vec<(f64, f64)>, anchor| {
+        let mut from = anchor;
+        let mut i = 0;
+        while i < run.len() {
+            // Extend the chord from `from` as far as every skipped point allows.
+            let mut j = i;
+            while j + 1 < run.len()
+                && run[i..=j]
+                    .iter()
+                    .all(|&p| line_distance(p, from, run[j + 1]) <= tolerance)
+            {
+                j += 1;
+            }
+            out.push(('L', vec![run[j].0, run[j].1]));
+            from = run[j];
+            i = j + 1;
+        }
+        run.clear();
+    };
+
+    for (command, args) in segments {
+        if command == 'L' {
+            run.push((args[0], args[1]));
+            continue;
+        }
+        if !run.is_empty() {
+            flush(&mut out, &mut run, anchor);
+        }
+        if let [.., x, y] = args[..] {
+            anchor = (x, y);
+        }
+        if command == 'Z' {
+            // The next run starts from the subpath start, found by walking back.
+            if let Some((_, a)) = out.iter().rev().find(|(c, _)| *c == 'M') {
+                anchor = (a[0], a[1]);
+            }
+        }
+        out.push((command, args));
+    }
+    if !run.is_empty() {
+        flush(&mut out, &mut run, anchor);
+    }
+    out
+}
+
+/// Distance from `p` to the segment from `a` to `b`.
+fn line_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0)
+    };
+    ((p.0 - a.0 - t * dx).powi(2) + (p.1 - a.1 - t * dy).powi(2)).sqrt()
+}
+
+/// Rewrites path data with rounded numbers and, if requested, relative commands.
+///
+/// Relative offsets are measured from the previously *written* point, so
+/// rounding errors do not accumulate along the path. Data that cannot be
+/// parsed is returned unchanged.
+fn rewrite_path(d: &str, format: &NumberFormat) -> String {
+    let Some(mut segments) = parse_path(d).and_then(absolutize) else {
+        return d.to_string();
+    };
+    if let Some(p) = format.precision {
+        segments = simplify_lines(segments, 0.5 * 10f64.powi(-(p as i32)));
+    }
+
+    let mut out = String::with_capacity(d.len());
+    // The current point and subpath start as the output reconstructs them.
+    let (mut written, mut written_start) = ((0.0, 0.0), (0.0, 0.0));
+    let mut last_letter = None;
+
+    for (i, (upper, args)) in segments.into_iter().enumerate() {
+        if upper == 'Z' {
+            if !format.tight {
+                out.push(' ');
+            }
+            out.push(if format.relative { 'z' } else { 'Z' });
+            written = written_start;
+            last_letter = None;
+            continue;
+        }
+
+        // Moves that start the path are always absolute.
+        let relative = format.relative && i > 0;
+        let base = if relative { written } else { (0.0, 0.0) };
+        let mut numbers: Vec<f64> = Vec::with_capacity(args.len());
+        let mut flags = None;
+        if upper == 'A' {
+            numbers.extend(args[..3].iter().map(|&n| format.round(n)));
+            flags = Some((args[3] != 0.0, args[4] != 0.0));
+            numbers.push(format.round(args[5] - base.0));
+            numbers.push(format.round(args[6] - base.1));
+        } else {
+            for pair in args.chunks(2) {
+                numbers.push(format.round(pair[0] - base.0));
+                numbers.push(format.round(pair[1] - base.1));
+            }
+        }
+        let end_written = (
+            base.0 + numbers[numbers.len() - 2],
+            base.1 + numbers[numbers.len() - 1],
+        );
+
+        // Axis-aligned lines only need one number.
+        let mut upper = upper;
+        if format.tight && upper == 'L' {
+            if end_written.1 == written.1 {
+                upper = 'H';
+                numbers.truncate(1);
+            } else if end_written.0 == written.0 {
+                upper = 'V';
+                numbers.remove(0);
+            }
+        }
+
+        let letter = if relative {
+            upper.to_ascii_lowercase()
+        } else {
+            upper
+        };
+        // A repeated command letter may be left out, except after a move,
+        // where implicit repeats mean line-to.
+        let repeat = format.tight && upper != 'M' && last_letter == Some(letter);
+        if !repeat {
+            if !out.is_empty() && !format.tight {
+                out.push(' ');
+            }
+            out.push(letter);
+        }
+        match flags {
+            Some((large_arc, sweep)) => {
+                push_numbers(&mut out, &numbers[..3], format, repeat);
+                push_flags(&mut out, large_arc, sweep, format);
+                push_numbers(&mut out, &numbers[3..], format, false);
+            }
+            None => push_numbers(&mut out, &numbers, format, repeat),
+        }
+        last_letter = Some(letter);
+
+        written = end_written;
+        if upper == 'M' {
+            written_start = written;
+        }
+    }
+    out
+}
+
+/// Appends `numbers`; `continuing` means the previous command's numbers come
+/// straight before them with no command letter in between.
+fn push_numbers(out: &mut String, numbers: &[f64], format: &NumberFormat, continuing: bool) {
+    for (i, &n) in numbers.iter().enumerate() {
+        let s = format.format(n);
+        if i > 0 || continuing {
+            // A sign, or a leading dot after a number that already has one,
+            // separates numbers on its own.
+            let previous_has_dot = out
+                .rsplit(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .next()
+                .is_some_and(|p| p.contains('.'));
+            let self_separating = s.starts_with('-') || (s.starts_with('.') && previous_has_dot);
+            if !(format.tight && self_separating) {
+                out.push(',');
+            }
+        }
+        out.push_str(&s);
+    }
+}
+
+/// Appends arc flags; tight output packs them against the following number,
+/// which the SVG path grammar allows because a flag is always one digit.
+fn push_flags(out: &mut String, large_arc: bool, sweep: bool, format: &NumberFormat) {
+    let flag = |f: bool| if f { '1' } else { '0' };
+    out.push(',');
+    out.push(flag(large_arc));
+    if !format.tight {
+        out.push(',');
+    }
+    out.push(flag(sweep));
+    if !format.tight {
+        out.push(',');
+    }
+}
+
+/// Moves presentation attributes shared by the drawn elements onto a `<g>`.
+///
+/// The root children from the first to the last stroked element are wrapped,
+/// so document order is unchanged. Each attribute is hoisted only if every
+/// drawn element in that range sets it; the most common value goes on the
+/// group and elements with a different value keep their own.
+fn share_styles(document: &mut Document) {
+    let Some(children) = Node::get_children_mut(document) else {
+        return;
+    };
+    let stroked: Vec<usize> = children
+        .iter()
+        .enumerate()
+        .filter(|(_, child)| has_stroked_shape(child.as_ref()))
+        .map(|(i, _)| i)
+        .collect();
+    let (Some(&first), Some(&last)) = (stroked.first(), stroked.last()) else {
+        return;
+    };
+
+    let mut range: Vec<Box<dyn Node>> = children.drain(first..=last).collect();
+    let mut shapes = 0;
+    let mut counts: HashMap<&str, HashMap<String, usize>> = HashMap::new();
+    for child in &range {
+        visit_shapes(child.as_ref(), &mut |shape| {
+            shapes += 1;
+            let attributes = shape.get_attributes();
+            for name in STYLE_ATTRIBUTES {
+                if let Some(value) = attributes.and_then(|a| a.get(name)) {
+                    *counts
+                        .entry(name)
+                        .or_default()
+                        .entry(value.to_string())
+                        .or_default() += 1;
+                }
+            }
+        });
+    }
+
+    let mut shared: Vec<(&str, String)> = Vec::new();
+    for name in STYLE_ATTRIBUTES {
+        let Some(values) = counts.get(name) else {
+            continue;
+        };
+        if values.values().sum::<usize>() != shapes {
+            continue;
+        }
+        if let Some((value, &count)) = values.iter().max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            && count > 1
+        {
+            shared.push((name, value.clone()));
+        }
+    }
+
+    if shared.is_empty() {
+        let tail = children.split_off(first);
+        children.extend(range);
+        children.extend(tail);
+        return;
+    }
+
+    for child in range.iter_mut() {
+        visit_shapes_mut(child, &mut |shape| {
+            if let Some(attributes) = shape.get_attributes_mut() {
+                for (name, value) in &shared {
+                    if attributes
+                        .get(*name)
+                        .is_some_and(|v| &**v == value.as_str())
+                    {
+                        attributes.remove(*name);
+                    }
+                }
+            }
+        });
+    }
+
+    let mut group = Element::new("g");
+    for (name, value) in &shared {
+        group.assign(*name, value.as_str());
+    }
+    group.get_children_mut().extend(range);
+    children.insert(first, Box::new(group));
+}
+
+/// Sets the join, cap, miter limit and vector effect on every stroked shape.
+fn add_stroke_geometry(document: &mut Document, visual: &VisualOptions) {
+    let mut attributes: Vec<(&str, Value)> = Vec::new();
+    if let Some(join) = visual.line_join {
+        attributes.push(("stroke-linejoin", join.as_str().into()));
+    }
+    if let Some(cap) = visual.line_cap {
+        attributes.push(("stroke-linecap", cap.as_str().into()));
+    }
+    if let Some(limit) = visual.miter_limit {
+        attributes.push(("stroke-miterlimit", limit.into()));
+    }
+    if visual.non_scaling_stroke {
+        attributes.push(("vector-effect", "non-scaling-stroke".into()));
+    }
+    if attributes.is_empty() {
+        return;
+    }
+    for child in Node::get_children_mut(document).into_iter().flatten() {
+        visit_shapes_mut(child, &mut |shape| {
+            let Some(shape_attributes) = shape.get_attributes_mut() else {
+                return;
+            };
+            if shape_attributes
+                .get("stroke")
+                .is_none_or(|stroke| &**stroke == "none")
+            {
+                return;
+            }
+            for (name, value) in &attributes {
+                shape_attributes.insert(name.to_string(), value.clone());
+            }
+        });
+    }
+}
+
+/// Returns whether `prefix` can start an XML id: a letter or `_`, then
+/// letters, digits, `-`, `_` or `.`.
+pub(crate) fn is_id_prefix(prefix: &str) -> bool {
+    let mut chars = prefix.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Replaces the `gm` starting every id under `node`, and every reference to
+/// one, with `prefix`.
+fn prefix_ids(node: &mut dyn Node, prefix: &str) {
+    if let Some(attributes) = node.get_attributes_mut() {
+        for (name, value) in attributes.iter_mut() {
+            let renamed = match name.as_str() {
+                "id" | "aria-labelledby" => value
+                    .split(' ')
+                    .map(|id| match id.strip_prefix("gm-") {
+                        Some(rest) => format!("{prefix}-{rest}"),
+                        None => id.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => value.replace("#gm-", &format!("#{prefix}-")),
+            };
+            *value = Value::from(renamed);
+        }
+    }
+    if let Some(children) = node.get_children_mut() {
+        for child in children.iter_mut() {
+            prefix_ids(child.as_mut(), prefix);
+        }
+    }
+}
+
+fn is_shape(node: &dyn Node) -> bool {
+    SHAPE_ELEMENTS.contains(&node.get_name())
+}
+
+fn has_stroked_shape(node: &dyn Node) -> bool {
+    let mut found = false;
+    visit_shapes(node, &mut |shape| {
+        found |= shape
+            .get_attributes()
+            .is_some_and(|a| a.contains_key("stroke"));
+    });
+    found
+}
+
+/// Calls `f` on every drawn shape under `node`, skipping `<defs>`.
+fn visit_shapes(node: &dyn Node, f: &mut dyn FnMut(&dyn Node)) {
+    if node.get_name() == "defs" {
+        return;
+    }
+    if is_shape(node) {
+        f(node);
+    }
+    for child in node.get_children().into_iter().flatten() {
+        visit_shapes(child.as_ref(), f);
+    }
+}
+
+fn visit_shapes_mut(node: &mut Box<dyn Node>, f: &mut dyn FnMut(&mut dyn Node)) {
+    if node.get_name() == "defs" {
+        return;
+    }
+    if is_shape(node.as_ref()) {
+        f(node.as_mut());
+    }
+    if let Some(children) = node.get_children_mut() {
+        for child in children.iter_mut() {
+            visit_shapes_mut(child, f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circle;
+    use crate::config::{GreekKeyCircleConfig, LineJoin};
+
+    fn format(precision: Option<u8>, relative: bool, tight: bool) -> NumberFormat {
+        NumberFormat {
+            precision,
+            relative,
+            tight,
+        }
+    }
+
+    #[test]
+    fn id_prefix_replaces_ids_and_references() {
+        let mut visual = VisualOptions {
+            id_prefix: Some("left".to_string()),
+            background_color: Some("linear-gradient(#000, #fff)".parse().unwrap()),
+            ..VisualOptions::default()
+        };
+        visual.metadata.title = Some("Border".to_string());
+        let config = GreekKeyCircleConfig::new(100.0, 12, 5, 2.0).unwrap();
+        let svg = circle::generate_svg_string(&config, &visual);
+        assert!(svg.contains(r#"id="left-background""#), "{svg}");
+        assert!(svg.contains(r#"fill="url(#left-background)""#));
+        assert!(svg.contains(r#"aria-labelledby="left-title""#));
+        assert!(!svg.contains("gm-"), "{svg}");
+    }
+
+    #[test]
+    fn id_prefixes_must_be_xml_names() {
+        assert!(is_id_prefix("left"));
+        assert!(is_id_prefix("_a.b-2"));
+        assert!(!is_id_prefix(""));
+        assert!(!is_id_prefix("2a"));
+        assert!(!is_id_prefix("a b"));
+        assert!(!is_id_prefix("a\"x"));
+    }
+
+    #[test]
+    fn rounds_path_numbers() {
+        let d = "M107,87.8656 L107,23.17312 Z";
+        assert_eq!(
+            rewrite_path(d, &format(Some(1), false, false)),
+            "M107,87.9 L107,23.2 Z"
+        );
+    }
+
+    #[test]
+    fn converts_to_relative_commands() {
+        let d = "M10,10 L20,10 L20,25 A5,5,0,0,1,25,30 Z";
+        assert_eq!(
+            rewrite_path(d, &format(Some(2), true, false)),
+            "M10,10 l10,0 l0,15 a5,5,0,0,1,5,5 z"
+        );
+    }
+
+    #[test]
+    fn relative_offsets_do_not_accumulate_rounding() {
+        let d = "M0,0 L0.4,3 L0.8,0 L1.2,3 L1.6,0";
+        let out = rewrite_path(d, &format(Some(0), true, false));
+        // Written x values 0, 0, 1, 1, 2 track the exact ones to within half a unit.
+        assert_eq!(out, "M0,0 l0,3 l1,-3 l0,3 l1,-3");
+    }
+
+    #[test]
+    fn tight_format_drops_redundant_separators() {
+        let d = "M0,0 L-0.5,0.25 L0.75,0.5";
+        assert_eq!(
+            rewrite_path(d, &format(Some(2), false, true)),
+            "M0,0L-.5.25.75.5"
+        );
+    }
+
+    #[test]
+    fn tight_format_uses_axis_commands() {
+        let d = "M7,7 l2050,0 l0,1175 l-2050,0 z";
+        assert_eq!(
+            rewrite_path(d, &format(Some(2), true, true)),
+            "M7,7h2050v1175h-2050z"
+        );
+    }
+
+    #[test]
+    fn accepts_relative_input() {
+        let d = "M7,7 l2050,0 l0,1175 l-2050,0 z";
+        assert_eq!(
+            rewrite_path(d, &format(Some(0), false, false)),
+            "M7,7 L2057,7 L2057,1182 L7,1182 Z"
+        );
+    }
+
+    #[test]
+    fn drops_points_on_a_straight_line() {
+        let d = "M0,0 L1,0.001 L2,0 L2,5";
+        assert_eq!(
+            rewrite_path(d, &format(Some(2), false, false)),
+            "M0,0 L2,0 L2,5"
+        );
+    }
+
+    #[test]
+    fn packs_arc_flags() {
+        let d = "M0,0 A5,5,0,0,1,5,5";
+        assert_eq!(
+            rewrite_path(d, &format(Some(2), true, true)),
+            "M0,0a5,5,0,015,5"
+        );
+    }
+
+    #[test]
+    fn rounds_plain_numeric_attributes() {
+        assert_eq!(
+            round_numbers("0 0 214.123456 214", &format(Some(2), false, false)),
+            "0 0 214.12 214"
+        );
+    }
+
+    #[test]
+    fn shared_styles_hoist_common_attributes() {
+        let shape = |fill: &str| {
+            svg::node::element::Path::new()
+                .set("d", "M0,0 L1,1")
+                .set("fill", fill)
+                .set("stroke", "#000")
+        };
+        let mut document = Document::new()
+            .add(shape("red"))
+            .add(shape("none"))
+            .add(shape("none"));
+        share_styles(&mut document);
+        let svg = document.to_string();
+        assert_eq!(svg.matches("stroke=").count(), 1);
+        assert!(svg.contains(r##"<g fill="none" stroke="#000">"##));
+        assert!(svg.contains(r#"fill="red""#));
+    }
+
+    #[test]
+    fn stroke_geometry_skips_unstroked_shapes() {
+        let mut document = Document::new()
+            .add(
+                svg::node::element::Path::new()
+                    .set("d", "M0,0 L1,1")
+                    .set("stroke", "#000"),
+            )
+            .add(
+                svg::node::element::Path::new()
+                    .set("d", "M0,0 L1,1")
+                    .set("stroke", "none"),
+            )
+            .add(svg::node::element::Rectangle::new().set("fill", "#FFF"));
+        let visual = VisualOptions {
+            line_join: Some(LineJoin::Round),
+            miter_limit: Some(2.0),
+            non_scaling_stroke: true,
+            ..VisualOptions::default()
+        };
+        add_stroke_geometry(&mut document, &visual);
+        let svg = document.to_string();
+        assert_eq!(svg.matches(r#"stroke-linejoin="round""#).count(), 1);
+        assert_eq!(svg.matches(r#"stroke-miterlimit="2""#).count(), 1);
+        assert_eq!(svg.matches("vector-effect").count(), 1);
+        assert!(!svg.contains("stroke-linecap"));
+    }
+}
@@ -1,6 +1,162 @@
 use std::f64::consts::PI;
 
-use crate::common::Point;
+use crate::common::{Point, SampledCurve};
+use crate::compact;
+use crate::effects::{self, Effect};
+use crate::metadata::Metadata;
+use crate::paint::{
+    BACKGROUND_ID, FILL_ID, INLAY_ID, INLAY_LINE_ID, INNER_FRAME_ID, OUTER_FRAME_ID, PATTERN_ID,
+    Paint, STROKE_ID, TEXT_ID, key_id,
+};
+use crate::text::Text;
+
+/// Stroke overrides for one drawn part of a shape.
+///
+/// Unset fields fall back to the document-wide values in [`VisualOptions`].
+#[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ElementStyle {
+    pub stroke_color: Option<Paint>,
+    pub stroke_opacity: Option<f32>,
+    /// Leaves the part out of the document entirely.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub hidden: bool,
+}
+
+/// Shape drawn where two stroke segments meet (SVG `stroke-linejoin`).
+#[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "native", serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+    /// Sharp corners, cut off past `miter_limit`.
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl LineJoin {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Miter => "miter",
+            Self::Round => "round",
+            Self::Bevel => "bevel",
+        }
+    }
+}
+
+impl std::str::FromStr for LineJoin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "miter" => Ok(Self::Miter),
+            "round" => Ok(Self::Round),
+            "bevel" => Ok(Self::Bevel),
+            _ => Err(format!(
+                "unknown line join '{s}'; expected 'miter', 'round' or 'bevel'"
+            )),
+        }
+    }
+}
+
+/// Shape drawn at the open ends of strokes and dashes (SVG `stroke-linecap`).
+#[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "native", serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    /// Ends flush with the end point.
+    Butt,
+    Round,
+    /// Ends half the stroke width past the end point.
+    Square,
+}
+
+impl LineCap {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Butt => "butt",
+            Self::Round => "round",
+            Self::Square => "square",
+        }
+    }
+}
+
+impl std::str::FromStr for LineCap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "butt" => Ok(Self::Butt),
+            "round" => Ok(Self::Round),
+            "square" => Ok(Self::Square),
+            _ => Err(format!(
+                "unknown line cap '{s}'; expected 'butt', 'round' or 'square'"
+            )),
+        }
+    }
+}
+
+/// Double-line rendering: every stroke becomes two parallel lines with an
+/// inlay between them.
+///
+/// The lines are offset from the stroke's centreline, so the whole band is
+/// [`Inlay::width`] wide. Pass that as the shape config's `stroke_width` so the
+/// canvas margins fit it.
+#[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inlay {
+    /// Distance between the inner edges of the two lines.
+    pub gap: f64,
+    /// Width of each line.
+    pub line_width: f64,
+    /// Paint for the lines. `None` uses the stroke of the part being drawn.
+    pub line_color: Option<Paint>,
+    /// Paint between the lines. `None` leaves the gap transparent; with
+    /// `key_colors` set, the pattern's inlay takes the key colours instead.
+    pub inlay_color: Option<Paint>,
+}
+
+impl Inlay {
+    pub fn new(gap: f64, line_width: f64) -> Self {
+        Self {
+            gap,
+            line_width,
+            line_color: None,
+            inlay_color: None,
+        }
+    }
+
+    /// Overall width of the band from the outer edge of one line to the other.
+    pub fn width(&self) -> f64 {
+        self.gap + 2.0 * self.line_width
+    }
+}
+
+/// The parts of a shape that [`ElementStyle`] applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Element {
+    Pattern,
+    InnerFrame,
+    OuterFrame,
+}
+
+impl Element {
+    fn id(self) -> &'static str {
+        match self {
+            Element::Pattern => PATTERN_ID,
+            Element::InnerFrame => INNER_FRAME_ID,
+            Element::OuterFrame => OUTER_FRAME_ID,
+        }
+    }
+
+    fn flag(self) -> &'static str {
+        match self {
+            Element::Pattern => "pattern",
+            Element::InnerFrame => "inner-frame",
+            Element::OuterFrame => "outer-frame",
+        }
+    }
+}
 
 /// Visual styling options for SVG pattern generation.
 ///
@@ -8,25 +164,271 @@ use crate::common::Point;
 #[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Debug, Clone)]
 pub struct VisualOptions {
-    pub stroke_color: String,
+    /// Colour or gradient for all strokes; see [`Paint`] for the syntax.
+    pub stroke_color: Paint,
     pub stroke_opacity: f32,
-    /// Fill color for the pattern interior. `None` leaves the interior transparent.
-    pub fill_color: Option<String>,
-    /// Background color for the SVG canvas. `None` omits the background element.
-    pub background_color: Option<String>,
+    /// Fill for the pattern interior. `None` leaves the interior transparent.
+    pub fill_color: Option<Paint>,
+    /// Background for the SVG canvas. `None` omits the background element.
+    pub background_color: Option<Paint>,
     /// SVG `stroke-dasharray` value (e.g. `"5,3"`). `None` produces solid strokes.
     pub stroke_dash: Option<String>,
+    /// Key unit length of an all-over key lattice drawn inside the inner frame.
+    /// `None` leaves the interior empty. Ignored by spirals, which have no interior.
+    pub field_spacing: Option<f64>,
+    /// Maximum distance in pixels between a curved key run and the straight
+    /// segments approximating it. `None` draws circle and ellipse runs as true
+    /// SVG arcs; set it for backends that only handle line segments.
+    pub arc_tolerance: Option<f64>,
+    /// Maximum number of decimal places written for coordinates and lengths.
+    /// `None` writes numbers at full precision.
+    pub precision: Option<u8>,
+    /// Moves stroke and fill attributes shared by the drawn elements onto one
+    /// wrapping `<g>` instead of repeating them on every element.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub shared_styles: bool,
+    /// Writes path data with relative commands after the initial move.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub relative_paths: bool,
+    /// Turns on `shared_styles` and `relative_paths`, rounds to 2 decimal
+    /// places unless `precision` is set, and drops optional whitespace.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub minify: bool,
+    /// Replaces the `gm` that starts every element id, e.g. `gm-stroke`, so
+    /// several SVGs inlined in one HTML page do not share gradients, clips
+    /// and filters. `None` keeps `gm`.
+    pub id_prefix: Option<String>,
+    /// Overrides for the key pattern path.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub pattern_style: ElementStyle,
+    /// Overrides for the frame inside the pattern band.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub inner_frame_style: ElementStyle,
+    /// Overrides for the frame outside the pattern band.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub outer_frame_style: ElementStyle,
+    /// Paints cycled over successive keys, e.g. red and black as on Attic
+    /// pottery. Empty draws the pattern as one path in the pattern stroke.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub key_colors: Vec<Paint>,
+    /// Draws every stroke as a double line. `None` draws single strokes.
+    pub inlay: Option<Inlay>,
+    /// `stroke-linejoin` for every stroke. `None` leaves the SVG default, miter.
+    pub line_join: Option<LineJoin>,
+    /// `stroke-linecap` for every stroke. `None` leaves the SVG default, butt.
+    pub line_cap: Option<LineCap>,
+    /// `stroke-miterlimit` for every stroke. `None` leaves the SVG default, 4.
+    pub miter_limit: Option<f64>,
+    /// Keeps strokes the same width on screen when the SVG is scaled, with
+    /// `vector-effect="non-scaling-stroke"`.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub non_scaling_stroke: bool,
+    /// Filter effects applied to the whole border; see [`Effect`].
+    #[cfg_attr(feature = "native", serde(default))]
+    pub effects: Vec<Effect>,
+    /// Title and ring text drawn with the border. `None` draws no text.
+    pub text: Option<Text>,
+    /// Accessible title and description, licence and generation parameters
+    /// stored in the SVG.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub metadata: Metadata,
 }
 
 impl VisualOptions {
-    pub fn new(stroke_color: impl Into<String>, stroke_opacity: f32) -> Self {
+    pub fn new(stroke_color: impl Into<Paint>, stroke_opacity: f32) -> Self {
         Self {
             stroke_color: stroke_color.into(),
             stroke_opacity,
             fill_color: None,
             background_color: None,
             stroke_dash: None,
+            field_spacing: None,
+            arc_tolerance: None,
+            precision: None,
+            shared_styles: false,
+            relative_paths: false,
+            minify: false,
+            id_prefix: None,
+            pattern_style: ElementStyle::default(),
+            inner_frame_style: ElementStyle::default(),
+            outer_frame_style: ElementStyle::default(),
+            key_colors: Vec::new(),
+            inlay: None,
+            line_join: None,
+            line_cap: None,
+            miter_limit: None,
+            non_scaling_stroke: false,
+            effects: Vec::new(),
+            text: None,
+            metadata: Metadata::default(),
+        }
+    }
+
+    /// Checks option values that cannot be expressed in the field types.
+    ///
+    /// Returns an error if `field_spacing` or `arc_tolerance` is set but not a
+    /// positive finite number, if `id_prefix` is not a valid XML name, if
+    /// `miter_limit` is below 1, if an element
+    /// opacity is outside 0.0–1.0, if a gradient paint has invalid geometry or
+    /// stops, or if `effects` asks for both emboss and engrave.
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.stroke_color.validate("--stroke-color")?;
+        for element in [Element::Pattern, Element::InnerFrame, Element::OuterFrame] {
+            let style = self.element_style(element);
+            let flag = element.flag();
+            if let Some(paint) = &style.stroke_color {
+                paint.validate(&format!("--{flag}-color"))?;
+            }
+            if let Some(opacity) = style.stroke_opacity
+                && !(0.0..=1.0).contains(&opacity)
+            {
+                return Err(format!("--{flag}-opacity must be between 0.0 and 1.0").into());
+            }
+        }
+        for paint in &self.key_colors {
+            paint.validate("--key-color")?;
+        }
+        if let Some(inlay) = &self.inlay {
+            if inlay.gap <= 0.0 || !inlay.gap.is_finite() {
+                return Err("--inlay-gap must be a positive finite number".into());
+            }
+            if inlay.line_width <= 0.0 || !inlay.line_width.is_finite() {
+                return Err("--inlay-line-width must be a positive finite number".into());
+            }
+            if let Some(paint) = &inlay.line_color {
+                paint.validate("--inlay-line-color")?;
+            }
+            if let Some(paint) = &inlay.inlay_color {
+                paint.validate("--inlay-color")?;
+            }
+        }
+        if let Some(fill) = &self.fill_color {
+            fill.validate("--fill-color")?;
+        }
+        if let Some(background) = &self.background_color {
+            background.validate("--background-color")?;
+        }
+        if let Some(spacing) = self.field_spacing
+            && (spacing <= 0.0 || !spacing.is_finite())
+        {
+            return Err("--field-spacing must be a positive finite number".into());
+        }
+        if let Some(tolerance) = self.arc_tolerance
+            && (tolerance <= 0.0 || !tolerance.is_finite())
+        {
+            return Err("--arc-tolerance must be a positive finite number".into());
+        }
+        if let Some(prefix) = &self.id_prefix
+            && !compact::is_id_prefix(prefix)
+        {
+            return Err("--id-prefix must start with a letter or '_' and hold only \
+                 letters, digits, '-', '_' and '.'"
+                .into());
+        }
+        if let Some(limit) = self.miter_limit
+            && (limit < 1.0 || !limit.is_finite())
+        {
+            return Err("--miter-limit must be a finite number of at least 1".into());
+        }
+        effects::validate(&self.effects)?;
+        if let Some(text) = &self.text {
+            text.validate()?;
         }
+        Ok(())
+    }
+
+    /// Checks option values that depend on the size of the canvas.
+    ///
+    /// Returns an error if `field_spacing` would need more than 250 000 keys to
+    /// fill a `width` by `height` canvas.
+    pub fn validate_canvas(
+        &self,
+        width: f64,
+        height: f64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self.field_spacing {
+            Some(spacing) => check_field_keys(width, height, spacing, "--field-spacing"),
+            None => Ok(()),
+        }
+    }
+
+    /// Value of the `stroke` attribute for pattern and frame strokes.
+    pub(crate) fn stroke_paint(&self) -> String {
+        self.stroke_color.attribute(STROKE_ID)
+    }
+
+    /// Value of the `fill` attribute for the pattern interior.
+    pub(crate) fn fill_paint(&self) -> String {
+        self.fill_color
+            .as_ref()
+            .map_or_else(|| "none".to_string(), |p| p.attribute(FILL_ID))
+    }
+
+    /// Value of the `fill` attribute for the canvas background, if any.
+    pub(crate) fn background_paint(&self) -> Option<String> {
+        self.background_color
+            .as_ref()
+            .map(|p| p.attribute(BACKGROUND_ID))
+    }
+
+    pub(crate) fn element_style(&self, element: Element) -> &ElementStyle {
+        match element {
+            Element::Pattern => &self.pattern_style,
+            Element::InnerFrame => &self.inner_frame_style,
+            Element::OuterFrame => &self.outer_frame_style,
+        }
+    }
+
+    /// Value of the `stroke` attribute for `element`.
+    pub(crate) fn element_stroke(&self, element: Element) -> String {
+        match &self.element_style(element).stroke_color {
+            Some(paint) => paint.attribute(element.id()),
+            None => self.stroke_paint(),
+        }
+    }
+
+    /// Value of the `stroke-opacity` attribute for `element`.
+    pub(crate) fn element_opacity(&self, element: Element) -> f32 {
+        self.element_style(element)
+            .stroke_opacity
+            .unwrap_or(self.stroke_opacity)
+    }
+
+    pub(crate) fn is_hidden(&self, element: Element) -> bool {
+        self.element_style(element).hidden
+    }
+
+    /// Values of the `stroke` attribute for the keys, in drawing order.
+    pub(crate) fn key_paints(&self) -> Vec<String> {
+        self.key_colors
+            .iter()
+            .enumerate()
+            .map(|(i, paint)| paint.attribute(&key_id(i)))
+            .collect()
+    }
+
+    /// Value of the `stroke` attribute for the inlay's lines along `element`.
+    pub(crate) fn inlay_line_paint(&self, element: Element) -> String {
+        match self.inlay.as_ref().and_then(|i| i.line_color.as_ref()) {
+            Some(paint) => paint.attribute(INLAY_LINE_ID),
+            None => self.element_stroke(element),
+        }
+    }
+
+    /// Value of the `fill` attribute for text.
+    pub(crate) fn text_paint(&self) -> String {
+        match self.text.as_ref().and_then(|t| t.color.as_ref()) {
+            Some(paint) => paint.attribute(TEXT_ID),
+            None => self.stroke_paint(),
+        }
+    }
+
+    /// Value of the `stroke` attribute for the band between the inlay's lines, if any.
+    pub(crate) fn inlay_paint(&self) -> Option<String> {
+        self.inlay
+            .as_ref()
+            .and_then(|i| i.inlay_color.as_ref())
+            .map(|p| p.attribute(INLAY_ID))
     }
 }
 
@@ -36,6 +438,230 @@ impl Default for VisualOptions {
     }
 }
 
+/// Most keys a field lattice may hold.
+const MAX_FIELD_KEYS: f64 = 250_000.0;
+
+/// Returns an error naming `flag` if a key lattice of key unit length `unit`
+/// needs more than [`MAX_FIELD_KEYS`] keys to cover a `width` by `height` area.
+fn check_field_keys(
+    width: f64,
+    height: f64,
+    unit: f64,
+    flag: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Matches the rows and overhanging keys drawn by the lattice.
+    let keys = ((width / (5.0 * unit)).ceil() + 2.0) * ((height / (4.0 * unit)).ceil() + 2.0);
+    if keys > MAX_FIELD_KEYS {
+        return Err(format!(
+            "{flag} is too small for a {width}×{height} field; \
+             it would need more than {MAX_FIELD_KEYS} keys"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Configuration for a rectangle covered by an all-over Greek Key lattice.
+///
+/// `width` and `height` are the size of the filled area in pixels, and
+/// `key_unit_length` is the lattice spacing.
+#[derive(Debug)]
+pub struct GreekKeyFieldConfig {
+    pub width: f64,
+    pub height: f64,
+    pub key_unit_length: f64,
+    pub border_margin: i32,
+    pub stroke_width: f32,
+}
+
+impl GreekKeyFieldConfig {
+    /// Creates a new field config.
+    ///
+    /// Returns an error if `width`, `height` or `key_unit_length` is not a positive finite
+    /// number, the lattice would hold more than 250 000 keys, `border_margin` < 0, or
+    /// `stroke_width` is not a positive finite number.
+    pub fn new(
+        width: f64,
+        height: f64,
+        key_unit_length: f64,
+        border_margin: i32,
+        stroke_width: f32,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if width <= 0.0 || !width.is_finite() {
+            return Err("--width must be a positive finite number".into());
+        }
+        if height <= 0.0 || !height.is_finite() {
+            return Err("--height must be a positive finite number".into());
+        }
+        if key_unit_length <= 0.0 || !key_unit_length.is_finite() {
+            return Err("--size must be a positive finite number".into());
+        }
+        check_field_keys(width, height, key_unit_length, "--size")?;
+        if border_margin < 0 {
+            return Err("--border-margin must be non-negative".into());
+        }
+        if stroke_width <= 0.0 || !stroke_width.is_finite() {
+            return Err("--stroke-width must be a positive finite number".into());
+        }
+        Ok(Self {
+            width,
+            height,
+            key_unit_length,
+            border_margin,
+            stroke_width,
+        })
+    }
+
+    /// Returns the `(width, height)` of the canvas in pixels.
+    pub fn get_canvas_size(&self) -> (f64, f64) {
+        let pad = (2 * self.border_margin) as f64 + (2.0 * self.stroke_width) as f64;
+        (self.width + pad, self.height + pad)
+    }
+
+    pub(crate) fn get_origin(&self) -> (f64, f64) {
+        let offset = self.border_margin as f64 + self.stroke_width as f64;
+        (offset, offset)
+    }
+}
+
+/// The border drawn around framed artwork.
+#[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "native", serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameShape {
+    /// A rectangle border whose inner frame covers the artwork.
+    Rect,
+    /// A circle border whose inner frame spans the artwork's shorter side.
+    Circle,
+    /// An ellipse border whose inner frame touches the middle of each of the
+    /// artwork's edges.
+    Ellipse,
+}
+
+impl FrameShape {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Rect => "rect",
+            Self::Circle => "circle",
+            Self::Ellipse => "ellipse",
+        }
+    }
+}
+
+impl std::str::FromStr for FrameShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rect" => Ok(Self::Rect),
+            "circle" => Ok(Self::Circle),
+            "ellipse" => Ok(Self::Ellipse),
+            _ => Err(format!(
+                "unknown frame shape '{s}'; expected 'rect', 'circle' or 'ellipse'"
+            )),
+        }
+    }
+}
+
+/// Configuration for a Greek Key border sized to fit around artwork.
+///
+/// The border's dimensions come from the artwork. A rectangle border uses
+/// `key_unit_length` and as many whole keys as the artwork needs; circle and
+/// ellipse borders use `pattern_count` keys.
+#[derive(Debug)]
+pub struct GreekKeyFrameConfig {
+    pub shape: FrameShape,
+    pub key_unit_length: i32,
+    pub pattern_count: i32,
+    pub border_margin: i32,
+    pub stroke_width: f32,
+}
+
+impl GreekKeyFrameConfig {
+    /// Creates a new frame config.
+    ///
+    /// Returns an error if `key_unit_length` ≤ 0, `pattern_count` < 4 or > 100 000,
+    /// `border_margin` < 0, or `stroke_width` is not a positive finite number.
+    pub fn new(
+        shape: FrameShape,
+        key_unit_length: i32,
+        pattern_count: i32,
+        border_margin: i32,
+        stroke_width: f32,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if key_unit_length <= 0 {
+            return Err("--size must be greater than 0".into());
+        }
+        check_pattern_count(pattern_count)?;
+        if border_margin < 0 {
+            return Err("--border-margin must be non-negative".into());
+        }
+        if stroke_width <= 0.0 || !stroke_width.is_finite() {
+            return Err("--stroke-width must be a positive finite number".into());
+        }
+        Ok(Self {
+            shape,
+            key_unit_length,
+            pattern_count,
+            border_margin,
+            stroke_width,
+        })
+    }
+
+    /// Returns the rectangle border whose inner frame covers a `width` by
+    /// `height` artwork.
+    pub(crate) fn rect_config(
+        &self,
+        width: f64,
+        height: f64,
+    ) -> Result<GreekKeyRectConfig, Box<dyn std::error::Error>> {
+        // The inner frame is two keys shorter than the border on each axis.
+        let key_pattern_length = (self.key_unit_length * 5) as f64;
+        let units = |length: f64| ((length / key_pattern_length).ceil() as i32 + 2).max(3);
+        GreekKeyRectConfig::new(
+            self.key_unit_length,
+            units(width),
+            units(height),
+            self.border_margin,
+            self.stroke_width,
+        )
+    }
+
+    /// Returns the circle border whose inner frame has radius `r_i`.
+    pub(crate) fn circle_config(
+        &self,
+        r_i: f64,
+    ) -> Result<GreekKeyCircleConfig, Box<dyn std::error::Error>> {
+        GreekKeyCircleConfig::new(
+            r_i / self.inner_radius_ratio()?,
+            self.pattern_count,
+            self.border_margin,
+            self.stroke_width,
+        )
+    }
+
+    /// Returns the ellipse border whose inner frame has semi-axes `rx_i` and `ry_i`.
+    pub(crate) fn ellipse_config(
+        &self,
+        rx_i: f64,
+        ry_i: f64,
+    ) -> Result<GreekKeyEllipseConfig, Box<dyn std::error::Error>> {
+        let ratio = self.inner_radius_ratio()?;
+        GreekKeyEllipseConfig::new(
+            rx_i / ratio,
+            ry_i / ratio,
+            self.pattern_count,
+            self.border_margin,
+            self.stroke_width,
+        )
+    }
+
+    // The inner frame radius of a circle border with outer radius 1.
+    fn inner_radius_ratio(&self) -> Result<f64, Box<dyn std::error::Error>> {
+        Ok(get_radii_for_outer_radius(1.0, PATTERN_UNIT_SIZE * self.pattern_count)?.r_i)
+    }
+}
+
 /// Configuration for a rectangle Greek Key border pattern.
 #[derive(Debug)]
 pub struct GreekKeyRectConfig {
@@ -51,7 +677,8 @@ impl GreekKeyRectConfig {
     /// Creates a new rect config.
     ///
     /// Returns an error if `key_unit_length` ≤ 0, `width` or `height` < 3, `border_margin` < 0,
-    /// or `stroke_width` is not a positive finite number.
+    /// `stroke_width` is not a positive finite number, or the canvas would be
+    /// wider than `i32::MAX` pixels.
     pub fn new(
         key_unit_length: i32,
         width_units: i32,
@@ -74,17 +701,26 @@ impl GreekKeyRectConfig {
         if stroke_width <= 0.0 || !stroke_width.is_finite() {
             return Err("--stroke-width must be a positive finite number".into());
         }
+        // Positions are whole pixels, so the widest side must fit in an i32.
+        let side = |units: i32| {
+            (i64::from(units) * i64::from(PATTERN_UNIT_SIZE) + 2) * i64::from(key_unit_length)
+                + 2 * i64::from(border_margin)
+        };
+        if side(width_units.max(height_units)) > i64::from(i32::MAX) {
+            return Err("--size, --width and --height give a canvas too large to draw".into());
+        }
         Ok(Self {
             key_unit_length,
             width_units,
             height_units,
-            key_pattern_length: key_unit_length * 5,
+            key_pattern_length: key_unit_length * PATTERN_UNIT_SIZE,
             border_margin,
             stroke_width,
         })
     }
 
-    pub(crate) fn get_canvas_size(&self) -> (f64, f64) {
+    /// Returns the `(width, height)` of the canvas in pixels.
+    pub fn get_canvas_size(&self) -> (f64, f64) {
         let width = (self.width_units * self.key_pattern_length
             + 2 * self.border_margin
             + 2 * self.key_unit_length) as f64
@@ -145,8 +781,23 @@ pub struct Radii {
 
 static PATTERN_UNIT_SIZE: i32 = 5;
 
+/// Most patterns a circle, ellipse, superellipse or frame border may hold.
+const MAX_PATTERN_COUNT: i32 = 100_000;
+
+/// Returns an error unless `pattern_count` is between 4 and
+/// [`MAX_PATTERN_COUNT`].
+fn check_pattern_count(pattern_count: i32) -> Result<(), Box<dyn std::error::Error>> {
+    if pattern_count < 4 {
+        return Err("--pattern-count must be at least 4".into());
+    }
+    if pattern_count > MAX_PATTERN_COUNT {
+        return Err(format!("--pattern-count must be at most {MAX_PATTERN_COUNT}").into());
+    }
+    Ok(())
+}
+
 type CirclePointSet = [Point; 6];
-type CirclePatternPoints = (
+pub(crate) type CirclePatternPoints = (
     CirclePointSet,
     CirclePointSet,
     CirclePointSet,
@@ -155,7 +806,7 @@ type CirclePatternPoints = (
 );
 
 type EllipsePointSet = [Point; 6];
-type EllipsePatternPoints = (
+pub(crate) type EllipsePatternPoints = (
     EllipsePointSet,
     EllipsePointSet,
     EllipsePointSet,
@@ -301,8 +952,8 @@ pub struct GreekKeyEllipseConfig {
 impl GreekKeyEllipseConfig {
     /// Creates a new ellipse config.
     ///
-    /// Returns an error if `rx` or `ry` is not a positive finite number, `pattern_count` < 4,
-    /// `border_margin` < 0, or `stroke_width` is not a positive finite number.
+    /// Returns an error if `rx` or `ry` is not a positive finite number, `pattern_count` < 4
+    /// or > 100 000, `border_margin` < 0, or `stroke_width` is not a positive finite number.
     pub fn new(
         rx: f64,
         ry: f64,
@@ -316,9 +967,7 @@ impl GreekKeyEllipseConfig {
         if ry <= 0.0 || !ry.is_finite() {
             return Err("--ry must be a positive finite number".into());
         }
-        if pattern_count < 4 {
-            return Err("--pattern-count must be at least 4".into());
-        }
+        check_pattern_count(pattern_count)?;
         if border_margin < 0 {
             return Err("--border-margin must be non-negative".into());
         }
@@ -336,7 +985,8 @@ impl GreekKeyEllipseConfig {
         })
     }
 
-    pub(crate) fn get_canvas_size(&self) -> (f64, f64) {
+    /// Returns the `(width, height)` of the canvas in pixels.
+    pub fn get_canvas_size(&self) -> (f64, f64) {
         let width =
             2.0 * self.rx + (2 * self.border_margin) as f64 + (2.0 * self.stroke_width) as f64;
         let height =
@@ -391,11 +1041,355 @@ impl GreekKeyEllipseConfig {
     }
 }
 
+/// Number of samples used to approximate the outer superellipse curve.
+const SUPERELLIPSE_SAMPLES: usize = 4096;
+
+/// Configuration for a superellipse (squircle) Greek Key border pattern.
+///
+/// The outer edge follows `|x/rx|^n + |y/ry|^n = 1`, where `n` is `exponent`.
+/// Keys are spaced by arc length along that curve and the rails are offset
+/// inwards along its normal, so an exponent of 2 with equal semi-axes
+/// reproduces the circle pattern.
+#[derive(Debug)]
+pub struct GreekKeySuperellipseConfig {
+    pub rx: f64,
+    pub ry: f64,
+    pub exponent: f64,
+    pub pattern_count: i32,
+    pub border_margin: i32,
+    pub stroke_width: f32,
+    /// Distance between neighbouring rails, measured along the normal.
+    pub rail_spacing: f64,
+    pub(crate) outline: SampledCurve,
+}
+
+impl GreekKeySuperellipseConfig {
+    /// Creates a new superellipse config.
+    ///
+    /// Returns an error if `rx` or `ry` is not a positive finite number, `exponent` is not a
+    /// finite number of at least 2, `pattern_count` < 4 or > 100 000, `border_margin` < 0,
+    /// `stroke_width` is not a positive finite number, or the band is too deep for the tightest
+    /// bend of the curve.
+    pub fn new(
+        rx: f64,
+        ry: f64,
+        exponent: f64,
+        pattern_count: i32,
+        border_margin: i32,
+        stroke_width: f32,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if rx <= 0.0 || !rx.is_finite() {
+            return Err("--rx must be a positive finite number".into());
+        }
+        if ry <= 0.0 || !ry.is_finite() {
+            return Err("--ry must be a positive finite number".into());
+        }
+        if exponent < 2.0 || !exponent.is_finite() {
+            return Err("--exponent must be a finite number of at least 2".into());
+        }
+        check_pattern_count(pattern_count)?;
+        if border_margin < 0 {
+            return Err("--border-margin must be non-negative".into());
+        }
+        if stroke_width <= 0.0 || !stroke_width.is_finite() {
+            return Err("--stroke-width must be a positive finite number".into());
+        }
+        let centre = Point {
+            x: border_margin as f64 + rx + stroke_width as f64,
+            y: border_margin as f64 + ry + stroke_width as f64,
+        };
+        let outline = sample_superellipse(centre, rx, ry, exponent);
+        // Offsetting a convex curve inwards by d shortens it by 2πd, so choosing
+        // the spacing this way makes the middle rail's key units square, exactly
+        // as `get_radii_for_outer_radius` does for circles.
+        let rail_spacing =
+            outline.length() / ((PATTERN_UNIT_SIZE * pattern_count) as f64 + 6.0 * PI);
+        if 6.0 * rail_spacing >= outline.min_radius_of_curvature() {
+            return Err(
+                "--pattern-count is too low for the corners of this superellipse; \
+                 increase it or lower --exponent"
+                    .into(),
+            );
+        }
+        Ok(Self {
+            rx,
+            ry,
+            exponent,
+            pattern_count,
+            border_margin,
+            stroke_width,
+            rail_spacing,
+            outline,
+        })
+    }
+
+    /// Returns the `(width, height)` of the canvas in pixels.
+    pub fn get_canvas_size(&self) -> (f64, f64) {
+        let width =
+            2.0 * self.rx + (2 * self.border_margin) as f64 + (2.0 * self.stroke_width) as f64;
+        let height =
+            2.0 * self.ry + (2 * self.border_margin) as f64 + (2.0 * self.stroke_width) as f64;
+        (width, height)
+    }
+
+    /// Returns the point `depth` rails inside the outer edge, `step` key
+    /// sub-units along the curve from the top.
+    pub(crate) fn rail_point(&self, depth: i32, step: i32) -> Point {
+        let unit = self.outline.length() / (PATTERN_UNIT_SIZE * self.pattern_count) as f64;
+        self.outline
+            .offset_point(step as f64 * unit, depth as f64 * self.rail_spacing)
+    }
+
+    /// Returns a closed polyline following the curve `depth` rails inside the outer edge.
+    pub(crate) fn rail_outline(&self, depth: i32) -> Vec<Point> {
+        let steps = SUPERELLIPSE_SAMPLES / 4;
+        let step = self.outline.length() / steps as f64;
+        (0..steps)
+            .map(|i| {
+                self.outline
+                    .offset_point(i as f64 * step, depth as f64 * self.rail_spacing)
+            })
+            .collect()
+    }
+}
+
+// Samples the superellipse clockwise (in SVG coordinates) starting from the top.
+fn sample_superellipse(centre: Point, rx: f64, ry: f64, exponent: f64) -> SampledCurve {
+    let power = 2.0 / exponent;
+    let points = (0..SUPERELLIPSE_SAMPLES)
+        .map(|i| {
+            let angle = -PI / 2.0 + 2.0 * PI * i as f64 / SUPERELLIPSE_SAMPLES as f64;
+            let (sin, cos) = angle.sin_cos();
+            Point {
+                x: centre.x + rx * cos.signum() * cos.abs().powf(power),
+                y: centre.y + ry * sin.signum() * sin.abs().powf(power),
+            }
+        })
+        .collect();
+    SampledCurve::new(points, true)
+}
+
+/// The curve family followed by a spiral border.
+#[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "native", serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpiralKind {
+    /// Radius changes linearly with angle, so the turns are evenly spaced.
+    Archimedean,
+    /// Radius changes geometrically with angle, so the turns widen outwards.
+    Logarithmic,
+}
+
+impl SpiralKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Archimedean => "archimedean",
+            Self::Logarithmic => "logarithmic",
+        }
+    }
+}
+
+impl std::str::FromStr for SpiralKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "archimedean" => Ok(Self::Archimedean),
+            "logarithmic" => Ok(Self::Logarithmic),
+            _ => Err(format!(
+                "unknown spiral kind '{s}'; expected 'archimedean' or 'logarithmic'"
+            )),
+        }
+    }
+}
+
+/// Samples per full turn used to approximate the spiral centreline.
+const SPIRAL_SAMPLES_PER_TURN: f64 = 720.0;
+
+/// Most turns a spiral may make, which bounds the centreline samples.
+const MAX_SPIRAL_TURNS: f64 = 1000.0;
+
+/// Most keys a spiral band may hold.
+const MAX_SPIRAL_KEYS: f64 = 100_000.0;
+
+/// Configuration for a spiral Greek Key band.
+///
+/// The band winds clockwise from `start_radius` at the top of the canvas to
+/// `end_radius` over `turns` full turns. Unlike the circle pattern, keys keep
+/// the same size along the whole path: `key_unit_length` is measured along
+/// the centreline, and the rails and frames are offset from it along the normal.
+#[derive(Debug)]
+pub struct GreekKeySpiralConfig {
+    pub kind: SpiralKind,
+    pub turns: f64,
+    pub start_radius: f64,
+    pub end_radius: f64,
+    pub key_unit_length: f64,
+    pub border_margin: i32,
+    pub stroke_width: f32,
+    pub(crate) centreline: SampledCurve,
+}
+
+impl GreekKeySpiralConfig {
+    /// Creates a new spiral config.
+    ///
+    /// Returns an error if `turns`, either radius or `key_unit_length` is not a positive finite
+    /// number, `turns` > 1000, `border_margin` < 0, `stroke_width` is not a positive finite
+    /// number, the band would reach the centre or overlap the neighbouring turn, or the path
+    /// is shorter than one key or longer than 100 000 keys.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        kind: SpiralKind,
+        turns: f64,
+        start_radius: f64,
+        end_radius: f64,
+        key_unit_length: f64,
+        border_margin: i32,
+        stroke_width: f32,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if turns <= 0.0 || !turns.is_finite() {
+            return Err("--turns must be a positive finite number".into());
+        }
+        if turns > MAX_SPIRAL_TURNS {
+            return Err(format!("--turns must be at most {MAX_SPIRAL_TURNS}").into());
+        }
+        if start_radius <= 0.0 || !start_radius.is_finite() {
+            return Err("--start-radius must be a positive finite number".into());
+        }
+        if end_radius <= 0.0 || !end_radius.is_finite() {
+            return Err("--end-radius must be a positive finite number".into());
+        }
+        if key_unit_length <= 0.0 || !key_unit_length.is_finite() {
+            return Err("--size must be a positive finite number".into());
+        }
+        if border_margin < 0 {
+            return Err("--border-margin must be non-negative".into());
+        }
+        if stroke_width <= 0.0 || !stroke_width.is_finite() {
+            return Err("--stroke-width must be a positive finite number".into());
+        }
+        let half_band = 3.0 * key_unit_length;
+        let (r_min, r_max) = if start_radius < end_radius {
+            (start_radius, end_radius)
+        } else {
+            (end_radius, start_radius)
+        };
+        if r_min <= half_band {
+            return Err("--size is too large for the inner radius of the spiral".into());
+        }
+        if turns > 1.0 {
+            // Narrowest radial gap between neighbouring turns, found at the inner end.
+            let gap = match kind {
+                SpiralKind::Archimedean => (r_max - r_min) / turns,
+                SpiralKind::Logarithmic => r_min * ((r_max / r_min).powf(1.0 / turns) - 1.0),
+            };
+            if gap < 2.0 * half_band {
+                return Err("--size is too large for the spacing between turns; \
+                     lower it, reduce --turns or spread the radii"
+                    .into());
+            }
+        }
+        let extent = r_max + half_band + border_margin as f64 + stroke_width as f64;
+        let centre = Point {
+            x: extent,
+            y: extent,
+        };
+        let centreline = sample_spiral(centre, kind, turns, start_radius, end_radius);
+        if centreline.length() < PATTERN_UNIT_SIZE as f64 * key_unit_length {
+            return Err("--size is too large to fit a single key along the spiral".into());
+        }
+        if centreline.length() > MAX_SPIRAL_KEYS * PATTERN_UNIT_SIZE as f64 * key_unit_length {
+            return Err(format!(
+                "--size is too small for the length of the spiral; \
+                 it would need more than {MAX_SPIRAL_KEYS} keys"
+            )
+            .into());
+        }
+        Ok(Self {
+            kind,
+            turns,
+            start_radius,
+            end_radius,
+            key_unit_length,
+            border_margin,
+            stroke_width,
+            centreline,
+        })
+    }
+
+    /// Returns the `(width, height)` of the canvas in pixels.
+    pub fn get_canvas_size(&self) -> (f64, f64) {
+        let r_max = self.start_radius.max(self.end_radius);
+        let size = 2.0
+            * (r_max
+                + 3.0 * self.key_unit_length
+                + self.border_margin as f64
+                + self.stroke_width as f64);
+        (size, size)
+    }
+
+    /// Returns the number of whole keys that fit along the centreline.
+    pub(crate) fn key_count(&self) -> i32 {
+        (self.centreline.length() / (PATTERN_UNIT_SIZE as f64 * self.key_unit_length)) as i32
+    }
+
+    /// Returns the arc length left over at each end once the keys are placed.
+    pub(crate) fn end_margin(&self) -> f64 {
+        let keys_length = self.key_count() as f64 * PATTERN_UNIT_SIZE as f64 * self.key_unit_length;
+        (self.centreline.length() - keys_length) / 2.0
+    }
+
+    pub(crate) fn length(&self) -> f64 {
+        self.centreline.length()
+    }
+
+    /// Returns the point at arc length `s`, moved `offset` key units towards the centre.
+    pub(crate) fn offset_point(&self, s: f64, offset: f64) -> Point {
+        self.centreline
+            .offset_point(s, offset * self.key_unit_length)
+    }
+
+    /// Returns a polyline following the centreline `offset` key units towards the centre.
+    pub(crate) fn rail(&self, offset: f64) -> Vec<Point> {
+        let steps = (self.turns * SPIRAL_SAMPLES_PER_TURN).ceil() as usize;
+        let step = self.centreline.length() / steps as f64;
+        (0..=steps)
+            .map(|i| self.offset_point(i as f64 * step, offset))
+            .collect()
+    }
+}
+
+// Samples the spiral clockwise (in SVG coordinates) starting from the top.
+fn sample_spiral(
+    centre: Point,
+    kind: SpiralKind,
+    turns: f64,
+    start_radius: f64,
+    end_radius: f64,
+) -> SampledCurve {
+    let samples = (turns * SPIRAL_SAMPLES_PER_TURN).ceil() as usize;
+    let points = (0..=samples)
+        .map(|i| {
+            let t = i as f64 / samples as f64;
+            let r = match kind {
+                SpiralKind::Archimedean => start_radius + (end_radius - start_radius) * t,
+                SpiralKind::Logarithmic => start_radius * (end_radius / start_radius).powf(t),
+            };
+            let angle = -PI / 2.0 + 2.0 * PI * turns * t;
+            Point {
+                x: centre.x + r * angle.cos(),
+                y: centre.y + r * angle.sin(),
+            }
+        })
+        .collect();
+    SampledCurve::new(points, false)
+}
+
 impl GreekKeyCircleConfig {
     /// Creates a new circle config.
     ///
-    /// Returns an error if `r_o` is not a positive finite number, `pattern_count` < 4,
-    /// `border_margin` < 0,
+    /// Returns an error if `r_o` is not a positive finite number, `pattern_count` < 4
+    /// or > 100 000, `border_margin` < 0,
     /// or `stroke_width` is not a positive finite number.
     pub fn new(
         r_o: f64,
@@ -406,9 +1400,7 @@ impl GreekKeyCircleConfig {
         if r_o <= 0.0 || !r_o.is_finite() {
             return Err("--radius must be a positive finite number".into());
         }
-        if pattern_count < 4 {
-            return Err("--pattern-count must be at least 4".into());
-        }
+        check_pattern_count(pattern_count)?;
         if border_margin < 0 {
             return Err("--border-margin must be non-negative".into());
         }
@@ -425,7 +1417,8 @@ impl GreekKeyCircleConfig {
         })
     }
 
-    pub(crate) fn get_canvas_size(&self) -> (f64, f64) {
+    /// Returns the `(width, height)` of the canvas in pixels.
+    pub fn get_canvas_size(&self) -> (f64, f64) {
         let offset =
             2. * self.r_o + (2 * self.border_margin) as f64 + (2.0 * self.stroke_width) as f64;
         (offset, offset)
@@ -475,6 +1468,8 @@ impl GreekKeyCircleConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "native")]
+    use crate::text::TextRail;
 
     // --- GreekKeyRectConfig validation ---
 
@@ -512,6 +1507,12 @@ mod tests {
         assert!(e.to_string().contains("--height"));
     }
 
+    #[test]
+    fn rect_too_large_to_draw_fails() {
+        let e = GreekKeyRectConfig::new(60_000, 60_000, 9, 10, 3.0).unwrap_err();
+        assert!(e.to_string().contains("too large to draw"));
+    }
+
     #[test]
     fn rect_negative_margin_fails() {
         let e = GreekKeyRectConfig::new(25, 16, 9, -1, 3.0).unwrap_err();
@@ -696,15 +1697,356 @@ mod tests {
         assert!(er.rx_e < config.rx && er.ry_e < config.ry);
     }
 
+    // --- GreekKeySuperellipseConfig validation ---
+
+    #[test]
+    fn superellipse_valid() {
+        assert!(GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 30, 10, 3.0).is_ok());
+    }
+
+    #[test]
+    fn superellipse_exponent_two_valid() {
+        assert!(GreekKeySuperellipseConfig::new(200.0, 200.0, 2.0, 20, 5, 2.0).is_ok());
+    }
+
+    #[test]
+    fn superellipse_exponent_below_two_fails() {
+        let e = GreekKeySuperellipseConfig::new(300.0, 200.0, 1.5, 30, 10, 3.0).unwrap_err();
+        assert!(e.to_string().contains("--exponent"));
+    }
+
+    #[test]
+    fn superellipse_nan_exponent_fails() {
+        let e = GreekKeySuperellipseConfig::new(300.0, 200.0, f64::NAN, 30, 10, 3.0).unwrap_err();
+        assert!(e.to_string().contains("--exponent"));
+    }
+
+    #[test]
+    fn superellipse_zero_rx_fails() {
+        let e = GreekKeySuperellipseConfig::new(0.0, 200.0, 4.0, 30, 10, 3.0).unwrap_err();
+        assert!(e.to_string().contains("--rx"));
+    }
+
+    #[test]
+    fn superellipse_infinite_ry_fails() {
+        let e =
+            GreekKeySuperellipseConfig::new(300.0, f64::INFINITY, 4.0, 30, 10, 3.0).unwrap_err();
+        assert!(e.to_string().contains("--ry"));
+    }
+
+    #[test]
+    fn superellipse_pattern_count_below_minimum_fails() {
+        let e = GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 3, 10, 3.0).unwrap_err();
+        assert!(e.to_string().contains("--pattern-count"));
+    }
+
+    #[test]
+    fn superellipse_pattern_count_above_maximum_fails() {
+        let e = GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, i32::MAX, 10, 3.0).unwrap_err();
+        assert_eq!(e.to_string(), "--pattern-count must be at most 100000");
+    }
+
+    #[test]
+    fn superellipse_band_too_deep_for_corners_fails() {
+        let e = GreekKeySuperellipseConfig::new(300.0, 200.0, 12.0, 30, 10, 3.0).unwrap_err();
+        assert!(e.to_string().contains("--pattern-count"));
+    }
+
+    #[test]
+    fn superellipse_negative_margin_fails() {
+        let e = GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 30, -1, 3.0).unwrap_err();
+        assert!(e.to_string().contains("--border-margin"));
+    }
+
+    #[test]
+    fn superellipse_zero_stroke_width_fails() {
+        let e = GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 30, 10, 0.0).unwrap_err();
+        assert!(e.to_string().contains("--stroke-width"));
+    }
+
+    #[test]
+    fn superellipse_canvas_size() {
+        let config = GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 30, 10, 3.0).unwrap();
+        let (w, h) = config.get_canvas_size();
+        assert!((w - (2.0 * 300.0 + 20.0 + 6.0)).abs() < 1e-6);
+        assert!((h - (2.0 * 200.0 + 20.0 + 6.0)).abs() < 1e-6);
+    }
+
+    // --- GreekKeySpiralConfig validation ---
+
+    #[test]
+    fn spiral_valid() {
+        assert!(
+            GreekKeySpiralConfig::new(SpiralKind::Archimedean, 3.0, 300.0, 60.0, 8.0, 10, 3.0)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn spiral_outward_valid() {
+        assert!(
+            GreekKeySpiralConfig::new(SpiralKind::Logarithmic, 2.0, 40.0, 300.0, 6.0, 10, 3.0)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn spiral_kind_parses() {
+        assert_eq!("archimedean".parse(), Ok(SpiralKind::Archimedean));
+        assert_eq!("logarithmic".parse(), Ok(SpiralKind::Logarithmic));
+        assert!("golden".parse::<SpiralKind>().is_err());
+    }
+
+    #[test]
+    fn spiral_zero_turns_fails() {
+        let e = GreekKeySpiralConfig::new(SpiralKind::Archimedean, 0.0, 300.0, 60.0, 8.0, 10, 3.0)
+            .unwrap_err();
+        assert!(e.to_string().contains("--turns"));
+    }
+
+    #[test]
+    fn spiral_too_many_turns_fails() {
+        let e = GreekKeySpiralConfig::new(SpiralKind::Archimedean, 1e9, 1e12, 60.0, 8.0, 10, 3.0)
+            .unwrap_err();
+        assert!(e.to_string().contains("--turns must be at most 1000"));
+    }
+
+    #[test]
+    fn spiral_with_too_many_keys_fails() {
+        let e = GreekKeySpiralConfig::new(SpiralKind::Archimedean, 1.0, 1e7, 60.0, 8.0, 10, 3.0)
+            .unwrap_err();
+        assert!(e.to_string().contains("100000 keys"));
+    }
+
+    #[test]
+    fn spiral_zero_start_radius_fails() {
+        let e = GreekKeySpiralConfig::new(SpiralKind::Archimedean, 3.0, 0.0, 60.0, 8.0, 10, 3.0)
+            .unwrap_err();
+        assert!(e.to_string().contains("--start-radius"));
+    }
+
+    #[test]
+    fn spiral_nan_end_radius_fails() {
+        let e =
+            GreekKeySpiralConfig::new(SpiralKind::Archimedean, 3.0, 300.0, f64::NAN, 8.0, 10, 3.0)
+                .unwrap_err();
+        assert!(e.to_string().contains("--end-radius"));
+    }
+
+    #[test]
+    fn spiral_zero_size_fails() {
+        let e = GreekKeySpiralConfig::new(SpiralKind::Archimedean, 3.0, 300.0, 60.0, 0.0, 10, 3.0)
+            .unwrap_err();
+        assert!(e.to_string().contains("--size"));
+    }
+
+    #[test]
+    fn spiral_band_reaching_centre_fails() {
+        let e = GreekKeySpiralConfig::new(SpiralKind::Archimedean, 1.0, 300.0, 20.0, 8.0, 10, 3.0)
+            .unwrap_err();
+        assert!(e.to_string().contains("inner radius"));
+    }
+
+    #[test]
+    fn spiral_overlapping_turns_fail() {
+        let e = GreekKeySpiralConfig::new(SpiralKind::Archimedean, 6.0, 300.0, 60.0, 8.0, 10, 3.0)
+            .unwrap_err();
+        assert!(e.to_string().contains("between turns"));
+    }
+
+    #[test]
+    fn spiral_negative_margin_fails() {
+        let e = GreekKeySpiralConfig::new(SpiralKind::Archimedean, 3.0, 300.0, 60.0, 8.0, -1, 3.0)
+            .unwrap_err();
+        assert!(e.to_string().contains("--border-margin"));
+    }
+
+    #[test]
+    fn spiral_canvas_size() {
+        let config =
+            GreekKeySpiralConfig::new(SpiralKind::Archimedean, 3.0, 300.0, 60.0, 8.0, 10, 3.0)
+                .unwrap();
+        let (w, h) = config.get_canvas_size();
+        assert!((w - 2.0 * (300.0 + 24.0 + 10.0 + 3.0)).abs() < 1e-6);
+        assert_eq!(w, h);
+    }
+
+    // --- GreekKeyFieldConfig validation ---
+
+    #[test]
+    fn field_valid() {
+        assert!(GreekKeyFieldConfig::new(400.0, 300.0, 10.0, 5, 2.0).is_ok());
+    }
+
+    #[test]
+    fn field_zero_width_fails() {
+        let e = GreekKeyFieldConfig::new(0.0, 300.0, 10.0, 5, 2.0).unwrap_err();
+        assert!(e.to_string().contains("--width"));
+    }
+
+    #[test]
+    fn field_nan_height_fails() {
+        let e = GreekKeyFieldConfig::new(400.0, f64::NAN, 10.0, 5, 2.0).unwrap_err();
+        assert!(e.to_string().contains("--height"));
+    }
+
+    #[test]
+    fn field_zero_size_fails() {
+        let e = GreekKeyFieldConfig::new(400.0, 300.0, 0.0, 5, 2.0).unwrap_err();
+        assert!(e.to_string().contains("--size"));
+    }
+
+    #[test]
+    fn field_with_too_many_keys_fails() {
+        let e = GreekKeyFieldConfig::new(1e5, 1e5, 1.0, 5, 2.0).unwrap_err();
+        assert!(e.to_string().contains("--size is too small"));
+    }
+
+    #[test]
+    fn field_spacing_too_small_for_canvas_fails() {
+        let visual = VisualOptions {
+            field_spacing: Some(0.01),
+            ..VisualOptions::default()
+        };
+        let e = visual.validate_canvas(400.0, 300.0).unwrap_err();
+        assert!(e.to_string().contains("--field-spacing is too small"));
+        assert!(VisualOptions::default().validate_canvas(1e9, 1e9).is_ok());
+    }
+
+    #[test]
+    fn field_canvas_size() {
+        let config = GreekKeyFieldConfig::new(400.0, 300.0, 10.0, 5, 2.0).unwrap();
+        assert_eq!(config.get_canvas_size(), (414.0, 314.0));
+    }
+
+    // --- VisualOptions validation ---
+
+    #[test]
+    fn default_visual_options_are_valid() {
+        assert!(VisualOptions::default().validate().is_ok());
+    }
+
+    #[test]
+    fn non_positive_field_spacing_fails() {
+        for spacing in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let visual = VisualOptions {
+                field_spacing: Some(spacing),
+                ..VisualOptions::default()
+            };
+            let e = visual.validate().unwrap_err();
+            assert!(e.to_string().contains("--field-spacing"));
+        }
+    }
+
+    #[test]
+    fn non_positive_arc_tolerance_fails() {
+        for tolerance in [0.0, -0.5, f64::NAN, f64::INFINITY] {
+            let visual = VisualOptions {
+                arc_tolerance: Some(tolerance),
+                ..VisualOptions::default()
+            };
+            let e = visual.validate().unwrap_err();
+            assert!(e.to_string().contains("--arc-tolerance"));
+        }
+    }
+
+    #[test]
+    fn invalid_inlay_fails() {
+        for (inlay, flag) in [
+            (Inlay::new(0.0, 2.0), "--inlay-gap"),
+            (Inlay::new(f64::NAN, 2.0), "--inlay-gap"),
+            (Inlay::new(4.0, -1.0), "--inlay-line-width"),
+            (
+                Inlay {
+                    inlay_color: Some("".into()),
+                    ..Inlay::new(4.0, 2.0)
+                },
+                "--inlay-color",
+            ),
+        ] {
+            let visual = VisualOptions {
+                inlay: Some(inlay),
+                ..VisualOptions::default()
+            };
+            let e = visual.validate().unwrap_err();
+            assert!(e.to_string().contains(flag), "{e}");
+        }
+    }
+
+    #[test]
+    fn miter_limit_below_one_fails() {
+        for limit in [0.5, -1.0, f64::NAN, f64::INFINITY] {
+            let visual = VisualOptions {
+                miter_limit: Some(limit),
+                ..VisualOptions::default()
+            };
+            let e = visual.validate().unwrap_err();
+            assert!(e.to_string().contains("--miter-limit"));
+        }
+    }
+
+    #[test]
+    fn line_join_and_cap_parse_from_str() {
+        assert_eq!("bevel".parse::<LineJoin>(), Ok(LineJoin::Bevel));
+        assert_eq!("round".parse::<LineCap>(), Ok(LineCap::Round));
+        assert!("square".parse::<LineJoin>().is_err());
+    }
+
+    #[test]
+    fn inlay_width_spans_both_lines() {
+        assert_eq!(Inlay::new(4.0, 1.5).width(), 7.0);
+    }
+
     #[cfg(feature = "native")]
     #[test]
     fn visual_options_round_trip_through_toml() {
         let visual = VisualOptions {
-            stroke_color: "#123456".to_string(),
+            stroke_color: "#123456".into(),
             stroke_opacity: 0.5,
-            fill_color: Some("#AABBCC".to_string()),
-            background_color: Some("#001122".to_string()),
+            fill_color: Some("#AABBCC".into()),
+            background_color: Some("#001122".into()),
             stroke_dash: Some("4,2".to_string()),
+            field_spacing: Some(12.5),
+            arc_tolerance: Some(0.25),
+            precision: Some(3),
+            shared_styles: true,
+            relative_paths: true,
+            minify: false,
+            id_prefix: Some("border".to_string()),
+            pattern_style: ElementStyle::default(),
+            inner_frame_style: ElementStyle {
+                stroke_color: Some("#445566".into()),
+                stroke_opacity: Some(1.0),
+                hidden: false,
+            },
+            outer_frame_style: ElementStyle {
+                hidden: true,
+                ..ElementStyle::default()
+            },
+            key_colors: vec!["#A3341F".into(), "#111111".into()],
+            inlay: Some(Inlay {
+                inlay_color: Some("#A3341F".into()),
+                ..Inlay::new(4.0, 1.5)
+            }),
+            line_join: Some(LineJoin::Bevel),
+            line_cap: Some(LineCap::Square),
+            miter_limit: Some(2.5),
+            non_scaling_stroke: true,
+            effects: vec![Effect::Shadow, Effect::InnerGlow],
+            text: Some(Text {
+                title: vec!["Certificate".to_string(), "of Merit".to_string()],
+                ring_text: Some("ΑΡΕΤΗ".to_string()),
+                rail: TextRail::Outer,
+                font_family: Some("Cinzel, serif".to_string()),
+                font_size: Some(18.0),
+                color: Some("#222222".into()),
+                font_data: None,
+            }),
+            metadata: Metadata {
+                title: Some("Greek key border".to_string()),
+                license: Some("https://creativecommons.org/licenses/by/4.0/".to_string()),
+                ..Metadata::default()
+            },
         };
 
         let toml = toml::to_string(&visual).unwrap();
@@ -715,5 +2057,22 @@ mod tests {
         assert_eq!(parsed.fill_color, visual.fill_color);
         assert_eq!(parsed.background_color, visual.background_color);
         assert_eq!(parsed.stroke_dash, visual.stroke_dash);
+        assert_eq!(parsed.field_spacing, visual.field_spacing);
+        assert_eq!(parsed.arc_tolerance, visual.arc_tolerance);
+        assert_eq!(parsed.precision, visual.precision);
+        assert_eq!(parsed.shared_styles, visual.shared_styles);
+        assert_eq!(parsed.relative_paths, visual.relative_paths);
+        assert_eq!(parsed.id_prefix, visual.id_prefix);
+        assert_eq!(parsed.minify, visual.minify);
+        assert_eq!(parsed.pattern_style, visual.pattern_style);
+        assert_eq!(parsed.inner_frame_style, visual.inner_frame_style);
+        assert_eq!(parsed.outer_frame_style, visual.outer_frame_style);
+        assert_eq!(parsed.key_colors, visual.key_colors);
+        assert_eq!(parsed.inlay, visual.inlay);
+        assert_eq!(parsed.line_join, visual.line_join);
+        assert_eq!(parsed.line_cap, visual.line_cap);
+        assert_eq!(parsed.miter_limit, visual.miter_limit);
+        assert_eq!(parsed.non_scaling_stroke, visual.non_scaling_stroke);
+        assert_eq!(parsed.effects, visual.effects);
     }
 }
@@ -0,0 +1,294 @@
+use std::str::FromStr;
+
+use svg::Document;
+use svg::node::Node;
+use svg::node::element::{
+    Definitions, Element, Filter, FilterEffectComposite, FilterEffectDistantLight,
+    FilterEffectFlood, FilterEffectGaussianBlur, FilterEffectMerge, FilterEffectMergeNode,
+    FilterEffectOffset, FilterEffectSpecularLighting,
+};
+
+use crate::common::has_stroke;
+use crate::config::VisualOptions;
+
+/// Element id of the filter combining every requested effect.
+pub(crate) const EFFECTS_ID: &str = "gm-effects";
+
+// Drop shadow: blur radius, offset down and to the right, and opacity.
+const SHADOW_BLUR: f64 = 2.5;
+const SHADOW_OFFSET: f64 = 3.0;
+const SHADOW_OPACITY: f64 = 0.5;
+// Bevel lighting: blur of the height map, its height, and the light's elevation.
+const BEVEL_BLUR: f64 = 1.5;
+const BEVEL_SURFACE_SCALE: f64 = 4.0;
+const BEVEL_ELEVATION: f64 = 40.0;
+// Inner glow: how far the glow reaches in from the edges, and its opacity.
+const GLOW_BLUR: f64 = 2.0;
+const GLOW_OPACITY: f64 = 0.7;
+
+/// A filter effect applied to the whole border.
+///
+/// Effects parse from the names `shadow`, `emboss`, `engrave` and
+/// `inner-glow`, which is also how they appear on the command line, in TOML
+/// config files and in the WASM exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// A soft drop shadow below and to the right, as if the border were raised
+    /// off the page.
+    Shadow,
+    /// Bevelled edges lit from the top left, so the lines look raised.
+    Emboss,
+    /// Bevelled edges lit from the bottom right, so the lines look carved in.
+    Engrave,
+    /// A pale glow along the inside edges of every line.
+    InnerGlow,
+}
+
+impl Effect {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Effect::Shadow => "shadow",
+            Effect::Emboss => "emboss",
+            Effect::Engrave => "engrave",
+            Effect::InnerGlow => "inner-glow",
+        }
+    }
+}
+
+impl FromStr for Effect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "shadow" => Ok(Effect::Shadow),
+            "emboss" => Ok(Effect::Emboss),
+            "engrave" => Ok(Effect::Engrave),
+            "inner-glow" => Ok(Effect::InnerGlow),
+            _ => Err(format!(
+                "unknown effect '{s}'; expected 'shadow', 'emboss', 'engrave' or 'inner-glow'"
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+impl serde::Serialize for Effect {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "native")]
+impl<'de> serde::Deserialize<'de> for Effect {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Checks that the requested effects can be combined.
+pub(crate) fn validate(effects: &[Effect]) -> Result<(), Box<dyn std::error::Error>> {
+    if effects.contains(&Effect::Emboss) && effects.contains(&Effect::Engrave) {
+        return Err("--effect emboss and engrave cannot be combined".into());
+    }
+    Ok(())
+}
+
+// Adds the primitives for `effect` to `filter`, reading the image so far from
+// `input`, and returns the name of the result.
+fn add_primitives(filter: Filter, effect: Effect, input: &str) -> (Filter, String) {
+    let result = format!("gm-{}", effect.name());
+    let filter = match effect {
+        Effect::Shadow => filter
+            .add(
+                FilterEffectGaussianBlur::new()
+                    .set("in", "SourceAlpha")
+                    .set("stdDeviation", SHADOW_BLUR),
+            )
+            .add(
+                FilterEffectOffset::new()
+                    .set("dx", SHADOW_OFFSET)
+                    .set("dy", SHADOW_OFFSET)
+                    .set("result", "gm-shadow-offset"),
+            )
+            .add(
+                FilterEffectFlood::new()
+                    .set("flood-color", "#000")
+                    .set("flood-opacity", SHADOW_OPACITY),
+            )
+            .add(
+                FilterEffectComposite::new()
+                    .set("in2", "gm-shadow-offset")
+                    .set("operator", "in")
+                    .set("result", "gm-shadow-color"),
+            )
+            .add(
+                FilterEffectMerge::new()
+                    .add(FilterEffectMergeNode::new().set("in", "gm-shadow-color"))
+                    .add(FilterEffectMergeNode::new().set("in", input))
+                    .set("result", result.as_str()),
+            ),
+        Effect::Emboss | Effect::Engrave => {
+            let azimuth = if effect == Effect::Emboss { 225 } else { 45 };
+            filter
+                .add(
+                    FilterEffectGaussianBlur::new()
+                        .set("in", "SourceAlpha")
+                        .set("stdDeviation", BEVEL_BLUR),
+                )
+                .add(
+                    FilterEffectSpecularLighting::new()
+                        .set("surfaceScale", BEVEL_SURFACE_SCALE)
+                        .set("specularConstant", 0.8)
+                        .set("specularExponent", 18)
+                        .set("lighting-color", "#FFF")
+                        .add(
+                            FilterEffectDistantLight::new()
+                                .set("azimuth", azimuth)
+                                .set("elevation", BEVEL_ELEVATION),
+                        ),
+                )
+                .add(
+                    FilterEffectComposite::new()
+                        .set("in2", "SourceAlpha")
+                        .set("operator", "in")
+                        .set("result", "gm-bevel-light"),
+                )
+                .add(
+                    FilterEffectComposite::new()
+                        .set("in", input)
+                        .set("in2", "gm-bevel-light")
+                        .set("operator", "arithmetic")
+                        .set("k2", 1)
+                        .set("k3", 1)
+                        .set("result", result.as_str()),
+                )
+        }
+        Effect::InnerGlow => filter
+            .add(
+                FilterEffectGaussianBlur::new()
+                    .set("in", "SourceAlpha")
+                    .set("stdDeviation", GLOW_BLUR)
+                    .set("result", "gm-glow-blur"),
+            )
+            // Opaque where the line is but its blurred copy has faded: the
+            // inside edges.
+            .add(
+                FilterEffectComposite::new()
+                    .set("in", "SourceAlpha")
+                    .set("in2", "gm-glow-blur")
+                    .set("operator", "arithmetic")
+                    .set("k2", 1)
+                    .set("k3", -1)
+                    .set("result", "gm-glow-edges"),
+            )
+            .add(
+                FilterEffectFlood::new()
+                    .set("flood-color", "#FFF")
+                    .set("flood-opacity", GLOW_OPACITY),
+            )
+            .add(
+                FilterEffectComposite::new()
+                    .set("in2", "gm-glow-edges")
+                    .set("operator", "in")
+                    .set("result", "gm-glow-color"),
+            )
+            .add(
+                FilterEffectMerge::new()
+                    .add(FilterEffectMergeNode::new().set("in", input))
+                    .add(FilterEffectMergeNode::new().set("in", "gm-glow-color"))
+                    .set("result", result.as_str()),
+            ),
+    };
+    (filter, result)
+}
+
+/// Returns one filter applying `effects`, in a fixed order so the result does
+/// not depend on how they were listed: bevel, then glow, then shadow.
+fn effects_filter(effects: &[Effect]) -> Filter {
+    let mut filter = Filter::new()
+        .set("id", EFFECTS_ID)
+        .set("color-interpolation-filters", "sRGB");
+    let mut input = "SourceGraphic".to_string();
+    for effect in [
+        Effect::Emboss,
+        Effect::Engrave,
+        Effect::InnerGlow,
+        Effect::Shadow,
+    ] {
+        if effects.contains(&effect) {
+            (filter, input) = add_primitives(filter, effect, &input);
+        }
+    }
+    filter
+}
+
+/// Wraps the drawn border of `document` in a group filtered by
+/// `visual.effects`, leaving the background outside it.
+///
+/// The border is everything from the first element with a `stroke` attribute
+/// onwards.
+pub(crate) fn apply_effects(document: &mut Document, visual: &VisualOptions) {
+    if visual.effects.is_empty() {
+        return;
+    }
+    let Some(children) = Node::get_children_mut(document) else {
+        return;
+    };
+    let Some(first) = children.iter().position(|child| has_stroke(child.as_ref())) else {
+        return;
+    };
+    let mut group = Element::new("g");
+    group.assign("filter", format!("url(#{EFFECTS_ID})"));
+    group.get_children_mut().extend(children.drain(first..));
+    children.push(Box::new(group));
+    children.insert(
+        0,
+        Box::new(Definitions::new().add(effects_filter(&visual.effects))),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use svg::node::element::{Path, Rectangle};
+
+    #[test]
+    fn effect_names_round_trip() {
+        for effect in [
+            Effect::Shadow,
+            Effect::Emboss,
+            Effect::Engrave,
+            Effect::InnerGlow,
+        ] {
+            assert_eq!(effect.name().parse::<Effect>(), Ok(effect));
+        }
+        assert!("glow".parse::<Effect>().is_err());
+    }
+
+    #[test]
+    fn border_is_grouped_under_the_filter_and_background_is_not() {
+        let mut document = Document::new()
+            .add(Rectangle::new().set("fill", "#000"))
+            .add(Path::new().set("stroke", "#FFF"))
+            .add(Path::new().set("stroke", "#FFF"));
+        let visual = VisualOptions {
+            effects: vec![Effect::Shadow, Effect::Emboss],
+            ..VisualOptions::default()
+        };
+        apply_effects(&mut document, &visual);
+        let svg = document.to_string();
+        let group = svg.find(r#"<g filter="url(#gm-effects)">"#).unwrap();
+        assert!(svg.find("<rect").unwrap() < group);
+        assert_eq!(svg[group..].matches("<path").count(), 2);
+        assert!(svg.contains("feSpecularLighting"));
+        assert!(svg.contains("feOffset"));
+        assert!(!svg.contains("gm-inner-glow"));
+    }
+
+    #[test]
+    fn emboss_and_engrave_are_exclusive() {
+        assert!(validate(&[Effect::Emboss, Effect::Engrave]).is_err());
+        assert!(validate(&[Effect::Engrave, Effect::Shadow]).is_ok());
+    }
+}
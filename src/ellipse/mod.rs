@@ -1,40 +1,83 @@
 use svg::Document;
 use svg::node::element::path::Data;
-use svg::node::element::{Ellipse as SvgEllipse, Path as SvgPath, Rectangle};
+use svg::node::element::{Ellipse as SvgEllipse, Rectangle};
 
 #[cfg(feature = "native")]
 use crate::common::save_and_convert_svg;
-use crate::config::{GreekKeyEllipseConfig, VisualOptions};
-
-fn draw_greek_key_patterns(config: &GreekKeyEllipseConfig) -> Data {
-    let (mut points_a, mut points_b, mut points_c, mut points_d, mut points_e) =
-        config.get_coords_for_patterns();
-
-    let mut data = Data::new().move_to((points_a[0].x, points_a[0].y));
-
-    for _ in 0..config.pattern_count {
-        data = data
-            .line_to((points_e[0].x, points_e[0].y))
-            .line_to((points_e[4].x, points_e[4].y))
-            .line_to((points_b[4].x, points_b[4].y))
-            .line_to((points_b[2].x, points_b[2].y))
-            .line_to((points_c[2].x, points_c[2].y))
-            .line_to((points_c[3].x, points_c[3].y))
-            .line_to((points_d[3].x, points_d[3].y))
-            .line_to((points_d[1].x, points_d[1].y))
-            .line_to((points_a[1].x, points_a[1].y))
-            .line_to((points_a[5].x, points_a[5].y));
-
-        (points_a, points_b, points_c, points_d, points_e) = config.get_coords_for_patterns_by_p0(
-            points_a[5],
-            points_b[5],
-            points_c[5],
-            points_d[5],
-            points_e[5],
-        );
+use crate::common::{Point, add_double_line, add_pattern, arc_to, ellipse_lines};
+use crate::compact;
+use crate::config::{Element, EllipsePatternPoints, GreekKeyEllipseConfig, VisualOptions};
+use crate::field::{FieldClip, add_field};
+use crate::inlay::INLAY_ARC_TOLERANCE;
+use crate::metadata;
+use crate::paint::add_paint_defs;
+use crate::text::{TextLayout, add_text};
+
+// Draws the pattern as one closed path, or with `split` as one open path per key.
+fn draw_greek_key_patterns(
+    config: &GreekKeyEllipseConfig,
+    arc_tolerance: Option<f64>,
+    split: bool,
+) -> Vec<Data> {
+    let centre = config.get_centre();
+    let er = &config.ellipse_radii;
+    let (r_a, r_b, r_c, r_d, r_e) = (
+        (er.rx_a, er.ry_a),
+        (er.rx_b, er.ry_b),
+        (er.rx_c, er.ry_c),
+        (er.rx_d, er.ry_d),
+        (er.rx_e, er.ry_e),
+    );
+    // Radial runs are straight; tangential runs follow their ring as true arcs.
+    let arc = |data: Data, (rx, ry): (f64, f64), from: Point, to: Point| {
+        arc_to(data, centre, rx, ry, from, to, arc_tolerance)
+    };
+    let n = config.pattern_count as usize;
+    let mut keys: Vec<EllipsePatternPoints> = Vec::with_capacity(n);
+    let mut points = config.get_coords_for_patterns();
+    for _ in 0..n {
+        keys.push(points);
+        let (a, b, c, d, e) = points;
+        points = config.get_coords_for_patterns_by_p0(a[5], b[5], c[5], d[5], e[5]);
+    }
+
+    // Draws one key from a0 round to a1, short of the run along ring a that
+    // joins it to the next key.
+    let body = |data: Data, (a, b, c, d, e): &EllipsePatternPoints| {
+        let data = data.line_to((e[0].x, e[0].y));
+        let data = arc(data, r_e, e[0], e[4]);
+        let data = data.line_to((b[4].x, b[4].y));
+        let data = arc(data, r_b, b[4], b[2]);
+        let data = data.line_to((c[2].x, c[2].y));
+        let data = arc(data, r_c, c[2], c[3]);
+        let data = data.line_to((d[3].x, d[3].y));
+        let data = arc(data, r_d, d[3], d[1]);
+        data.line_to((a[1].x, a[1].y))
+    };
+
+    if !split {
+        let start = keys[0].0[0];
+        let mut data = Data::new().move_to((start.x, start.y));
+        for key in &keys {
+            data = body(data, key);
+            data = arc(data, r_a, key.0[1], key.0[5]);
+        }
+        return vec![data.close()];
     }
 
-    data.close()
+    // Each piece runs from the middle of the ring a run before its key to the
+    // middle of the one after, where the arcs of neighbouring pieces meet
+    // tangentially.
+    (0..n)
+        .map(|i| {
+            let before = &keys[(i + n - 1) % n].0;
+            let after = &keys[i].0;
+            let data = Data::new().move_to((before[3].x, before[3].y));
+            let data = arc(data, r_a, before[3], before[5]);
+            let data = body(data, &keys[i]);
+            arc(data, r_a, after[1], after[3])
+        })
+        .collect()
 }
 
 fn draw_frame(
@@ -64,64 +107,80 @@ fn apply_dash(ellipse: SvgEllipse, dash: Option<&str>) -> SvgEllipse {
     }
 }
 
-fn build_document(config: &GreekKeyEllipseConfig, visual: &VisualOptions) -> Document {
+pub(crate) fn build_document(config: &GreekKeyEllipseConfig, visual: &VisualOptions) -> Document {
     let stroke_width = config.stroke_width;
-    let stroke_color = visual.stroke_color.as_str();
-    let stroke_opacity = visual.stroke_opacity;
     let dash = visual.stroke_dash.as_deref();
     let (width, height) = config.get_canvas_size();
     let mut document = Document::new().set("viewBox", (0, 0, width, height));
+    document = add_paint_defs(document, visual, width, height);
 
-    if let Some(bg) = &visual.background_color {
+    if let Some(bg) = visual.background_paint() {
         document = document.add(
             Rectangle::new()
                 .set("width", width)
                 .set("height", height)
-                .set("fill", bg.as_str()),
+                .set("fill", bg),
         );
     }
 
-    let path_data = draw_greek_key_patterns(config);
-    let path = SvgPath::new()
-        .set("fill", visual.fill_color.as_deref().unwrap_or("none"))
-        .set("stroke", stroke_color)
-        .set("stroke-width", stroke_width)
-        .set("stroke-opacity", stroke_opacity)
-        .set("d", path_data);
-    let path = match dash {
-        Some(d) => path.set("stroke-dasharray", d),
-        None => path,
+    // The inlay's offset lines are built from straight segments.
+    let tolerance = match visual.inlay {
+        Some(_) => Some(visual.arc_tolerance.unwrap_or(INLAY_ARC_TOLERANCE)),
+        None => visual.arc_tolerance,
     };
-    document = document.add(path);
+    document = add_pattern(
+        document,
+        visual,
+        draw_greek_key_patterns(config, tolerance, false).remove(0),
+        || draw_greek_key_patterns(config, tolerance, true),
+        &visual.fill_paint(),
+        stroke_width,
+    );
 
     let centre = config.get_centre();
     let er = &config.ellipse_radii;
-    document = document.add(apply_dash(
-        draw_frame(
-            centre.x,
-            centre.y,
-            er.rx_i,
-            er.ry_i,
-            stroke_color,
-            stroke_width,
-            stroke_opacity,
-        ),
-        dash,
-    ));
-    document = document.add(apply_dash(
-        draw_frame(
-            centre.x,
-            centre.y,
-            config.rx,
-            config.ry,
-            stroke_color,
-            stroke_width,
-            stroke_opacity,
-        ),
-        dash,
-    ));
-
-    document
+    if let Some(spacing) = visual.field_spacing {
+        let clip = FieldClip::Ellipse {
+            centre,
+            rx: er.rx_i,
+            ry: er.ry_i,
+        };
+        document = add_field(document, clip, spacing, stroke_width, visual);
+    }
+    for (element, (rx, ry)) in [
+        (Element::InnerFrame, (er.rx_i, er.ry_i)),
+        (Element::OuterFrame, (config.rx, config.ry)),
+    ] {
+        if visual.is_hidden(element) {
+            continue;
+        }
+        document = match (&visual.inlay, tolerance) {
+            (Some(inlay), Some(tolerance)) => {
+                let data = ellipse_lines(centre, rx, ry, tolerance);
+                add_double_line(document, visual, inlay, element, data)
+            }
+            _ => document.add(apply_dash(
+                draw_frame(
+                    centre.x,
+                    centre.y,
+                    rx,
+                    ry,
+                    &visual.element_stroke(element),
+                    stroke_width,
+                    visual.element_opacity(element),
+                ),
+                dash,
+            )),
+        };
+    }
+
+    let layout = TextLayout {
+        centre,
+        inner: (er.rx_i, er.ry_i),
+        rings: Some([(er.rx_i, er.ry_i), (config.rx, config.ry)]),
+        stroke_width,
+    };
+    add_text(document, visual, &layout)
 }
 
 /// Returns the ellipse Greek Key pattern as an SVG string.
@@ -129,7 +188,18 @@ fn build_document(config: &GreekKeyEllipseConfig, visual: &VisualOptions) -> Doc
 /// Available on all targets including WASM. For file output, use
 /// [`generate_pattern_svg`] (requires the `native` feature).
 pub fn generate_svg_string(config: &GreekKeyEllipseConfig, visual: &VisualOptions) -> String {
-    build_document(config, visual).to_string()
+    let visual = metadata::stamped(
+        visual,
+        config.stroke_width,
+        config.border_margin,
+        "ellipse",
+        vec![
+            ("pattern_count", config.pattern_count.into()),
+            ("rx", config.rx.into()),
+            ("ry", config.ry.into()),
+        ],
+    );
+    compact::to_string(build_document(config, &visual), &visual)
 }
 
 /// Generates an ellipse Greek Key pattern and writes `<filename>.svg` and `<filename>.png`.
@@ -142,7 +212,7 @@ pub fn generate_pattern_svg(
     visual: &VisualOptions,
     filename: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    save_and_convert_svg(build_document(config, visual), filename)
+    save_and_convert_svg(&generate_svg_string(config, visual), filename)
 }
 
 #[cfg(test)]
@@ -175,7 +245,7 @@ mod tests {
     fn ellipse_svg_contains_fill_color() {
         let config = GreekKeyEllipseConfig::new(300.0, 200.0, 30, 10, 3.0).unwrap();
         let visual = VisualOptions {
-            fill_color: Some("#FF0000".to_string()),
+            fill_color: Some("#FF0000".into()),
             ..VisualOptions::default()
         };
         let svg = generate_svg_string(&config, &visual);
@@ -186,7 +256,7 @@ mod tests {
     fn ellipse_svg_contains_background() {
         let config = GreekKeyEllipseConfig::new(300.0, 200.0, 30, 10, 3.0).unwrap();
         let visual = VisualOptions {
-            background_color: Some("#001122".to_string()),
+            background_color: Some("#001122".into()),
             ..VisualOptions::default()
         };
         let svg = generate_svg_string(&config, &visual);
@@ -205,6 +275,43 @@ mod tests {
         assert!(svg.contains("stroke-dasharray"));
     }
 
+    #[test]
+    fn ellipse_key_runs_are_arcs() {
+        let config = GreekKeyEllipseConfig::new(300.0, 200.0, 30, 10, 3.0).unwrap();
+        let svg = generate_svg_string(&config, &VisualOptions::default());
+        // Five tangential runs per key unit.
+        assert_eq!(svg.matches(" A").count(), 5 * 30);
+    }
+
+    #[test]
+    fn ellipse_key_colors_split_ring_a_runs() {
+        let config = GreekKeyEllipseConfig::new(300.0, 200.0, 30, 10, 3.0).unwrap();
+        let visual = VisualOptions {
+            key_colors: vec!["#A3341F".into(), "#111111".into()],
+            ..VisualOptions::default()
+        };
+        let svg = generate_svg_string(&config, &visual);
+        assert_eq!(svg.matches("stroke=\"#A3341F\"").count(), 15);
+        assert_eq!(svg.matches("stroke=\"#111111\"").count(), 15);
+        // Each piece takes half of the ring a run on either side of its key.
+        assert_eq!(svg.matches(" A").count(), 6 * 30);
+    }
+
+    #[test]
+    fn ellipse_arc_tolerance_uses_line_segments() {
+        let config = GreekKeyEllipseConfig::new(300.0, 200.0, 8, 10, 3.0).unwrap();
+        let segments = |tolerance: f64| {
+            let visual = VisualOptions {
+                arc_tolerance: Some(tolerance),
+                ..VisualOptions::default()
+            };
+            let svg = generate_svg_string(&config, &visual);
+            assert!(!svg.contains(" A"));
+            svg.matches(" L").count()
+        };
+        assert!(segments(0.05) > segments(1.0));
+    }
+
     #[test]
     fn ellipse_equal_axes_produces_valid_svg() {
         let config = GreekKeyEllipseConfig::new(200.0, 200.0, 20, 5, 2.0).unwrap();
@@ -0,0 +1,303 @@
+use svg::Document;
+use svg::node::element::path::Data;
+use svg::node::element::{
+    Circle, ClipPath, Definitions, Ellipse as SvgEllipse, Group, Path as SvgPath, Rectangle,
+};
+
+use crate::common::Point;
+#[cfg(feature = "native")]
+use crate::common::save_and_convert_svg;
+use crate::compact;
+use crate::config::{Element, GreekKeyFieldConfig, VisualOptions};
+use crate::inlay::inlay_paths;
+use crate::metadata;
+use crate::paint::add_paint_defs;
+use crate::text::{TextLayout, add_text};
+
+const CLIP_ID: &str = "gm-field-clip";
+// Row spacing in key units; a key is four units tall.
+const ROW_PITCH: f64 = 4.0;
+
+// One running key in key units, drawn left to right from its baseline. Matches
+// the horizontal unit used along the top of the rectangle border.
+const UNIT_STEPS: [(f64, f64); 10] = [
+    (0.0, -4.0),
+    (4.0, 0.0),
+    (0.0, 3.0),
+    (-2.0, 0.0),
+    (0.0, -1.0),
+    (1.0, 0.0),
+    (0.0, -1.0),
+    (-2.0, 0.0),
+    (0.0, 3.0),
+    (4.0, 0.0),
+];
+
+/// The region a field fill is clipped to.
+pub(crate) enum FieldClip {
+    Rect { x: f64, y: f64, w: f64, h: f64 },
+    Circle { centre: Point, r: f64 },
+    Ellipse { centre: Point, rx: f64, ry: f64 },
+    Polygon(Vec<Point>),
+}
+
+impl FieldClip {
+    /// Returns the `(x, y, width, height)` of the clip's bounding box.
+    pub(crate) fn bounds(&self) -> (f64, f64, f64, f64) {
+        match self {
+            FieldClip::Rect { x, y, w, h } => (*x, *y, *w, *h),
+            FieldClip::Circle { centre, r } => (centre.x - r, centre.y - r, 2.0 * r, 2.0 * r),
+            FieldClip::Ellipse { centre, rx, ry } => {
+                (centre.x - rx, centre.y - ry, 2.0 * rx, 2.0 * ry)
+            }
+            FieldClip::Polygon(points) => {
+                let min_x = points.iter().map(|p| p.x).fold(f64::INFINITY, f64::min);
+                let min_y = points.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+                let max_x = points.iter().map(|p| p.x).fold(f64::NEG_INFINITY, f64::max);
+                let max_y = points.iter().map(|p| p.y).fold(f64::NEG_INFINITY, f64::max);
+                (min_x, min_y, max_x - min_x, max_y - min_y)
+            }
+        }
+    }
+
+    fn to_clip_path(&self) -> ClipPath {
+        let clip = ClipPath::new().set("id", CLIP_ID);
+        match self {
+            FieldClip::Rect { x, y, w, h } => clip.add(
+                Rectangle::new()
+                    .set("x", *x)
+                    .set("y", *y)
+                    .set("width", *w)
+                    .set("height", *h),
+            ),
+            FieldClip::Circle { centre, r } => clip.add(
+                Circle::new()
+                    .set("cx", centre.x)
+                    .set("cy", centre.y)
+                    .set("r", *r),
+            ),
+            FieldClip::Ellipse { centre, rx, ry } => clip.add(
+                SvgEllipse::new()
+                    .set("cx", centre.x)
+                    .set("cy", centre.y)
+                    .set("rx", *rx)
+                    .set("ry", *ry),
+            ),
+            FieldClip::Polygon(points) => {
+                let mut data = Data::new().move_to((points[0].x, points[0].y));
+                for point in &points[1..] {
+                    data = data.line_to((point.x, point.y));
+                }
+                clip.add(SvgPath::new().set("d", data.close()))
+            }
+        }
+    }
+}
+
+// Covers the bounding box with rows of running keys, four key units apart so
+// each row's baseline closes the tops of the keys below it. Alternate rows are
+// staggered by half a key, which interlocks the keys like bricks in a wall.
+fn draw_lattice(bounds: (f64, f64, f64, f64), unit: f64) -> Data {
+    let (x, y, w, h) = bounds;
+    let key_length = 5.0 * unit;
+    let pitch = ROW_PITCH * unit;
+    let keys = (w / key_length).ceil() as i32 + 2;
+    let rows = (h / pitch).ceil() as i32 + 1;
+    let mut data = Data::new();
+    for row in 0..=rows {
+        let stagger = if row % 2 == 1 { key_length / 2.0 } else { 0.0 };
+        data = data.move_to((x - key_length - stagger, y + row as f64 * pitch));
+        for _ in 0..keys {
+            for (dx, dy) in UNIT_STEPS {
+                data = data.line_by((dx * unit, dy * unit));
+            }
+        }
+    }
+    data
+}
+
+/// Adds an all-over key lattice with key unit length `spacing`, clipped to `clip`.
+///
+/// Does nothing if `spacing` is not a positive finite number.
+pub(crate) fn add_field(
+    document: Document,
+    clip: FieldClip,
+    spacing: f64,
+    stroke_width: f32,
+    visual: &VisualOptions,
+) -> Document {
+    if spacing <= 0.0 || !spacing.is_finite() {
+        return document;
+    }
+    let data = draw_lattice(clip.bounds(), spacing);
+    let dash = visual.stroke_dash.as_deref();
+    let lattice: Vec<SvgPath> = match &visual.inlay {
+        Some(inlay) => {
+            let bands = visual
+                .inlay_paint()
+                .map(|paint| vec![(data.clone(), paint)])
+                .unwrap_or_default();
+            let line = match inlay.line_color {
+                Some(_) => visual.inlay_line_paint(Element::Pattern),
+                None => visual.stroke_paint(),
+            };
+            inlay_paths(inlay, &data, bands, &line, visual.stroke_opacity, dash)
+        }
+        None => {
+            let lattice = SvgPath::new()
+                .set("fill", "none")
+                .set("stroke", visual.stroke_paint())
+                .set("stroke-width", stroke_width)
+                .set("stroke-opacity", visual.stroke_opacity)
+                .set("d", data);
+            vec![match dash {
+                Some(d) => lattice.set("stroke-dasharray", d),
+                None => lattice,
+            }]
+        }
+    };
+    let group = lattice.into_iter().fold(
+        Group::new().set("clip-path", format!("url(#{CLIP_ID})")),
+        |group, path| group.add(path),
+    );
+    document
+        .add(Definitions::new().add(clip.to_clip_path()))
+        .add(group)
+}
+
+fn build_document(config: &GreekKeyFieldConfig, visual: &VisualOptions) -> Document {
+    let (width, height) = config.get_canvas_size();
+    let mut document = Document::new().set("viewBox", (0, 0, width, height));
+    document = add_paint_defs(document, visual, width, height);
+
+    if let Some(bg) = visual.background_paint() {
+        document = document.add(
+            Rectangle::new()
+                .set("width", width)
+                .set("height", height)
+                .set("fill", bg),
+        );
+    }
+
+    let (x, y) = config.get_origin();
+    let clip = FieldClip::Rect {
+        x,
+        y,
+        w: config.width,
+        h: config.height,
+    };
+    if visual.fill_color.is_some() {
+        document = document.add(
+            Rectangle::new()
+                .set("x", x)
+                .set("y", y)
+                .set("width", config.width)
+                .set("height", config.height)
+                .set("fill", visual.fill_paint()),
+        );
+    }
+    document = add_field(
+        document,
+        clip,
+        config.key_unit_length,
+        config.stroke_width,
+        visual,
+    );
+    let (half_width, half_height) = (config.width / 2.0, config.height / 2.0);
+    let layout = TextLayout {
+        centre: Point {
+            x: x + half_width,
+            y: y + half_height,
+        },
+        inner: (half_width, half_height),
+        rings: None,
+        stroke_width: config.stroke_width,
+    };
+    add_text(document, visual, &layout)
+}
+
+/// Returns a rectangle filled with an all-over Greek Key lattice as an SVG string.
+///
+/// Available on all targets including WASM. For file output, use
+/// [`generate_pattern_svg`] (requires the `native` feature).
+pub fn generate_svg_string(config: &GreekKeyFieldConfig, visual: &VisualOptions) -> String {
+    let visual = metadata::stamped(
+        visual,
+        config.stroke_width,
+        config.border_margin,
+        "field",
+        vec![
+            ("width", config.width.into()),
+            ("height", config.height.into()),
+            ("size", config.key_unit_length.into()),
+        ],
+    );
+    compact::to_string(build_document(config, &visual), &visual)
+}
+
+/// Generates a rectangle filled with an all-over Greek Key lattice and writes
+/// `<filename>.svg` and `<filename>.png`.
+///
+/// Requires the `native` feature (enabled by default). For WASM targets, use
+/// [`generate_svg_string`] instead.
+#[cfg(feature = "native")]
+pub fn generate_pattern_svg(
+    config: &GreekKeyFieldConfig,
+    visual: &VisualOptions,
+    filename: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    save_and_convert_svg(&generate_svg_string(config, visual), filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_svg_contains_clipped_lattice() {
+        let config = GreekKeyFieldConfig::new(400.0, 300.0, 10.0, 5, 2.0).unwrap();
+        let svg = generate_svg_string(&config, &VisualOptions::default());
+        assert!(svg.contains("<clipPath"));
+        assert!(svg.contains("clip-path=\"url(#gm-field-clip)\""));
+        assert!(svg.contains("<path"));
+    }
+
+    #[test]
+    fn field_fill_color_adds_backing_rect() {
+        let config = GreekKeyFieldConfig::new(400.0, 300.0, 10.0, 5, 2.0).unwrap();
+        let visual = VisualOptions {
+            fill_color: Some("#FF0000".into()),
+            ..VisualOptions::default()
+        };
+        let svg = generate_svg_string(&config, &visual);
+        assert!(svg.contains("fill=\"#FF0000\""));
+    }
+
+    #[test]
+    fn lattice_covers_bounds() {
+        let data = draw_lattice((0.0, 0.0, 100.0, 100.0), 10.0);
+        let rows = data
+            .iter()
+            .filter(|c| matches!(c, svg::node::element::path::Command::Move(..)))
+            .count();
+        // Rows start at the top edge and must reach past the bottom edge.
+        assert!((rows - 1) as f64 * 40.0 >= 100.0);
+    }
+
+    #[test]
+    fn invalid_spacing_adds_nothing() {
+        let document = add_field(
+            Document::new(),
+            FieldClip::Rect {
+                x: 0.0,
+                y: 0.0,
+                w: 10.0,
+                h: 10.0,
+            },
+            0.0,
+            1.0,
+            &VisualOptions::default(),
+        );
+        assert!(!document.to_string().contains("<clipPath"));
+    }
+}
@@ -1,54 +1,431 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use greek_meander::{
+    Effect, ElementStyle, FrameShape, LineCap, LineJoin, Paint, SpiralKind, TextRail, Theme,
+    VisualOptions, metadata,
+};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-/// Serializable model for a TOML config file.
+/// Syntax of a config file or batch manifest.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Picks the format from the file extension: JSON for `.json`, YAML for
+    /// `.yaml` or `.yml`, and TOML for anything else.
+    pub fn detect(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => ConfigFormat::Json,
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    /// Parses `content` written in this format.
+    pub fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T, String> {
+        match self {
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml_ng::from_str(content).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Serializable model for a TOML, JSON or YAML config file.
 ///
 /// All fields are optional so CLI flags can override any subset.
 /// Output routing flags (`--stdout`, `--no-svg`, `--no-png`) are intentionally
 /// omitted: they are session-level choices that cannot be reliably overridden
 /// from the CLI when set in a file, and do not belong in a reusable design config.
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
 pub struct FileConfig {
+    pub theme: Option<Theme>,
+    #[serde(serialize_with = "shortest")]
     pub stroke_width: Option<f32>,
-    pub stroke_color: Option<String>,
+    pub stroke_color: Option<Paint>,
+    #[serde(serialize_with = "shortest")]
     pub stroke_opacity: Option<f32>,
-    pub fill_color: Option<String>,
-    pub background_color: Option<String>,
+    pub fill_color: Option<Paint>,
+    pub background_color: Option<Paint>,
     pub stroke_dash: Option<String>,
+    pub line_join: Option<LineJoin>,
+    pub line_cap: Option<LineCap>,
+    pub miter_limit: Option<f64>,
+    pub non_scaling_stroke: Option<bool>,
+    pub effects: Option<Vec<Effect>>,
+    pub field_spacing: Option<f64>,
+    pub arc_tolerance: Option<f64>,
+    pub precision: Option<u8>,
+    pub shared_styles: Option<bool>,
+    pub relative_paths: Option<bool>,
+    pub minify: Option<bool>,
+    pub id_prefix: Option<String>,
+    pub key_colors: Option<Vec<Paint>>,
+    pub pattern: Option<ElementFileConfig>,
+    pub inner_frame: Option<ElementFileConfig>,
+    pub outer_frame: Option<ElementFileConfig>,
+    pub inlay: Option<InlayFileConfig>,
+    pub text: Option<TextFileConfig>,
+    pub metadata: Option<MetadataFileConfig>,
     pub border_margin: Option<i32>,
     pub file: Option<String>,
+    #[serde(serialize_with = "shortest")]
     pub scale: Option<f32>,
     pub rect: Option<RectFileConfig>,
     pub circle: Option<CircleFileConfig>,
     pub ellipse: Option<EllipseFileConfig>,
+    pub superellipse: Option<SuperellipseFileConfig>,
+    pub spiral: Option<SpiralFileConfig>,
+    pub field: Option<FieldAreaFileConfig>,
+    pub frame: Option<FrameFileConfig>,
+    /// Named variants, each laid over the rest of the file when selected
+    /// with `--profile`.
+    pub profile: Option<BTreeMap<String, toml::Table>>,
+}
+
+// Writes an option held as f32 as the shortest decimal that reads back as it,
+// so 0.7 is written 0.7 rather than 0.699999988079071.
+fn shortest<S: serde::Serializer>(value: &Option<f32>, serializer: S) -> Result<S::Ok, S::Error> {
+    value
+        .map(|value| {
+            value
+                .to_string()
+                .parse::<f64>()
+                .expect("an f32 reads as an f64")
+        })
+        .serialize(serializer)
+}
+
+/// Stroke overrides for the key pattern or one of the frames.
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct ElementFileConfig {
+    pub color: Option<Paint>,
+    #[serde(serialize_with = "shortest")]
+    pub opacity: Option<f32>,
+    pub hidden: Option<bool>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+/// Double-line settings; `gap` turns the inlay on.
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct InlayFileConfig {
+    pub gap: Option<f64>,
+    pub line_width: Option<f64>,
+    pub line_color: Option<Paint>,
+    pub color: Option<Paint>,
+}
+
+/// Title and ring text; `title` or `ring_text` turns text on.
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct TextFileConfig {
+    pub title: Option<Vec<String>>,
+    pub ring_text: Option<String>,
+    pub rail: Option<TextRail>,
+    pub font_family: Option<String>,
+    pub font_size: Option<f64>,
+    pub color: Option<Paint>,
+    pub font_file: Option<PathBuf>,
+}
+
+/// Accessible title and description, author and licence stored in the SVG.
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct MetadataFileConfig {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub license: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
 pub struct RectFileConfig {
     pub size: Option<i32>,
     pub width: Option<i32>,
     pub height: Option<i32>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
 pub struct CircleFileConfig {
     pub pattern_count: Option<i32>,
     pub radius: Option<f64>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
 pub struct EllipseFileConfig {
     pub pattern_count: Option<i32>,
     pub rx: Option<f64>,
     pub ry: Option<f64>,
 }
 
-pub fn load(path: &std::path::Path) -> Result<FileConfig, Box<dyn std::error::Error>> {
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct SuperellipseFileConfig {
+    pub pattern_count: Option<i32>,
+    pub rx: Option<f64>,
+    pub ry: Option<f64>,
+    pub exponent: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct SpiralFileConfig {
+    pub kind: Option<SpiralKind>,
+    pub turns: Option<f64>,
+    pub start_radius: Option<f64>,
+    pub end_radius: Option<f64>,
+    pub size: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct FieldAreaFileConfig {
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub size: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct FrameFileConfig {
+    pub input: Option<PathBuf>,
+    pub shape: Option<FrameShape>,
+    pub size: Option<i32>,
+    pub pattern_count: Option<i32>,
+}
+
+impl FileConfig {
+    /// Returns a config holding exactly the resolved options of a run, with
+    /// no theme and no command section. Loading it and adding the command's
+    /// section rebuilds the same design.
+    pub fn resolved(
+        visual: &VisualOptions,
+        stroke_width: f32,
+        border_margin: i32,
+        file: &str,
+        scale: f32,
+        font_file: Option<PathBuf>,
+    ) -> Self {
+        let element = |style: &ElementStyle| {
+            (*style != ElementStyle::default()).then(|| ElementFileConfig {
+                color: style.stroke_color.clone(),
+                opacity: style.stroke_opacity,
+                hidden: style.hidden.then_some(true),
+            })
+        };
+        let metadata = &visual.metadata;
+        Self {
+            stroke_width: Some(stroke_width),
+            stroke_color: Some(visual.stroke_color.clone()),
+            stroke_opacity: Some(visual.stroke_opacity),
+            fill_color: visual.fill_color.clone(),
+            background_color: visual.background_color.clone(),
+            stroke_dash: visual.stroke_dash.clone(),
+            line_join: visual.line_join,
+            line_cap: visual.line_cap,
+            miter_limit: visual.miter_limit,
+            non_scaling_stroke: Some(visual.non_scaling_stroke),
+            effects: (!visual.effects.is_empty()).then(|| visual.effects.clone()),
+            field_spacing: visual.field_spacing,
+            arc_tolerance: visual.arc_tolerance,
+            precision: visual.precision,
+            shared_styles: Some(visual.shared_styles),
+            relative_paths: Some(visual.relative_paths),
+            minify: Some(visual.minify),
+            id_prefix: visual.id_prefix.clone(),
+            key_colors: (!visual.key_colors.is_empty()).then(|| visual.key_colors.clone()),
+            pattern: element(&visual.pattern_style),
+            inner_frame: element(&visual.inner_frame_style),
+            outer_frame: element(&visual.outer_frame_style),
+            inlay: visual.inlay.as_ref().map(|inlay| InlayFileConfig {
+                gap: Some(inlay.gap),
+                line_width: Some(inlay.line_width),
+                line_color: inlay.line_color.clone(),
+                color: inlay.inlay_color.clone(),
+            }),
+            text: visual.text.as_ref().map(|text| TextFileConfig {
+                title: Some(text.title.clone()),
+                ring_text: text.ring_text.clone(),
+                rail: Some(text.rail),
+                font_family: text.font_family.clone(),
+                font_size: text.font_size,
+                color: text.color.clone(),
+                font_file,
+            }),
+            metadata: (metadata.title.is_some()
+                || metadata.description.is_some()
+                || metadata.author.is_some()
+                || metadata.license.is_some())
+            .then(|| MetadataFileConfig {
+                title: metadata.title.clone(),
+                description: metadata.description.clone(),
+                author: metadata.author.clone(),
+                license: metadata.license.clone(),
+            }),
+            border_margin: Some(border_margin),
+            file: Some(file.to_string()),
+            scale: Some(scale),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the config with the profile `name` laid over it. Sections
+    /// merge key by key, so a profile need only list what it changes.
+    pub fn with_profile(mut self, name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut profiles = self.profile.take().unwrap_or_default();
+        let Some(profile) = profiles.remove(name) else {
+            let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            return Err(match names.as_slice() {
+                [] => format!("no profile named '{name}'; the config defines none"),
+                names => format!(
+                    "no profile named '{name}'; the config defines {}",
+                    names.join(", ")
+                ),
+            }
+            .into());
+        };
+        let mut table = toml::Table::try_from(&self)?;
+        merge(&mut table, profile);
+        toml::Value::Table(table)
+            .try_into()
+            .map_err(|e| format!("invalid profile '{name}': {e}").into())
+    }
+}
+
+/// Lays `over` onto `base`; tables merge key by key, anything else replaces.
+pub fn merge(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(over)) => merge(base, over),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Loads the config stored in an SVG written by greek-meander.
+pub fn load_svg(path: &Path) -> Result<FileConfig, Box<dyn std::error::Error>> {
+    let svg = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read SVG file '{}': {}", path.display(), e))?;
+    let content = metadata::read_config(&svg).ok_or_else(|| {
+        format!(
+            "'{}' has no stored config; only SVGs written by greek-meander can be reproduced",
+            path.display()
+        )
+    })?;
+    toml::from_str(&content)
+        .map_err(|e| format!("invalid config stored in '{}': {}", path.display(), e).into())
+}
+
+/// Loads a config file in `format`, or the format its extension names.
+pub fn load(
+    path: &Path,
+    format: Option<ConfigFormat>,
+) -> Result<FileConfig, Box<dyn std::error::Error>> {
     let content = std::fs::read_to_string(path)
         .map_err(|e| format!("could not read config file '{}': {}", path.display(), e))?;
-    toml::from_str(&content)
+    format
+        .unwrap_or_else(|| ConfigFormat::detect(path))
+        .parse(&content)
         .map_err(|e| format!("invalid config file '{}': {}", path.display(), e).into())
 }
 
+/// Sets the option `key` in a config table and returns the key it was stored
+/// under, or `None` if the config has no such option or it cannot take
+/// `value`.
+///
+/// `key` may be dotted for an option in a section, such as `inlay.gap`. A key
+/// that names no top-level option is tried in `section`, so `size` sets
+/// `rect.size` for a rectangle.
+pub fn set_option(
+    table: &mut toml::Table,
+    section: &str,
+    key: &str,
+    value: toml::Value,
+) -> Option<String> {
+    for key in [key.to_string(), format!("{section}.{key}")] {
+        let mut trial = table.clone();
+        if !set(&mut trial, &key, value.clone()) {
+            continue;
+        }
+        // Unknown keys are dropped on the way through FileConfig.
+        let Ok(config) = toml::Value::Table(trial.clone()).try_into::<FileConfig>() else {
+            continue;
+        };
+        if toml::Table::try_from(&config).is_ok_and(|stored| get(&stored, &key).is_some()) {
+            *table = trial;
+            return Some(key);
+        }
+    }
+    None
+}
+
+/// Sets an option given as text, such as a query parameter, reading the text
+/// as the first of an integer, a number, a boolean, a string or a
+/// comma-separated list that the option takes. See [`set_option`].
+pub fn set_option_text(
+    table: &mut toml::Table,
+    section: &str,
+    key: &str,
+    text: &str,
+) -> Option<String> {
+    let items: Vec<&str> = text.split(',').map(str::trim).collect();
+    let string = |text: &str| toml::Value::String(text.to_string());
+    let mut candidates = vec![
+        scalar(text),
+        string(text),
+        toml::Value::Array(items.iter().map(|item| scalar(item)).collect()),
+        toml::Value::Array(items.iter().map(|item| string(item)).collect()),
+    ];
+    candidates.dedup();
+    candidates
+        .into_iter()
+        .find_map(|value| set_option(table, section, key, value))
+}
+
+fn scalar(text: &str) -> toml::Value {
+    if let Ok(integer) = text.parse() {
+        toml::Value::Integer(integer)
+    } else if let Some(float) = text.parse::<f64>().ok().filter(|f| f.is_finite()) {
+        toml::Value::Float(float)
+    } else if let Ok(boolean) = text.parse() {
+        toml::Value::Boolean(boolean)
+    } else {
+        toml::Value::String(text.to_string())
+    }
+}
+
+// Sets a dotted key, adding sections as needed. Fails if a section name is
+// taken by a value.
+fn set(table: &mut toml::Table, key: &str, value: toml::Value) -> bool {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let Some(name) = parts.pop() else {
+        return false;
+    };
+    let mut table = table;
+    for section in parts {
+        let entry = table
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        let Some(section) = entry.as_table_mut() else {
+            return false;
+        };
+        table = section;
+    }
+    table.insert(name.to_string(), value);
+    true
+}
+
+/// Looks up a dotted key, such as `inlay.gap`, in a config table.
+pub fn get<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -78,13 +455,24 @@ mod tests {
             "##,
         );
         assert_eq!(cfg.stroke_width, Some(4.0));
-        assert_eq!(cfg.stroke_color.as_deref(), Some("#FF0000"));
+        assert_eq!(cfg.stroke_color, Some(Paint::from("#FF0000")));
         assert_eq!(cfg.stroke_opacity, Some(0.5));
         assert_eq!(cfg.border_margin, Some(10));
         assert_eq!(cfg.file.as_deref(), Some("out"));
         assert_eq!(cfg.scale, Some(2.0));
     }
 
+    #[test]
+    fn f32_options_are_written_as_given() {
+        let cfg = parse("stroke_opacity = 0.7\nscale = 1.1\n[pattern]\nopacity = 0.3\n");
+        let written = toml::to_string(&cfg).unwrap();
+        assert!(written.contains("stroke_opacity = 0.7\n"), "{written}");
+        assert!(written.contains("scale = 1.1\n"), "{written}");
+        assert!(written.contains("opacity = 0.3\n"), "{written}");
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert!(json.contains(r#""stroke_opacity":0.7,"#), "{json}");
+    }
+
     #[test]
     fn rect_section_parses() {
         let cfg = parse(
@@ -131,6 +519,184 @@ mod tests {
         assert_eq!(ellipse.ry, Some(150.0));
     }
 
+    #[test]
+    fn superellipse_section_parses() {
+        let cfg = parse(
+            r#"
+            [superellipse]
+            pattern_count = 24
+            rx = 250.0
+            ry = 150.0
+            exponent = 5.0
+            "#,
+        );
+        let superellipse = cfg.superellipse.unwrap();
+        assert_eq!(superellipse.pattern_count, Some(24));
+        assert_eq!(superellipse.rx, Some(250.0));
+        assert_eq!(superellipse.ry, Some(150.0));
+        assert_eq!(superellipse.exponent, Some(5.0));
+    }
+
+    #[test]
+    fn spiral_section_parses() {
+        let cfg = parse(
+            r#"
+            [spiral]
+            kind = "logarithmic"
+            turns = 2.5
+            start_radius = 280.0
+            end_radius = 40.0
+            size = 6.0
+            "#,
+        );
+        let spiral = cfg.spiral.unwrap();
+        assert_eq!(spiral.kind, Some(SpiralKind::Logarithmic));
+        assert_eq!(spiral.turns, Some(2.5));
+        assert_eq!(spiral.start_radius, Some(280.0));
+        assert_eq!(spiral.end_radius, Some(40.0));
+        assert_eq!(spiral.size, Some(6.0));
+    }
+
+    #[test]
+    fn unknown_spiral_kind_is_rejected() {
+        let result: Result<FileConfig, _> = toml::from_str("[spiral]\nkind = \"golden\"");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn field_spacing_and_section_parse() {
+        let cfg = parse(
+            r#"
+            field_spacing = 12.0
+            [field]
+            width = 320.0
+            height = 200.0
+            size = 8.0
+            "#,
+        );
+        assert_eq!(cfg.field_spacing, Some(12.0));
+        assert_eq!(cfg.arc_tolerance, None);
+        let field = cfg.field.unwrap();
+        assert_eq!(field.width, Some(320.0));
+        assert_eq!(field.height, Some(200.0));
+        assert_eq!(field.size, Some(8.0));
+    }
+
+    #[test]
+    fn frame_section_parses() {
+        let cfg = parse(
+            r#"
+            [frame]
+            input = "photo.jpg"
+            shape = "ellipse"
+            size = 12
+            pattern_count = 24
+            "#,
+        );
+        let frame = cfg.frame.unwrap();
+        assert_eq!(frame.input, Some(PathBuf::from("photo.jpg")));
+        assert_eq!(frame.shape, Some(FrameShape::Ellipse));
+        assert_eq!(frame.size, Some(12));
+        assert_eq!(frame.pattern_count, Some(24));
+    }
+
+    #[test]
+    fn text_section_parses() {
+        let cfg = parse(
+            r#"
+            [text]
+            title = ["Certificate", "of Merit"]
+            ring_text = "ΑΡΕΤΗ"
+            rail = "outer"
+            font_size = 18.0
+            font_file = "Cinzel.ttf"
+            "#,
+        );
+        let text = cfg.text.unwrap();
+        assert_eq!(text.title.unwrap(), ["Certificate", "of Merit"]);
+        assert_eq!(text.ring_text.as_deref(), Some("ΑΡΕΤΗ"));
+        assert_eq!(text.rail, Some(TextRail::Outer));
+        assert_eq!(text.font_size, Some(18.0));
+        assert_eq!(text.font_file, Some(PathBuf::from("Cinzel.ttf")));
+    }
+
+    #[test]
+    fn resolved_config_round_trips_through_toml() {
+        let mut visual = Theme::GoldLeaf.visual_options();
+        visual.metadata.author = Some("A. Potter".to_string());
+        let resolved = FileConfig::resolved(&visual, 3.0, 5, "menu", 2.0, None);
+        let cfg = parse(&toml::to_string(&resolved).unwrap());
+        assert!(cfg.theme.is_none());
+        assert_eq!(cfg.stroke_color, Some(visual.stroke_color));
+        assert_eq!(cfg.effects, Some(visual.effects));
+        assert_eq!(cfg.metadata.unwrap().author.as_deref(), Some("A. Potter"));
+        assert_eq!(cfg.file.as_deref(), Some("menu"));
+        assert!(cfg.text.is_none() && cfg.rect.is_none());
+    }
+
+    #[test]
+    fn element_sections_and_key_colors_parse() {
+        let cfg = parse(
+            r##"
+            key_colors = ["#A3341F", "linear-gradient(#111, #333)"]
+
+            [pattern]
+            opacity = 1.0
+
+            [outer_frame]
+            color = "#445566"
+            hidden = true
+            "##,
+        );
+        let keys = cfg.key_colors.unwrap();
+        assert_eq!(keys[0], Paint::from("#A3341F"));
+        assert!(matches!(keys[1], Paint::Linear(_)));
+        assert_eq!(cfg.pattern.unwrap().opacity, Some(1.0));
+        let outer = cfg.outer_frame.unwrap();
+        assert_eq!(outer.color, Some(Paint::from("#445566")));
+        assert_eq!(outer.hidden, Some(true));
+        assert!(cfg.inner_frame.is_none());
+    }
+
+    #[test]
+    fn stroke_geometry_and_effects_parse() {
+        let cfg = parse(
+            r#"
+            line_join = "round"
+            line_cap = "square"
+            miter_limit = 2.0
+            non_scaling_stroke = true
+            effects = ["shadow", "inner-glow"]
+            "#,
+        );
+        assert_eq!(cfg.line_join, Some(LineJoin::Round));
+        assert_eq!(cfg.line_cap, Some(LineCap::Square));
+        assert_eq!(cfg.miter_limit, Some(2.0));
+        assert_eq!(cfg.non_scaling_stroke, Some(true));
+        assert_eq!(cfg.effects, Some(vec![Effect::Shadow, Effect::InnerGlow]));
+    }
+
+    #[test]
+    fn theme_parses() {
+        let cfg = parse(r#"theme = "attic-red-figure""#);
+        assert_eq!(cfg.theme, Some(Theme::AtticRedFigure));
+    }
+
+    #[test]
+    fn inlay_section_parses() {
+        let cfg = parse(
+            r##"
+            [inlay]
+            gap = 5.0
+            color = "#C0C0C0"
+            "##,
+        );
+        let inlay = cfg.inlay.unwrap();
+        assert_eq!(inlay.gap, Some(5.0));
+        assert_eq!(inlay.color, Some(Paint::from("#C0C0C0")));
+        assert!(inlay.line_width.is_none());
+    }
+
     #[test]
     fn output_routing_flags_are_ignored() {
         // stdout/no_svg/no_png are not config file fields; they must be silently
@@ -152,4 +718,149 @@ mod tests {
         let cfg: FileConfig = toml::from_str("unknown_key = 1\nscale = 2.0").unwrap();
         assert_eq!(cfg.scale, Some(2.0));
     }
+
+    #[test]
+    fn format_follows_the_extension() {
+        for (path, format) in [
+            ("a.json", ConfigFormat::Json),
+            ("a.yaml", ConfigFormat::Yaml),
+            ("dir.v2/a.yml", ConfigFormat::Yaml),
+            ("a.toml", ConfigFormat::Toml),
+            ("a.conf", ConfigFormat::Toml),
+            ("a", ConfigFormat::Toml),
+        ] {
+            assert_eq!(ConfigFormat::detect(Path::new(path)), format, "{path}");
+        }
+    }
+
+    #[test]
+    fn json_and_yaml_configs_parse_like_toml() {
+        let json: FileConfig = ConfigFormat::Json
+            .parse(
+                r##"{
+                    "$schema": "greek-meander.schema.json",
+                    "theme": "gold-leaf",
+                    "stroke_width": 4,
+                    "effects": ["shadow"],
+                    "circle": { "pattern_count": 20, "radius": 150 }
+                }"##,
+            )
+            .unwrap();
+        let yaml: FileConfig = ConfigFormat::Yaml
+            .parse(
+                "theme: gold-leaf\nstroke_width: 4\neffects: [shadow]\ncircle:\n  pattern_count: 20\n  radius: 150\n",
+            )
+            .unwrap();
+        for cfg in [json, yaml] {
+            assert_eq!(cfg.theme, Some(Theme::GoldLeaf));
+            assert_eq!(cfg.stroke_width, Some(4.0));
+            assert_eq!(cfg.effects, Some(vec![Effect::Shadow]));
+            let circle = cfg.circle.unwrap();
+            assert_eq!(circle.pattern_count, Some(20));
+            assert_eq!(circle.radius, Some(150.0));
+        }
+        let err = ConfigFormat::Yaml
+            .parse::<FileConfig>("circle:\n  radius: wide\n")
+            .unwrap_err();
+        assert!(err.contains("radius"), "{err}");
+    }
+
+    #[test]
+    fn profiles_are_laid_over_the_base() {
+        let cfg = parse(
+            r##"
+            stroke_color = "#AB8E0E"
+            stroke_width = 4.0
+            [circle]
+            radius = 200.0
+            pattern_count = 24
+
+            [profile.print]
+            scale = 4.0
+            [profile.print.circle]
+            radius = 300.0
+
+            [profile.web-dark]
+            background_color = "#101010"
+            "##,
+        );
+        let print = cfg.clone().with_profile("print").unwrap();
+        assert_eq!(print.scale, Some(4.0));
+        assert_eq!(print.stroke_width, Some(4.0));
+        let circle = print.circle.unwrap();
+        assert_eq!(circle.radius, Some(300.0));
+        assert_eq!(circle.pattern_count, Some(24));
+        assert!(print.profile.is_none());
+
+        let err = cfg.with_profile("web-light").unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "no profile named 'web-light'; the config defines print, web-dark"
+        );
+        let err = FileConfig::default().with_profile("print").unwrap_err();
+        assert!(err.to_string().ends_with("defines none"));
+    }
+
+    #[test]
+    fn options_are_set_by_name_or_within_the_section() {
+        let mut table = toml::Table::new();
+        assert_eq!(
+            set_option(&mut table, "circle", "radius", toml::Value::Integer(120)),
+            Some("circle.radius".to_string())
+        );
+        assert_eq!(
+            set_option(&mut table, "circle", "inlay.gap", toml::Value::Float(2.5)),
+            Some("inlay.gap".to_string())
+        );
+        assert_eq!(
+            set_option(
+                &mut table,
+                "circle",
+                "stroke_width",
+                toml::Value::Integer(3)
+            ),
+            Some("stroke_width".to_string())
+        );
+        let cfg: FileConfig = toml::Value::Table(table.clone()).try_into().unwrap();
+        assert_eq!(cfg.circle.unwrap().radius, Some(120.0));
+        assert_eq!(cfg.inlay.unwrap().gap, Some(2.5));
+        assert_eq!(cfg.stroke_width, Some(3.0));
+
+        let before = table.clone();
+        assert_eq!(
+            set_option(&mut table, "rect", "radius", toml::Value::Integer(1)),
+            None
+        );
+        assert_eq!(
+            set_option(&mut table, "rect", "size", toml::Value::Float(1.5)),
+            None
+        );
+        assert_eq!(table, before);
+    }
+
+    #[test]
+    fn option_text_takes_the_type_the_option_needs() {
+        let mut table = toml::Table::new();
+        for (key, text) in [
+            ("size", "12"),
+            ("stroke_color", "#112233"),
+            ("effects", "shadow"),
+            ("key_colors", "#ff0000,#00ff00"),
+            ("shared_styles", "true"),
+            ("text.title", "2024"),
+            ("metadata.title", "Border, gold"),
+        ] {
+            assert!(
+                set_option_text(&mut table, "rect", key, text).is_some(),
+                "{key}"
+            );
+        }
+        let cfg: FileConfig = toml::Value::Table(table).try_into().unwrap();
+        assert_eq!(cfg.rect.unwrap().size, Some(12));
+        assert_eq!(cfg.effects.unwrap().len(), 1);
+        assert_eq!(cfg.key_colors.unwrap().len(), 2);
+        assert_eq!(cfg.shared_styles, Some(true));
+        assert_eq!(cfg.text.unwrap().title, Some(vec!["2024".to_string()]));
+        assert_eq!(cfg.metadata.unwrap().title.as_deref(), Some("Border, gold"));
+    }
 }
@@ -0,0 +1,437 @@
+use std::error::Error;
+
+use svg::Document;
+use svg::node::Node;
+use svg::node::element::{Circle, ClipPath, Definitions, Ellipse, Image, Rectangle};
+
+use crate::circle;
+#[cfg(feature = "native")]
+use crate::common::save_and_convert_svg;
+use crate::common::{base64_encode, has_stroke};
+use crate::compact;
+use crate::config::{FrameShape, GreekKeyFrameConfig, VisualOptions};
+use crate::ellipse;
+use crate::metadata;
+use crate::rect;
+
+/// Element id of the clip path that trims the artwork to the inner frame.
+pub(crate) const ARTWORK_CLIP_ID: &str = "gm-artwork-clip";
+
+const PNG_MAGIC: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+const JPEG_MAGIC: [u8; 3] = [0xFF, 0xD8, 0xFF];
+
+/// The image formats that can be framed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtworkFormat {
+    Png,
+    Jpeg,
+    Svg,
+}
+
+impl ArtworkFormat {
+    fn mime_type(self) -> &'static str {
+        match self {
+            ArtworkFormat::Png => "image/png",
+            ArtworkFormat::Jpeg => "image/jpeg",
+            ArtworkFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+/// A PNG, JPEG or SVG image to place inside a border.
+///
+/// The image is embedded in the output as a data URI, so the framed SVG does
+/// not depend on the original file.
+#[derive(Debug, Clone)]
+pub struct Artwork {
+    format: ArtworkFormat,
+    width: f64,
+    height: f64,
+    data: Vec<u8>,
+}
+
+impl Artwork {
+    /// Reads the format and size of an encoded image.
+    ///
+    /// Returns an error if `data` is not a PNG, JPEG or SVG image, or its size
+    /// cannot be read. An SVG needs a `width` and `height` or a `viewBox` on its
+    /// root element.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Box<dyn Error>> {
+        let (format, size) = if data.starts_with(&PNG_MAGIC) {
+            (ArtworkFormat::Png, png_size(&data))
+        } else if data.starts_with(&JPEG_MAGIC) {
+            (ArtworkFormat::Jpeg, jpeg_size(&data))
+        } else if let Some(text) = std::str::from_utf8(&data)
+            .ok()
+            .filter(|t| t.contains("<svg"))
+        {
+            (ArtworkFormat::Svg, svg_size(text))
+        } else {
+            return Err("artwork must be a PNG, JPEG or SVG image".into());
+        };
+        let Some((width, height)) =
+            size.filter(|&(w, h)| w > 0.0 && h > 0.0 && w.is_finite() && h.is_finite())
+        else {
+            return Err(match format {
+                ArtworkFormat::Svg => {
+                    "SVG artwork needs a width and height or a viewBox on its <svg> element"
+                }
+                _ => "could not read the artwork's width and height",
+            }
+            .into());
+        };
+        Ok(Self {
+            format,
+            width,
+            height,
+            data,
+        })
+    }
+
+    /// Reads an image file.
+    ///
+    /// Requires the `native` feature (enabled by default).
+    #[cfg(feature = "native")]
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .map_err(|e| format!("could not read artwork '{}': {}", path.display(), e))?;
+        Self::from_bytes(data).map_err(|e| format!("'{}': {}", path.display(), e).into())
+    }
+
+    pub fn format(&self) -> ArtworkFormat {
+        self.format
+    }
+
+    /// Width in pixels, or in SVG user units for an SVG.
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    /// Height in pixels, or in SVG user units for an SVG.
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    pub(crate) fn data_uri(&self) -> String {
+        format!(
+            "data:{};base64,{}",
+            self.format.mime_type(),
+            base64_encode(&self.data)
+        )
+    }
+}
+
+fn png_size(data: &[u8]) -> Option<(f64, f64)> {
+    // The IHDR chunk always comes first.
+    if data.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(data.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(data.get(20..24)?.try_into().ok()?);
+    Some((width as f64, height as f64))
+}
+
+// Walks the JPEG segments to the first start-of-frame marker.
+fn jpeg_size(data: &[u8]) -> Option<(f64, f64)> {
+    let mut i = 2;
+    while i + 9 <= data.len() {
+        if data[i] != 0xFF {
+            return None;
+        }
+        let marker = data[i + 1];
+        match marker {
+            // Fill byte before a marker.
+            0xFF => i += 1,
+            // Markers without a length.
+            0x01 | 0xD0..=0xD7 => i += 2,
+            // Start of frame; C4, C8 and CC are other tables.
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let height = u16::from_be_bytes([data[i + 5], data[i + 6]]);
+                let width = u16::from_be_bytes([data[i + 7], data[i + 8]]);
+                return Some((width as f64, height as f64));
+            }
+            _ => i += 2 + u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize,
+        }
+    }
+    None
+}
+
+// Reads the size of an SVG from its root element, as a renderer would: the
+// width and height, with a missing one following the viewBox aspect ratio,
+// or the viewBox alone.
+fn svg_size(text: &str) -> Option<(f64, f64)> {
+    let start = text.find("<svg")?;
+    let tag = &text[start..start + text[start..].find('>')?];
+    let width = attribute(tag, "width").and_then(length);
+    let height = attribute(tag, "height").and_then(length);
+    let view_box = attribute(tag, "viewBox").and_then(|v| {
+        let numbers: Vec<f64> = v
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|n| !n.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .ok()?;
+        match numbers[..] {
+            [_, _, w, h] if w > 0.0 && h > 0.0 => Some((w, h)),
+            _ => None,
+        }
+    });
+    match (width, height, view_box) {
+        (Some(w), Some(h), _) => Some((w, h)),
+        (Some(w), None, Some((vw, vh))) => Some((w, w * vh / vw)),
+        (None, Some(h), Some((vw, vh))) => Some((h * vw / vh, h)),
+        (None, None, view_box) => view_box,
+        _ => None,
+    }
+}
+
+// Returns the value of attribute `name` in the start tag `tag`.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(found) = rest.find(name) {
+        let before = rest[..found].chars().next_back();
+        let after = rest[found + name.len()..].trim_start();
+        rest = &rest[found + name.len()..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+// Converts an absolute CSS length to pixels; percentages need a viewport, so
+// they are treated as missing.
+fn length(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (number, px_per_unit) = [
+        ("px", 1.0),
+        ("in", 96.0),
+        ("cm", 96.0 / 2.54),
+        ("mm", 96.0 / 25.4),
+        ("pt", 4.0 / 3.0),
+        ("pc", 16.0),
+    ]
+    .iter()
+    .find_map(|&(unit, scale)| value.strip_suffix(unit).map(|n| (n, scale)))
+    .unwrap_or((value, 1.0));
+    number.trim().parse::<f64>().ok().map(|n| n * px_per_unit)
+}
+
+fn build_document(
+    config: &GreekKeyFrameConfig,
+    artwork: &Artwork,
+    visual: &VisualOptions,
+) -> Result<Document, Box<dyn Error>> {
+    let (width, height) = (artwork.width, artwork.height);
+    let clip = ClipPath::new().set("id", ARTWORK_CLIP_ID);
+    // Each border comes with its inner frame as the clip shape and the box the
+    // artwork is scaled to cover.
+    let (mut document, clip, (x, y, w, h)) = match config.shape {
+        FrameShape::Rect => {
+            let border = config.rect_config(width, height)?;
+            let (x, y, w, h) = border.get_inner_frame_size();
+            let inner = Rectangle::new()
+                .set("x", x)
+                .set("y", y)
+                .set("width", w)
+                .set("height", h);
+            (
+                rect::build_document(&border, visual),
+                clip.add(inner),
+                (x, y, w as f64, h as f64),
+            )
+        }
+        FrameShape::Circle => {
+            let border = config.circle_config(width.min(height) / 2.0)?;
+            let centre = border.get_centre();
+            let r = border.radii.r_i;
+            let inner = Circle::new()
+                .set("cx", centre.x)
+                .set("cy", centre.y)
+                .set("r", r);
+            (
+                circle::build_document(&border, visual),
+                clip.add(inner),
+                (centre.x - r, centre.y - r, 2.0 * r, 2.0 * r),
+            )
+        }
+        FrameShape::Ellipse => {
+            let border = config.ellipse_config(width / 2.0, height / 2.0)?;
+            let centre = border.get_centre();
+            let (rx, ry) = (border.ellipse_radii.rx_i, border.ellipse_radii.ry_i);
+            let inner = Ellipse::new()
+                .set("cx", centre.x)
+                .set("cy", centre.y)
+                .set("rx", rx)
+                .set("ry", ry);
+            (
+                ellipse::build_document(&border, visual),
+                clip.add(inner),
+                (centre.x - rx, centre.y - ry, 2.0 * rx, 2.0 * ry),
+            )
+        }
+    };
+    let image = Image::new()
+        .set("x", x)
+        .set("y", y)
+        .set("width", w)
+        .set("height", h)
+        .set("preserveAspectRatio", "xMidYMid slice")
+        .set("clip-path", format!("url(#{ARTWORK_CLIP_ID})"))
+        .set("href", artwork.data_uri());
+    // Above the background and below the border, so the inner frame covers
+    // the clipped edge.
+    if let Some(children) = Node::get_children_mut(&mut document) {
+        let border = children
+            .iter()
+            .position(|child| has_stroke(child.as_ref()))
+            .unwrap_or(children.len());
+        children.insert(border, Box::new(image));
+        children.insert(0, Box::new(Definitions::new().add(clip)));
+    }
+    Ok(document)
+}
+
+/// Returns `artwork` framed by a Greek Key border as an SVG string.
+///
+/// The border is sized to fit the artwork, which is scaled to cover the inner
+/// frame and clipped to it. Returns an error if the border cannot be built at
+/// that size.
+///
+/// Available on all targets including WASM. For file output, use
+/// [`generate_pattern_svg`] (requires the `native` feature).
+pub fn generate_svg_string(
+    config: &GreekKeyFrameConfig,
+    artwork: &Artwork,
+    visual: &VisualOptions,
+) -> Result<String, Box<dyn Error>> {
+    let visual = metadata::stamped(
+        visual,
+        config.stroke_width,
+        config.border_margin,
+        "frame",
+        vec![
+            ("shape", config.shape.as_str().into()),
+            ("size", config.key_unit_length.into()),
+            ("pattern_count", config.pattern_count.into()),
+        ],
+    );
+    Ok(compact::to_string(
+        build_document(config, artwork, &visual)?,
+        &visual,
+    ))
+}
+
+/// Frames `artwork` with a Greek Key border and writes `<filename>.svg` and `<filename>.png`.
+///
+/// Requires the `native` feature (enabled by default). For WASM targets, use
+/// [`generate_svg_string`] instead.
+#[cfg(feature = "native")]
+pub fn generate_pattern_svg(
+    config: &GreekKeyFrameConfig,
+    artwork: &Artwork,
+    visual: &VisualOptions,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    save_and_convert_svg(&generate_svg_string(config, artwork, visual)?, filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = PNG_MAGIC.to_vec();
+        data.extend([0, 0, 0, 13]);
+        data.extend(b"IHDR");
+        data.extend(width.to_be_bytes());
+        data.extend(height.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn image_sizes_are_read_from_headers() {
+        let artwork = Artwork::from_bytes(png(640, 480)).unwrap();
+        assert_eq!(artwork.format(), ArtworkFormat::Png);
+        assert_eq!((artwork.width(), artwork.height()), (640.0, 480.0));
+
+        // SOI, an APP0 segment, then a baseline start of frame.
+        let jpeg = vec![
+            0xFF, 0xD8, 0xFF, 0xE0, 0, 4, 0, 0, 0xFF, 0xC0, 0, 17, 8, 1, 0xE0, 2, 0x80, 3,
+        ];
+        let artwork = Artwork::from_bytes(jpeg).unwrap();
+        assert_eq!(artwork.format(), ArtworkFormat::Jpeg);
+        assert_eq!((artwork.width(), artwork.height()), (640.0, 480.0));
+    }
+
+    #[test]
+    fn svg_size_follows_width_height_and_view_box() {
+        let size = |svg: &str| svg_size(svg);
+        assert_eq!(
+            size(r#"<svg stroke-width="3" width="2in" height="96">"#),
+            Some((192.0, 96.0))
+        );
+        assert_eq!(
+            size(r#"<?xml version="1.0"?><svg viewBox="0 0 40 30" width="80">"#),
+            Some((80.0, 60.0))
+        );
+        assert_eq!(
+            size(r#"<svg width="100%" viewBox='0,0,40,30'>"#),
+            Some((40.0, 30.0))
+        );
+        assert_eq!(size(r#"<svg width="100%">"#), None);
+        assert!(Artwork::from_bytes(br#"<svg width="100%"/>"#.to_vec()).is_err());
+    }
+
+    #[test]
+    fn unknown_formats_are_rejected() {
+        let e = Artwork::from_bytes(b"GIF89a".to_vec()).unwrap_err();
+        assert!(e.to_string().contains("PNG, JPEG or SVG"));
+    }
+
+    #[test]
+    fn artwork_sits_between_background_and_border() {
+        let artwork = Artwork::from_bytes(png(640, 480)).unwrap();
+        let visual = VisualOptions {
+            background_color: Some("#101010".into()),
+            ..VisualOptions::default()
+        };
+        for shape in [FrameShape::Rect, FrameShape::Circle, FrameShape::Ellipse] {
+            let config = GreekKeyFrameConfig::new(shape, 10, 30, 1, 3.0).unwrap();
+            let svg = generate_svg_string(&config, &artwork, &visual).unwrap();
+            let image = svg.find("<image").unwrap();
+            assert!(svg.find(r##"fill="#101010""##).unwrap() < image);
+            assert!(image < svg.find("<path").unwrap());
+            assert!(svg.contains(r#"clip-path="url(#gm-artwork-clip)""#));
+            assert!(svg.contains("href=\"data:image/png;base64,"));
+        }
+    }
+
+    #[test]
+    fn rect_inner_frame_covers_the_artwork() {
+        let config = GreekKeyFrameConfig::new(FrameShape::Rect, 10, 30, 1, 3.0).unwrap();
+        let border = config.rect_config(640.0, 480.0).unwrap();
+        let (_, _, w, h) = border.get_inner_frame_size();
+        assert_eq!((w, h), (650, 500));
+    }
+
+    #[test]
+    fn circle_and_ellipse_inner_frames_match_the_artwork() {
+        let config = GreekKeyFrameConfig::new(FrameShape::Circle, 10, 30, 1, 3.0).unwrap();
+        let circle = config.circle_config(240.0).unwrap();
+        assert!((circle.radii.r_i - 240.0).abs() < 1e-9);
+        let ellipse = config.ellipse_config(320.0, 240.0).unwrap();
+        assert!((ellipse.ellipse_radii.rx_i - 320.0).abs() < 1e-9);
+        assert!((ellipse.ellipse_radii.ry_i - 240.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,216 @@
+use svg::node::element::Path as SvgPath;
+use svg::node::element::path::{Command, Data, Position};
+
+use crate::common::Point;
+use crate::config::Inlay;
+
+/// Maximum deviation in pixels when flattening curves for the offset lines,
+/// unless `arc_tolerance` asks for something coarser.
+pub(crate) const INLAY_ARC_TOLERANCE: f64 = 0.05;
+
+// Points closer than this are treated as one, so repeated points and the
+// closing point of a closed subpath do not produce zero-length segments.
+const SAME_POINT: f64 = 1e-3;
+
+/// Returns the subpaths of `data` as polylines, each with whether it is closed.
+///
+/// Handles move, line and close commands only; curves must be flattened into
+/// line segments first.
+fn polylines(data: &Data) -> Vec<(Vec<Point>, bool)> {
+    let mut subpaths: Vec<(Vec<Point>, bool)> = Vec::new();
+    let mut current = Point { x: 0.0, y: 0.0 };
+    for command in data.iter() {
+        let (position, parameters, is_move) = match command {
+            Command::Move(position, parameters) => (position, parameters, true),
+            Command::Line(position, parameters) => (position, parameters, false),
+            Command::Close => {
+                if let Some((points, closed)) = subpaths.last_mut() {
+                    *closed = true;
+                    current = points[0];
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        for (i, pair) in parameters.chunks_exact(2).enumerate() {
+            let (x, y) = (pair[0] as f64, pair[1] as f64);
+            current = match position {
+                Position::Absolute => Point { x, y },
+                Position::Relative => Point {
+                    x: current.x + x,
+                    y: current.y + y,
+                },
+            };
+            // Extra pairs after a move are implicit lines.
+            if is_move && i == 0 {
+                subpaths.push((vec![current], false));
+            } else if let Some((points, _)) = subpaths.last_mut() {
+                points.push(current);
+            }
+        }
+    }
+
+    for (points, closed) in &mut subpaths {
+        points.dedup_by(|a, b| distance(*a, *b) < SAME_POINT);
+        if *closed && points.len() > 1 && distance(points[0], points[points.len() - 1]) < SAME_POINT
+        {
+            points.pop();
+        }
+    }
+    subpaths.retain(|(points, _)| points.len() > 1);
+    subpaths
+}
+
+fn distance(p: Point, q: Point) -> f64 {
+    (q.x - p.x).hypot(q.y - p.y)
+}
+
+// Unit normal to the right of travel from `p` to `q` in SVG coordinates.
+fn normal(p: Point, q: Point) -> Point {
+    let len = distance(p, q);
+    Point {
+        x: -(q.y - p.y) / len,
+        y: (q.x - p.x) / len,
+    }
+}
+
+/// Returns `points` moved `offset` to the right of the direction of travel.
+///
+/// Neighbouring offset segments are extended to meet, giving mitred corners
+/// that match the centreline's own.
+fn offset_polyline(points: &[Point], closed: bool, offset: f64) -> Vec<Point> {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let p = points[i];
+            let before = if i > 0 {
+                Some(normal(points[i - 1], p))
+            } else if closed {
+                Some(normal(points[n - 1], p))
+            } else {
+                None
+            };
+            let after = if i + 1 < n {
+                Some(normal(p, points[i + 1]))
+            } else if closed {
+                Some(normal(p, points[0]))
+            } else {
+                None
+            };
+            let (a, b) = match (before, after) {
+                (Some(a), Some(b)) => (a, b),
+                (Some(a), None) | (None, Some(a)) => (a, a),
+                (None, None) => unreachable!("polylines have at least two points"),
+            };
+            // The miter point lies along a + b, which has squared length
+            // 2(1 + a·b); scaling it by offset / (1 + a·b) puts it offset / cos(θ/2) out.
+            let denominator = 1.0 + a.x * b.x + a.y * b.y;
+            if denominator < 1e-6 {
+                // The path doubles back on itself; there is no miter to take.
+                return Point {
+                    x: p.x + a.x * offset,
+                    y: p.y + a.y * offset,
+                };
+            }
+            let scale = offset / denominator;
+            Point {
+                x: p.x + (a.x + b.x) * scale,
+                y: p.y + (a.y + b.y) * scale,
+            }
+        })
+        .collect()
+}
+
+/// Returns the paths drawing `data` as a double line.
+///
+/// Each of `bands` is stroked `gap` wide along its centreline in its paint,
+/// filling the space between the lines; the two lines, `line` in paint, then
+/// run either side of every subpath of `data`, which must hold only straight
+/// segments.
+pub(crate) fn inlay_paths(
+    inlay: &Inlay,
+    data: &Data,
+    bands: Vec<(Data, String)>,
+    line: &str,
+    opacity: f32,
+    dash: Option<&str>,
+) -> Vec<SvgPath> {
+    let mut paths: Vec<SvgPath> = bands
+        .into_iter()
+        .map(|(band, paint)| {
+            SvgPath::new()
+                .set("fill", "none")
+                .set("stroke", paint)
+                .set("stroke-width", inlay.gap)
+                .set("stroke-opacity", opacity)
+                .set("d", band)
+        })
+        .collect();
+
+    let offset = (inlay.gap + inlay.line_width) / 2.0;
+    let mut lines = Data::new();
+    for (points, closed) in polylines(data) {
+        for side in [offset, -offset] {
+            let edge = offset_polyline(&points, closed, side);
+            lines = lines.move_to((edge[0].x, edge[0].y));
+            for point in &edge[1..] {
+                lines = lines.line_to((point.x, point.y));
+            }
+            if closed {
+                lines = lines.close();
+            }
+        }
+    }
+    let lines = SvgPath::new()
+        .set("fill", "none")
+        .set("stroke", line)
+        .set("stroke-width", inlay.line_width)
+        .set("stroke-opacity", opacity)
+        .set("d", lines);
+    paths.push(match dash {
+        Some(d) => lines.set("stroke-dasharray", d),
+        None => lines,
+    });
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_closed_path_becomes_one_closed_polyline() {
+        let data = Data::new()
+            .move_to((0, 0))
+            .line_by((10, 0))
+            .line_by((0, 10))
+            .line_by((-10, 0))
+            .line_by((0, -10))
+            .close();
+        let subpaths = polylines(&data);
+        assert_eq!(subpaths.len(), 1);
+        let (points, closed) = &subpaths[0];
+        assert!(*closed);
+        assert_eq!(points.len(), 4);
+    }
+
+    #[test]
+    fn offset_square_keeps_square_corners() {
+        let square: Vec<Point> = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]
+            .into_iter()
+            .map(|(x, y)| Point { x, y })
+            .collect();
+        // Clockwise on screen, so positive offsets move inwards.
+        let inner = offset_polyline(&square, true, 2.0);
+        assert!((inner[0].x - 2.0).abs() < 1e-9 && (inner[0].y - 2.0).abs() < 1e-9);
+        assert!((inner[2].x - 8.0).abs() < 1e-9 && (inner[2].y - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn open_polyline_ends_are_offset_square() {
+        let line = [Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 0.0 }];
+        let below = offset_polyline(&line, false, 1.5);
+        assert!((below[0].y - 1.5).abs() < 1e-9 && below[0].x.abs() < 1e-9);
+        assert!((below[1].y - 1.5).abs() < 1e-9 && (below[1].x - 10.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::file_config::{self, FileConfig};
+use crate::preset::Preset;
+use crate::schema;
+use crate::validate;
+
+/// Prefix of the environment variables that set options, as in
+/// `GREEK_MEANDER_STROKE_COLOR` or `GREEK_MEANDER_CIRCLE_RADIUS`.
+pub const ENV_PREFIX: &str = "GREEK_MEANDER_";
+
+/// Name of the project config, looked for in the current directory and each
+/// one above it.
+pub const PROJECT_CONFIG: &str = "greek-meander.toml";
+
+/// Where an option's value came from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Default,
+    Theme(String),
+    User(PathBuf),
+    Preset(Preset),
+    Project(PathBuf),
+    File(PathBuf),
+    Job(String),
+    Profile(String, PathBuf),
+    Env(String),
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::Theme(name) => write!(f, "theme {name}"),
+            Source::User(path) => write!(f, "user config {}", path.display()),
+            Source::Preset(preset) => {
+                let value = preset.to_possible_value().expect("presets have names");
+                write!(f, "preset {}", value.get_name())
+            }
+            Source::Project(path) => write!(f, "project config {}", path.display()),
+            Source::File(path) => write!(f, "--config {}", path.display()),
+            Source::Job(name) => write!(f, "job '{name}'"),
+            Source::Profile(name, path) => write!(f, "profile '{name}' in {}", path.display()),
+            Source::Env(name) => write!(f, "{name}"),
+            Source::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+impl Source {
+    /// The config file the source names, if any.
+    fn path(&self) -> Option<&Path> {
+        match self {
+            Source::User(path) | Source::Project(path) | Source::File(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+/// Options from one source, as a config table.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub source: Source,
+    pub table: toml::Table,
+}
+
+impl Layer {
+    pub fn new(source: Source, config: &FileConfig) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            source,
+            table: toml::Table::try_from(config)?,
+        })
+    }
+}
+
+/// The user config, `$XDG_CONFIG_HOME/greek-meander/config.toml`, or under
+/// `~/.config` when `XDG_CONFIG_HOME` is unset.
+pub fn user_config_path() -> Option<PathBuf> {
+    let absolute =
+        |value: std::ffi::OsString| Some(PathBuf::from(value)).filter(|p| p.is_absolute());
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .and_then(absolute)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .and_then(absolute)
+                .map(|home| home.join(".config"))
+        })?;
+    Some(base.join("greek-meander").join("config.toml"))
+}
+
+/// The nearest [`PROJECT_CONFIG`] in `start` or a directory above it.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG))
+        .find(|path| path.is_file())
+}
+
+/// Splits the profiles off the configs of `files` and, if `profile` is given,
+/// puts the profile of that name after each config that defines it. Fails if
+/// none does.
+pub fn with_profile(
+    files: Vec<(Source, FileConfig)>,
+    profile: Option<&str>,
+) -> Result<Vec<Layer>, Box<dyn Error>> {
+    let mut layers = Vec::new();
+    let mut defined = BTreeSet::new();
+    let mut found = false;
+    for (source, mut config) in files {
+        let profiles = config.profile.take().unwrap_or_default();
+        layers.push(Layer::new(source.clone(), &config)?);
+        let Some(name) = profile else {
+            continue;
+        };
+        if profiles.contains_key(name) {
+            // Taken through FileConfig so a mistyped value fails here.
+            let selected = FileConfig {
+                profile: Some(profiles),
+                ..FileConfig::default()
+            }
+            .with_profile(name)?;
+            let path = source.path().unwrap_or(Path::new("")).to_path_buf();
+            layers.push(Layer::new(
+                Source::Profile(name.to_string(), path),
+                &selected,
+            )?);
+            found = true;
+        } else {
+            defined.extend(profiles.into_keys());
+        }
+    }
+    match profile {
+        Some(name) if !found => {
+            let names: Vec<&str> = defined.iter().map(String::as_str).collect();
+            Err(match names.as_slice() {
+                [] => format!("no profile named '{name}'; the config defines none"),
+                names => format!(
+                    "no profile named '{name}'; the config defines {}",
+                    names.join(", ")
+                ),
+            }
+            .into())
+        }
+        _ => Ok(layers),
+    }
+}
+
+/// The environment variable that sets the option `key`.
+pub fn env_name(key: &str) -> String {
+    format!("{ENV_PREFIX}{}", key.replace('.', "_").to_uppercase())
+}
+
+/// Reads the options set by `GREEK_MEANDER_*` variables among `vars`, one
+/// layer per variable in name order. Values are read as `--vary` and query
+/// parameters are, so lists are comma-separated.
+///
+/// Also returns a warning for each variable that names no option.
+pub fn env_layers(
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<(Vec<Layer>, Vec<String>), Box<dyn Error>> {
+    let keys: BTreeMap<String, String> = schema::option_keys()
+        .into_iter()
+        .map(|key| (env_name(&key), key))
+        .collect();
+    let mut vars: Vec<(String, String)> = vars
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect();
+    vars.sort();
+    let mut layers = Vec::new();
+    let mut warnings = Vec::new();
+    for (name, value) in vars {
+        let Some(key) = keys.get(&name) else {
+            let mut warning = format!("{name} names no option");
+            if let Some(suggestion) = validate::suggestion(&name, keys.keys()) {
+                warning.push_str(&format!("; did you mean {suggestion}?"));
+            }
+            warnings.push(warning);
+            continue;
+        };
+        let mut table = toml::Table::new();
+        file_config::set_option_text(&mut table, "", key, &value)
+            .ok_or_else(|| format!("{name}: {key} cannot take '{value}'"))?;
+        layers.push(Layer {
+            source: Source::Env(name),
+            table,
+        });
+    }
+    Ok((layers, warnings))
+}
+
+/// Lays the layers over each other, the last on top.
+pub fn merged(layers: &[Layer]) -> Result<FileConfig, Box<dyn Error>> {
+    let mut table = toml::Table::new();
+    for layer in layers {
+        file_config::merge(&mut table, layer.table.clone());
+    }
+    Ok(toml::Value::Table(table).try_into()?)
+}
+
+/// The topmost layer that sets the dotted `key`.
+pub fn source_of<'a>(layers: &'a [Layer], key: &str) -> Option<&'a Source> {
+    layers
+        .iter()
+        .rev()
+        .find(|layer| file_config::get(&layer.table, key).is_some())
+        .map(|layer| &layer.source)
+}
+
+/// Writes a config table as TOML with a comment after each option naming
+/// where it came from, found by `source` from the dotted key.
+pub fn annotated(table: &toml::Table, source: impl Fn(&str) -> String) -> String {
+    let mut blocks = vec![(None, Vec::new())];
+    for (key, value) in table {
+        match value {
+            toml::Value::Table(section) => blocks.push((
+                Some(key),
+                section
+                    .iter()
+                    .map(|(name, value)| (format!("{key}.{name}"), name, value))
+                    .collect(),
+            )),
+            value => blocks[0].1.push((key.clone(), key, value)),
+        }
+    }
+    let mut text = String::new();
+    for (section, options) in blocks {
+        if options.is_empty() {
+            continue;
+        }
+        if let Some(section) = section {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&format!("[{section}]\n"));
+        }
+        let lines: Vec<(String, String)> = options
+            .iter()
+            .map(|(dotted, name, value)| (format!("{name} = {value}"), source(dotted)))
+            .collect();
+        // Long values, such as gradients, are left out of the alignment.
+        let width = lines
+            .iter()
+            .map(|(line, _)| line.len())
+            .filter(|&len| len <= 40)
+            .max()
+            .unwrap_or(0);
+        for (line, source) in lines {
+            text.push_str(&format!("{line:width$}  # {source}\n"));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn every_option_has_its_own_variable() {
+        let keys = schema::option_keys();
+        let names: std::collections::BTreeSet<String> =
+            keys.iter().map(|key| env_name(key)).collect();
+        assert_eq!(names.len(), keys.len());
+        assert_eq!(env_name("circle.radius"), "GREEK_MEANDER_CIRCLE_RADIUS");
+    }
+
+    #[test]
+    fn variables_set_options() {
+        let (layers, warnings) = env_layers(vars(&[
+            ("GREEK_MEANDER_STROKE_COLOR", "#112233"),
+            ("GREEK_MEANDER_CIRCLE_RADIUS", "120"),
+            ("GREEK_MEANDER_KEY_COLORS", "#111111, #222222"),
+            ("GREEK_MEANDER_STROKE_COLOUR", "#445566"),
+            ("HOME", "/home/someone"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            warnings,
+            [
+                "GREEK_MEANDER_STROKE_COLOUR names no option; did you mean GREEK_MEANDER_STROKE_COLOR?"
+            ]
+        );
+        let config = merged(&layers).unwrap();
+        assert_eq!(config.circle.unwrap().radius, Some(120.0));
+        assert_eq!(config.key_colors.unwrap().len(), 2);
+        assert_eq!(
+            source_of(&layers, "stroke_color"),
+            Some(&Source::Env("GREEK_MEANDER_STROKE_COLOR".into()))
+        );
+
+        let err = env_layers(vars(&[("GREEK_MEANDER_RECT_SIZE", "big")])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "GREEK_MEANDER_RECT_SIZE: rect.size cannot take 'big'"
+        );
+    }
+
+    #[test]
+    fn profiles_follow_the_configs_that_define_them() {
+        let user: FileConfig = toml::from_str(
+            "stroke_width = 2.0\n[profile.print]\nscale = 4.0\n[profile.web]\nscale = 1.0\n",
+        )
+        .unwrap();
+        let project: FileConfig =
+            toml::from_str("stroke_width = 3.0\n[profile.print]\nstroke_width = 5.0\n").unwrap();
+        let files = || {
+            vec![
+                (Source::User("user.toml".into()), user.clone()),
+                (Source::Project("project.toml".into()), project.clone()),
+            ]
+        };
+        let layers = with_profile(files(), Some("print")).unwrap();
+        assert_eq!(layers.len(), 4);
+        let config = merged(&layers).unwrap();
+        assert_eq!(config.stroke_width, Some(5.0));
+        assert_eq!(config.scale, Some(4.0));
+        assert!(config.profile.is_none());
+        assert_eq!(
+            source_of(&layers, "scale").unwrap().to_string(),
+            "profile 'print' in user.toml"
+        );
+
+        assert_eq!(with_profile(files(), None).unwrap().len(), 2);
+        let err = with_profile(files(), Some("dark")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no profile named 'dark'; the config defines print, web"
+        );
+    }
+
+    #[test]
+    fn tables_are_annotated_by_section() {
+        let table: toml::Table =
+            toml::from_str("stroke_width = 6.0\nfile = \"out\"\n[rect]\nsize = 25\n").unwrap();
+        let text = annotated(&table, |key| format!("from {key}"));
+        assert_eq!(
+            text,
+            "file = \"out\"        # from file\nstroke_width = 6.0  # from stroke_width\n\n[rect]\nsize = 25  # from rect.size\n"
+        );
+        assert_eq!(text.parse::<toml::Table>().unwrap(), table);
+    }
+
+    #[test]
+    fn project_config_is_found_above() {
+        let dir = std::env::temp_dir().join(format!("greek-meander-layers-{}", std::process::id()));
+        let nested = dir.join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(dir.join(PROJECT_CONFIG), "").unwrap();
+        assert_eq!(find_project_config(&nested), Some(dir.join(PROJECT_CONFIG)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
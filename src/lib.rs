@@ -1,7 +1,10 @@
 //! Greek Key (Meander) pattern generator.
 //!
 //! Generates SVG and PNG files containing Greek Key (Meander) border designs
-//! in rectangle and circle forms.
+//! in rectangle, circle, ellipse and superellipse forms, and as spiral bands.
+//! A border can also be sized to frame an existing image; see [`frame`], and
+//! carry a title and ring text; see [`text`]. Several designs can be laid out
+//! side by side on a labelled contact sheet; see [`sheet`].
 //!
 //! # Features
 //!
@@ -71,6 +74,39 @@
 //! # #[cfg(not(feature = "native"))] fn main() {}
 //! ```
 //!
+//! # Superellipse example
+//!
+//! ```
+//! # #[cfg(feature = "native")] fn main() {
+//! use greek_meander::{GreekKeySuperellipseConfig, VisualOptions};
+//!
+//! let path = std::env::temp_dir().join("doctest_superellipse").to_string_lossy().into_owned();
+//! let config = GreekKeySuperellipseConfig::new(200.0, 120.0, 4.0, 28, 8, 3.0).unwrap();
+//! let visual = VisualOptions::default();
+//! greek_meander::superellipse::generate_pattern_svg(&config, &visual, &path).unwrap();
+//! # let _ = std::fs::remove_file(format!("{}.svg", path));
+//! # let _ = std::fs::remove_file(format!("{}.png", path));
+//! # }
+//! # #[cfg(not(feature = "native"))] fn main() {}
+//! ```
+//!
+//! # Spiral example
+//!
+//! ```
+//! # #[cfg(feature = "native")] fn main() {
+//! use greek_meander::{GreekKeySpiralConfig, SpiralKind, VisualOptions};
+//!
+//! let path = std::env::temp_dir().join("doctest_spiral").to_string_lossy().into_owned();
+//! let config =
+//!     GreekKeySpiralConfig::new(SpiralKind::Archimedean, 3.0, 300.0, 60.0, 8.0, 8, 3.0).unwrap();
+//! let visual = VisualOptions::default();
+//! greek_meander::spiral::generate_pattern_svg(&config, &visual, &path).unwrap();
+//! # let _ = std::fs::remove_file(format!("{}.svg", path));
+//! # let _ = std::fs::remove_file(format!("{}.png", path));
+//! # }
+//! # #[cfg(not(feature = "native"))] fn main() {}
+//! ```
+//!
 //! # WASM / SVG-string example
 //!
 //! ```
@@ -85,18 +121,40 @@
 //!
 //! With the `wasm` feature enabled, [`wasm::rect_generate_svg`] and
 //! [`wasm::circle_generate_svg`] expose JavaScript-callable functions that
-//! return SVG markup.
+//! return SVG markup. Each takes the shape's geometry and one
+//! [`wasm::SvgOptions`] holding the shared drawing options.
 
 pub mod circle;
+mod color;
 pub(crate) mod common;
+mod compact;
 pub mod config;
+pub mod effects;
 pub mod ellipse;
+pub mod field;
+pub mod frame;
+mod inlay;
+pub mod metadata;
+pub mod paint;
 pub mod rect;
+pub mod sheet;
+pub mod spiral;
+pub mod superellipse;
+pub mod text;
+pub mod theme;
 #[cfg(feature = "wasm")]
 pub mod wasm;
 
 pub use common::Point;
 pub use config::{
-    EllipseRadii, GreekKeyCircleConfig, GreekKeyEllipseConfig, GreekKeyRectConfig, Radii,
-    VisualOptions,
+    ElementStyle, EllipseRadii, FrameShape, GreekKeyCircleConfig, GreekKeyEllipseConfig,
+    GreekKeyFieldConfig, GreekKeyFrameConfig, GreekKeyRectConfig, GreekKeySpiralConfig,
+    GreekKeySuperellipseConfig, Inlay, LineCap, LineJoin, Radii, SpiralKind, VisualOptions,
 };
+pub use effects::Effect;
+pub use frame::Artwork;
+pub use metadata::Metadata;
+pub use paint::{GradientStop, LinearGradient, Paint, RadialGradient};
+pub use sheet::SheetEntry;
+pub use text::{Text, TextRail};
+pub use theme::Theme;
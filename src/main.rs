@@ -1,20 +1,60 @@
-use clap::Parser;
+use std::error::Error;
+use std::net::TcpListener;
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
 
 mod args;
+mod batch;
 mod cli_output;
 mod file_config;
-use args::{Args, Commands};
+mod layers;
+mod preset;
+mod schema;
+mod serve;
+mod sweep;
+mod template;
+mod validate;
+mod watch;
+use args::{
+    Args, BatchArgs, CircleArgs, Commands, ConfigArgs, ConfigCommand, DumpArgs, EllipseArgs,
+    FieldArgs, FrameArgs, InitArgs, RectArgs, ServeArgs, ShowArgs, SpiralArgs, SuperellipseArgs,
+    SweepArgs, ValidateArgs,
+};
+use batch::{Job, Outcome, Shape};
 use cli_output::OutputOptions;
+use file_config::{
+    CircleFileConfig, ConfigFormat, EllipseFileConfig, FieldAreaFileConfig, FileConfig,
+    FrameFileConfig, InlayFileConfig, RectFileConfig, SpiralFileConfig, SuperellipseFileConfig,
+};
+use layers::{Layer, Source};
+use serve::{Format, Server};
+use validate::Problem;
+use watch::Watcher;
 
 use greek_meander::{
     circle,
-    config::{GreekKeyCircleConfig, GreekKeyEllipseConfig, GreekKeyRectConfig, VisualOptions},
-    ellipse, rect,
+    config::{
+        ElementStyle, FrameShape, GreekKeyCircleConfig, GreekKeyEllipseConfig, GreekKeyFieldConfig,
+        GreekKeyFrameConfig, GreekKeyRectConfig, GreekKeySpiralConfig, GreekKeySuperellipseConfig,
+        Inlay, SpiralKind, VisualOptions,
+    },
+    ellipse, field,
+    frame::{self, Artwork},
+    metadata::Metadata,
+    paint::Paint,
+    rect,
+    sheet::{self, SheetEntry},
+    spiral, superellipse,
+    text::Text,
 };
 
 const DEFAULT_STROKE_WIDTH: f32 = 6.0;
 const DEFAULT_STROKE_COLOR: &str = "#AB8E0E";
 const DEFAULT_STROKE_OPACITY: f32 = 0.7;
+const DEFAULT_INLAY_LINE_WIDTH: f64 = 2.0;
 const DEFAULT_BORDER_MARGIN: i32 = 1;
 const DEFAULT_FILE: &str = "meander";
 const DEFAULT_SCALE: f32 = 1.0;
@@ -26,34 +66,797 @@ const DEFAULT_CIRCLE_RADIUS: f64 = 300.0;
 const DEFAULT_ELLIPSE_PATTERN_COUNT: i32 = 30;
 const DEFAULT_ELLIPSE_RX: f64 = 300.0;
 const DEFAULT_ELLIPSE_RY: f64 = 200.0;
+const DEFAULT_SUPERELLIPSE_PATTERN_COUNT: i32 = 30;
+const DEFAULT_SUPERELLIPSE_RX: f64 = 300.0;
+const DEFAULT_SUPERELLIPSE_RY: f64 = 200.0;
+const DEFAULT_SUPERELLIPSE_EXPONENT: f64 = 4.0;
+const DEFAULT_SPIRAL_KIND: SpiralKind = SpiralKind::Archimedean;
+const DEFAULT_SPIRAL_TURNS: f64 = 3.0;
+const DEFAULT_SPIRAL_START_RADIUS: f64 = 300.0;
+const DEFAULT_SPIRAL_END_RADIUS: f64 = 60.0;
+const DEFAULT_SPIRAL_SIZE: f64 = 8.0;
+const DEFAULT_FIELD_WIDTH: f64 = 600.0;
+const DEFAULT_FIELD_HEIGHT: f64 = 400.0;
+const DEFAULT_FIELD_SIZE: f64 = 10.0;
+const DEFAULT_FRAME_SHAPE: FrameShape = FrameShape::Rect;
+const DEFAULT_FRAME_SIZE: i32 = 10;
+const DEFAULT_FRAME_PATTERN_COUNT: i32 = 30;
+const DEFAULT_SHEET_CELL_SIZE: f64 = 240.0;
+const DEFAULT_SERVE_HOST: &str = "127.0.0.1";
+const DEFAULT_SERVE_PORT: u16 = 8080;
+const DEFAULT_SERVE_CACHE_SIZE: usize = 128;
+const MAX_SERVED_CANVAS_SIDE: f64 = 8192.0;
+const MAX_SERVED_CANVAS_AREA: f64 = 16_777_216.0;
+const MAX_SERVED_PNG_AREA: f64 = 16_777_216.0;
+const MAX_SERVED_KEYS: f64 = 20_000.0;
+const MAX_SERVED_TURNS: f64 = 100.0;
+
+// Stores the resolved options in the SVG as a config file, so the design can be
+// rebuilt from it.
+fn stamped(visual: &VisualOptions, config: FileConfig) -> VisualOptions {
+    let mut visual = visual.clone();
+    visual.metadata.config = toml::to_string(&config).ok();
+    visual
+}
+
+// The command whose section a stored config holds.
+fn reproduced_command(cfg: &FileConfig) -> Option<Commands> {
+    let command = if cfg.rect.is_some() {
+        Commands::Rect(RectArgs::default())
+    } else if cfg.circle.is_some() {
+        Commands::Circle(CircleArgs::default())
+    } else if cfg.ellipse.is_some() {
+        Commands::Ellipse(EllipseArgs::default())
+    } else if cfg.superellipse.is_some() {
+        Commands::Superellipse(SuperellipseArgs::default())
+    } else if cfg.spiral.is_some() {
+        Commands::Spiral(SpiralArgs::default())
+    } else if cfg.field.is_some() {
+        Commands::Field(FieldArgs::default())
+    } else if cfg.frame.is_some() {
+        Commands::Frame(FrameArgs::default())
+    } else {
+        return None;
+    };
+    Some(command)
+}
 
 fn main() {
-    let args = Args::parse();
-
-    let file_cfg = match &args.config {
-        Some(path) => match file_config::load(path) {
-            Ok(cfg) => cfg,
-            Err(e) => {
-                eprintln!("Error: {}", e);
-                std::process::exit(1);
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let result = match &args.command {
+        _ if args.watch => run_watch(args),
+        Commands::Batch(batch_args) => run_batch(&args, batch_args),
+        Commands::Sweep(sweep_args) => run_sweep(&args, sweep_args),
+        Commands::Serve(serve_args) => run_serve(&args, serve_args),
+        Commands::Config(config_args) => run_config(&args, &matches, config_args),
+        Commands::Validate(validate_args) => run_validate(&args, validate_args),
+        _ => run(args),
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+// Loads the config for a single design and renders it.
+fn run(mut args: Args) -> Result<(), Box<dyn Error>> {
+    let file_cfg = match (&args.command, &args.config) {
+        (Commands::Reproduce(_), Some(_)) => {
+            return Err("reproduce reads its config from the SVG; remove --config".into());
+        }
+        (Commands::Reproduce(_), None) if args.profile.is_some() || args.preset.is_some() => {
+            return Err(
+                "reproduce reads its config from the SVG; remove --profile and --preset".into(),
+            );
+        }
+        (Commands::Reproduce(reproduce), None) => file_config::load_svg(&reproduce.input)?,
+        _ => design_config(&args, None)?,
+    };
+    // reproduce runs the stored command, writing next to its input unless
+    // --file is set. An export keeps the stored file name, so the exported
+    // config differs from the stored one only by the flags passed.
+    let mut export = None;
+    if let Commands::Reproduce(reproduce) = &args.command {
+        let command = reproduced_command(&file_cfg).ok_or_else(|| {
+            format!(
+                "the config stored in '{}' names no shape",
+                reproduce.input.display()
+            )
+        })?;
+        export = reproduce.export.clone();
+        if export.is_none() {
+            args.file = args.file.or_else(|| {
+                let base = reproduce.input.with_extension("");
+                Some(format!("{}-reproduced", base.display()))
+            });
+        }
+        args.command = command;
+    }
+    let no_clobber = args.no_clobber;
+    let rendered = render(args, file_cfg)?;
+    // With reproduce --export, the stored config is the only output.
+    match export {
+        Some(path) => cli_output::write_atomically(
+            &path,
+            rendered.config.as_deref().unwrap_or("").as_bytes(),
+            no_clobber,
+        ),
+        None => rendered.write(),
+    }
+}
+
+// Renders, then renders again each time the config file changes. Errors are
+// reported and the watch carries on.
+fn run_watch(args: Args) -> Result<(), Box<dyn Error>> {
+    let Some(path) = args.config.clone() else {
+        return Err("--watch needs a --config file to watch".into());
+    };
+    if matches!(
+        args.command,
+        Commands::Reproduce(_)
+            | Commands::Batch(_)
+            | Commands::Sweep(_)
+            | Commands::Serve(_)
+            | Commands::Config(_)
+            | Commands::Validate(_)
+    ) {
+        return Err("--watch works only with shape commands".into());
+    }
+    let mut watcher = Watcher::new(&path);
+    eprintln!(
+        "Watching '{}' for changes; press Ctrl+C to stop",
+        path.display()
+    );
+    loop {
+        match run(args.clone()) {
+            Ok(()) => eprintln!("Rendered from '{}'", path.display()),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+        watcher.wait();
+    }
+}
+
+// Renders every job in a manifest. Options given before `batch` apply to all
+// of them.
+fn run_batch(args: &Args, batch_args: &BatchArgs) -> Result<(), Box<dyn Error>> {
+    if args.config.is_some() {
+        return Err("batch reads its defaults from the manifest; remove --config".into());
+    }
+    if args.file.is_some() {
+        return Err("batch jobs name their own outputs; remove --file".into());
+    }
+    if args.stdout {
+        return Err("batch cannot write to stdout; remove --stdout".into());
+    }
+    let jobs = batch::load(&batch_args.manifest)?
+        .into_iter()
+        .map(|mut job| {
+            let file = (Source::Job(job.name.clone()), job.config.clone());
+            job.config = design_config(args, Some(file))
+                .map_err(|e| format!("job '{}' in manifest: {}", job.name, e))?;
+            Ok(job)
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+    batch::report(&render_jobs(args, jobs, batch_args.jobs))
+}
+
+// Renders every combination of the swept options, then lays them out on a
+// contact sheet labelled with their values.
+fn run_sweep(args: &Args, sweep_args: &SweepArgs) -> Result<(), Box<dyn Error>> {
+    if args.stdout {
+        return Err("sweep cannot write to stdout; remove --stdout".into());
+    }
+    let cell_size = sweep_args.cell_size.unwrap_or(DEFAULT_SHEET_CELL_SIZE);
+    if !(cell_size > 0.0 && cell_size.is_finite()) {
+        return Err("--cell-size must be a positive finite number".into());
+    }
+    let base = design_config(args, None)?;
+    let file = args
+        .file
+        .clone()
+        .or(base.file.clone())
+        .unwrap_or_else(|| DEFAULT_FILE.to_string());
+    let scale = args.scale.or(base.scale).unwrap_or(DEFAULT_SCALE);
+    let output_options = OutputOptions {
+        no_clobber: args.no_clobber,
+        ..OutputOptions::new(!args.no_svg, !args.no_png, false, scale)?
+    };
+    // The sheet's name is filled in from the options the variants share.
+    let mut shared = toml::Table::try_from(default_config())?;
+    file_config::merge(&mut shared, toml::Table::try_from(&base)?);
+    let sheet_file = cli_output::output_name(
+        &format!("{file}-sheet"),
+        sweep_args.shape.section(),
+        &shared,
+    )?;
+    let sheet_file = in_out_dir(args.out_dir.as_deref(), sheet_file);
+    let variants = sweep::variants(
+        sweep_args.shape,
+        &toml::Table::try_from(&base)?,
+        &sweep_args.vary,
+        &file,
+    )?;
+    let (jobs, labels): (Vec<_>, Vec<_>) = variants
+        .into_iter()
+        .map(|variant| (variant.job, variant.label))
+        .unzip();
+    let outcomes = render_jobs(args, jobs, sweep_args.jobs);
+
+    let entries: Vec<SheetEntry> = outcomes
+        .iter()
+        .zip(labels)
+        .map(|(outcome, label)| SheetEntry {
+            artwork: outcome
+                .result
+                .as_ref()
+                .ok()
+                .and_then(|svg| Artwork::from_bytes(svg.clone().into_bytes()).ok()),
+            label,
+        })
+        .collect();
+    // A grid of two options puts the last one along each row.
+    let columns = match (sweep_args.columns, sweep_args.vary.as_slice()) {
+        (Some(columns), _) => columns.get(),
+        (None, [_, .., last]) => last.values.len(),
+        (None, _) => (entries.len() as f64).sqrt().ceil() as usize,
+    };
+    let sheet = sheet::generate_svg_string(&entries, columns, cell_size);
+    cli_output::write_outputs(sheet.as_bytes(), &sheet_file, &output_options)?;
+    println!("contact sheet: {sheet_file}");
+    batch::report(&outcomes)
+}
+
+// Loads a config file. Keys that name no option are reported as warnings, or
+// fail the run under --strict.
+fn load_config(
+    args: &Args,
+    path: &Path,
+    format: Option<ConfigFormat>,
+) -> Result<FileConfig, Box<dyn Error>> {
+    let config = file_config::load(path, format)?;
+    let problems = validate::check_keys(path, format)?;
+    if args.strict && !problems.is_empty() {
+        let lines: Vec<String> = problems
+            .iter()
+            .map(|problem| format!("  {}", problem.located(path)))
+            .collect();
+        return Err(format!(
+            "unknown keys in config file '{}' (--strict):\n{}",
+            path.display(),
+            lines.join("\n")
+        )
+        .into());
+    }
+    for problem in problems {
+        eprintln!("warning: {} (ignored)", problem.located(path));
+    }
+    Ok(config)
+}
+
+// Collects the config of a design as layers, lowest first: the user config,
+// the --preset, the project config, the --profile of each config file, and
+// GREEK_MEANDER_* variables. `file`, or else the --config file, takes the
+// project config's place. --isolated keeps only `file` or --config, and the
+// --preset.
+fn config_layers(
+    args: &Args,
+    file: Option<(Source, FileConfig)>,
+) -> Result<Vec<Layer>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    if let Some(path) = layers::user_config_path().filter(|path| !args.isolated && path.is_file()) {
+        let config = load_config(args, &path, None)?;
+        files.push((Source::User(path), config));
+    }
+    if let Some(preset) = args.preset {
+        files.push((Source::Preset(preset), preset.config()));
+    }
+    let file = match (file, &args.config) {
+        (Some(file), _) => Some(file),
+        (None, Some(path)) => Some((
+            Source::File(path.clone()),
+            load_config(args, path, args.config_format)?,
+        )),
+        (None, None) if args.isolated => None,
+        (None, None) => match std::env::current_dir()
+            .ok()
+            .and_then(|dir| layers::find_project_config(&dir))
+        {
+            Some(path) => {
+                let config = load_config(args, &path, None)?;
+                Some((Source::Project(path), config))
             }
+            None => None,
         },
-        None => file_config::FileConfig::default(),
     };
+    files.extend(file);
+    let mut layers = layers::with_profile(files, args.profile.as_deref())?;
+    if !args.isolated {
+        let vars = std::env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        });
+        let (env, warnings) = layers::env_layers(vars)?;
+        if args.strict && !warnings.is_empty() {
+            return Err(format!(
+                "unknown {}* variables (--strict):\n  {}",
+                layers::ENV_PREFIX,
+                warnings.join("\n  ")
+            )
+            .into());
+        }
+        for warning in warnings {
+            eprintln!("warning: {} (ignored)", warning);
+        }
+        layers.extend(env);
+    }
+    Ok(layers)
+}
 
-    // CLI flags win; file config fills gaps; hardcoded defaults are the last resort.
-    let stroke_width = args
-        .stroke_width
-        .or(file_cfg.stroke_width)
-        .unwrap_or(DEFAULT_STROKE_WIDTH);
-    let stroke_color = args
-        .stroke_color
-        .or(file_cfg.stroke_color)
-        .unwrap_or_else(|| DEFAULT_STROKE_COLOR.to_string());
-    let stroke_opacity = args
-        .stroke_opacity
-        .or(file_cfg.stroke_opacity)
-        .unwrap_or(DEFAULT_STROKE_OPACITY);
+// The config of a design, all its layers merged. See `config_layers`.
+fn design_config(
+    args: &Args,
+    file: Option<(Source, FileConfig)>,
+) -> Result<FileConfig, Box<dyn Error>> {
+    layers::merged(&config_layers(args, file)?)
+}
+
+// The shapes a config has sections for.
+fn sections(config: &FileConfig) -> Vec<Shape> {
+    [
+        (Shape::Rect, config.rect.is_some()),
+        (Shape::Circle, config.circle.is_some()),
+        (Shape::Ellipse, config.ellipse.is_some()),
+        (Shape::Superellipse, config.superellipse.is_some()),
+        (Shape::Spiral, config.spiral.is_some()),
+        (Shape::Field, config.field.is_some()),
+        (Shape::Frame, config.frame.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(shape, present)| present.then_some(shape))
+    .collect()
+}
+
+// Checks a config file without rendering: unknown keys, and the option ranges
+// of each shape it is checked as. Options given before `validate` are merged
+// in as for a render.
+fn run_validate(args: &Args, validate_args: &ValidateArgs) -> Result<(), Box<dyn Error>> {
+    if args.config.is_some() {
+        return Err("validate checks the file it is given; remove --config".into());
+    }
+    let path = &validate_args.config;
+    let mut problems = validate::check_keys(path, args.config_format)?;
+    let file = file_config::load(path, args.config_format)?;
+    let config = design_config(args, Some((Source::File(path.clone()), file)))?;
+    let mut shapes = validate_args.shapes.clone();
+    if shapes.is_empty() {
+        shapes = sections(&config);
+    }
+    if shapes.is_empty() {
+        shapes.push(Shape::Rect);
+    }
+    for shape in shapes {
+        let args = Args {
+            command: shape.command(),
+            ..args.clone()
+        };
+        if let Err(e) = build(args, config.clone()) {
+            problems.push(Problem::new(format!("as a {}: {}", shape.section(), e)));
+        }
+    }
+    for problem in &problems {
+        println!("{}", problem.located(path));
+    }
+    match problems.len() {
+        0 => {
+            println!("{}: ok", path.display());
+            Ok(())
+        }
+        1 => Err(format!("1 problem in '{}'", path.display()).into()),
+        count => Err(format!("{} problems in '{}'", count, path.display()).into()),
+    }
+}
+
+fn run_config(
+    args: &Args,
+    matches: &ArgMatches,
+    config_args: &ConfigArgs,
+) -> Result<(), Box<dyn Error>> {
+    match &config_args.command {
+        ConfigCommand::Schema => print!("{}", schema::config_schema_text()),
+        ConfigCommand::Show(show_args) => print!("{}", show_config(args, matches, show_args)?),
+        ConfigCommand::Init(init_args) => init_config(init_args)?,
+        ConfigCommand::Dump(dump_args) => print!("{}", dump_config(args, dump_args)?),
+    }
+    Ok(())
+}
+
+// Every option that has a default, set to it.
+fn default_config() -> FileConfig {
+    let visual = VisualOptions::new(DEFAULT_STROKE_COLOR, DEFAULT_STROKE_OPACITY);
+    FileConfig {
+        inlay: Some(InlayFileConfig {
+            line_width: Some(DEFAULT_INLAY_LINE_WIDTH),
+            ..InlayFileConfig::default()
+        }),
+        rect: Some(RectFileConfig {
+            size: Some(DEFAULT_RECT_SIZE),
+            width: Some(DEFAULT_RECT_WIDTH),
+            height: Some(DEFAULT_RECT_HEIGHT),
+        }),
+        circle: Some(CircleFileConfig {
+            pattern_count: Some(DEFAULT_CIRCLE_PATTERN_COUNT),
+            radius: Some(DEFAULT_CIRCLE_RADIUS),
+        }),
+        ellipse: Some(EllipseFileConfig {
+            pattern_count: Some(DEFAULT_ELLIPSE_PATTERN_COUNT),
+            rx: Some(DEFAULT_ELLIPSE_RX),
+            ry: Some(DEFAULT_ELLIPSE_RY),
+        }),
+        superellipse: Some(SuperellipseFileConfig {
+            pattern_count: Some(DEFAULT_SUPERELLIPSE_PATTERN_COUNT),
+            rx: Some(DEFAULT_SUPERELLIPSE_RX),
+            ry: Some(DEFAULT_SUPERELLIPSE_RY),
+            exponent: Some(DEFAULT_SUPERELLIPSE_EXPONENT),
+        }),
+        spiral: Some(SpiralFileConfig {
+            kind: Some(DEFAULT_SPIRAL_KIND),
+            turns: Some(DEFAULT_SPIRAL_TURNS),
+            start_radius: Some(DEFAULT_SPIRAL_START_RADIUS),
+            end_radius: Some(DEFAULT_SPIRAL_END_RADIUS),
+            size: Some(DEFAULT_SPIRAL_SIZE),
+        }),
+        field: Some(FieldAreaFileConfig {
+            width: Some(DEFAULT_FIELD_WIDTH),
+            height: Some(DEFAULT_FIELD_HEIGHT),
+            size: Some(DEFAULT_FIELD_SIZE),
+        }),
+        frame: Some(FrameFileConfig {
+            input: None,
+            shape: Some(DEFAULT_FRAME_SHAPE),
+            size: Some(DEFAULT_FRAME_SIZE),
+            pattern_count: Some(DEFAULT_FRAME_PATTERN_COUNT),
+        }),
+        ..FileConfig::resolved(
+            &visual,
+            DEFAULT_STROKE_WIDTH,
+            DEFAULT_BORDER_MARGIN,
+            DEFAULT_FILE,
+            DEFAULT_SCALE,
+            None,
+        )
+    }
+}
+
+// Writes the config template, refusing to replace a file unless --force is
+// given.
+fn init_config(init_args: &InitArgs) -> Result<(), Box<dyn Error>> {
+    let template = template::config_template(&toml::Table::try_from(default_config())?);
+    let path = init_args
+        .path
+        .clone()
+        .unwrap_or_else(|| layers::PROJECT_CONFIG.into());
+    if path.as_os_str() == "-" {
+        print!("{template}");
+        return Ok(());
+    }
+    cli_output::write_atomically(&path, template.as_bytes(), !init_args.force)?;
+    println!("wrote {}", path.display());
+    Ok(())
+}
+
+// The config a shape command would store in its SVG: every option it renders
+// with, from the command line, the config layers, the theme and the defaults.
+fn dump_config(args: &Args, dump_args: &DumpArgs) -> Result<String, Box<dyn Error>> {
+    let args = Args {
+        command: dump_args.command.clone().into(),
+        ..args.clone()
+    };
+    let config = design_config(&args, None)?;
+    let stored = build(args, config)?.visual.metadata.config;
+    Ok(stored.unwrap_or_default())
+}
+
+// The options the config layers set, or with --resolved every option a render
+// would use, each commented with where it came from.
+fn show_config(
+    args: &Args,
+    matches: &ArgMatches,
+    show_args: &ShowArgs,
+) -> Result<String, Box<dyn Error>> {
+    let layers = config_layers(args, None)?;
+    let config = layers::merged(&layers)?;
+    let from_layers = |key: &str| layers::source_of(&layers, key).cloned();
+    if !show_args.resolved {
+        let table = toml::Table::try_from(&config)?;
+        return Ok(layers::annotated(&table, |key| {
+            from_layers(key).unwrap_or(Source::Default).to_string()
+        }));
+    }
+
+    let shape = show_args
+        .shape
+        .or_else(|| sections(&config).first().copied())
+        .unwrap_or(Shape::Rect);
+    // The stored config of a render holds every option it used.
+    let resolved = |args: Args, config: FileConfig| -> Result<toml::Table, Box<dyn Error>> {
+        let args = Args {
+            command: shape.command(),
+            ..args
+        };
+        let stored = build(args, config)?.visual.metadata.config;
+        Ok(toml::from_str(&stored.unwrap_or_default())?)
+    };
+    let table = resolved(args.clone(), config.clone())?;
+    // Options nothing set come from the theme where it differs from the
+    // defaults.
+    let bare = Args::try_parse_from(["greek-meander", "--isolated", shape.section()])?;
+    let defaults = resolved(bare.clone(), FileConfig::default())?;
+    let theme = args.theme.or(config.theme);
+    let themed = match theme {
+        Some(theme) => {
+            let config = FileConfig {
+                theme: Some(theme),
+                ..FileConfig::default()
+            };
+            Some((Source::Theme(theme_name(theme)), resolved(bare, config)?))
+        }
+        None => None,
+    };
+    let given = flag_keys(matches);
+    Ok(layers::annotated(&table, |key| {
+        if given.iter().any(|given| given == key) {
+            return Source::CommandLine.to_string();
+        }
+        if let Some(source) = from_layers(key) {
+            return source.to_string();
+        }
+        match &themed {
+            Some((source, themed))
+                if file_config::get(themed, key) != file_config::get(&defaults, key) =>
+            {
+                source.to_string()
+            }
+            _ => Source::Default.to_string(),
+        }
+    }))
+}
+
+fn theme_name(theme: greek_meander::Theme) -> String {
+    match toml::Value::try_from(theme) {
+        Ok(toml::Value::String(name)) => name,
+        _ => format!("{theme:?}"),
+    }
+}
+
+// Config keys of the flags given on the command line.
+fn flag_keys(matches: &ArgMatches) -> Vec<String> {
+    // Flags not named `<section>_<option>` after their key.
+    const RENAMED: [(&str, &str); 13] = [
+        ("hide_pattern", "pattern.hidden"),
+        ("hide_inner_frame", "inner_frame.hidden"),
+        ("hide_outer_frame", "outer_frame.hidden"),
+        ("title", "text.title"),
+        ("ring_text", "text.ring_text"),
+        ("ring_text_rail", "text.rail"),
+        ("font_family", "text.font_family"),
+        ("font_size", "text.font_size"),
+        ("font_file", "text.font_file"),
+        ("svg_title", "metadata.title"),
+        ("description", "metadata.description"),
+        ("author", "metadata.author"),
+        ("license", "metadata.license"),
+    ];
+    let keys = schema::option_keys();
+    matches
+        .ids()
+        .map(|id| id.as_str())
+        .filter(|id| matches.value_source(id) == Some(ValueSource::CommandLine))
+        .filter_map(|id| match RENAMED.iter().find(|(flag, _)| *flag == id) {
+            Some((_, key)) => Some(key.to_string()),
+            None => keys.iter().find(|key| key.replace('.', "_") == id).cloned(),
+        })
+        .collect()
+}
+
+// Serves designs over HTTP. The --config file supplies defaults for options a
+// query leaves out; options given before `serve` override the query.
+fn run_serve(args: &Args, serve_args: &ServeArgs) -> Result<(), Box<dyn Error>> {
+    let defaults = toml::Table::try_from(design_config(args, None)?)?;
+    let host = serve_args.host.as_deref().unwrap_or(DEFAULT_SERVE_HOST);
+    let port = serve_args.port.unwrap_or(DEFAULT_SERVE_PORT);
+    let listener = TcpListener::bind((host, port))
+        .map_err(|e| format!("could not listen on {host}:{port}: {e}"))?;
+    let template = Args {
+        config: None,
+        file: None,
+        stdout: false,
+        no_svg: false,
+        no_png: false,
+        ..args.clone()
+    };
+    let server = Server::new(
+        defaults,
+        serve_args.cache_size.unwrap_or(DEFAULT_SERVE_CACHE_SIZE),
+        |shape, format, config| {
+            let args = Args {
+                command: shape.command(),
+                ..template.clone()
+            };
+            let built = build(args, config).map_err(|e| e.to_string())?;
+            check_served(&built, format)?;
+            let rendered = draw(built).map_err(|e| e.to_string())?;
+            match format {
+                Format::Svg => Ok(rendered.svg.into_bytes()),
+                Format::Png => {
+                    cli_output::png_bytes(rendered.svg.as_bytes(), rendered.output.png_scale)
+                        .map_err(|e| e.to_string())
+                }
+            }
+        },
+    );
+    eprintln!(
+        "Serving on http://{}; press Ctrl+C to stop",
+        listener.local_addr()?
+    );
+    let workers = serve_args.jobs.map_or_else(
+        || std::thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        NonZeroUsize::get,
+    );
+    Ok(server.run(listener, workers)?)
+}
+
+// Refuses a design larger than the server draws, so that one request cannot
+// hold a worker for long or use up its memory.
+fn check_served(built: &Built, format: Format) -> Result<(), String> {
+    let ((width, height), keys) = match &built.design {
+        Design::Rect(config) => (
+            config.get_canvas_size(),
+            2.0 * f64::from(config.width_units) + 2.0 * f64::from(config.height_units),
+        ),
+        Design::Circle(config) => (config.get_canvas_size(), f64::from(config.pattern_count)),
+        Design::Ellipse(config) => (config.get_canvas_size(), f64::from(config.pattern_count)),
+        Design::Superellipse(config) => (config.get_canvas_size(), f64::from(config.pattern_count)),
+        Design::Spiral(config) => {
+            if config.turns > MAX_SERVED_TURNS {
+                return Err(format!(
+                    "--turns must be at most {MAX_SERVED_TURNS} when served"
+                ));
+            }
+            // At most every turn at the larger radius.
+            let length =
+                config.turns * std::f64::consts::TAU * config.start_radius.max(config.end_radius);
+            (
+                config.get_canvas_size(),
+                length / (5.0 * config.key_unit_length),
+            )
+        }
+        Design::Field(config) => {
+            let unit = config.key_unit_length;
+            let keys = ((config.width / (5.0 * unit)).ceil() + 2.0)
+                * ((config.height / (4.0 * unit)).ceil() + 2.0);
+            (config.get_canvas_size(), keys)
+        }
+        Design::Frame(..) => return Ok(()),
+    };
+    if width.max(height) > MAX_SERVED_CANVAS_SIDE || width * height > MAX_SERVED_CANVAS_AREA {
+        return Err(format!(
+            "a {width}×{height} canvas is larger than the server draws; \
+             it allows {MAX_SERVED_CANVAS_SIDE} pixels a side and {MAX_SERVED_CANVAS_AREA} in all"
+        ));
+    }
+    let scale = f64::from(built.output.png_scale);
+    if format == Format::Png && width * height * scale * scale > MAX_SERVED_PNG_AREA {
+        return Err(format!(
+            "--scale {scale} makes a PNG larger than the server draws; \
+             it allows {MAX_SERVED_PNG_AREA} pixels"
+        ));
+    }
+    let fill = built
+        .visual
+        .field_spacing
+        .map_or(0.0, |spacing| width * height / (20.0 * spacing * spacing));
+    if keys + fill > MAX_SERVED_KEYS {
+        return Err(format!(
+            "the design needs about {:.0} keys, more than the {MAX_SERVED_KEYS} the server draws",
+            keys + fill
+        ));
+    }
+    Ok(())
+}
+
+// Renders jobs in parallel, each with the options given on the command line
+// laid over its config. Returns the SVG of each.
+fn render_jobs(args: &Args, jobs: Vec<Job>, threads: Option<NonZeroUsize>) -> Vec<Outcome<String>> {
+    let threads = threads.map_or_else(
+        || std::thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        NonZeroUsize::get,
+    );
+    batch::run(jobs, threads, |job| {
+        let args = Args {
+            file: Some(job.output),
+            command: job.shape.command(),
+            ..args.clone()
+        };
+        render(args, job.config)
+            .and_then(|rendered| {
+                rendered.write()?;
+                Ok(rendered.svg)
+            })
+            .map_err(|e| e.to_string())
+    })
+}
+
+// A rendered design and where the command line sends it.
+struct Rendered {
+    svg: String,
+    // The resolved options, as stored in the SVG.
+    config: Option<String>,
+    file: String,
+    output: OutputOptions,
+}
+
+impl Rendered {
+    fn write(&self) -> Result<(), Box<dyn Error>> {
+        cli_output::write_outputs(self.svg.as_bytes(), &self.file, &self.output)
+    }
+}
+
+// A design whose options have passed its shape's checks, ready to draw.
+enum Design {
+    Rect(GreekKeyRectConfig),
+    Circle(GreekKeyCircleConfig),
+    Ellipse(GreekKeyEllipseConfig),
+    Superellipse(GreekKeySuperellipseConfig),
+    Spiral(GreekKeySpiralConfig),
+    Field(GreekKeyFieldConfig),
+    Frame(GreekKeyFrameConfig, Artwork),
+}
+
+// Everything needed to draw and write one design.
+struct Built {
+    design: Design,
+    visual: VisualOptions,
+    file: String,
+    output: OutputOptions,
+}
+
+// Renders one design without writing it.
+fn render(args: Args, file_cfg: FileConfig) -> Result<Rendered, Box<dyn Error>> {
+    draw(build(args, file_cfg)?)
+}
+
+// Draws a built design.
+fn draw(built: Built) -> Result<Rendered, Box<dyn Error>> {
+    let Built {
+        design,
+        visual,
+        file,
+        output,
+    } = built;
+    let svg = match &design {
+        Design::Rect(config) => rect::generate_svg_string(config, &visual),
+        Design::Circle(config) => circle::generate_svg_string(config, &visual),
+        Design::Ellipse(config) => ellipse::generate_svg_string(config, &visual),
+        Design::Superellipse(config) => superellipse::generate_svg_string(config, &visual),
+        Design::Spiral(config) => spiral::generate_svg_string(config, &visual),
+        Design::Field(config) => field::generate_svg_string(config, &visual),
+        Design::Frame(config, artwork) => frame::generate_svg_string(config, artwork, &visual)?,
+    };
+    Ok(Rendered {
+        svg,
+        config: visual.metadata.config,
+        file,
+        output,
+    })
+}
+
+// Merges the command line over the config and checks the result, without
+// drawing anything.
+fn build(args: Args, file_cfg: FileConfig) -> Result<Built, Box<dyn Error>> {
+    // CLI flags win; file config fills gaps; then the theme, if any; hardcoded
+    // defaults are the last resort.
+    let explicit_stroke_width = args.stroke_width.or(file_cfg.stroke_width);
+    let mut stroke_width = explicit_stroke_width.unwrap_or(DEFAULT_STROKE_WIDTH);
     let border_margin = args
         .border_margin
         .or(file_cfg.border_margin)
@@ -64,83 +867,436 @@ fn main() {
         .unwrap_or_else(|| DEFAULT_FILE.to_string());
     let scale = args.scale.or(file_cfg.scale).unwrap_or(DEFAULT_SCALE);
 
-    let mut visual = VisualOptions::new(stroke_color, stroke_opacity);
-    visual.fill_color = args.fill_color.or(file_cfg.fill_color);
-    visual.background_color = args.background_color.or(file_cfg.background_color);
-    visual.stroke_dash = args.stroke_dash.or(file_cfg.stroke_dash);
-
-    let output_options = match OutputOptions::new(!args.no_svg, !args.no_png, args.stdout, scale) {
-        Ok(options) => options,
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+    let mut visual = match args.theme.or(file_cfg.theme) {
+        Some(theme) => theme.visual_options(),
+        None => VisualOptions::new(DEFAULT_STROKE_COLOR, DEFAULT_STROKE_OPACITY),
+    };
+    visual.stroke_color = args
+        .stroke_color
+        .or(file_cfg.stroke_color)
+        .unwrap_or(visual.stroke_color);
+    visual.stroke_opacity = args
+        .stroke_opacity
+        .or(file_cfg.stroke_opacity)
+        .unwrap_or(visual.stroke_opacity);
+    visual.fill_color = args
+        .fill_color
+        .or(file_cfg.fill_color)
+        .or(visual.fill_color);
+    visual.background_color = args
+        .background_color
+        .or(file_cfg.background_color)
+        .or(visual.background_color);
+    visual.stroke_dash = args
+        .stroke_dash
+        .or(file_cfg.stroke_dash)
+        .or(visual.stroke_dash);
+    visual.line_join = args.line_join.or(file_cfg.line_join).or(visual.line_join);
+    visual.line_cap = args.line_cap.or(file_cfg.line_cap).or(visual.line_cap);
+    visual.miter_limit = args
+        .miter_limit
+        .or(file_cfg.miter_limit)
+        .or(visual.miter_limit);
+    visual.non_scaling_stroke = args.non_scaling_stroke
+        || file_cfg
+            .non_scaling_stroke
+            .unwrap_or(visual.non_scaling_stroke);
+    if !args.effects.is_empty() {
+        visual.effects = args.effects;
+    } else if let Some(effects) = file_cfg.effects {
+        visual.effects = effects;
+    }
+    visual.field_spacing = args
+        .field_spacing
+        .or(file_cfg.field_spacing)
+        .or(visual.field_spacing);
+    visual.arc_tolerance = args
+        .arc_tolerance
+        .or(file_cfg.arc_tolerance)
+        .or(visual.arc_tolerance);
+    visual.precision = args.precision.or(file_cfg.precision).or(visual.precision);
+    visual.shared_styles =
+        args.shared_styles || file_cfg.shared_styles.unwrap_or(visual.shared_styles);
+    visual.relative_paths =
+        args.relative_paths || file_cfg.relative_paths.unwrap_or(visual.relative_paths);
+    visual.minify = args.minify || file_cfg.minify.unwrap_or(visual.minify);
+    visual.id_prefix = args.id_prefix.or(file_cfg.id_prefix).or(visual.id_prefix);
+    let element = |color: Option<Paint>,
+                   opacity: Option<f32>,
+                   hidden: bool,
+                   file: Option<file_config::ElementFileConfig>,
+                   base: ElementStyle| {
+        let file = file.unwrap_or_default();
+        ElementStyle {
+            stroke_color: color.or(file.color).or(base.stroke_color),
+            stroke_opacity: opacity.or(file.opacity).or(base.stroke_opacity),
+            hidden: hidden || file.hidden.unwrap_or(base.hidden),
+        }
+    };
+    visual.pattern_style = element(
+        args.pattern_color,
+        args.pattern_opacity,
+        args.hide_pattern,
+        file_cfg.pattern,
+        visual.pattern_style,
+    );
+    visual.inner_frame_style = element(
+        args.inner_frame_color,
+        args.inner_frame_opacity,
+        args.hide_inner_frame,
+        file_cfg.inner_frame,
+        visual.inner_frame_style,
+    );
+    visual.outer_frame_style = element(
+        args.outer_frame_color,
+        args.outer_frame_opacity,
+        args.hide_outer_frame,
+        file_cfg.outer_frame,
+        visual.outer_frame_style,
+    );
+    if !args.key_colors.is_empty() {
+        visual.key_colors = args.key_colors;
+    } else if let Some(key_colors) = file_cfg.key_colors {
+        visual.key_colors = key_colors;
+    }
+    let inlay_cfg = file_cfg.inlay.unwrap_or_default();
+    if let Some(gap) = args.inlay_gap.or(inlay_cfg.gap) {
+        let line_width = args
+            .inlay_line_width
+            .or(inlay_cfg.line_width)
+            .unwrap_or(DEFAULT_INLAY_LINE_WIDTH);
+        let mut inlay = Inlay::new(gap, line_width);
+        inlay.line_color = args.inlay_line_color.or(inlay_cfg.line_color);
+        inlay.inlay_color = args.inlay_color.or(inlay_cfg.color);
+        // The shapes leave room for the whole double line. A stored config
+        // repeats that width, so only a different one is a conflict.
+        stroke_width = inlay.width() as f32;
+        if explicit_stroke_width.is_some_and(|width| width != stroke_width) {
+            return Err(format!(
+                "--stroke-width cannot be combined with --inlay-gap, \
+                 whose double line is {stroke_width} wide"
+            )
+            .into());
         }
+        visual.inlay = Some(inlay);
+    }
+    let text_cfg = file_cfg.text.unwrap_or_default();
+    let title = if args.title.is_empty() {
+        text_cfg.title.unwrap_or_default()
+    } else {
+        args.title
+    };
+    let ring_text = args.ring_text.or(text_cfg.ring_text);
+    let font_file = args.font_file.or(text_cfg.font_file);
+    if !title.is_empty() || ring_text.is_some() {
+        let font_data = match &font_file {
+            Some(path) => Some(
+                std::fs::read(path)
+                    .map_err(|e| format!("could not read font file '{}': {}", path.display(), e))?,
+            ),
+            None => None,
+        };
+        visual.text = Some(Text {
+            title,
+            ring_text,
+            rail: args.ring_text_rail.or(text_cfg.rail).unwrap_or_default(),
+            font_family: args.font_family.or(text_cfg.font_family),
+            font_size: args.font_size.or(text_cfg.font_size),
+            color: args.text_color.or(text_cfg.color),
+            font_data,
+        });
+    }
+    let metadata_cfg = file_cfg.metadata.unwrap_or_default();
+    visual.metadata = Metadata {
+        title: args.svg_title.or(metadata_cfg.title),
+        description: args.description.or(metadata_cfg.description),
+        author: args.author.or(metadata_cfg.author),
+        license: args.license.or(metadata_cfg.license),
+        config: None,
+    };
+    visual.validate()?;
+
+    let output_options = OutputOptions {
+        no_clobber: args.no_clobber,
+        ..OutputOptions::new(!args.no_svg, !args.no_png, args.stdout, scale)?
     };
 
+    let resolved = FileConfig::resolved(
+        &visual,
+        stroke_width,
+        border_margin,
+        &file,
+        scale,
+        font_file,
+    );
     let rect_cfg = file_cfg.rect.unwrap_or_default();
     let circle_cfg = file_cfg.circle.unwrap_or_default();
     let ellipse_cfg = file_cfg.ellipse.unwrap_or_default();
+    let superellipse_cfg = file_cfg.superellipse.unwrap_or_default();
+    let spiral_cfg = file_cfg.spiral.unwrap_or_default();
+    let field_cfg = file_cfg.field.unwrap_or_default();
+    let frame_cfg = file_cfg.frame.unwrap_or_default();
 
-    let result =
-        match args.command {
-            Commands::Rect(rect_args) => {
-                let size = rect_args
-                    .size
-                    .or(rect_cfg.size)
-                    .unwrap_or(DEFAULT_RECT_SIZE);
-                let width = rect_args
-                    .width
-                    .or(rect_cfg.width)
-                    .unwrap_or(DEFAULT_RECT_WIDTH);
-                let height = rect_args
-                    .height
-                    .or(rect_cfg.height)
-                    .unwrap_or(DEFAULT_RECT_HEIGHT);
-                GreekKeyRectConfig::new(size, width, height, border_margin, stroke_width).and_then(
-                    |config| {
-                        let svg = rect::generate_svg_string(&config, &visual);
-                        cli_output::write_outputs(svg.as_bytes(), &file, &output_options)
-                    },
-                )
-            }
-            Commands::Circle(circle_args) => {
-                let radius = circle_args
-                    .radius
-                    .or(circle_cfg.radius)
-                    .unwrap_or(DEFAULT_CIRCLE_RADIUS);
-                let pattern_count = circle_args
-                    .pattern_count
-                    .or(circle_cfg.pattern_count)
-                    .unwrap_or(DEFAULT_CIRCLE_PATTERN_COUNT);
-                GreekKeyCircleConfig::new(radius, pattern_count, border_margin, stroke_width)
-                    .and_then(|config| {
-                        let svg = circle::generate_svg_string(&config, &visual);
-                        cli_output::write_outputs(svg.as_bytes(), &file, &output_options)
-                    })
-            }
-            Commands::Ellipse(ellipse_args) => {
-                let rx = ellipse_args
-                    .rx
-                    .or(ellipse_cfg.rx)
-                    .unwrap_or(DEFAULT_ELLIPSE_RX);
-                let ry = ellipse_args
-                    .ry
-                    .or(ellipse_cfg.ry)
-                    .unwrap_or(DEFAULT_ELLIPSE_RY);
-                let pattern_count = ellipse_args
-                    .pattern_count
-                    .or(ellipse_cfg.pattern_count)
-                    .unwrap_or(DEFAULT_ELLIPSE_PATTERN_COUNT);
-                GreekKeyEllipseConfig::new(rx, ry, pattern_count, border_margin, stroke_width)
-                    .and_then(|config| {
-                        let svg = ellipse::generate_svg_string(&config, &visual);
-                        cli_output::write_outputs(svg.as_bytes(), &file, &output_options)
-                    })
-            }
-        };
+    let (design, visual) = match args.command {
+        Commands::Rect(rect_args) => {
+            let size = rect_args
+                .size
+                .or(rect_cfg.size)
+                .unwrap_or(DEFAULT_RECT_SIZE);
+            let width = rect_args
+                .width
+                .or(rect_cfg.width)
+                .unwrap_or(DEFAULT_RECT_WIDTH);
+            let height = rect_args
+                .height
+                .or(rect_cfg.height)
+                .unwrap_or(DEFAULT_RECT_HEIGHT);
+            let visual = stamped(
+                &visual,
+                FileConfig {
+                    rect: Some(RectFileConfig {
+                        size: Some(size),
+                        width: Some(width),
+                        height: Some(height),
+                    }),
+                    ..resolved
+                },
+            );
+            let config = GreekKeyRectConfig::new(size, width, height, border_margin, stroke_width)?;
+            let (width, height) = config.get_canvas_size();
+            visual.validate_canvas(width, height)?;
+            (Design::Rect(config), visual)
+        }
+        Commands::Circle(circle_args) => {
+            let radius = circle_args
+                .radius
+                .or(circle_cfg.radius)
+                .unwrap_or(DEFAULT_CIRCLE_RADIUS);
+            let pattern_count = circle_args
+                .pattern_count
+                .or(circle_cfg.pattern_count)
+                .unwrap_or(DEFAULT_CIRCLE_PATTERN_COUNT);
+            let visual = stamped(
+                &visual,
+                FileConfig {
+                    circle: Some(CircleFileConfig {
+                        pattern_count: Some(pattern_count),
+                        radius: Some(radius),
+                    }),
+                    ..resolved
+                },
+            );
+            let config =
+                GreekKeyCircleConfig::new(radius, pattern_count, border_margin, stroke_width)?;
+            let (width, height) = config.get_canvas_size();
+            visual.validate_canvas(width, height)?;
+            (Design::Circle(config), visual)
+        }
+        Commands::Ellipse(ellipse_args) => {
+            let rx = ellipse_args
+                .rx
+                .or(ellipse_cfg.rx)
+                .unwrap_or(DEFAULT_ELLIPSE_RX);
+            let ry = ellipse_args
+                .ry
+                .or(ellipse_cfg.ry)
+                .unwrap_or(DEFAULT_ELLIPSE_RY);
+            let pattern_count = ellipse_args
+                .pattern_count
+                .or(ellipse_cfg.pattern_count)
+                .unwrap_or(DEFAULT_ELLIPSE_PATTERN_COUNT);
+            let visual = stamped(
+                &visual,
+                FileConfig {
+                    ellipse: Some(EllipseFileConfig {
+                        pattern_count: Some(pattern_count),
+                        rx: Some(rx),
+                        ry: Some(ry),
+                    }),
+                    ..resolved
+                },
+            );
+            let config =
+                GreekKeyEllipseConfig::new(rx, ry, pattern_count, border_margin, stroke_width)?;
+            let (width, height) = config.get_canvas_size();
+            visual.validate_canvas(width, height)?;
+            (Design::Ellipse(config), visual)
+        }
+        Commands::Superellipse(superellipse_args) => {
+            let rx = superellipse_args
+                .rx
+                .or(superellipse_cfg.rx)
+                .unwrap_or(DEFAULT_SUPERELLIPSE_RX);
+            let ry = superellipse_args
+                .ry
+                .or(superellipse_cfg.ry)
+                .unwrap_or(DEFAULT_SUPERELLIPSE_RY);
+            let exponent = superellipse_args
+                .exponent
+                .or(superellipse_cfg.exponent)
+                .unwrap_or(DEFAULT_SUPERELLIPSE_EXPONENT);
+            let pattern_count = superellipse_args
+                .pattern_count
+                .or(superellipse_cfg.pattern_count)
+                .unwrap_or(DEFAULT_SUPERELLIPSE_PATTERN_COUNT);
+            let visual = stamped(
+                &visual,
+                FileConfig {
+                    superellipse: Some(SuperellipseFileConfig {
+                        pattern_count: Some(pattern_count),
+                        rx: Some(rx),
+                        ry: Some(ry),
+                        exponent: Some(exponent),
+                    }),
+                    ..resolved
+                },
+            );
+            let config = GreekKeySuperellipseConfig::new(
+                rx,
+                ry,
+                exponent,
+                pattern_count,
+                border_margin,
+                stroke_width,
+            )?;
+            let (width, height) = config.get_canvas_size();
+            visual.validate_canvas(width, height)?;
+            (Design::Superellipse(config), visual)
+        }
+        Commands::Spiral(spiral_args) => {
+            let kind = spiral_args
+                .kind
+                .or(spiral_cfg.kind)
+                .unwrap_or(DEFAULT_SPIRAL_KIND);
+            let turns = spiral_args
+                .turns
+                .or(spiral_cfg.turns)
+                .unwrap_or(DEFAULT_SPIRAL_TURNS);
+            let start_radius = spiral_args
+                .start_radius
+                .or(spiral_cfg.start_radius)
+                .unwrap_or(DEFAULT_SPIRAL_START_RADIUS);
+            let end_radius = spiral_args
+                .end_radius
+                .or(spiral_cfg.end_radius)
+                .unwrap_or(DEFAULT_SPIRAL_END_RADIUS);
+            let size = spiral_args
+                .size
+                .or(spiral_cfg.size)
+                .unwrap_or(DEFAULT_SPIRAL_SIZE);
+            let visual = stamped(
+                &visual,
+                FileConfig {
+                    spiral: Some(SpiralFileConfig {
+                        kind: Some(kind),
+                        turns: Some(turns),
+                        start_radius: Some(start_radius),
+                        end_radius: Some(end_radius),
+                        size: Some(size),
+                    }),
+                    ..resolved
+                },
+            );
+            let config = GreekKeySpiralConfig::new(
+                kind,
+                turns,
+                start_radius,
+                end_radius,
+                size,
+                border_margin,
+                stroke_width,
+            )?;
+            (Design::Spiral(config), visual)
+        }
+        Commands::Field(field_args) => {
+            let width = field_args
+                .width
+                .or(field_cfg.width)
+                .unwrap_or(DEFAULT_FIELD_WIDTH);
+            let height = field_args
+                .height
+                .or(field_cfg.height)
+                .unwrap_or(DEFAULT_FIELD_HEIGHT);
+            let size = field_args
+                .size
+                .or(field_cfg.size)
+                .unwrap_or(DEFAULT_FIELD_SIZE);
+            let visual = stamped(
+                &visual,
+                FileConfig {
+                    field: Some(FieldAreaFileConfig {
+                        width: Some(width),
+                        height: Some(height),
+                        size: Some(size),
+                    }),
+                    ..resolved
+                },
+            );
+            let config =
+                GreekKeyFieldConfig::new(width, height, size, border_margin, stroke_width)?;
+            (Design::Field(config), visual)
+        }
+        Commands::Frame(frame_args) => {
+            let shape = frame_args
+                .shape
+                .or(frame_cfg.shape)
+                .unwrap_or(DEFAULT_FRAME_SHAPE);
+            let size = frame_args
+                .size
+                .or(frame_cfg.size)
+                .unwrap_or(DEFAULT_FRAME_SIZE);
+            let pattern_count = frame_args
+                .pattern_count
+                .or(frame_cfg.pattern_count)
+                .unwrap_or(DEFAULT_FRAME_PATTERN_COUNT);
+            let input = frame_args.input.or(frame_cfg.input);
+            let visual = stamped(
+                &visual,
+                FileConfig {
+                    frame: Some(FrameFileConfig {
+                        input: input.clone(),
+                        shape: Some(shape),
+                        size: Some(size),
+                        pattern_count: Some(pattern_count),
+                    }),
+                    ..resolved
+                },
+            );
+            let artwork = Artwork::open(input.ok_or("frame needs an input image")?)?;
+            let config =
+                GreekKeyFrameConfig::new(shape, size, pattern_count, border_margin, stroke_width)?;
+            (Design::Frame(config, artwork), visual)
+        }
+        Commands::Reproduce(_)
+        | Commands::Batch(_)
+        | Commands::Sweep(_)
+        | Commands::Serve(_)
+        | Commands::Config(_)
+        | Commands::Validate(_) => {
+            unreachable!(
+                "reproduce, batch, sweep, serve, config and validate render through shape commands"
+            )
+        }
+    };
+    // The output name is filled in from the options the SVG stores.
+    let stored: toml::Table = toml::from_str(visual.metadata.config.as_deref().unwrap_or(""))?;
+    let shape = Shape::value_variants()
+        .iter()
+        .find(|shape| stored.contains_key(shape.section()))
+        .map_or("", |shape| shape.section());
+    let file = cli_output::output_name(&file, shape, &stored)?;
+    Ok(Built {
+        design,
+        visual,
+        file: in_out_dir(args.out_dir.as_deref(), file),
+        output: output_options,
+    })
+}
 
-    if let Err(e) = result {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+// Puts an output name in the --out-dir, if one is given.
+fn in_out_dir(out_dir: Option<&Path>, file: String) -> String {
+    match out_dir {
+        Some(dir) => dir.join(file).to_string_lossy().into_owned(),
+        None => file,
     }
 }
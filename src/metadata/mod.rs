@@ -0,0 +1,597 @@
+use std::borrow::Cow;
+
+use svg::Document;
+use svg::node::element::{Description, Element, Title};
+use svg::node::{Blob, Node, Text as TextNode};
+
+use crate::config::{ElementStyle, VisualOptions};
+use crate::paint::Paint;
+
+const RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+const CC_NAMESPACE: &str = "http://creativecommons.org/ns#";
+/// Namespace of the `<gm:config>` element holding the generation parameters.
+pub const CONFIG_NAMESPACE: &str = "https://github.com/bingqiao/meander";
+/// Element id of the `<title>`.
+pub(crate) const TITLE_ID: &str = "gm-title";
+/// Element id of the `<desc>`.
+pub(crate) const DESCRIPTION_ID: &str = "gm-desc";
+
+/// Accessible name and description, licence and generation parameters
+/// stored in the SVG.
+///
+/// The title and description become `<title>` and `<desc>` for screen
+/// readers. They, the author and the licence are also recorded as Dublin Core
+/// RDF inside `<metadata>`, where tools such as Inkscape read them.
+#[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    /// The accessible name. `None` uses the lines of the text title, if any.
+    pub title: Option<String>,
+    /// A longer description of the image.
+    pub description: Option<String>,
+    /// Who made the design.
+    pub author: Option<String>,
+    /// A licence URL, such as `https://creativecommons.org/licenses/by/4.0/`,
+    /// or a rights statement.
+    pub license: Option<String>,
+    /// Generation parameters stored in a `<gm:config>` element, for the
+    /// program that wrote the SVG to read back. `None` stores the design's
+    /// options as a config file the command line's `reproduce` reads; an
+    /// embedded font and framed artwork are not stored.
+    pub config: Option<String>,
+}
+
+impl Metadata {
+    // The accessible name: the title, or the text title's lines.
+    fn title(&self, visual: &VisualOptions) -> Option<String> {
+        self.title.clone().or_else(|| {
+            visual
+                .text
+                .as_ref()
+                .filter(|text| !text.title.is_empty())
+                .map(|text| text.title.join(" "))
+        })
+    }
+}
+
+/// Puts the `<title>`, `<desc>` and `<metadata>` for `visual.metadata` first
+/// in the document, and labels the document with them.
+pub(crate) fn add_metadata(document: &mut Document, visual: &VisualOptions) {
+    let metadata = &visual.metadata;
+    let title = metadata.title(visual);
+    let mut nodes: Vec<Box<dyn Node>> = Vec::new();
+    let mut labels = Vec::new();
+    if let Some(title) = &title {
+        nodes.push(Box::new(Title::new(title.as_str()).set("id", TITLE_ID)));
+        labels.push(TITLE_ID);
+    }
+    if let Some(description) = &metadata.description {
+        nodes.push(Box::new(
+            Description::new()
+                .set("id", DESCRIPTION_ID)
+                .add(TextNode::new(description.as_str())),
+        ));
+        labels.push(DESCRIPTION_ID);
+    }
+    if !labels.is_empty() {
+        document.assign("role", "img");
+        document.assign("aria-labelledby", labels.join(" "));
+    }
+
+    let mut element = Element::new("metadata");
+    let mut work = Element::new("cc:Work");
+    work.assign("rdf:about", "");
+    let mut described = false;
+    for (name, value) in [
+        ("dc:title", &title),
+        ("dc:description", &metadata.description),
+    ] {
+        if let Some(value) = value {
+            work.append(text_element(name, value));
+            described = true;
+        }
+    }
+    if let Some(author) = &metadata.author {
+        let mut agent = Element::new("cc:Agent");
+        agent.append(text_element("dc:title", author));
+        let mut creator = Element::new("dc:creator");
+        creator.append(agent);
+        work.append(creator);
+        described = true;
+    }
+    if let Some(license) = &metadata.license {
+        if license.starts_with("http://") || license.starts_with("https://") {
+            let mut element = Element::new("cc:license");
+            element.assign("rdf:resource", license.as_str());
+            work.append(element);
+        } else {
+            work.append(text_element("dc:rights", license));
+        }
+        described = true;
+    }
+    if described {
+        let mut rdf = Element::new("rdf:RDF");
+        rdf.assign("xmlns:rdf", RDF_NAMESPACE);
+        rdf.assign("xmlns:dc", DC_NAMESPACE);
+        rdf.assign("xmlns:cc", CC_NAMESPACE);
+        rdf.append(work);
+        element.append(rdf);
+    }
+    if let Some(config) = &metadata.config {
+        // Written by hand so minified output can keep the config's line
+        // breaks as character references.
+        element.append(Blob::new(format!(
+            r#"<gm:config xmlns:gm="{CONFIG_NAMESPACE}">{}</gm:config>"#,
+            escape(config, visual.minify)
+        )));
+    }
+    if described || metadata.config.is_some() {
+        nodes.push(Box::new(element));
+    }
+
+    if let Some(children) = Node::get_children_mut(document) {
+        children.splice(0..0, nodes);
+    }
+}
+
+/// A value in a config written by [`stamped`].
+pub(crate) enum ConfigValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    /// Written as the shortest decimal that reads back as the same `f32`.
+    Float32(f32),
+    Text(String),
+    List(Vec<ConfigValue>),
+}
+
+impl ConfigValue {
+    fn toml(&self) -> String {
+        let float = |text: String| {
+            if text.contains(['.', 'e', 'i', 'N']) {
+                text
+            } else {
+                format!("{text}.0")
+            }
+        };
+        match self {
+            ConfigValue::Bool(value) => value.to_string(),
+            ConfigValue::Integer(value) => value.to_string(),
+            ConfigValue::Float(value) => float(value.to_string()),
+            ConfigValue::Float32(value) => float(value.to_string()),
+            ConfigValue::Text(text) => toml_string(text),
+            ConfigValue::List(items) => {
+                let items: Vec<String> = items.iter().map(ConfigValue::toml).collect();
+                format!("[{}]", items.join(", "))
+            }
+        }
+    }
+}
+
+impl From<bool> for ConfigValue {
+    fn from(value: bool) -> Self {
+        ConfigValue::Bool(value)
+    }
+}
+
+impl From<i32> for ConfigValue {
+    fn from(value: i32) -> Self {
+        ConfigValue::Integer(value.into())
+    }
+}
+
+impl From<u8> for ConfigValue {
+    fn from(value: u8) -> Self {
+        ConfigValue::Integer(value.into())
+    }
+}
+
+impl From<f64> for ConfigValue {
+    fn from(value: f64) -> Self {
+        ConfigValue::Float(value)
+    }
+}
+
+impl From<f32> for ConfigValue {
+    fn from(value: f32) -> Self {
+        ConfigValue::Float32(value)
+    }
+}
+
+impl From<&str> for ConfigValue {
+    fn from(value: &str) -> Self {
+        ConfigValue::Text(value.to_string())
+    }
+}
+
+impl From<&String> for ConfigValue {
+    fn from(value: &String) -> Self {
+        ConfigValue::Text(value.clone())
+    }
+}
+
+impl From<&Paint> for ConfigValue {
+    fn from(value: &Paint) -> Self {
+        ConfigValue::Text(value.to_string())
+    }
+}
+
+impl<T> From<&[T]> for ConfigValue
+where
+    for<'a> &'a T: Into<ConfigValue>,
+{
+    fn from(items: &[T]) -> Self {
+        ConfigValue::List(items.iter().map(Into::into).collect())
+    }
+}
+
+// The keys of one table of a config, in the order they are written.
+type Table = Vec<(&'static str, ConfigValue)>;
+
+fn set(table: &mut Table, key: &'static str, value: Option<impl Into<ConfigValue>>) {
+    if let Some(value) = value {
+        table.push((key, value.into()));
+    }
+}
+
+/// Returns `visual` with [`Metadata::config`] holding the config file that
+/// rebuilds the design, unless the caller stored a config already. `shape`
+/// names the section holding the shape's `options`.
+pub(crate) fn stamped<'a>(
+    visual: &'a VisualOptions,
+    stroke_width: f32,
+    border_margin: i32,
+    shape: &'static str,
+    options: Table,
+) -> Cow<'a, VisualOptions> {
+    if visual.metadata.config.is_some() {
+        return Cow::Borrowed(visual);
+    }
+    let mut visual = visual.clone();
+    visual.metadata.config = Some(design_config(
+        &visual,
+        stroke_width,
+        border_margin,
+        shape,
+        options,
+    ));
+    Cow::Owned(visual)
+}
+
+// Writes the options of `visual` with the names the command line's config
+// files use, leaving out those at their defaults.
+fn design_config(
+    visual: &VisualOptions,
+    stroke_width: f32,
+    border_margin: i32,
+    shape: &'static str,
+    options: Table,
+) -> String {
+    let mut top: Table = vec![
+        ("stroke_width", stroke_width.into()),
+        ("stroke_color", (&visual.stroke_color).into()),
+        ("stroke_opacity", visual.stroke_opacity.into()),
+    ];
+    set(&mut top, "fill_color", visual.fill_color.as_ref());
+    set(
+        &mut top,
+        "background_color",
+        visual.background_color.as_ref(),
+    );
+    set(&mut top, "stroke_dash", visual.stroke_dash.as_ref());
+    set(
+        &mut top,
+        "line_join",
+        visual.line_join.map(|join| join.as_str()),
+    );
+    set(
+        &mut top,
+        "line_cap",
+        visual.line_cap.map(|cap| cap.as_str()),
+    );
+    set(&mut top, "miter_limit", visual.miter_limit);
+    set(
+        &mut top,
+        "non_scaling_stroke",
+        visual.non_scaling_stroke.then_some(true),
+    );
+    if !visual.effects.is_empty() {
+        let names = visual.effects.iter().map(|effect| effect.name().into());
+        top.push(("effects", ConfigValue::List(names.collect())));
+    }
+    set(&mut top, "field_spacing", visual.field_spacing);
+    set(&mut top, "arc_tolerance", visual.arc_tolerance);
+    set(&mut top, "precision", visual.precision);
+    set(
+        &mut top,
+        "shared_styles",
+        visual.shared_styles.then_some(true),
+    );
+    set(
+        &mut top,
+        "relative_paths",
+        visual.relative_paths.then_some(true),
+    );
+    set(&mut top, "minify", visual.minify.then_some(true));
+    set(&mut top, "id_prefix", visual.id_prefix.as_ref());
+    if !visual.key_colors.is_empty() {
+        top.push(("key_colors", visual.key_colors.as_slice().into()));
+    }
+    top.push(("border_margin", border_margin.into()));
+
+    let element = |style: &ElementStyle| {
+        let mut table = Table::new();
+        set(&mut table, "color", style.stroke_color.as_ref());
+        set(&mut table, "opacity", style.stroke_opacity);
+        set(&mut table, "hidden", style.hidden.then_some(true));
+        table
+    };
+    let mut sections: Vec<(&str, Table)> = vec![
+        ("pattern", element(&visual.pattern_style)),
+        ("inner_frame", element(&visual.inner_frame_style)),
+        ("outer_frame", element(&visual.outer_frame_style)),
+    ];
+    if let Some(inlay) = &visual.inlay {
+        let mut table: Table = vec![
+            ("gap", inlay.gap.into()),
+            ("line_width", inlay.line_width.into()),
+        ];
+        set(&mut table, "line_color", inlay.line_color.as_ref());
+        set(&mut table, "color", inlay.inlay_color.as_ref());
+        sections.push(("inlay", table));
+    }
+    if let Some(text) = &visual.text {
+        let mut table: Table = vec![("title", text.title.as_slice().into())];
+        set(&mut table, "ring_text", text.ring_text.as_ref());
+        table.push(("rail", text.rail.as_str().into()));
+        set(&mut table, "font_family", text.font_family.as_ref());
+        set(&mut table, "font_size", text.font_size);
+        set(&mut table, "color", text.color.as_ref());
+        sections.push(("text", table));
+    }
+    let metadata = &visual.metadata;
+    let mut table = Table::new();
+    set(&mut table, "title", metadata.title.as_ref());
+    set(&mut table, "description", metadata.description.as_ref());
+    set(&mut table, "author", metadata.author.as_ref());
+    set(&mut table, "license", metadata.license.as_ref());
+    sections.push(("metadata", table));
+    sections.push((shape, options));
+
+    let mut text = String::new();
+    write_table(&mut text, &top);
+    for (name, table) in sections.iter().filter(|(_, table)| !table.is_empty()) {
+        text.push_str(&format!("\n[{name}]\n"));
+        write_table(&mut text, table);
+    }
+    text
+}
+
+fn write_table(text: &mut String, table: &Table) {
+    for (key, value) in table {
+        text.push_str(&format!("{key} = {}\n", value.toml()));
+    }
+}
+
+// Quotes `text` as a TOML basic string.
+fn toml_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Returns the generation parameters stored in `svg` by
+/// [`Metadata::config`], or `None` if it has none.
+///
+/// SVGs written by the command line hold a config file that rebuilds the
+/// design; see the `reproduce` command.
+pub fn read_config(svg: &str) -> Option<String> {
+    let start = svg.find("<gm:config")?;
+    let start = start + svg[start..].find('>')? + 1;
+    if svg[..start].ends_with("/>") {
+        return Some(String::new());
+    }
+    let end = start + svg[start..].find("</gm:config>")?;
+    Some(unescape(&svg[start..end]))
+}
+
+// Undoes `escape`, and the other entities an XML editor may have written.
+fn unescape(text: &str) -> String {
+    text.replace("&#10;", "\n")
+        .replace("&#xA;", "\n")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn escape(text: &str, line_breaks: bool) -> String {
+    let text = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    if line_breaks {
+        text.replace('\n', "&#10;")
+    } else {
+        text
+    }
+}
+
+fn text_element(name: &str, content: &str) -> Element {
+    let mut element = Element::new(name);
+    element.append(TextNode::new(content));
+    element
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::Text;
+
+    fn render(metadata: Metadata) -> String {
+        let visual = VisualOptions {
+            metadata,
+            ..VisualOptions::default()
+        };
+        let mut document = Document::new().add(svg::node::element::Path::new());
+        add_metadata(&mut document, &visual);
+        document.to_string()
+    }
+
+    #[test]
+    fn title_and_description_label_the_document() {
+        let svg = render(Metadata {
+            title: Some("Greek key border".into()),
+            description: Some("A circle of thirty keys".into()),
+            ..Metadata::default()
+        });
+        assert!(svg.contains(r#"aria-labelledby="gm-title gm-desc" role="img""#));
+        assert!(
+            svg.contains(r#"<title id="gm-title">Greek key border</title>"#),
+            "{svg}"
+        );
+        assert!(svg.contains(r#"<desc id="gm-desc">A circle of thirty keys</desc>"#));
+        // Both come before the drawing.
+        assert!(svg.find("<title").unwrap() < svg.find("<path").unwrap());
+    }
+
+    #[test]
+    fn text_title_is_the_default_accessible_name() {
+        let visual = VisualOptions {
+            text: Some(Text {
+                title: vec!["Certificate".into(), "of Merit".into()],
+                ..Text::default()
+            }),
+            ..VisualOptions::default()
+        };
+        assert_eq!(
+            visual.metadata.title(&visual).as_deref(),
+            Some("Certificate of Merit")
+        );
+    }
+
+    #[test]
+    fn author_and_licence_are_recorded_as_rdf() {
+        let svg = render(Metadata {
+            author: Some("A. Potter".into()),
+            license: Some("https://creativecommons.org/licenses/by/4.0/".into()),
+            ..Metadata::default()
+        });
+        assert!(svg.contains("<dc:creator>\n<cc:Agent>\n<dc:title>A. Potter</dc:title>"));
+        assert!(svg.contains(
+            r#"<cc:license rdf:resource="https://creativecommons.org/licenses/by/4.0/"/>"#
+        ));
+        let svg = render(Metadata {
+            license: Some("All rights reserved".into()),
+            ..Metadata::default()
+        });
+        assert!(svg.contains("<dc:rights>All rights reserved</dc:rights>"));
+    }
+
+    #[test]
+    fn config_is_stored_escaped() {
+        let svg = render(Metadata {
+            config: Some("stroke_dash = \"<5,3>\"".into()),
+            ..Metadata::default()
+        });
+        assert!(svg.contains(
+            r#"<gm:config xmlns:gm="https://github.com/bingqiao/meander">stroke_dash = "&lt;5,3&gt;"</gm:config>"#
+        ), "{svg}");
+        assert!(!svg.contains("<rdf:RDF"));
+    }
+
+    #[test]
+    fn minified_config_keeps_its_line_breaks_escaped() {
+        let visual = VisualOptions {
+            minify: true,
+            metadata: Metadata {
+                config: Some("[rect]\nsize = 10\n".into()),
+                ..Metadata::default()
+            },
+            ..VisualOptions::default()
+        };
+        let mut document = Document::new();
+        add_metadata(&mut document, &visual);
+        assert!(
+            document
+                .to_string()
+                .contains(">[rect]&#10;size = 10&#10;</gm:config>")
+        );
+    }
+
+    #[test]
+    fn stored_config_reads_back() {
+        let config = "stroke_dash = \"<5,3>\"\n[rect]\nsize = 10\n";
+        for minify in [false, true] {
+            let visual = VisualOptions {
+                minify,
+                metadata: Metadata {
+                    config: Some(config.into()),
+                    ..Metadata::default()
+                },
+                ..VisualOptions::default()
+            };
+            let mut document = Document::new();
+            add_metadata(&mut document, &visual);
+            assert_eq!(read_config(&document.to_string()).as_deref(), Some(config));
+        }
+        assert_eq!(read_config("<svg/>"), None);
+    }
+
+    #[test]
+    fn design_config_lists_the_options_that_differ_from_the_defaults() {
+        let visual = VisualOptions {
+            stroke_opacity: 0.7,
+            key_colors: vec!["#A3341F".into(), "#111".into()],
+            text: Some(Text {
+                title: vec!["Say \"hi\"\\".into()],
+                ..Text::default()
+            }),
+            ..VisualOptions::default()
+        };
+        let options = vec![("size", 25.into()), ("radius", 120.0.into())];
+        let visual = stamped(&visual, 6.0, 1, "rect", options);
+        assert_eq!(
+            visual.metadata.config.as_deref(),
+            Some(concat!(
+                "stroke_width = 6.0\n",
+                "stroke_color = \"#AB8E0E\"\n",
+                "stroke_opacity = 0.7\n",
+                "key_colors = [\"#A3341F\", \"#111\"]\n",
+                "border_margin = 1\n",
+                "\n[text]\n",
+                "title = [\"Say \\\"hi\\\"\\\\\"]\n",
+                "rail = \"inner\"\n",
+                "\n[rect]\n",
+                "size = 25\n",
+                "radius = 120.0\n",
+            ))
+        );
+        // A config the caller stored is kept.
+        let stored = stamped(&visual, 2.0, 0, "circle", Table::new());
+        assert_eq!(stored.metadata.config, visual.metadata.config);
+    }
+
+    #[test]
+    fn nothing_is_added_without_metadata() {
+        assert_eq!(render(Metadata::default()), render_plain());
+    }
+
+    fn render_plain() -> String {
+        Document::new()
+            .add(svg::node::element::Path::new())
+            .to_string()
+    }
+}
@@ -0,0 +1,595 @@
+use std::fmt;
+use std::str::FromStr;
+
+use svg::Document;
+use svg::Node;
+use svg::node::element::{
+    Definitions, LinearGradient as SvgLinearGradient, RadialGradient as SvgRadialGradient, Stop,
+};
+
+use crate::color::check_color;
+use crate::config::VisualOptions;
+
+/// Element id of the gradient referenced by strokes.
+pub(crate) const STROKE_ID: &str = "gm-stroke";
+/// Element id of the gradient referenced by pattern fills.
+pub(crate) const FILL_ID: &str = "gm-fill";
+/// Element id of the gradient referenced by the canvas background.
+pub(crate) const BACKGROUND_ID: &str = "gm-background";
+/// Element id of the gradient referenced by the pattern when it has its own stroke.
+pub(crate) const PATTERN_ID: &str = "gm-pattern-stroke";
+/// Element id of the gradient referenced by the inner frame when it has its own stroke.
+pub(crate) const INNER_FRAME_ID: &str = "gm-inner-frame-stroke";
+/// Element id of the gradient referenced by the outer frame when it has its own stroke.
+pub(crate) const OUTER_FRAME_ID: &str = "gm-outer-frame-stroke";
+/// Element id of the gradient referenced by the lines of a double-line inlay.
+pub(crate) const INLAY_LINE_ID: &str = "gm-inlay-line";
+/// Element id of the gradient referenced by the band between the inlay's lines.
+pub(crate) const INLAY_ID: &str = "gm-inlay";
+/// Element id of the gradient painting text.
+pub(crate) const TEXT_ID: &str = "gm-text";
+
+/// Element id of the gradient referenced by the key drawn in `key_colors[index]`.
+pub(crate) fn key_id(index: usize) -> String {
+    format!("gm-key-{index}")
+}
+
+/// A colour or gradient used to stroke or fill part of a pattern.
+///
+/// Paints parse from CSS-like strings, which is also how they appear on the
+/// command line, in TOML config files and in the WASM exports:
+///
+/// - any other string is a solid colour and is written to the SVG unchanged,
+///   e.g. `#AB8E0E`, `rgb(171, 142, 14)`, `hsl(49, 85%, 36%)` or `gold`;
+///   [`VisualOptions::validate`](crate::config::VisualOptions::validate)
+///   rejects strings that are not CSS colours
+/// - `linear-gradient(90deg, #8B6914, #FFF1A8 50%, #8B6914)`
+/// - `radial-gradient(80% at 50% 50%, #FFF1A8, #AB8E0E00)`
+///
+/// Gradients span the whole canvas, so a sheen runs continuously across the
+/// keys and both frames. Linear angles follow CSS: `0deg` runs bottom to top
+/// and `90deg` left to right. A radial centre is a percentage of the canvas
+/// width and height, and its radius a percentage of half the shorter side.
+/// Stops without an offset are spaced evenly between their neighbours.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Paint {
+    Solid(String),
+    Linear(LinearGradient),
+    Radial(RadialGradient),
+}
+
+/// A colour at a position along a gradient.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradientStop {
+    /// Position along the gradient, from 0.0 to 1.0.
+    pub offset: f64,
+    pub color: String,
+}
+
+/// A gradient along a straight line through the centre of the canvas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearGradient {
+    /// Direction in degrees, clockwise from pointing up.
+    pub angle: f64,
+    pub stops: Vec<GradientStop>,
+}
+
+/// A circular gradient spreading out from `centre`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialGradient {
+    /// Centre as fractions of the canvas width and height.
+    pub centre: (f64, f64),
+    /// Radius as a fraction of half the shorter canvas side.
+    pub radius: f64,
+    pub stops: Vec<GradientStop>,
+}
+
+impl Paint {
+    /// Returns the value for a `fill` or `stroke` attribute, referring to the
+    /// gradient with id `id` when this paint is not a solid colour.
+    pub(crate) fn attribute(&self, id: &str) -> String {
+        match self {
+            Paint::Solid(color) => color.clone(),
+            Paint::Linear(_) | Paint::Radial(_) => format!("url(#{id})"),
+        }
+    }
+
+    /// Checks that every colour parses and that gradient geometry and stops
+    /// are usable.
+    ///
+    /// `option` names the flag that set this paint, for the error message.
+    pub(crate) fn validate(&self, option: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let stops = match self {
+            Paint::Solid(color) => {
+                return check_color(color).map_err(|e| format!("{option} {e}").into());
+            }
+            Paint::Linear(linear) => {
+                if !linear.angle.is_finite() {
+                    return Err(format!("{option} gradient angle must be finite").into());
+                }
+                &linear.stops
+            }
+            Paint::Radial(radial) => {
+                if !radial.centre.0.is_finite() || !radial.centre.1.is_finite() {
+                    return Err(format!("{option} gradient centre must be finite").into());
+                }
+                if radial.radius <= 0.0 || !radial.radius.is_finite() {
+                    return Err(format!("{option} gradient radius must be positive").into());
+                }
+                &radial.stops
+            }
+        };
+        if stops.len() < 2 {
+            return Err(format!("{option} gradient needs at least two colour stops").into());
+        }
+        let mut previous = 0.0;
+        for stop in stops {
+            check_color(&stop.color).map_err(|e| format!("{option} gradient stop {e}"))?;
+            if !(0.0..=1.0).contains(&stop.offset) || stop.offset < previous {
+                return Err(
+                    format!("{option} gradient stop offsets must rise from 0% to 100%").into(),
+                );
+            }
+            previous = stop.offset;
+        }
+        Ok(())
+    }
+
+    /// Builds the SVG gradient element for this paint over a `width` by
+    /// `height` canvas, or `None` for a solid colour.
+    fn gradient(&self, id: &str, width: f64, height: f64) -> Option<Box<dyn Node>> {
+        match self {
+            Paint::Solid(_) => None,
+            Paint::Linear(linear) => {
+                // CSS sizes the gradient line so the corners get the end colours.
+                // Snap float noise so axis-aligned angles give exact end points.
+                let snap = |v: f64| if v.abs() < 1e-12 { 0.0 } else { v };
+                let radians = linear.angle.to_radians();
+                let (dx, dy) = (snap(radians.sin()), snap(-radians.cos()));
+                let half = (width * dx.abs() + height * dy.abs()) / 2.0;
+                let (cx, cy) = (width / 2.0, height / 2.0);
+                let gradient = SvgLinearGradient::new()
+                    .set("id", id)
+                    .set("gradientUnits", "userSpaceOnUse")
+                    .set("x1", cx - dx * half)
+                    .set("y1", cy - dy * half)
+                    .set("x2", cx + dx * half)
+                    .set("y2", cy + dy * half);
+                Some(Box::new(add_stops(gradient, &linear.stops)))
+            }
+            Paint::Radial(radial) => {
+                let gradient = SvgRadialGradient::new()
+                    .set("id", id)
+                    .set("gradientUnits", "userSpaceOnUse")
+                    .set("cx", radial.centre.0 * width)
+                    .set("cy", radial.centre.1 * height)
+                    .set("r", radial.radius * width.min(height) / 2.0);
+                Some(Box::new(add_stops(gradient, &radial.stops)))
+            }
+        }
+    }
+}
+
+fn add_stops<T: Node>(mut gradient: T, stops: &[GradientStop]) -> T {
+    for stop in stops {
+        gradient.append(
+            Stop::new()
+                .set("offset", stop.offset)
+                .set("stop-color", stop.color.as_str()),
+        );
+    }
+    gradient
+}
+
+/// Adds a `<defs>` with the gradients `visual` refers to, if any.
+///
+/// Call before adding any painted element so the gradients precede their use.
+pub(crate) fn add_paint_defs(
+    document: Document,
+    visual: &VisualOptions,
+    width: f64,
+    height: f64,
+) -> Document {
+    let mut paints = vec![
+        (Some(&visual.stroke_color), STROKE_ID.to_string()),
+        (visual.fill_color.as_ref(), FILL_ID.to_string()),
+        (visual.background_color.as_ref(), BACKGROUND_ID.to_string()),
+        (
+            visual.pattern_style.stroke_color.as_ref(),
+            PATTERN_ID.to_string(),
+        ),
+        (
+            visual.inner_frame_style.stroke_color.as_ref(),
+            INNER_FRAME_ID.to_string(),
+        ),
+        (
+            visual.outer_frame_style.stroke_color.as_ref(),
+            OUTER_FRAME_ID.to_string(),
+        ),
+    ];
+    if let Some(inlay) = &visual.inlay {
+        paints.push((inlay.line_color.as_ref(), INLAY_LINE_ID.to_string()));
+        paints.push((inlay.inlay_color.as_ref(), INLAY_ID.to_string()));
+    }
+    for (i, paint) in visual.key_colors.iter().enumerate() {
+        paints.push((Some(paint), key_id(i)));
+    }
+    if let Some(text) = &visual.text {
+        paints.push((text.color.as_ref(), TEXT_ID.to_string()));
+    }
+    let mut defs = Definitions::new();
+    let mut any = false;
+    for (paint, id) in paints {
+        if let Some(gradient) = paint.and_then(|p| p.gradient(&id, width, height)) {
+            defs.append(gradient);
+            any = true;
+        }
+    }
+    if any { document.add(defs) } else { document }
+}
+
+impl From<&str> for Paint {
+    /// Wraps `color` as a solid colour without parsing it; use
+    /// [`str::parse`] to accept gradient syntax.
+    fn from(color: &str) -> Self {
+        Paint::Solid(color.to_string())
+    }
+}
+
+impl From<String> for Paint {
+    /// Wraps `color` as a solid colour without parsing it; use
+    /// [`str::parse`] to accept gradient syntax.
+    fn from(color: String) -> Self {
+        Paint::Solid(color)
+    }
+}
+
+impl FromStr for Paint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(args) = function_args(s, "linear-gradient") {
+            let mut args = split_top_level(args);
+            let angle = match args.first().and_then(|a| parse_direction(a)) {
+                Some(angle) => {
+                    args.remove(0);
+                    angle?
+                }
+                None => 180.0,
+            };
+            return Ok(Paint::Linear(LinearGradient {
+                angle,
+                stops: parse_stops(&args)?,
+            }));
+        }
+        if let Some(args) = function_args(s, "radial-gradient") {
+            let mut args = split_top_level(args);
+            let (centre, radius) = match args.first().and_then(|a| parse_radial_shape(a)) {
+                Some(shape) => {
+                    args.remove(0);
+                    shape?
+                }
+                None => ((0.5, 0.5), 1.0),
+            };
+            return Ok(Paint::Radial(RadialGradient {
+                centre,
+                radius,
+                stops: parse_stops(&args)?,
+            }));
+        }
+        if s.is_empty() {
+            return Err("colour must not be empty".to_string());
+        }
+        Ok(Paint::Solid(s.to_string()))
+    }
+}
+
+impl fmt::Display for Paint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Paint::Solid(color) => write!(f, "{color}"),
+            Paint::Linear(linear) => {
+                write!(f, "linear-gradient({}deg", linear.angle)?;
+                write_stops(f, &linear.stops)
+            }
+            Paint::Radial(radial) => {
+                write!(
+                    f,
+                    "radial-gradient({}% at {}% {}%",
+                    percent(radial.radius),
+                    percent(radial.centre.0),
+                    percent(radial.centre.1)
+                )?;
+                write_stops(f, &radial.stops)
+            }
+        }
+    }
+}
+
+fn write_stops(f: &mut fmt::Formatter<'_>, stops: &[GradientStop]) -> fmt::Result {
+    for stop in stops {
+        write!(f, ", {} {}%", stop.color, percent(stop.offset))?;
+    }
+    write!(f, ")")
+}
+
+/// Converts a fraction to a percentage without float noise like `40.00000000000001`.
+fn percent(fraction: f64) -> f64 {
+    (fraction * 100.0 * 1e9).round() / 1e9
+}
+
+#[cfg(feature = "native")]
+impl serde::Serialize for Paint {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "native")]
+impl<'de> serde::Deserialize<'de> for Paint {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Returns the text between the parentheses of `name(...)`.
+fn function_args<'a>(s: &'a str, name: &str) -> Option<&'a str> {
+    s.strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+/// Splits on commas that are not inside parentheses, so `rgba(...)` stays whole.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts
+}
+
+/// Parses a leading `<angle>deg` or `to <side>` argument, or returns `None` if
+/// the argument is not a direction.
+fn parse_direction(arg: &str) -> Option<Result<f64, String>> {
+    if let Some(side) = arg.strip_prefix("to ") {
+        return Some(match side.trim() {
+            "top" => Ok(0.0),
+            "right" => Ok(90.0),
+            "bottom" => Ok(180.0),
+            "left" => Ok(270.0),
+            other => Err(format!("unknown gradient direction 'to {other}'")),
+        });
+    }
+    let degrees = arg.strip_suffix("deg")?;
+    Some(
+        degrees
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|a| a.is_finite())
+            .ok_or_else(|| format!("invalid gradient angle '{arg}'")),
+    )
+}
+
+/// Centre and radius of a radial gradient, as fractions.
+type RadialShape = ((f64, f64), f64);
+
+/// Parses a leading `[<radius>%] [at <x>% <y>%]` argument, or returns `None` if
+/// the argument is a colour stop instead.
+fn parse_radial_shape(arg: &str) -> Option<Result<RadialShape, String>> {
+    let tokens: Vec<&str> = arg.split_whitespace().collect();
+    // No colour starts with `at`, `circle` or a percentage.
+    let shape = tokens
+        .first()
+        .is_some_and(|t| *t == "at" || *t == "circle" || t.ends_with('%'));
+    if !shape {
+        return None;
+    }
+    let invalid = || format!("invalid radial gradient shape '{arg}'");
+    let mut rest = &tokens[..];
+    if rest.first() == Some(&"circle") {
+        rest = &rest[1..];
+    }
+    let mut radius = 1.0;
+    if let Some(first) = rest.first()
+        && *first != "at"
+    {
+        match parse_percent(first) {
+            Some(r) if r > 0.0 => radius = r,
+            _ => return Some(Err(invalid())),
+        }
+        rest = &rest[1..];
+    }
+    let mut centre = (0.5, 0.5);
+    match rest {
+        [] => {}
+        ["at", x, y] => match (parse_percent(x), parse_percent(y)) {
+            (Some(x), Some(y)) => centre = (x, y),
+            _ => return Some(Err(invalid())),
+        },
+        _ => return Some(Err(invalid())),
+    }
+    Some(Ok((centre, radius)))
+}
+
+fn parse_percent(s: &str) -> Option<f64> {
+    s.strip_suffix('%')?
+        .parse::<f64>()
+        .ok()
+        .filter(|p| p.is_finite())
+        .map(|p| p / 100.0)
+}
+
+/// Parses `<colour> [<offset>%]` stops, filling in missing offsets the way CSS does.
+fn parse_stops(args: &[&str]) -> Result<Vec<GradientStop>, String> {
+    if args.len() < 2 {
+        return Err("a gradient needs at least two colour stops".to_string());
+    }
+    let mut stops: Vec<(String, Option<f64>)> = Vec::with_capacity(args.len());
+    for arg in args {
+        let (color, offset) = match arg.rsplit_once(char::is_whitespace) {
+            Some((color, offset)) if offset.ends_with('%') => {
+                let offset = parse_percent(offset)
+                    .ok_or_else(|| format!("invalid stop offset '{offset}'"))?;
+                (color.trim(), Some(offset))
+            }
+            _ => (arg.trim(), None),
+        };
+        if color.is_empty() {
+            return Err("gradient stop colour must not be empty".to_string());
+        }
+        stops.push((color.to_string(), offset));
+    }
+
+    let last = stops.len() - 1;
+    stops[0].1.get_or_insert(0.0);
+    stops[last].1.get_or_insert(1.0);
+    let mut i = 0;
+    while i < last {
+        // Spread the stops between two known offsets evenly.
+        let next = (i + 1..=last)
+            .find(|&j| stops[j].1.is_some())
+            .unwrap_or(last);
+        let (from, to) = (stops[i].1.unwrap_or(0.0), stops[next].1.unwrap_or(1.0));
+        let span = (next - i) as f64;
+        for (k, stop) in stops[i + 1..next].iter_mut().enumerate() {
+            stop.1 = Some(from + (to - from) * (k + 1) as f64 / span);
+        }
+        i = next;
+    }
+
+    let mut previous = 0.0;
+    stops
+        .into_iter()
+        .map(|(color, offset)| {
+            let offset = offset.unwrap_or(previous);
+            if !(0.0..=1.0).contains(&offset) || offset < previous {
+                return Err("gradient stop offsets must rise from 0% to 100%".to_string());
+            }
+            previous = offset;
+            Ok(GradientStop { offset, color })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_colour_is_solid() {
+        assert_eq!(
+            "#AB8E0E".parse::<Paint>(),
+            Ok(Paint::Solid("#AB8E0E".into()))
+        );
+    }
+
+    #[test]
+    fn linear_gradient_parses_angle_and_stops() {
+        let paint: Paint = "linear-gradient(90deg, #8B6914, #FFF1A8 40%, rgba(0, 0, 0, 0.5))"
+            .parse()
+            .unwrap();
+        let Paint::Linear(linear) = paint else {
+            panic!("expected a linear gradient");
+        };
+        assert_eq!(linear.angle, 90.0);
+        let offsets: Vec<f64> = linear.stops.iter().map(|s| s.offset).collect();
+        assert_eq!(offsets, [0.0, 0.4, 1.0]);
+        assert_eq!(linear.stops[2].color, "rgba(0, 0, 0, 0.5)");
+    }
+
+    #[test]
+    fn missing_offsets_are_spread_evenly() {
+        let paint: Paint = "linear-gradient(to right, red, green, blue, white)"
+            .parse()
+            .unwrap();
+        let Paint::Linear(linear) = paint else {
+            panic!("expected a linear gradient");
+        };
+        assert_eq!(linear.angle, 90.0);
+        let offsets: Vec<f64> = linear.stops.iter().map(|s| s.offset).collect();
+        assert_eq!(offsets, [0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0]);
+    }
+
+    #[test]
+    fn radial_gradient_parses_radius_and_centre() {
+        let paint: Paint = "radial-gradient(80% at 25% 75%, white, black)"
+            .parse()
+            .unwrap();
+        let Paint::Radial(radial) = paint else {
+            panic!("expected a radial gradient");
+        };
+        assert_eq!(radial.radius, 0.8);
+        assert_eq!(radial.centre, (0.25, 0.75));
+    }
+
+    #[test]
+    fn radial_gradient_defaults_to_canvas_centre() {
+        let paint: Paint = "radial-gradient(white, black)".parse().unwrap();
+        let Paint::Radial(radial) = paint else {
+            panic!("expected a radial gradient");
+        };
+        assert_eq!((radial.centre, radial.radius), ((0.5, 0.5), 1.0));
+    }
+
+    #[test]
+    fn invalid_gradients_are_rejected() {
+        for s in [
+            "linear-gradient(red)",
+            "linear-gradient(45deg, red)",
+            "linear-gradient(to middle, red, blue)",
+            "linear-gradient(red 60%, blue 20%)",
+            "radial-gradient(at 10%, red, blue)",
+            "",
+        ] {
+            assert!(s.parse::<Paint>().is_err(), "{s:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in [
+            "#AB8E0E",
+            "linear-gradient(45deg, #8B6914 0%, #FFF1A8 50%, #8B6914 100%)",
+            "radial-gradient(80% at 50% 40%, white 0%, black 100%)",
+        ] {
+            let paint: Paint = s.parse().unwrap();
+            assert_eq!(paint.to_string(), s);
+            assert_eq!(paint.to_string().parse::<Paint>().unwrap(), paint);
+        }
+    }
+
+    #[test]
+    fn gradient_paint_adds_defs() {
+        let visual = VisualOptions::new(
+            "linear-gradient(90deg, #8B6914, #FFF1A8)"
+                .parse::<Paint>()
+                .unwrap(),
+            1.0,
+        );
+        let svg = add_paint_defs(Document::new(), &visual, 200.0, 100.0).to_string();
+        assert!(svg.contains("<linearGradient"));
+        assert!(svg.contains(r#"id="gm-stroke""#));
+        assert!(svg.contains(r#"x1="0""#) && svg.contains(r#"x2="200""#));
+    }
+
+    #[test]
+    fn solid_paints_add_no_defs() {
+        let svg =
+            add_paint_defs(Document::new(), &VisualOptions::default(), 200.0, 100.0).to_string();
+        assert!(!svg.contains("<defs"));
+    }
+}
@@ -0,0 +1,44 @@
+use crate::file_config::FileConfig;
+
+/// A built-in config that reproduces one of the sample images in `images/`.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    PlainRect,
+    PlainCircle,
+    StyledRect,
+    StyledCircle,
+}
+
+impl Preset {
+    /// The preset as written in `presets/`.
+    pub fn source(self) -> &'static str {
+        match self {
+            Preset::PlainRect => include_str!("../presets/plain-rect.toml"),
+            Preset::PlainCircle => include_str!("../presets/plain-circle.toml"),
+            Preset::StyledRect => include_str!("../presets/styled-rect.toml"),
+            Preset::StyledCircle => include_str!("../presets/styled-circle.toml"),
+        }
+    }
+
+    pub fn config(self) -> FileConfig {
+        toml::from_str(self.source()).expect("built-in presets are valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ValueEnum;
+
+    use super::*;
+    use crate::validate;
+
+    #[test]
+    fn presets_use_only_known_options() {
+        for preset in Preset::value_variants() {
+            let source = preset.source();
+            let value: serde_json::Value = toml::from_str(source).unwrap();
+            assert_eq!(validate::unknown_keys(source, &value), [], "{preset:?}");
+            preset.config();
+        }
+    }
+}
@@ -4,75 +4,76 @@ use svg::node::element::{Path as SvgPath, Rectangle};
 
 #[cfg(feature = "native")]
 use crate::common::save_and_convert_svg;
-use crate::config::{GreekKeyRectConfig, VisualOptions};
-
-fn draw_horizontal_unit(data: Data, key_unit_length: i32) -> Data {
-    data.line_by((0, -4 * key_unit_length))
-        .line_by((4 * key_unit_length, 0))
-        .line_by((0, 3 * key_unit_length))
-        .line_by((-2 * key_unit_length, 0))
-        .line_by((0, -key_unit_length))
-        .line_by((key_unit_length, 0))
-        .line_by((0, -key_unit_length))
-        .line_by((-2 * key_unit_length, 0))
-        .line_by((0, 3 * key_unit_length))
-        .line_by((4 * key_unit_length, 0))
-}
-
-fn draw_vertical_unit(data: Data, key_unit_length: i32) -> Data {
-    data.line_by((4 * key_unit_length, 0))
-        .line_by((0, 4 * key_unit_length))
-        .line_by((-3 * key_unit_length, 0))
-        .line_by((0, -2 * key_unit_length))
-        .line_by((key_unit_length, 0))
-        .line_by((0, key_unit_length))
-        .line_by((key_unit_length, 0))
-        .line_by((0, -2 * key_unit_length))
-        .line_by((-3 * key_unit_length, 0))
-        .line_by((0, 4 * key_unit_length))
-}
-
-fn draw_horizontal_unit_right_to_left(data: Data, key_unit_length: i32) -> Data {
-    data.line_by((-4 * key_unit_length, 0))
-        .line_by((0, -3 * key_unit_length))
-        .line_by((2 * key_unit_length, 0))
-        .line_by((0, key_unit_length))
-        .line_by((-key_unit_length, 0))
-        .line_by((0, key_unit_length))
-        .line_by((2 * key_unit_length, 0))
-        .line_by((0, -3 * key_unit_length))
-        .line_by((-4 * key_unit_length, 0))
-        .line_by((0, 4 * key_unit_length))
-}
-
-fn draw_vertical_unit_bottom_up(data: Data, key_unit_length: i32) -> Data {
-    data.line_by((0, -4 * key_unit_length))
-        .line_by((3 * key_unit_length, 0))
-        .line_by((0, 2 * key_unit_length))
-        .line_by((-key_unit_length, 0))
-        .line_by((0, -key_unit_length))
-        .line_by((-key_unit_length, 0))
-        .line_by((0, 2 * key_unit_length))
-        .line_by((3 * key_unit_length, 0))
-        .line_by((0, -4 * key_unit_length))
-        .line_by((-4 * key_unit_length, 0))
-}
-
-fn draw_frame(
-    x: f64,
-    y: f64,
-    w: i32,
-    h: i32,
-    stroke_color: &str,
-    stroke_width: f32,
-    stroke_opacity: f32,
-) -> SvgPath {
-    let data = Data::new()
+use crate::common::{Point, add_double_line, add_pattern, split_polyline};
+use crate::compact;
+use crate::config::{Element, GreekKeyRectConfig, VisualOptions};
+use crate::field::{FieldClip, add_field};
+use crate::metadata;
+use crate::paint::add_paint_defs;
+use crate::text::{TextLayout, add_text};
+
+// One key per unit along each side, in key units, walking the border clockwise.
+// The run along the rail shared by neighbouring keys is the last step of the
+// top and right units and the ninth of the bottom and left units.
+const TOP_UNIT: [(i32, i32); 10] = [
+    (0, -4),
+    (4, 0),
+    (0, 3),
+    (-2, 0),
+    (0, -1),
+    (1, 0),
+    (0, -1),
+    (-2, 0),
+    (0, 3),
+    (4, 0),
+];
+const RIGHT_UNIT: [(i32, i32); 10] = [
+    (4, 0),
+    (0, 4),
+    (-3, 0),
+    (0, -2),
+    (1, 0),
+    (0, 1),
+    (1, 0),
+    (0, -2),
+    (-3, 0),
+    (0, 4),
+];
+const BOTTOM_UNIT: [(i32, i32); 10] = [
+    (-4, 0),
+    (0, -3),
+    (2, 0),
+    (0, 1),
+    (-1, 0),
+    (0, 1),
+    (2, 0),
+    (0, -3),
+    (-4, 0),
+    (0, 4),
+];
+const LEFT_UNIT: [(i32, i32); 10] = [
+    (0, -4),
+    (3, 0),
+    (0, 2),
+    (-1, 0),
+    (0, -1),
+    (-1, 0),
+    (0, 2),
+    (3, 0),
+    (0, -4),
+    (-4, 0),
+];
+
+fn frame_data(x: f64, y: f64, w: i32, h: i32) -> Data {
+    Data::new()
         .move_to((x, y))
         .line_by((w, 0))
         .line_by((0, h))
         .line_by((-w, 0))
-        .close();
+        .close()
+}
+
+fn draw_frame(data: Data, stroke_color: &str, stroke_width: f32, stroke_opacity: f32) -> SvgPath {
     SvgPath::new()
         .set("fill", "none")
         .set("stroke", stroke_color.to_string())
@@ -81,40 +82,56 @@ fn draw_frame(
         .set("d", data)
 }
 
+// Returns the border's steps in key units, from the start position round to
+// the start again, and the index of each key's shared rail run.
+fn pattern_steps(config: &GreekKeyRectConfig) -> (Vec<(i32, i32)>, Vec<usize>) {
+    let mut steps = Vec::new();
+    let mut cuts = Vec::new();
+    let mut side =
+        |steps: &mut Vec<(i32, i32)>, unit: &[(i32, i32); 10], count: i32, cut: usize| {
+            for _ in 0..count {
+                cuts.push(steps.len() + cut);
+                steps.extend_from_slice(unit);
+            }
+        };
+
+    steps.push((0, -1));
+    side(&mut steps, &TOP_UNIT, config.width_units - 1, 9);
+    steps.extend([(0, -4), (1, 0)]);
+    side(&mut steps, &RIGHT_UNIT, config.height_units - 1, 9);
+    steps.extend([(4, 0), (0, 5)]);
+    side(&mut steps, &BOTTOM_UNIT, config.width_units - 1, 8);
+    steps.push((-5, 0));
+    side(&mut steps, &LEFT_UNIT, config.height_units - 1, 8);
+    (steps, cuts)
+}
+
 fn draw_greek_key_patterns(config: &GreekKeyRectConfig) -> Data {
     let (start_x, start_y) = config.get_start_position();
     let key_unit_length = config.key_unit_length;
-    let width_units = config.width_units;
-    let height_units = config.height_units;
 
     let mut data = Data::new().move_to((start_x, start_y));
-    data = data.line_by((0, -key_unit_length));
-
-    for _ in 0..width_units - 1 {
-        data = draw_horizontal_unit(data, key_unit_length);
-    }
-
-    data = data.line_by((0, -4 * key_unit_length));
-    data = data.line_by((key_unit_length, 0));
-
-    for _ in 0..height_units - 1 {
-        data = draw_vertical_unit(data, key_unit_length);
+    for (dx, dy) in pattern_steps(config).0 {
+        data = data.line_by((dx * key_unit_length, dy * key_unit_length));
     }
+    data.close()
+}
 
-    data = data.line_by((4 * key_unit_length, 0));
-    data = data.line_by((0, 5 * key_unit_length));
-
-    for _ in 0..width_units - 1 {
-        data = draw_horizontal_unit_right_to_left(data, key_unit_length);
-    }
-
-    data = data.line_by((-5 * key_unit_length, 0));
-
-    for _ in 0..height_units - 1 {
-        data = draw_vertical_unit_bottom_up(data, key_unit_length);
+fn draw_key_segments(config: &GreekKeyRectConfig) -> Vec<Data> {
+    let (x, y) = config.get_start_position();
+    let unit = config.key_unit_length as f64;
+    let (steps, cuts) = pattern_steps(config);
+    let mut points = vec![Point { x, y }];
+    for (dx, dy) in steps {
+        let last = points[points.len() - 1];
+        points.push(Point {
+            x: last.x + dx as f64 * unit,
+            y: last.y + dy as f64 * unit,
+        });
     }
-
-    data.close()
+    // The steps end back at the start position.
+    points.pop();
+    split_polyline(&points, &cuts, true)
 }
 
 fn apply_dash(path: SvgPath, dash: Option<&str>) -> SvgPath {
@@ -124,64 +141,81 @@ fn apply_dash(path: SvgPath, dash: Option<&str>) -> SvgPath {
     }
 }
 
-fn build_document(config: &GreekKeyRectConfig, visual: &VisualOptions) -> Document {
+pub(crate) fn build_document(config: &GreekKeyRectConfig, visual: &VisualOptions) -> Document {
     let stroke_width = config.stroke_width;
-    let stroke_color = visual.stroke_color.as_str();
-    let stroke_opacity = visual.stroke_opacity;
     let dash = visual.stroke_dash.as_deref();
     let (width, height) = config.get_canvas_size();
     let mut document = Document::new().set("viewBox", (0, 0, width, height));
+    document = add_paint_defs(document, visual, width, height);
 
-    if let Some(bg) = &visual.background_color {
+    if let Some(bg) = visual.background_paint() {
         document = document.add(
             Rectangle::new()
                 .set("width", width)
                 .set("height", height)
-                .set("fill", bg.as_str()),
+                .set("fill", bg),
         );
     }
 
-    let path_data = draw_greek_key_patterns(config);
-    let path = apply_dash(
-        SvgPath::new()
-            .set("fill", visual.fill_color.as_deref().unwrap_or("none"))
-            .set("stroke", stroke_color)
-            .set("stroke-width", stroke_width)
-            .set("stroke-opacity", stroke_opacity)
-            .set("d", path_data),
-        dash,
+    document = add_pattern(
+        document,
+        visual,
+        draw_greek_key_patterns(config),
+        || draw_key_segments(config),
+        &visual.fill_paint(),
+        stroke_width,
     );
-    document = document.add(path);
+
+    let (inner_x, inner_y, inner_width, inner_height) = config.get_inner_frame_size();
+    if let Some(spacing) = visual.field_spacing {
+        let clip = FieldClip::Rect {
+            x: inner_x,
+            y: inner_y,
+            w: inner_width as f64,
+            h: inner_height as f64,
+        };
+        document = add_field(document, clip, spacing, stroke_width, visual);
+    }
 
     let (outer_x, outer_y, outer_width, outer_height) = config.get_outer_frame_size();
-    document = document.add(apply_dash(
-        draw_frame(
-            outer_x,
-            outer_y,
-            outer_width,
-            outer_height,
-            stroke_color,
-            stroke_width,
-            stroke_opacity,
+    for (element, data) in [
+        (
+            Element::OuterFrame,
+            frame_data(outer_x, outer_y, outer_width, outer_height),
         ),
-        dash,
-    ));
-
-    let (inner_x, inner_y, inner_width, inner_height) = config.get_inner_frame_size();
-    document = document.add(apply_dash(
-        draw_frame(
-            inner_x,
-            inner_y,
-            inner_width,
-            inner_height,
-            stroke_color,
-            stroke_width,
-            stroke_opacity,
+        (
+            Element::InnerFrame,
+            frame_data(inner_x, inner_y, inner_width, inner_height),
         ),
-        dash,
-    ));
+    ] {
+        if visual.is_hidden(element) {
+            continue;
+        }
+        document = match &visual.inlay {
+            Some(inlay) => add_double_line(document, visual, inlay, element, data),
+            None => document.add(apply_dash(
+                draw_frame(
+                    data,
+                    &visual.element_stroke(element),
+                    stroke_width,
+                    visual.element_opacity(element),
+                ),
+                dash,
+            )),
+        };
+    }
 
-    document
+    let (half_width, half_height) = (inner_width as f64 / 2.0, inner_height as f64 / 2.0);
+    let layout = TextLayout {
+        centre: Point {
+            x: inner_x + half_width,
+            y: inner_y + half_height,
+        },
+        inner: (half_width, half_height),
+        rings: None,
+        stroke_width,
+    };
+    add_text(document, visual, &layout)
 }
 
 /// Returns the rectangle Greek Key pattern as an SVG string.
@@ -189,7 +223,18 @@ fn build_document(config: &GreekKeyRectConfig, visual: &VisualOptions) -> Docume
 /// Available on all targets including WASM. For file output, use
 /// [`generate_pattern_svg`] (requires the `native` feature).
 pub fn generate_svg_string(config: &GreekKeyRectConfig, visual: &VisualOptions) -> String {
-    build_document(config, visual).to_string()
+    let visual = metadata::stamped(
+        visual,
+        config.stroke_width,
+        config.border_margin,
+        "rect",
+        vec![
+            ("size", config.key_unit_length.into()),
+            ("width", config.width_units.into()),
+            ("height", config.height_units.into()),
+        ],
+    );
+    compact::to_string(build_document(config, &visual), &visual)
 }
 
 /// Generates a rectangle Greek Key pattern and writes `<filename>.svg` and `<filename>.png`.
@@ -202,5 +247,5 @@ pub fn generate_pattern_svg(
     visual: &VisualOptions,
     filename: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    save_and_convert_svg(build_document(config, visual), filename)
+    save_and_convert_svg(&generate_svg_string(config, visual), filename)
 }
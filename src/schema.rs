@@ -0,0 +1,669 @@
+use serde_json::{Map, Value, json};
+
+fn number(description: &str) -> Value {
+    json!({ "type": "number", "description": description })
+}
+
+fn integer(description: &str) -> Value {
+    json!({ "type": "integer", "description": description })
+}
+
+fn boolean(description: &str) -> Value {
+    json!({ "type": "boolean", "description": description })
+}
+
+fn string(description: &str) -> Value {
+    json!({ "type": "string", "description": description })
+}
+
+fn one_of(names: &[&str], description: &str) -> Value {
+    json!({ "type": "string", "enum": names, "description": description })
+}
+
+fn list(items: Value, description: &str) -> Value {
+    json!({ "type": "array", "items": items, "description": description })
+}
+
+// `option` with an example value, which `config init` writes for options that
+// have no default.
+fn example(mut option: Value, value: Value) -> Value {
+    option["examples"] = json!([value]);
+    option
+}
+
+fn object(properties: Vec<(&str, Value)>) -> Value {
+    Value::Object(
+        properties
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+fn section(description: &str, properties: Vec<(&str, Value)>) -> Value {
+    json!({
+        "type": "object",
+        "description": description,
+        "properties": object(properties),
+        "additionalProperties": false,
+    })
+}
+
+fn element(part: &str) -> Value {
+    section(
+        &format!("Stroke overrides for the {part}"),
+        vec![
+            (
+                "color",
+                example(
+                    string(&format!(
+                        "Stroke color or gradient for the {part} [default: stroke_color]"
+                    )),
+                    json!("#7C3B2E"),
+                ),
+            ),
+            (
+                "opacity",
+                example(
+                    number(&format!(
+                        "Stroke opacity for the {part} [default: stroke_opacity]"
+                    )),
+                    json!(1.0),
+                ),
+            ),
+            ("hidden", boolean(&format!("Leave out the {part}"))),
+        ],
+    )
+}
+
+/// Returns the JSON Schema for config files, whether written as TOML, JSON or
+/// YAML. Every key is optional and unknown keys are flagged, so editors can
+/// complete and check a config as it is typed.
+pub fn config_schema() -> Value {
+    let pattern_count = |around: &str| {
+        integer(&format!(
+            "Number of patterns around the {around} [default: 30]"
+        ))
+    };
+    let options = vec![
+        (
+            "theme",
+            one_of(
+                &["attic-red-figure", "gold-leaf", "marble", "monochrome"],
+                "Built-in look; other options override it",
+            ),
+        ),
+        (
+            "stroke_width",
+            number("Stroke width; not allowed with inlay.gap, which sets it [default: 6]"),
+        ),
+        (
+            "stroke_color",
+            string(
+                "Stroke color or gradient, e.g. \"linear-gradient(90deg, #8B6914, #FFF1A8)\" [default: #AB8E0E]",
+            ),
+        ),
+        ("stroke_opacity", number("Stroke opacity [default: 0.7]")),
+        (
+            "fill_color",
+            example(
+                string("Fill color or gradient for pattern interior [default: none]"),
+                json!("#F5E6C8"),
+            ),
+        ),
+        (
+            "background_color",
+            example(
+                string("Background color or gradient for the SVG canvas [default: none]"),
+                json!("#FFFDF7"),
+            ),
+        ),
+        (
+            "stroke_dash",
+            example(
+                string("SVG stroke-dasharray value [default: solid]"),
+                json!("5,3"),
+            ),
+        ),
+        (
+            "line_join",
+            one_of(
+                &["miter", "round", "bevel"],
+                "Stroke corner style [default: miter]",
+            ),
+        ),
+        (
+            "line_cap",
+            one_of(
+                &["butt", "round", "square"],
+                "Stroke end style [default: butt]",
+            ),
+        ),
+        (
+            "miter_limit",
+            example(
+                number("Longest miter, in stroke widths, before a corner is beveled [default: 4]"),
+                json!(4.0),
+            ),
+        ),
+        (
+            "non_scaling_stroke",
+            boolean("Keep the stroke width fixed when the SVG is scaled"),
+        ),
+        (
+            "effects",
+            list(
+                one_of(
+                    &["shadow", "emboss", "engrave", "inner-glow"],
+                    "Filter effect",
+                ),
+                "Filter effects for the border, combined in order",
+            ),
+        ),
+        (
+            "field_spacing",
+            example(
+                number(
+                    "Fill the inside of the border with a key lattice of this spacing [default: none]",
+                ),
+                json!(12.0),
+            ),
+        ),
+        (
+            "arc_tolerance",
+            example(
+                number(
+                    "Draw circle and ellipse key runs as line segments within this many pixels of the true arc [default: true arcs]",
+                ),
+                json!(0.5),
+            ),
+        ),
+        (
+            "precision",
+            example(
+                integer(
+                    "Maximum decimal places for coordinates and lengths [default: full precision]",
+                ),
+                json!(2),
+            ),
+        ),
+        (
+            "shared_styles",
+            boolean("Put shared stroke and fill attributes on one <g>"),
+        ),
+        (
+            "relative_paths",
+            boolean("Write path data with relative commands"),
+        ),
+        (
+            "minify",
+            boolean(
+                "Compact SVG output, about 1.6 to 4 times smaller: shared styles, relative paths, 2 decimal places unless precision is set",
+            ),
+        ),
+        (
+            "id_prefix",
+            example(
+                string(
+                    "Start element ids with this instead of gm, so SVGs inlined in one page do not clash",
+                ),
+                json!("border"),
+            ),
+        ),
+        (
+            "key_colors",
+            example(
+                list(
+                    string("Stroke color or gradient"),
+                    "Stroke colors for successive keys, alternating",
+                ),
+                json!(["#AB8E0E", "#1F5B73"]),
+            ),
+        ),
+        ("pattern", element("key pattern")),
+        ("inner_frame", element("inner frame")),
+        ("outer_frame", element("outer frame")),
+        (
+            "inlay",
+            section(
+                "Draw every line as two thin lines; gap turns the inlay on",
+                vec![
+                    (
+                        "gap",
+                        example(
+                            number(
+                                "Distance between the two lines in pixels, replacing stroke_width",
+                            ),
+                            json!(4.0),
+                        ),
+                    ),
+                    (
+                        "line_width",
+                        number("Stroke width of each of the two lines [default: 2]"),
+                    ),
+                    (
+                        "line_color",
+                        example(
+                            string(
+                                "Color or gradient of the inlay lines [default: the part's stroke color]",
+                            ),
+                            json!("#AB8E0E"),
+                        ),
+                    ),
+                    (
+                        "color",
+                        example(
+                            string("Color or gradient between the inlay lines [default: none]"),
+                            json!("#1F5B73"),
+                        ),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "text",
+            section(
+                "Title and ring text; title or ring_text turns text on",
+                vec![
+                    (
+                        "title",
+                        example(
+                            list(
+                                string("Title line"),
+                                "Title lines centred inside the border",
+                            ),
+                            json!(["Symposium", "MMXXVI"]),
+                        ),
+                    ),
+                    (
+                        "ring_text",
+                        example(
+                            string("Text running around a circle or ellipse border"),
+                            json!("Greek key"),
+                        ),
+                    ),
+                    (
+                        "rail",
+                        one_of(
+                            &["inner", "outer"],
+                            "Frame the ring text follows [default: inner]",
+                        ),
+                    ),
+                    (
+                        "font_family",
+                        example(
+                            string("CSS font family [default: serif]"),
+                            json!("Cinzel, serif"),
+                        ),
+                    ),
+                    (
+                        "font_size",
+                        example(
+                            number("Font size in pixels [default: fit the space]"),
+                            json!(24.0),
+                        ),
+                    ),
+                    (
+                        "color",
+                        example(
+                            string("Color or gradient for text [default: stroke_color]"),
+                            json!("#7C3B2E"),
+                        ),
+                    ),
+                    (
+                        "font_file",
+                        example(
+                            string(
+                                "TrueType or OpenType font to embed, so the SVG and PNG draw the same glyphs",
+                            ),
+                            json!("fonts/Cinzel.ttf"),
+                        ),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "metadata",
+            section(
+                "Accessible title and description, author and licence stored in the SVG",
+                vec![
+                    (
+                        "title",
+                        example(
+                            string(
+                                "Accessible name stored as the SVG <title> [default: the text title lines]",
+                            ),
+                            json!("Gold key border"),
+                        ),
+                    ),
+                    (
+                        "description",
+                        example(
+                            string("Description stored as the SVG <desc>"),
+                            json!("A gold Greek key border around a round menu card"),
+                        ),
+                    ),
+                    (
+                        "author",
+                        example(
+                            string("Author recorded in the SVG metadata"),
+                            json!("A. Potter"),
+                        ),
+                    ),
+                    (
+                        "license",
+                        example(
+                            string("Licence URL or rights statement recorded in the SVG metadata"),
+                            json!("https://creativecommons.org/licenses/by/4.0/"),
+                        ),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "border_margin",
+            integer("Border margin in pixels [default: 1]"),
+        ),
+        (
+            "file",
+            string(
+                "Base name of the output file; {shape} and {<option>} placeholders take the design's values [default: meander]",
+            ),
+        ),
+        ("scale", number("PNG scale factor [default: 1.0]")),
+        (
+            "rect",
+            section(
+                "Options for the rect command",
+                vec![
+                    ("size", integer("Key unit length in pixels [default: 25]")),
+                    ("width", integer("Width in pattern units [default: 16]")),
+                    ("height", integer("Height in pattern units [default: 9]")),
+                ],
+            ),
+        ),
+        (
+            "circle",
+            section(
+                "Options for the circle command",
+                vec![
+                    ("pattern_count", pattern_count("circle")),
+                    ("radius", number("Outer radius in pixels [default: 300]")),
+                ],
+            ),
+        ),
+        (
+            "ellipse",
+            section(
+                "Options for the ellipse command",
+                vec![
+                    ("pattern_count", pattern_count("ellipse")),
+                    (
+                        "rx",
+                        number("Horizontal outer semi-axis in pixels [default: 300]"),
+                    ),
+                    (
+                        "ry",
+                        number("Vertical outer semi-axis in pixels [default: 200]"),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "superellipse",
+            section(
+                "Options for the superellipse command",
+                vec![
+                    ("pattern_count", pattern_count("superellipse")),
+                    (
+                        "rx",
+                        number("Horizontal outer semi-axis in pixels [default: 300]"),
+                    ),
+                    (
+                        "ry",
+                        number("Vertical outer semi-axis in pixels [default: 200]"),
+                    ),
+                    (
+                        "exponent",
+                        number("Superellipse exponent; 2 is an ellipse, 4 a squircle [default: 4]"),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "spiral",
+            section(
+                "Options for the spiral command",
+                vec![
+                    (
+                        "kind",
+                        one_of(
+                            &["archimedean", "logarithmic"],
+                            "Spiral kind [default: archimedean]",
+                        ),
+                    ),
+                    ("turns", number("Number of full turns [default: 3]")),
+                    (
+                        "start_radius",
+                        number("Radius where the band starts, at the top [default: 300]"),
+                    ),
+                    (
+                        "end_radius",
+                        number("Radius where the band ends [default: 60]"),
+                    ),
+                    ("size", number("Key unit length in pixels [default: 8]")),
+                ],
+            ),
+        ),
+        (
+            "field",
+            section(
+                "Options for the field command",
+                vec![
+                    (
+                        "width",
+                        number("Width of the filled area in pixels [default: 600]"),
+                    ),
+                    (
+                        "height",
+                        number("Height of the filled area in pixels [default: 400]"),
+                    ),
+                    (
+                        "size",
+                        number("Key unit length of the lattice in pixels [default: 10]"),
+                    ),
+                ],
+            ),
+        ),
+        (
+            "frame",
+            section(
+                "Options for the frame command",
+                vec![
+                    (
+                        "input",
+                        example(
+                            string("PNG, JPEG or SVG image to put inside the border"),
+                            json!("artwork.png"),
+                        ),
+                    ),
+                    (
+                        "shape",
+                        one_of(
+                            &["rect", "circle", "ellipse"],
+                            "Border shape [default: rect]",
+                        ),
+                    ),
+                    (
+                        "size",
+                        integer("Key unit length in pixels for a rect border [default: 10]"),
+                    ),
+                    (
+                        "pattern_count",
+                        integer(
+                            "Number of patterns around a circle or ellipse border [default: 30]",
+                        ),
+                    ),
+                ],
+            ),
+        ),
+    ];
+    let options = json!({ "type": "object", "properties": object(options) });
+    let profile = json!({
+        "type": "object",
+        "description": "Named variants of the options above, selected with --profile; each is laid over the rest of the file",
+        "additionalProperties": {
+            "description": "Options that differ in this profile",
+            "$ref": "#/$defs/options",
+            "unevaluatedProperties": false,
+        },
+    });
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "greek-meander config",
+        "description": "Design options for greek-meander. Command-line flags override them.",
+        "type": "object",
+        "$ref": "#/$defs/options",
+        "properties": object(vec![
+            ("$schema", string("Path or URL of this schema, for editors")),
+            ("profile", profile),
+        ]),
+        "unevaluatedProperties": false,
+        "$defs": { "options": options },
+    })
+}
+
+/// The options in `schema`, as returned by [`config_schema`]: top-level
+/// options and sections, which profiles take too.
+pub fn options(schema: &Value) -> &Map<String, Value> {
+    schema["$defs"]["options"]["properties"]
+        .as_object()
+        .expect("the schema has options")
+}
+
+/// The schema as shipped in `schema/greek-meander.schema.json`.
+pub fn config_schema_text() -> String {
+    let mut text = serde_json::to_string_pretty(&config_schema()).expect("the schema is JSON");
+    text.push('\n');
+    text
+}
+
+/// The key of every option the schema lists, dotted for options in a section,
+/// such as `inlay.gap`.
+pub fn option_keys() -> Vec<String> {
+    let schema = config_schema();
+    let mut keys = Vec::new();
+    for (key, property) in options(&schema) {
+        match property.get("properties").and_then(Value::as_object) {
+            Some(options) => keys.extend(options.keys().map(|name| format!("{key}.{name}"))),
+            None => keys.push(key.clone()),
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_config::{self, FileConfig};
+
+    fn properties(schema: &Value) -> &Map<String, Value> {
+        schema["properties"].as_object().unwrap()
+    }
+
+    // Values of the type the schema gives, every name for a choice, to check
+    // the config accepts them.
+    fn samples(schema: &Value) -> Vec<toml::Value> {
+        if let Some(names) = schema["enum"].as_array() {
+            return names
+                .iter()
+                .map(|name| toml::Value::String(name.as_str().unwrap().to_string()))
+                .collect();
+        }
+        match schema["type"].as_str().unwrap() {
+            "number" => vec![toml::Value::Float(1.5)],
+            "integer" => vec![toml::Value::Integer(2)],
+            "boolean" => vec![toml::Value::Boolean(true)],
+            "string" => vec![toml::Value::String("#123456".to_string())],
+            "array" => samples(&schema["items"])
+                .into_iter()
+                .map(|item| toml::Value::Array(vec![item]))
+                .collect(),
+            other => panic!("no sample for {other}"),
+        }
+    }
+
+    #[test]
+    fn schema_lists_every_config_option() {
+        let schema = config_schema();
+        // Empty sections come back with every option, unset ones as null.
+        let sections: toml::Table = options(&schema)
+            .iter()
+            .filter(|(_, property)| property.get("properties").is_some())
+            .map(|(key, _)| (key.clone(), toml::Value::Table(toml::Table::new())))
+            .collect();
+        let config: FileConfig = toml::Value::Table(sections).try_into().unwrap();
+        let config = serde_json::to_value(&config).unwrap();
+
+        let keys = |object: &Map<String, Value>| {
+            let mut keys: Vec<String> = object.keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        let mut top_level = options(&schema).clone();
+        top_level.extend(properties(&schema).clone());
+        top_level.remove("$schema");
+        assert_eq!(keys(&top_level), keys(config.as_object().unwrap()));
+        for (key, property) in options(&schema) {
+            if property.get("properties").is_some() {
+                assert_eq!(
+                    keys(properties(property)),
+                    keys(config[key].as_object().unwrap()),
+                    "[{key}]"
+                );
+            }
+        }
+        // A profile takes every option but profiles, and the file and its
+        // profiles share one list of them.
+        assert!(!options(&schema).contains_key("profile"));
+        assert_eq!(schema["$ref"], "#/$defs/options");
+        assert_eq!(
+            properties(&schema)["profile"]["additionalProperties"]["$ref"],
+            "#/$defs/options"
+        );
+    }
+
+    #[test]
+    fn schema_types_are_accepted_by_the_config() {
+        let schema = config_schema();
+        let mut keyed = Vec::new();
+        for (key, property) in options(&schema) {
+            if property.get("properties").is_some() {
+                for (name, option) in properties(property) {
+                    keyed.push((format!("{key}.{name}"), option));
+                }
+            } else {
+                keyed.push((key.clone(), property));
+            }
+        }
+        for (key, option) in keyed {
+            for value in samples(option) {
+                let mut table = toml::Table::new();
+                assert_eq!(
+                    file_config::set_option(&mut table, "", &key, value.clone()),
+                    Some(key.clone()),
+                    "{key} = {value}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shipped_schema_is_up_to_date() {
+        let shipped = include_str!("../schema/greek-meander.schema.json");
+        assert!(
+            shipped == config_schema_text(),
+            "schema/greek-meander.schema.json is stale; regenerate it with `greek-meander config schema > schema/greek-meander.schema.json`"
+        );
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::batch::Shape;
+use crate::file_config::{self, FileConfig};
+
+/// Longest request head read before giving up.
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Renders depend only on their options and the version, so clients may keep
+/// them for a day and revalidate with the ETag after that.
+const CACHE_CONTROL: &str = "public, max-age=86400";
+/// Options that would read local files or name output files.
+const REFUSED_OPTIONS: [&str; 2] = ["file", "text.font_file"];
+
+/// The image format a request asks for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Svg,
+    Png,
+}
+
+impl Format {
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Svg => "image/svg+xml",
+            Format::Png => "image/png",
+        }
+    }
+}
+
+/// A design named by a request target, `/<shape>.<svg|png>?<options>`.
+#[derive(Debug)]
+pub struct Design {
+    pub shape: Shape,
+    pub format: Format,
+    /// The server defaults with the query options laid over them.
+    pub config: FileConfig,
+    /// The path and resolved options, the same however the query is written.
+    key: String,
+}
+
+/// Reads a request target. Query parameters name config options as in a
+/// config file, with dotted keys for sections; shape options may leave out
+/// their section. Errors carry the HTTP status to answer with.
+pub fn parse_design(target: &str, defaults: &toml::Table) -> Result<Design, (u16, String)> {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let not_found = || {
+        (
+            404,
+            format!(
+                "no design at '{path}'; request /<shape>.svg or /<shape>.png, with shape one of rect, circle, ellipse, superellipse, spiral or field"
+            ),
+        )
+    };
+    let (name, extension) = path
+        .strip_prefix('/')
+        .and_then(|file| file.rsplit_once('.'))
+        .ok_or_else(not_found)?;
+    let shape = Shape::from_str(name, false)
+        .ok()
+        .filter(|&shape| shape != Shape::Frame)
+        .ok_or_else(not_found)?;
+    let format = match extension {
+        "svg" => Format::Svg,
+        "png" => Format::Png,
+        _ => return Err(not_found()),
+    };
+
+    let mut table = defaults.clone();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let (key, value) = match (decode(key), decode(value)) {
+            (Some(key), Some(value)) => (key, value),
+            _ => return Err((400, format!("malformed query parameter '{pair}'"))),
+        };
+        let section = shape.section();
+        if REFUSED_OPTIONS.contains(&key.as_str())
+            || file_config::set_option_text(&mut table, section, &key, &value).is_none()
+        {
+            return Err((
+                400,
+                format!("'{key}' is not an option for {section}, or cannot take '{value}'"),
+            ));
+        }
+    }
+    let config: FileConfig = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e| (400, e.to_string()))?;
+    let options = toml::to_string(&config).map_err(|e| (500, e.to_string()))?;
+    Ok(Design {
+        shape,
+        format,
+        config,
+        key: format!("{path}\n{options}"),
+    })
+}
+
+// Decodes a query component: `+` is a space and `%XX` a byte of UTF-8.
+fn decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.bytes();
+    while let Some(byte) = rest.next() {
+        bytes.push(match byte {
+            b'+' => b' ',
+            b'%' => {
+                let hex = [rest.next()?, rest.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            }
+            byte => byte,
+        });
+    }
+    String::from_utf8(bytes).ok()
+}
+
+// 64-bit FNV-1a, stable across runs for use in ETags.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Least recently used renders, most recent last.
+struct Lru {
+    capacity: usize,
+    entries: VecDeque<(String, Arc<[u8]>)>,
+}
+
+impl Lru {
+    fn get(&mut self, key: &str) -> Option<Arc<[u8]>> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(index)?;
+        let body = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(body)
+    }
+
+    fn insert(&mut self, key: String, body: Arc<[u8]>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.retain(|(k, _)| *k != key);
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, body));
+    }
+}
+
+struct Request {
+    method: String,
+    target: String,
+    if_none_match: Option<String>,
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<Request, String> {
+    let mut head = Vec::new();
+    let mut buffer = [0; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_HEAD {
+            return Err("request head is too large".into());
+        }
+        let read = stream.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("connection closed before the request ended".into());
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let mut parts = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err("malformed request line".into());
+    };
+    let if_none_match = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("if-none-match"))
+        .map(|(_, value)| value.trim().to_string());
+    Ok(Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        if_none_match,
+    })
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Arc<[u8]>,
+}
+
+impl Response {
+    fn error(status: u16, message: &str) -> Self {
+        let body = serde_json::json!({ "error": message }).to_string();
+        Self {
+            status,
+            content_type: "application/json",
+            headers: Vec::new(),
+            body: body.into_bytes().into(),
+        }
+    }
+
+    fn write_to(&self, stream: &mut impl Write, with_body: bool) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            304 => "Not Modified",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        };
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            reason,
+            self.content_type,
+            self.body.len()
+        );
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes())?;
+        if with_body && self.status != 304 {
+            stream.write_all(&self.body)?;
+        }
+        stream.flush()
+    }
+}
+
+/// Serves designs over HTTP, rendering each with `render` and keeping the
+/// most recent in an in-memory cache.
+pub struct Server<F> {
+    defaults: toml::Table,
+    cache: Mutex<Lru>,
+    render: F,
+}
+
+impl<F> Server<F>
+where
+    F: Fn(Shape, Format, FileConfig) -> Result<Vec<u8>, String> + Sync,
+{
+    /// `defaults` holds the options a query leaves out; `cache_size` is the
+    /// number of renders kept, or 0 to render every request.
+    pub fn new(defaults: toml::Table, cache_size: usize, render: F) -> Self {
+        Self {
+            defaults,
+            cache: Mutex::new(Lru {
+                capacity: cache_size,
+                entries: VecDeque::new(),
+            }),
+            render,
+        }
+    }
+
+    /// Answers connections on `listener` with `workers` threads, each taking
+    /// one connection at a time, until the process ends.
+    pub fn run(&self, listener: TcpListener, workers: usize) -> io::Result<()> {
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..workers.max(1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        for stream in listener.incoming() {
+                            self.answer(stream?);
+                        }
+                        Ok(())
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .try_for_each(|worker| worker.join().expect("workers catch render panics"))
+        })
+    }
+
+    fn answer(&self, mut stream: TcpStream) {
+        let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+        let (response, with_body) = match read_request(&mut stream) {
+            Ok(request) => (self.respond(&request), request.method != "HEAD"),
+            Err(e) => (Response::error(400, &e), true),
+        };
+        let _ = response.write_to(&mut stream, with_body);
+    }
+
+    fn respond(&self, request: &Request) -> Response {
+        if request.method != "GET" && request.method != "HEAD" {
+            let mut response = Response::error(405, "only GET and HEAD are supported");
+            response.headers.push(("Allow", "GET, HEAD".into()));
+            return response;
+        }
+        let design = match parse_design(&request.target, &self.defaults) {
+            Ok(design) => design,
+            Err((status, message)) => return Response::error(status, &message),
+        };
+        let etag = format!(
+            "\"{:016x}\"",
+            fnv1a(format!("{}\n{}", env!("CARGO_PKG_VERSION"), design.key).as_bytes())
+        );
+        let headers = vec![
+            ("Cache-Control", CACHE_CONTROL.to_string()),
+            ("ETag", etag.clone()),
+        ];
+        let content_type = design.format.content_type();
+        if request.if_none_match.as_deref() == Some(etag.as_str()) {
+            return Response {
+                status: 304,
+                content_type,
+                headers,
+                body: Arc::new([]),
+            };
+        }
+
+        let cached = self.cache.lock().unwrap().get(&design.key);
+        let (body, hit) = match cached {
+            Some(body) => (body, "HIT"),
+            None => {
+                let Design {
+                    shape,
+                    format,
+                    config,
+                    key,
+                } = design;
+                let rendered =
+                    catch_unwind(AssertUnwindSafe(|| (self.render)(shape, format, config)));
+                let body: Arc<[u8]> = match rendered {
+                    Ok(Ok(body)) => body.into(),
+                    Ok(Err(e)) => return Response::error(400, &e),
+                    Err(_) => return Response::error(500, "rendering panicked"),
+                };
+                self.cache.lock().unwrap().insert(key, body.clone());
+                (body, "MISS")
+            }
+        };
+        let mut response = Response {
+            status: 200,
+            content_type,
+            headers,
+            body,
+        };
+        response.headers.push(("X-Cache", hit.into()));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_map_onto_config_options() {
+        let mut defaults = toml::Table::new();
+        defaults.insert("stroke_opacity".into(), toml::Value::Float(0.5));
+        let design = parse_design(
+            "/circle.png?radius=120&stroke_color=%23aa0000&inlay.gap=3&metadata.title=Gold+border",
+            &defaults,
+        )
+        .unwrap();
+        assert_eq!(design.shape, Shape::Circle);
+        assert_eq!(design.format, Format::Png);
+        let config = &design.config;
+        assert_eq!(config.circle.as_ref().unwrap().radius, Some(120.0));
+        assert_eq!(config.stroke_color.as_ref().unwrap().to_string(), "#aa0000");
+        assert_eq!(config.inlay.as_ref().unwrap().gap, Some(3.0));
+        assert_eq!(
+            config.metadata.as_ref().unwrap().title.as_deref(),
+            Some("Gold border")
+        );
+        assert_eq!(config.stroke_opacity, Some(0.5));
+    }
+
+    #[test]
+    fn equivalent_queries_share_a_key() {
+        let defaults = toml::Table::new();
+        let key = |target| parse_design(target, &defaults).unwrap().key;
+        assert_eq!(
+            key("/circle.svg?pattern_count=20&radius=120"),
+            key("/circle.svg?radius=120.0&circle.pattern_count=20")
+        );
+        assert_ne!(key("/rect.svg?size=10"), key("/rect.png?size=10"));
+    }
+
+    #[test]
+    fn bad_targets_are_refused() {
+        let defaults = toml::Table::new();
+        let status = |target| parse_design(target, &defaults).unwrap_err().0;
+        assert_eq!(status("/hexagon.svg"), 404);
+        assert_eq!(status("/frame.svg"), 404);
+        assert_eq!(status("/rect.gif"), 404);
+        assert_eq!(status("/rect.svg?radius=10"), 400);
+        assert_eq!(status("/rect.svg?size=big"), 400);
+        assert_eq!(status("/rect.svg?text.font_file=%2Fetc%2Fpasswd"), 400);
+        assert_eq!(status("/rect.svg?size=%zz"), 400);
+    }
+
+    #[test]
+    fn a_panicking_render_gets_a_500() {
+        let server = Server::new(toml::Table::new(), 0, |_, _, _| panic!("overflow"));
+        let response = server.respond(&Request {
+            method: "GET".into(),
+            target: "/rect.svg".into(),
+            if_none_match: None,
+        });
+        assert_eq!(response.status, 500);
+        assert_eq!(&*response.body, br#"{"error":"rendering panicked"}"#);
+    }
+
+    #[test]
+    fn least_recently_used_renders_are_evicted() {
+        let mut cache = Lru {
+            capacity: 2,
+            entries: VecDeque::new(),
+        };
+        let body = |byte| Arc::from(vec![byte]);
+        cache.insert("a".into(), body(1));
+        cache.insert("b".into(), body(2));
+        assert!(cache.get("a").is_some());
+        cache.insert("c".into(), body(3));
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").as_deref(), Some(&[1][..]));
+        assert_eq!(cache.get("c").as_deref(), Some(&[3][..]));
+    }
+}
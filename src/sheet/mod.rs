@@ -0,0 +1,179 @@
+use svg::Document;
+use svg::node::element::{Image, Rectangle, Text};
+
+#[cfg(feature = "native")]
+use crate::common::save_and_convert_svg;
+use crate::frame::Artwork;
+
+/// Space around and between cells, in pixels.
+const GAP: f64 = 16.0;
+const LABEL_FONT_SIZE: f64 = 12.0;
+const LABEL_LINE_HEIGHT: f64 = 16.0;
+/// Advance of a monospace glyph, as a fraction of the font size.
+const LABEL_CHAR_WIDTH: f64 = 0.6;
+const LABEL_COLOR: &str = "#333333";
+const CELL_OUTLINE: &str = "#dddddd";
+
+/// One design on a contact sheet, with the lines printed under it.
+#[derive(Debug, Clone)]
+pub struct SheetEntry {
+    /// The design, or `None` to leave its cell empty, such as for a variant
+    /// that could not be built.
+    pub artwork: Option<Artwork>,
+    pub label: Vec<String>,
+}
+
+/// Returns a contact sheet of `entries` as an SVG string.
+///
+/// The entries are laid out left to right in rows of `columns`, each scaled to
+/// fit a `cell` pixel square with its label lines below it in a monospace
+/// font. Columns widen to fit the longest label line. Entries are embedded as
+/// data URIs, so their element ids cannot clash.
+///
+/// Available on all targets including WASM. For file output, use
+/// [`generate_pattern_svg`] (requires the `native` feature).
+pub fn generate_svg_string(entries: &[SheetEntry], columns: usize, cell: f64) -> String {
+    let columns = columns.clamp(1, entries.len().max(1));
+    let rows = entries.len().div_ceil(columns);
+    let label_lines = entries.iter().map(|e| e.label.len()).max().unwrap_or(0);
+    let label_height = label_lines as f64 * LABEL_LINE_HEIGHT;
+    let longest = entries
+        .iter()
+        .flat_map(|e| &e.label)
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let column_width = cell.max(longest as f64 * LABEL_FONT_SIZE * LABEL_CHAR_WIDTH);
+    let width = GAP + columns as f64 * (column_width + GAP);
+    let height = GAP + rows as f64 * (cell + label_height + GAP);
+
+    let mut document = Document::new()
+        .set("width", width)
+        .set("height", height)
+        .set("viewBox", (0, 0, width, height))
+        .add(
+            Rectangle::new()
+                .set("width", width)
+                .set("height", height)
+                .set("fill", "white"),
+        );
+    for (index, entry) in entries.iter().enumerate() {
+        let left = GAP + (index % columns) as f64 * (column_width + GAP);
+        let x = left + (column_width - cell) / 2.0;
+        let y = GAP + (index / columns) as f64 * (cell + label_height + GAP);
+        document = document.add(
+            Rectangle::new()
+                .set("x", x)
+                .set("y", y)
+                .set("width", cell)
+                .set("height", cell)
+                .set("fill", "none")
+                .set("stroke", CELL_OUTLINE),
+        );
+        if let Some(artwork) = &entry.artwork {
+            let scale = (cell / artwork.width()).min(cell / artwork.height());
+            let (w, h) = (artwork.width() * scale, artwork.height() * scale);
+            document = document.add(
+                Image::new()
+                    .set("x", x + (cell - w) / 2.0)
+                    .set("y", y + (cell - h) / 2.0)
+                    .set("width", w)
+                    .set("height", h)
+                    .set("href", artwork.data_uri()),
+            );
+        }
+        for (line, text) in entry.label.iter().enumerate() {
+            document = document.add(
+                Text::new(text.as_str())
+                    .set("x", left)
+                    .set("y", y + cell + (line + 1) as f64 * LABEL_LINE_HEIGHT - 3.0)
+                    .set("font-family", "monospace")
+                    .set("font-size", LABEL_FONT_SIZE)
+                    .set("fill", LABEL_COLOR),
+            );
+        }
+    }
+    document.to_string()
+}
+
+/// Writes a contact sheet of `entries` to `<filename>.svg` and `<filename>.png`.
+///
+/// Requires the `native` feature (enabled by default). For WASM targets, use
+/// [`generate_svg_string`] instead.
+#[cfg(feature = "native")]
+pub fn generate_pattern_svg(
+    entries: &[SheetEntry],
+    columns: usize,
+    cell: f64,
+    filename: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    save_and_convert_svg(&generate_svg_string(entries, columns, cell), filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(width: u32, height: u32, label: &[&str]) -> SheetEntry {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}"/>"#
+        );
+        SheetEntry {
+            artwork: Some(Artwork::from_bytes(svg.into_bytes()).unwrap()),
+            label: label.iter().map(|line| line.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn entries_fill_rows_of_columns() {
+        let entries = vec![
+            entry(100, 100, &["a = 1"]),
+            entry(200, 100, &["a = 2", "b = 3"]),
+            entry(100, 400, &["a = 3"]),
+        ];
+        let svg = generate_svg_string(&entries, 2, 100.0);
+        // Two columns, two rows of a 100px cell and two label lines each.
+        assert!(
+            svg.contains(r#"height="312" viewBox="0 0 248 312" width="248""#),
+            "{svg}"
+        );
+        // Wide and tall designs are fitted inside their cell and centred.
+        assert!(
+            svg.contains(r#"width="100" x="132" y="41""#) && svg.contains(r#"<image height="50""#),
+            "{svg}"
+        );
+        assert!(svg.contains(r#"width="25" x="53.5" y="164""#), "{svg}");
+        assert!(svg.contains("a = 2\n</text>"));
+        assert!(svg.contains("b = 3\n</text>"));
+        assert_eq!(svg.matches("data:image/svg+xml;base64,").count(), 3);
+    }
+
+    #[test]
+    fn missing_designs_leave_an_empty_cell() {
+        let entries = [
+            SheetEntry {
+                artwork: None,
+                label: vec!["failed".into()],
+            },
+            entry(10, 10, &[]),
+        ];
+        let svg = generate_svg_string(&entries, 2, 50.0);
+        assert_eq!(svg.matches("<image").count(), 1);
+        assert!(svg.contains(r#"x="82" y="16""#), "{svg}");
+    }
+
+    #[test]
+    fn columns_widen_for_long_labels() {
+        let label = "circle.pattern_count = 20";
+        let svg = generate_svg_string(&[entry(10, 10, &[label])], 1, 100.0);
+        // 25 characters at 7.2px each.
+        assert!(svg.contains(r#"viewBox="0 0 212 148""#), "{svg}");
+        assert!(svg.contains(r#"x="56" y="16""#), "{svg}");
+    }
+
+    #[test]
+    fn columns_are_limited_to_the_entries() {
+        let svg = generate_svg_string(&[entry(10, 10, &[])], 5, 50.0);
+        assert!(svg.contains(r#"viewBox="0 0 82 82""#), "{svg}");
+    }
+}
@@ -0,0 +1,288 @@
+use svg::Document;
+use svg::node::element::path::Data;
+use svg::node::element::{Path as SvgPath, Rectangle};
+
+#[cfg(feature = "native")]
+use crate::common::save_and_convert_svg;
+use crate::common::{Point, add_double_line, add_pattern, split_polyline};
+use crate::compact;
+use crate::config::{Element, GreekKeySpiralConfig, VisualOptions};
+use crate::metadata;
+use crate::paint::add_paint_defs;
+
+// Rail offsets from the centreline in key units, positive towards the centre.
+// The band is six units wide, matching the circle's r_o..r_i.
+const RAIL_A: f64 = 2.0;
+const RAIL_B: f64 = 1.0;
+const RAIL_C: f64 = 0.0;
+const RAIL_D: f64 = -1.0;
+const RAIL_E: f64 = -2.0;
+const FRAME_INNER: f64 = 3.0;
+const FRAME_OUTER: f64 = -3.0;
+
+// Returns the pattern's corners along the band and, for each key but the
+// last, the index of the corner starting its run along rail A to the next key.
+fn pattern_points(config: &GreekKeySpiralConfig) -> (Vec<Point>, Vec<usize>) {
+    let unit = config.key_unit_length;
+    let margin = config.end_margin();
+    let p = |offset: f64, s: f64| config.offset_point(s, offset);
+
+    let mut points = vec![p(RAIL_A, 0.0), p(RAIL_A, margin)];
+    let mut cuts = Vec::new();
+    for key in 0..config.key_count() {
+        let s = margin + 5.0 * unit * key as f64;
+        points.extend([
+            p(RAIL_E, s),
+            p(RAIL_E, s + 4.0 * unit),
+            p(RAIL_B, s + 4.0 * unit),
+            p(RAIL_B, s + 2.0 * unit),
+            p(RAIL_C, s + 2.0 * unit),
+            p(RAIL_C, s + 3.0 * unit),
+            p(RAIL_D, s + 3.0 * unit),
+            p(RAIL_D, s + unit),
+            p(RAIL_A, s + unit),
+        ]);
+        cuts.push(points.len() - 1);
+        points.push(p(RAIL_A, s + 5.0 * unit));
+    }
+    cuts.pop();
+    points.push(p(RAIL_A, config.length()));
+    (points, cuts)
+}
+
+fn polyline(points: &[Point]) -> Data {
+    let mut data = Data::new().move_to((points[0].x, points[0].y));
+    for point in &points[1..] {
+        data = data.line_to((point.x, point.y));
+    }
+    data
+}
+
+fn draw_greek_key_patterns(config: &GreekKeySpiralConfig) -> Data {
+    polyline(&pattern_points(config).0)
+}
+
+fn draw_key_segments(config: &GreekKeySpiralConfig) -> Vec<Data> {
+    let (points, cuts) = pattern_points(config);
+    split_polyline(&points, &cuts, false)
+}
+
+fn draw_frame(
+    data: Data,
+    stroke_color: &str,
+    stroke_width: f32,
+    stroke_opacity: f32,
+    fill_color: &str,
+) -> SvgPath {
+    SvgPath::new()
+        .set("fill", fill_color)
+        .set("stroke", stroke_color.to_string())
+        .set("stroke-width", stroke_width)
+        .set("stroke-opacity", stroke_opacity)
+        .set("d", data)
+}
+
+fn apply_dash(path: SvgPath, dash: Option<&str>) -> SvgPath {
+    match dash {
+        Some(d) => path.set("stroke-dasharray", d),
+        None => path,
+    }
+}
+
+fn build_document(config: &GreekKeySpiralConfig, visual: &VisualOptions) -> Document {
+    let stroke_width = config.stroke_width;
+    let dash = visual.stroke_dash.as_deref();
+    let (width, height) = config.get_canvas_size();
+    let mut document = Document::new().set("viewBox", (0, 0, width, height));
+    document = add_paint_defs(document, visual, width, height);
+
+    if let Some(bg) = visual.background_paint() {
+        document = document.add(
+            Rectangle::new()
+                .set("width", width)
+                .set("height", height)
+                .set("fill", bg),
+        );
+    }
+
+    // The pattern is an open path, so the fill colour goes on the band outline
+    // underneath it instead: along the outer frame, across the end cap, back
+    // along the inner frame and across the start cap.
+    let fill = visual.fill_paint();
+    let outer = config.rail(FRAME_OUTER);
+    let inner = config.rail(FRAME_INNER);
+    let outline: Vec<Point> = outer.iter().chain(inner.iter().rev()).copied().collect();
+    // A single-stroke outline carries the fill itself; otherwise it goes on
+    // an unstroked copy underneath the frames.
+    let apart = visual.inner_frame_style != visual.outer_frame_style;
+    let fill_apart = apart || visual.inlay.is_some() || visual.is_hidden(Element::OuterFrame);
+    if fill != "none" && fill_apart {
+        document = document.add(
+            SvgPath::new()
+                .set("fill", fill.as_str())
+                .set("stroke", "none")
+                .set("d", polyline(&outline).close()),
+        );
+    }
+    let frame_fill = if fill_apart { "none" } else { fill.as_str() };
+    let mut frames = Vec::new();
+    if !apart {
+        frames.push((Element::OuterFrame, polyline(&outline).close()));
+    } else {
+        // Styled apart, the outer frame keeps both end caps.
+        let mut points = vec![inner[0]];
+        points.extend(&outer);
+        points.push(inner[inner.len() - 1]);
+        frames.push((Element::OuterFrame, polyline(&points)));
+        frames.push((Element::InnerFrame, polyline(&inner)));
+    }
+    for (element, data) in frames {
+        if visual.is_hidden(element) {
+            continue;
+        }
+        document = match &visual.inlay {
+            Some(inlay) => add_double_line(document, visual, inlay, element, data),
+            None => document.add(apply_dash(
+                draw_frame(
+                    data,
+                    &visual.element_stroke(element),
+                    stroke_width,
+                    visual.element_opacity(element),
+                    frame_fill,
+                ),
+                dash,
+            )),
+        };
+    }
+
+    document = add_pattern(
+        document,
+        visual,
+        draw_greek_key_patterns(config),
+        || draw_key_segments(config),
+        "none",
+        stroke_width,
+    );
+
+    document
+}
+
+/// Returns the spiral Greek Key pattern as an SVG string.
+///
+/// Available on all targets including WASM. For file output, use
+/// [`generate_pattern_svg`] (requires the `native` feature).
+pub fn generate_svg_string(config: &GreekKeySpiralConfig, visual: &VisualOptions) -> String {
+    let visual = metadata::stamped(
+        visual,
+        config.stroke_width,
+        config.border_margin,
+        "spiral",
+        vec![
+            ("kind", config.kind.as_str().into()),
+            ("turns", config.turns.into()),
+            ("start_radius", config.start_radius.into()),
+            ("end_radius", config.end_radius.into()),
+            ("size", config.key_unit_length.into()),
+        ],
+    );
+    compact::to_string(build_document(config, &visual), &visual)
+}
+
+/// Generates a spiral Greek Key pattern and writes `<filename>.svg` and `<filename>.png`.
+///
+/// Requires the `native` feature (enabled by default). For WASM targets, use
+/// [`generate_svg_string`] instead.
+#[cfg(feature = "native")]
+pub fn generate_pattern_svg(
+    config: &GreekKeySpiralConfig,
+    visual: &VisualOptions,
+    filename: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    save_and_convert_svg(&generate_svg_string(config, visual), filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ElementStyle, SpiralKind};
+
+    fn config() -> GreekKeySpiralConfig {
+        GreekKeySpiralConfig::new(SpiralKind::Archimedean, 3.0, 300.0, 60.0, 8.0, 10, 3.0).unwrap()
+    }
+
+    #[test]
+    fn spiral_svg_contains_svg_element() {
+        let svg = generate_svg_string(&config(), &VisualOptions::default());
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("<path"));
+    }
+
+    #[test]
+    fn spiral_svg_has_band_outline_and_pattern() {
+        let svg = generate_svg_string(&config(), &VisualOptions::default());
+        assert_eq!(svg.matches("<path").count(), 2);
+    }
+
+    #[test]
+    fn spiral_fill_color_goes_on_band_outline() {
+        let visual = VisualOptions {
+            fill_color: Some("#FF0000".into()),
+            ..VisualOptions::default()
+        };
+        let svg = generate_svg_string(&config(), &visual);
+        assert_eq!(svg.matches("fill=\"#FF0000\"").count(), 1);
+    }
+
+    #[test]
+    fn spiral_frames_styled_apart_split_the_outline() {
+        let visual = VisualOptions {
+            fill_color: Some("#FF0000".into()),
+            inner_frame_style: ElementStyle {
+                stroke_color: Some("#0000FF".into()),
+                ..ElementStyle::default()
+            },
+            ..VisualOptions::default()
+        };
+        let svg = generate_svg_string(&config(), &visual);
+        // Fill outline, outer frame, inner frame and pattern.
+        assert_eq!(svg.matches("<path").count(), 4);
+        assert_eq!(svg.matches("stroke=\"#0000FF\"").count(), 1);
+        assert_eq!(svg.matches("fill=\"#FF0000\"").count(), 1);
+    }
+
+    #[test]
+    fn spiral_key_colors_cover_every_key() {
+        let config = config();
+        let visual = VisualOptions {
+            key_colors: vec!["#A3341F".into()],
+            ..VisualOptions::default()
+        };
+        let svg = generate_svg_string(&config, &visual);
+        let keys = config.key_count() as usize;
+        assert_eq!(svg.matches("stroke=\"#A3341F\"").count(), keys);
+    }
+
+    #[test]
+    fn spiral_keys_keep_constant_size() {
+        let config =
+            GreekKeySpiralConfig::new(SpiralKind::Logarithmic, 2.0, 300.0, 40.0, 6.0, 10, 3.0)
+                .unwrap();
+        let unit = config.key_unit_length;
+        let first = config.offset_point(config.end_margin(), 0.0);
+        let next = config.offset_point(config.end_margin() + unit, 0.0);
+        let last = config.offset_point(config.length() - config.end_margin() - unit, 0.0);
+        let before_last = config.offset_point(config.length() - config.end_margin(), 0.0);
+        let d1 = (next.x - first.x).hypot(next.y - first.y);
+        let d2 = (before_last.x - last.x).hypot(before_last.y - last.y);
+        assert!((d1 - unit).abs() < 0.05);
+        assert!((d2 - unit).abs() < 0.05);
+    }
+
+    #[test]
+    fn spiral_keys_fit_within_path() {
+        let config = config();
+        let used = config.key_count() as f64 * 5.0 * config.key_unit_length;
+        assert!(used <= config.length());
+        assert!(config.length() - used < 5.0 * config.key_unit_length);
+    }
+}
@@ -0,0 +1,232 @@
+use svg::Document;
+use svg::node::element::path::Data;
+use svg::node::element::{Path as SvgPath, Rectangle};
+
+#[cfg(feature = "native")]
+use crate::common::save_and_convert_svg;
+use crate::common::{Point, add_double_line, add_pattern, split_polyline};
+use crate::compact;
+use crate::config::{Element, GreekKeySuperellipseConfig, VisualOptions};
+use crate::field::{FieldClip, add_field};
+use crate::metadata;
+use crate::paint::add_paint_defs;
+use crate::text::{TextLayout, add_text};
+
+// Rail depths measured inwards from the outer edge, matching the circle's r_a..r_e.
+const RAIL_A: i32 = 5;
+const RAIL_B: i32 = 4;
+const RAIL_C: i32 = 3;
+const RAIL_D: i32 = 2;
+const RAIL_E: i32 = 1;
+const RAIL_INNER: i32 = 6;
+
+// Returns the pattern's corners round the curve and, for each key, the index
+// of the corner starting its run along rail A to the next key.
+fn pattern_points(config: &GreekKeySuperellipseConfig) -> (Vec<Point>, Vec<usize>) {
+    let p = |depth: i32, step: i32| config.rail_point(depth, step);
+
+    let mut points = vec![p(RAIL_A, 0)];
+    let mut cuts = Vec::new();
+    for key in 0..config.pattern_count {
+        let s = 5 * key;
+        points.extend([
+            p(RAIL_E, s),
+            p(RAIL_E, s + 4),
+            p(RAIL_B, s + 4),
+            p(RAIL_B, s + 2),
+            p(RAIL_C, s + 2),
+            p(RAIL_C, s + 3),
+            p(RAIL_D, s + 3),
+            p(RAIL_D, s + 1),
+            p(RAIL_A, s + 1),
+        ]);
+        cuts.push(points.len() - 1);
+        points.push(p(RAIL_A, s + 5));
+    }
+    // The last key ends back on the first point.
+    points.pop();
+    (points, cuts)
+}
+
+fn draw_greek_key_patterns(config: &GreekKeySuperellipseConfig) -> Data {
+    outline_data(&pattern_points(config).0)
+}
+
+fn draw_key_segments(config: &GreekKeySuperellipseConfig) -> Vec<Data> {
+    let (points, cuts) = pattern_points(config);
+    split_polyline(&points, &cuts, true)
+}
+
+fn outline_data(points: &[Point]) -> Data {
+    let mut data = Data::new().move_to((points[0].x, points[0].y));
+    for point in &points[1..] {
+        data = data.line_to((point.x, point.y));
+    }
+    data.close()
+}
+
+fn draw_frame(data: Data, stroke_color: &str, stroke_width: f32, stroke_opacity: f32) -> SvgPath {
+    SvgPath::new()
+        .set("fill", "none")
+        .set("stroke", stroke_color.to_string())
+        .set("stroke-width", stroke_width)
+        .set("stroke-opacity", stroke_opacity)
+        .set("d", data)
+}
+
+fn apply_dash(path: SvgPath, dash: Option<&str>) -> SvgPath {
+    match dash {
+        Some(d) => path.set("stroke-dasharray", d),
+        None => path,
+    }
+}
+
+fn build_document(config: &GreekKeySuperellipseConfig, visual: &VisualOptions) -> Document {
+    let stroke_width = config.stroke_width;
+    let dash = visual.stroke_dash.as_deref();
+    let (width, height) = config.get_canvas_size();
+    let mut document = Document::new().set("viewBox", (0, 0, width, height));
+    document = add_paint_defs(document, visual, width, height);
+
+    if let Some(bg) = visual.background_paint() {
+        document = document.add(
+            Rectangle::new()
+                .set("width", width)
+                .set("height", height)
+                .set("fill", bg),
+        );
+    }
+
+    document = add_pattern(
+        document,
+        visual,
+        draw_greek_key_patterns(config),
+        || draw_key_segments(config),
+        &visual.fill_paint(),
+        stroke_width,
+    );
+
+    let inner_outline = config.rail_outline(RAIL_INNER);
+    // Text is laid out in the inner outline's bounding box.
+    let (x, y, w, h) = FieldClip::Polygon(inner_outline.clone()).bounds();
+    if let Some(spacing) = visual.field_spacing {
+        let clip = FieldClip::Polygon(inner_outline.clone());
+        document = add_field(document, clip, spacing, stroke_width, visual);
+    }
+
+    for (element, outline) in [
+        (Element::InnerFrame, inner_outline),
+        (Element::OuterFrame, config.rail_outline(0)),
+    ] {
+        if visual.is_hidden(element) {
+            continue;
+        }
+        let data = outline_data(&outline);
+        document = match &visual.inlay {
+            Some(inlay) => add_double_line(document, visual, inlay, element, data),
+            None => document.add(apply_dash(
+                draw_frame(
+                    data,
+                    &visual.element_stroke(element),
+                    stroke_width,
+                    visual.element_opacity(element),
+                ),
+                dash,
+            )),
+        };
+    }
+
+    let layout = TextLayout {
+        centre: Point {
+            x: x + w / 2.0,
+            y: y + h / 2.0,
+        },
+        inner: (w / 2.0, h / 2.0),
+        rings: None,
+        stroke_width,
+    };
+    add_text(document, visual, &layout)
+}
+
+/// Returns the superellipse Greek Key pattern as an SVG string.
+///
+/// Available on all targets including WASM. For file output, use
+/// [`generate_pattern_svg`] (requires the `native` feature).
+pub fn generate_svg_string(config: &GreekKeySuperellipseConfig, visual: &VisualOptions) -> String {
+    let visual = metadata::stamped(
+        visual,
+        config.stroke_width,
+        config.border_margin,
+        "superellipse",
+        vec![
+            ("pattern_count", config.pattern_count.into()),
+            ("rx", config.rx.into()),
+            ("ry", config.ry.into()),
+            ("exponent", config.exponent.into()),
+        ],
+    );
+    compact::to_string(build_document(config, &visual), &visual)
+}
+
+/// Generates a superellipse Greek Key pattern and writes `<filename>.svg` and `<filename>.png`.
+///
+/// Requires the `native` feature (enabled by default). For WASM targets, use
+/// [`generate_svg_string`] instead.
+#[cfg(feature = "native")]
+pub fn generate_pattern_svg(
+    config: &GreekKeySuperellipseConfig,
+    visual: &VisualOptions,
+    filename: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    save_and_convert_svg(&generate_svg_string(config, visual), filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn superellipse_svg_contains_svg_element() {
+        let config = GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 30, 10, 3.0).unwrap();
+        let svg = generate_svg_string(&config, &VisualOptions::default());
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("<path"));
+    }
+
+    #[test]
+    fn superellipse_svg_has_pattern_and_two_frames() {
+        let config = GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 30, 10, 3.0).unwrap();
+        let svg = generate_svg_string(&config, &VisualOptions::default());
+        assert_eq!(svg.matches("<path").count(), 3);
+    }
+
+    #[test]
+    fn superellipse_svg_contains_dash() {
+        let config = GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 30, 10, 3.0).unwrap();
+        let visual = VisualOptions {
+            stroke_dash: Some("5,3".to_string()),
+            ..VisualOptions::default()
+        };
+        let svg = generate_svg_string(&config, &visual);
+        assert_eq!(svg.matches("stroke-dasharray").count(), 3);
+    }
+
+    #[test]
+    fn superellipse_outer_frame_touches_semi_axes() {
+        let config = GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 30, 10, 3.0).unwrap();
+        let outline = config.rail_outline(0);
+        let max_x = outline.iter().map(|p| p.x).fold(f64::MIN, f64::max);
+        let max_y = outline.iter().map(|p| p.y).fold(f64::MIN, f64::max);
+        assert!((max_x - (13.0 + 600.0)).abs() < 0.5);
+        assert!((max_y - (13.0 + 400.0)).abs() < 0.5);
+    }
+
+    #[test]
+    fn exponent_two_with_equal_axes_matches_circle_radii() {
+        let config = GreekKeySuperellipseConfig::new(300.0, 300.0, 2.0, 30, 10, 3.0).unwrap();
+        let circle = crate::GreekKeyCircleConfig::new(300.0, 30, 10, 3.0).unwrap();
+        let inner = config.rail_point(RAIL_INNER, 0);
+        let centre = circle.get_centre();
+        assert!(((centre.y - inner.y) - circle.radii.r_i).abs() < 0.1);
+    }
+}
@@ -0,0 +1,257 @@
+use std::error::Error;
+use std::str::FromStr;
+
+use crate::batch::{Job, Shape};
+use crate::file_config::{self, FileConfig};
+
+/// Most variants one sweep may render.
+const MAX_VARIANTS: usize = 1000;
+
+/// A `--vary` option: a config key and the values it takes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sweep {
+    /// Config key, dotted for options in a section, e.g. `circle.radius`.
+    pub key: String,
+    pub values: Vec<toml::Value>,
+}
+
+impl FromStr for Sweep {
+    type Err = String;
+
+    /// Parses `key=values`, where values is a list such as `2,4,8` or an
+    /// inclusive range such as `20..40` or `20..40:5`. Whole numbers stay
+    /// integers so they can set integer options.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || {
+            format!(
+                "invalid --vary '{s}': expected key=values, with a list like 2,4,8 or a range like 20..40:5"
+            )
+        };
+        let (key, values) = s.split_once('=').ok_or_else(usage)?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(usage());
+        }
+        let values = match values.split_once("..") {
+            Some((start, rest)) => {
+                let (end, step) = rest.split_once(':').unwrap_or((rest, "1"));
+                range(start, end, step).ok_or_else(usage)?
+            }
+            None => list(values).ok_or_else(usage)?,
+        };
+        if values.is_empty() {
+            return Err(format!("invalid --vary '{s}': the range is empty"));
+        }
+        Ok(Self {
+            key: key.to_string(),
+            values,
+        })
+    }
+}
+
+fn list(values: &str) -> Option<Vec<toml::Value>> {
+    let parts: Vec<&str> = values.split(',').map(str::trim).collect();
+    if let Ok(integers) = parts
+        .iter()
+        .map(|p| p.parse())
+        .collect::<Result<Vec<_>, _>>()
+    {
+        return Some(integers.into_iter().map(toml::Value::Integer).collect());
+    }
+    parts
+        .iter()
+        .map(|p| p.parse::<f64>().ok().filter(|v| v.is_finite()))
+        .map(|v| v.map(toml::Value::Float))
+        .collect()
+}
+
+fn range(start: &str, end: &str, step: &str) -> Option<Vec<toml::Value>> {
+    let (start, end, step) = (start.trim(), end.trim(), step.trim());
+    if let (Ok(start), Ok(end), Ok(step)) = (
+        start.parse::<i64>(),
+        end.parse::<i64>(),
+        step.parse::<i64>(),
+    ) {
+        if step <= 0 {
+            return None;
+        }
+        let values = (start..=end).step_by(step as usize);
+        return Some(values.map(toml::Value::Integer).collect());
+    }
+    let (start, end, step) = (
+        start.parse::<f64>().ok()?,
+        end.parse::<f64>().ok()?,
+        step.parse::<f64>().ok()?,
+    );
+    if !(start.is_finite() && end.is_finite() && step.is_finite() && step > 0.0) {
+        return None;
+    }
+    // Counted rather than accumulated, so 0.1 steps land on round values.
+    let count = ((end - start) / step + 1e-9).floor();
+    if count < 0.0 {
+        return Some(Vec::new());
+    }
+    Some(
+        (0..=count as usize)
+            .map(|i| toml::Value::Float(((start + i as f64 * step) * 1e9).round() / 1e9))
+            .collect(),
+    )
+}
+
+/// One combination of swept values.
+#[derive(Debug)]
+pub struct Variant {
+    pub job: Job,
+    /// The output name and a `key = value` line per swept option, ready to
+    /// paste into a config file.
+    pub label: Vec<String>,
+}
+
+/// Returns every combination of `sweeps` laid over `base`, the last sweep
+/// varying fastest. Outputs are `<file>-01`, `<file>-02` and so on.
+///
+/// A key that names no top-level option is looked up in the shape's section,
+/// so `pattern_count` sweeps `circle.pattern_count` for a circle.
+pub fn variants(
+    shape: Shape,
+    base: &toml::Table,
+    sweeps: &[Sweep],
+    file: &str,
+) -> Result<Vec<Variant>, Box<dyn Error>> {
+    let count = sweeps
+        .iter()
+        .try_fold(1usize, |count, sweep| count.checked_mul(sweep.values.len()))
+        .filter(|&count| count <= MAX_VARIANTS)
+        .ok_or_else(|| format!("a sweep may render at most {MAX_VARIANTS} variants"))?;
+    let digits = count.to_string().len().max(2);
+
+    let mut variants = Vec::with_capacity(count);
+    for index in 0..count {
+        let mut table = base.clone();
+        let output = format!("{file}-{:0digits$}", index + 1);
+        let mut label = vec![output.clone()];
+        // Mixed-radix digits of `index` pick each sweep's value.
+        let mut rest = index;
+        let mut picks = vec![0; sweeps.len()];
+        for (pick, sweep) in picks.iter_mut().zip(sweeps).rev() {
+            *pick = rest % sweep.values.len();
+            rest /= sweep.values.len();
+        }
+        for (sweep, pick) in sweeps.iter().zip(picks) {
+            let value = &sweep.values[pick];
+            let key =
+                file_config::set_option(&mut table, shape.section(), &sweep.key, value.clone())
+                    .ok_or_else(|| {
+                        format!(
+                            "--vary {}: no option for {} takes {}",
+                            sweep.key,
+                            shape.section(),
+                            value
+                        )
+                    })?;
+            label.push(format!("{key} = {value}"));
+        }
+        let config: FileConfig = toml::Value::Table(table).try_into()?;
+        variants.push(Variant {
+            job: Job {
+                name: output.clone(),
+                shape,
+                output,
+                config,
+            },
+            label,
+        });
+    }
+    Ok(variants)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sweep(s: &str) -> Sweep {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn values_parse_as_lists_and_ranges() {
+        assert_eq!(
+            sweep("stroke_width=2, 4,8").values,
+            [2, 4, 8].map(toml::Value::Integer)
+        );
+        assert_eq!(
+            sweep("circle.pattern_count=20..40:10").values,
+            [20, 30, 40].map(toml::Value::Integer)
+        );
+        assert_eq!(sweep("x=1..3").values.len(), 3);
+        assert_eq!(
+            sweep("stroke_opacity=0.3..0.9:0.2").values,
+            [0.3, 0.5, 0.7, 0.9].map(toml::Value::Float)
+        );
+        assert_eq!(
+            sweep("scale=1,1.5").values,
+            [1.0, 1.5].map(toml::Value::Float)
+        );
+        for bad in ["stroke_width", "=1,2", "x=a,b", "x=1..5:0", "x=1..5:-1"] {
+            assert!(bad.parse::<Sweep>().is_err(), "{bad}");
+        }
+        assert!(
+            "x=5..1"
+                .parse::<Sweep>()
+                .unwrap_err()
+                .ends_with("the range is empty")
+        );
+    }
+
+    #[test]
+    fn every_combination_is_labelled() {
+        let variants = variants(
+            Shape::Circle,
+            &toml::Table::new(),
+            &[sweep("pattern_count=20,30"), sweep("stroke_width=2..6:2")],
+            "out/v",
+        )
+        .unwrap();
+        assert_eq!(variants.len(), 6);
+        assert_eq!(
+            variants[4].label,
+            ["out/v-05", "circle.pattern_count = 30", "stroke_width = 4"]
+        );
+        let config = &variants[4].job.config;
+        assert_eq!(config.circle.as_ref().unwrap().pattern_count, Some(30));
+        assert_eq!(config.stroke_width, Some(4.0));
+        assert_eq!(variants[4].job.output, "out/v-05");
+    }
+
+    #[test]
+    fn unknown_and_mistyped_options_are_rejected() {
+        let err = variants(
+            Shape::Rect,
+            &toml::Table::new(),
+            &[sweep("radius=1,2")],
+            "v",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "--vary radius: no option for rect takes 1");
+        let err = variants(
+            Shape::Rect,
+            &toml::Table::new(),
+            &[sweep("size=1.5,2")],
+            "v",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "--vary size: no option for rect takes 1.5");
+    }
+
+    #[test]
+    fn sweeps_are_capped() {
+        let err = variants(
+            Shape::Rect,
+            &toml::Table::new(),
+            &[sweep("size=1..100"), sweep("width=1..11")],
+            "v",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("at most 1000"));
+    }
+}
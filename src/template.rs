@@ -0,0 +1,171 @@
+use serde_json::Value;
+
+use crate::schema;
+
+const HEADER: &str = "\
+# greek-meander config. Every option is listed with its description and
+# default, or an example where it has none, commented out; uncomment the ones
+# to change. Command-line flags override this file. Check it with
+# `greek-meander validate <file>`.
+";
+
+/// Writes a config file listing every option the schema knows, each
+/// commented out under its description and set to its value in `defaults`.
+/// Options with no default are set to the schema's example, the first of
+/// their choices, or `false`, and marked as an example.
+pub fn config_template(defaults: &toml::Table) -> String {
+    let schema = schema::config_schema();
+    let options = schema::options(&schema);
+    let mut text = HEADER.to_string();
+    // Plain options first, as TOML needs them before the first section.
+    for (key, option) in options {
+        if option.get("properties").is_none() {
+            push_option(&mut text, key, option, defaults.get(key));
+        }
+    }
+    for (name, section) in options {
+        let Some(section_options) = section.get("properties").and_then(Value::as_object) else {
+            continue;
+        };
+        text.push_str(&format!("\n# {}\n# [{name}]\n", description(section)));
+        let defaults = defaults.get(name).and_then(toml::Value::as_table);
+        for (key, option) in section_options {
+            push_option(
+                &mut text,
+                key,
+                option,
+                defaults.and_then(|defaults| defaults.get(key)),
+            );
+        }
+    }
+    text.push_str(&format!(
+        "\n# {}:\n# [profile.<name>]\n",
+        description(&schema["properties"]["profile"])
+    ));
+    text
+}
+
+fn push_option(text: &mut String, key: &str, option: &Value, default: Option<&toml::Value>) {
+    text.push_str(&format!("\n# {}\n", description(option)));
+    let choices = option
+        .get("enum")
+        .or_else(|| option.get("items").and_then(|items| items.get("enum")))
+        .and_then(Value::as_array);
+    if let Some(choices) = choices {
+        let names: Vec<&str> = choices.iter().filter_map(Value::as_str).collect();
+        let kind = if option.get("items").is_some() {
+            "Any"
+        } else {
+            "One"
+        };
+        text.push_str(&format!("# {kind} of: {}\n", names.join(", ")));
+    }
+    match (default, example(option)) {
+        (Some(value), _) => text.push_str(&format!("# {key} = {value}\n")),
+        (None, Some(value)) => text.push_str(&format!("# {key} = {value}  # example\n")),
+        (None, None) => text.push_str(&format!("# {key} =\n")),
+    }
+}
+
+// A value for an option with no default: the schema's example, else the first
+// of its choices, or false.
+fn example(option: &Value) -> Option<toml::Value> {
+    let first = |choices: &Value| choices.get(0).cloned();
+    let value = first(&option["examples"]).or_else(|| match option["type"].as_str()? {
+        "boolean" => Some(Value::Bool(false)),
+        "string" => first(&option["enum"]),
+        "array" => first(&option["items"]["enum"]).map(|choice| Value::Array(vec![choice])),
+        _ => None,
+    })?;
+    toml::Value::try_from(value).ok()
+}
+
+fn description(option: &Value) -> &str {
+    option["description"].as_str().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Map;
+
+    use super::*;
+
+    // The template with every option that has a value uncommented.
+    fn uncommented(template: &str) -> toml::Table {
+        let lines: Vec<&str> = template
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            .filter(|line| {
+                let is_key = |key: &str| key.chars().all(|c| c.is_alphanumeric() || c == '_');
+                match line.split_once(" = ") {
+                    Some((key, _)) => is_key(key),
+                    None => line
+                        .strip_prefix('[')
+                        .and_then(|line| line.strip_suffix(']'))
+                        .is_some_and(is_key),
+                }
+            })
+            .collect();
+        lines.join("\n").parse().unwrap()
+    }
+
+    fn options(schema: &Map<String, Value>) -> usize {
+        schema
+            .iter()
+            .map(|(_, option)| match option.get("properties") {
+                Some(section) => section.as_object().unwrap().len(),
+                None => 1,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn template_lists_every_option() {
+        let template = config_template(&toml::Table::new());
+        let schema = schema::config_schema();
+        let listed = template
+            .lines()
+            .filter_map(|line| line.strip_prefix("# ")?.split_once(" ="))
+            .filter(|(key, _)| key.chars().all(|c| c.is_alphanumeric() || c == '_'))
+            .count();
+        assert_eq!(listed, options(schema::options(&schema)));
+        assert!(template.contains("# One of: attic-red-figure, gold-leaf, marble, monochrome\n"));
+        assert!(template.contains("# Any of: shadow, emboss, engrave, inner-glow\n"));
+        // Everything is commented out, so the template sets nothing.
+        assert_eq!(template.parse::<toml::Table>().unwrap(), toml::Table::new());
+    }
+
+    #[test]
+    fn uncommented_defaults_are_the_defaults() {
+        let defaults: toml::Table = toml::from_str(
+            "stroke_width = 6.0\nstroke_opacity = 0.7\nkey_colors = [\"#111111\"]\n[rect]\nsize = 25\n",
+        )
+        .unwrap();
+        let template = config_template(&defaults);
+        assert!(template.contains("\n# stroke_opacity = 0.7\n"));
+        let table = uncommented(&template);
+        assert_eq!(table["stroke_width"], defaults["stroke_width"]);
+        assert_eq!(table["stroke_opacity"].as_float(), Some(0.7));
+        assert_eq!(table["key_colors"], defaults["key_colors"]);
+        assert_eq!(table["rect"]["size"], defaults["rect"]["size"]);
+        assert!(table["circle"].as_table().unwrap().is_empty());
+    }
+
+    #[test]
+    fn options_without_a_default_show_an_example() {
+        let defaults = toml::Table::try_from(crate::default_config()).unwrap();
+        let template = config_template(&defaults);
+        assert!(template.contains("\n# arc_tolerance = 0.5  # example\n"));
+        assert!(template.contains("\n# hidden = false  # example\n"));
+        assert!(template.contains("\n# effects = [\"shadow\"]  # example\n"));
+        // Every option has a value, and uncommenting them all gives a config
+        // that reads.
+        assert!(
+            !template.lines().any(|line| line.ends_with(" =")),
+            "{template}"
+        );
+        let config: Result<crate::file_config::FileConfig, _> =
+            toml::Value::Table(uncommented(&template)).try_into();
+        assert!(config.is_ok(), "{config:?}");
+    }
+}
@@ -0,0 +1,503 @@
+use std::str::FromStr;
+
+use svg::node::element::path::Data;
+use svg::node::element::{Definitions, Element, Path as SvgPath, Style, TSpan, TextPath};
+use svg::{Document, Node};
+
+use crate::common::{Point, base64_encode};
+use crate::config::VisualOptions;
+use crate::paint::Paint;
+
+/// Element id of the path ring text runs along.
+pub(crate) const RING_TEXT_PATH_ID: &str = "gm-ring-text-path";
+
+// Line height, in font sizes, between the lines of a title.
+const LINE_HEIGHT: f64 = 1.25;
+// How far the middle of a capital sits above the baseline, in font sizes.
+const CAP_MIDDLE: f64 = 0.35;
+// Height of a capital, in font sizes, and the space kept between it and a frame.
+const CAP_HEIGHT: f64 = 0.72;
+const FRAME_GAP: f64 = 0.25;
+// Rough advance of an average glyph, in font sizes, for sizing titles to fit.
+const AVERAGE_ADVANCE: f64 = 0.6;
+// Share of the inner area's width and height a default-sized title may take.
+const TITLE_FILL: f64 = 0.6;
+// Default ring text size as a share of the band between the frames.
+const RING_TEXT_SIZE: f64 = 0.45;
+const DEFAULT_FONT_FAMILY: &str = "serif";
+
+/// The frame that ring text follows on a circle or ellipse border.
+#[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "native", serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextRail {
+    /// Just inside the inner frame, over the artwork or empty interior.
+    #[default]
+    Inner,
+    /// Just outside the outer frame, in the border margin. Raise the border
+    /// margin to about the font size to keep it on the canvas.
+    Outer,
+}
+
+impl TextRail {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Inner => "inner",
+            Self::Outer => "outer",
+        }
+    }
+}
+
+impl FromStr for TextRail {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inner" => Ok(Self::Inner),
+            "outer" => Ok(Self::Outer),
+            _ => Err(format!(
+                "unknown text rail '{s}'; expected 'inner' or 'outer'"
+            )),
+        }
+    }
+}
+
+/// Text drawn with a border: a title centred inside the inner frame and, on
+/// circles and ellipses, a line running around the band.
+///
+/// Spirals have no interior and draw no text.
+#[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Text {
+    /// Lines of the title, centred inside the inner frame. Empty draws no title.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub title: Vec<String>,
+    /// Text along a circle or ellipse frame, centred at the top. Ignored by
+    /// other shapes.
+    pub ring_text: Option<String>,
+    /// The frame the ring text follows.
+    #[cfg_attr(feature = "native", serde(default))]
+    pub rail: TextRail,
+    /// CSS `font-family`. `None` uses the embedded font, if any, then serif.
+    pub font_family: Option<String>,
+    /// Font size in pixels. `None` sizes the title to fit the inner area and
+    /// the ring text to the band.
+    pub font_size: Option<f64>,
+    /// Paint for the text. `None` uses the stroke paint.
+    pub color: Option<Paint>,
+    /// A TrueType or OpenType font embedded in the SVG and used before
+    /// `font_family`, so every renderer draws the same glyphs.
+    #[cfg_attr(feature = "native", serde(skip))]
+    pub font_data: Option<Vec<u8>>,
+}
+
+impl Text {
+    /// Checks the font size, colour and embedded font.
+    pub(crate) fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(size) = self.font_size
+            && (size <= 0.0 || !size.is_finite())
+        {
+            return Err("--font-size must be a positive finite number".into());
+        }
+        if let Some(color) = &self.color {
+            color.validate("--text-color")?;
+        }
+        if let Some(data) = &self.font_data
+            && font_family_name(data).is_none()
+        {
+            return Err("--font-file must be a TrueType or OpenType font".into());
+        }
+        Ok(())
+    }
+
+    // The `font-family` attribute: the embedded font first, then the chosen
+    // family or serif as the fallback.
+    fn font_family(&self) -> String {
+        let fallback = self.font_family.as_deref().unwrap_or(DEFAULT_FONT_FAMILY);
+        match self.font_data.as_deref().and_then(font_family_name) {
+            Some(embedded) => format!("{}, {fallback}", css_string(&embedded)),
+            None => fallback.to_string(),
+        }
+    }
+}
+
+/// Where a shape has room for text.
+pub(crate) struct TextLayout {
+    /// Centre of the area inside the inner frame.
+    pub(crate) centre: Point,
+    /// Half the width and height of the area inside the inner frame.
+    pub(crate) inner: (f64, f64),
+    /// Semi-axes of the inner and outer frames of a circle or ellipse, which
+    /// ring text can follow.
+    pub(crate) rings: Option<[(f64, f64); 2]>,
+    pub(crate) stroke_width: f32,
+}
+
+/// Adds the title and ring text in `visual.text`, with any embedded font, on
+/// top of the border.
+pub(crate) fn add_text(
+    document: Document,
+    visual: &VisualOptions,
+    layout: &TextLayout,
+) -> Document {
+    let Some(text) = &visual.text else {
+        return document;
+    };
+    let mut defs = Definitions::new();
+    let mut has_defs = false;
+    if let Some(data) = &text.font_data
+        && let Some(family) = font_family_name(data)
+    {
+        defs.append(Style::new(format!(
+            "@font-face{{font-family:{};src:url(data:{};base64,{})}}",
+            css_string(&family),
+            font_mime_type(data),
+            base64_encode(data)
+        )));
+        has_defs = true;
+    }
+
+    let text_element = |size: f64| {
+        let mut element = Element::new("text");
+        element.assign("font-family", text.font_family());
+        element.assign("font-size", size);
+        element.assign("fill", visual.text_paint());
+        element.assign("text-anchor", "middle");
+        element
+    };
+    let mut elements = Vec::new();
+
+    let lines = &text.title;
+    if !lines.is_empty() {
+        let (rx, ry) = layout.inner;
+        let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let size = text.font_size.unwrap_or_else(|| {
+            let fit_height = 2.0 * ry * TITLE_FILL / (lines.len() as f64 * LINE_HEIGHT);
+            let fit_width = 2.0 * rx * TITLE_FILL / (longest.max(1) as f64 * AVERAGE_ADVANCE);
+            fit_height.min(fit_width)
+        });
+        let mut element = text_element(size);
+        let middle = (lines.len() - 1) as f64 / 2.0;
+        for (i, line) in lines.iter().enumerate() {
+            let y = layout.centre.y + ((i as f64 - middle) * LINE_HEIGHT + CAP_MIDDLE) * size;
+            element.append(
+                TSpan::new(line.as_str())
+                    .set("x", layout.centre.x)
+                    .set("y", y),
+            );
+        }
+        elements.push(element);
+    }
+
+    if let (Some(ring_text), Some([inner, outer])) = (&text.ring_text, layout.rings) {
+        let band = (outer.0 - inner.0).min(outer.1 - inner.1);
+        let size = text.font_size.unwrap_or(band * RING_TEXT_SIZE);
+        let half_stroke = layout.stroke_width as f64 / 2.0;
+        // The baseline sits so the capitals stop one gap short of the frame.
+        let ((rx, ry), offset) = match text.rail {
+            TextRail::Inner => (inner, -(half_stroke + (FRAME_GAP + CAP_HEIGHT) * size)),
+            TextRail::Outer => (outer, half_stroke + FRAME_GAP * size),
+        };
+        defs.append(
+            SvgPath::new()
+                .set("id", RING_TEXT_PATH_ID)
+                .set("d", ring_data(layout.centre, rx + offset, ry + offset)),
+        );
+        has_defs = true;
+        let mut element = text_element(size);
+        element.append(
+            TextPath::new(ring_text.as_str())
+                .set("href", format!("#{RING_TEXT_PATH_ID}"))
+                .set("startOffset", "50%"),
+        );
+        elements.push(element);
+    }
+
+    let mut document = document;
+    if has_defs {
+        document = document.add(defs);
+    }
+    elements
+        .into_iter()
+        .fold(document, |document, element| document.add(element))
+}
+
+// A clockwise ellipse from the bottom, so text centred on it sits upright
+// across the top.
+fn ring_data(centre: Point, rx: f64, ry: f64) -> Data {
+    Data::new()
+        .move_to((centre.x, centre.y + ry))
+        .elliptical_arc_to((rx, ry, 0, 1, 1, centre.x, centre.y - ry))
+        .elliptical_arc_to((rx, ry, 0, 1, 1, centre.x, centre.y + ry))
+}
+
+fn font_mime_type(data: &[u8]) -> &'static str {
+    if data.starts_with(b"OTTO") {
+        "font/otf"
+    } else {
+        "font/ttf"
+    }
+}
+
+/// Returns the family name a renderer will match `data` by: the typographic
+/// family if the font has one, else its family. Returns `None` if `data` is
+/// not a single TrueType or OpenType font.
+// Quotes `value` as a CSS string. Quotes, backslashes and control characters
+// are escaped, so a font's own name cannot end the string or the rule early.
+fn css_string(value: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in value.chars() {
+        match c {
+            '\'' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c if c.is_control() => quoted.push_str(&format!("\\{:x} ", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+fn font_family_name(data: &[u8]) -> Option<String> {
+    let u16_at = |i: usize| data.get(i..i + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+    let u32_at = |i: usize| {
+        data.get(i..i + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    if !matches!(data.get(..4)?, [0, 1, 0, 0] | b"OTTO" | b"true") {
+        return None;
+    }
+    let tables = u16_at(4)? as usize;
+    let name = (0..tables).find_map(|i| {
+        let record = 12 + 16 * i;
+        (data.get(record..record + 4)? == b"name")
+            .then(|| u32_at(record + 8))
+            .flatten()
+    })? as usize;
+    let strings = name + u16_at(name + 4)? as usize;
+    let mut family = None;
+    for i in 0..u16_at(name + 2)? as usize {
+        let record = name + 6 + 12 * i;
+        let (platform, name_id) = (u16_at(record)?, u16_at(record + 6)?);
+        let (length, offset) = (u16_at(record + 8)? as usize, u16_at(record + 10)? as usize);
+        let bytes = data.get(strings + offset..strings + offset + length)?;
+        let value = match platform {
+            // Unicode and Windows names are UTF-16BE.
+            0 | 3 => String::from_utf16(
+                &bytes
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect::<Vec<_>>(),
+            )
+            .ok()?,
+            1 => bytes.iter().map(|&b| b as char).collect(),
+            _ => continue,
+        };
+        match name_id {
+            16 => return Some(value),
+            1 if family.is_none() => family = Some(value),
+            _ => {}
+        }
+    }
+    family
+}
+
+/// Returns resvg options that can draw the text in `svg`: the system fonts,
+/// plus any fonts embedded with [`Text::font_data`].
+///
+/// Requires the `native` feature (enabled by default).
+#[cfg(feature = "native")]
+pub fn render_options(svg: &[u8]) -> resvg::usvg::Options<'static> {
+    let mut options = resvg::usvg::Options::default();
+    let svg = String::from_utf8_lossy(svg);
+    // Loading the system fonts is slow; only text needs them.
+    if svg.contains("<text") {
+        let fontdb = options.fontdb_mut();
+        fontdb.load_system_fonts();
+        for font in embedded_fonts(&svg) {
+            fontdb.load_font_data(font);
+        }
+        install_generic_families(fontdb);
+    }
+    options
+}
+
+// Points each generic family whose default (such as Times New Roman for
+// serif) is not installed at an installed font, so text still draws.
+#[cfg(feature = "native")]
+fn install_generic_families(fontdb: &mut resvg::usvg::fontdb::Database) {
+    use resvg::usvg::fontdb::Family;
+
+    let families: Vec<String> = fontdb
+        .faces()
+        .flat_map(|face| face.families.iter().map(|(name, _)| name.clone()))
+        .collect();
+    for (generic, hint) in [
+        (Family::Serif, "Serif"),
+        (Family::SansSerif, "Sans"),
+        (Family::Monospace, "Mono"),
+    ] {
+        if families.iter().any(|f| f == fontdb.family_name(&generic)) {
+            continue;
+        }
+        let Some(family) = families
+            .iter()
+            .find(|f| f.contains(hint))
+            .or(families.first())
+        else {
+            return;
+        };
+        match generic {
+            Family::Serif => fontdb.set_serif_family(family.clone()),
+            Family::SansSerif => fontdb.set_sans_serif_family(family.clone()),
+            _ => fontdb.set_monospace_family(family.clone()),
+        }
+    }
+}
+
+// The fonts in `@font-face` data URIs written by `add_text`.
+#[cfg(feature = "native")]
+fn embedded_fonts(svg: &str) -> Vec<Vec<u8>> {
+    ["data:font/ttf;base64,", "data:font/otf;base64,"]
+        .iter()
+        .flat_map(|prefix| svg.match_indices(prefix).map(|(i, _)| i + prefix.len()))
+        .filter_map(|start| {
+            let end = svg[start..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=')))
+                .map_or(svg.len(), |end| start + end);
+            crate::common::base64_decode(&svg[start..end])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A font with only a `name` table naming family `family`.
+    fn font(family: &str) -> Vec<u8> {
+        let name: Vec<u8> = family.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        let mut data = vec![0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+        data.extend(b"name");
+        data.extend([0, 0, 0, 0, 0, 0, 0, 28, 0, 0, 0, 0]);
+        // Format 0, one record, strings after it.
+        data.extend([0, 0, 0, 1, 0, 18]);
+        data.extend([0, 3, 0, 1, 4, 9, 0, 1]);
+        data.extend((name.len() as u16).to_be_bytes());
+        data.extend([0, 0]);
+        data.extend(name);
+        data
+    }
+
+    fn layout() -> TextLayout {
+        TextLayout {
+            centre: Point { x: 200.0, y: 200.0 },
+            inner: (100.0, 100.0),
+            rings: Some([(100.0, 100.0), (150.0, 150.0)]),
+            stroke_width: 2.0,
+        }
+    }
+
+    fn render(text: Text) -> String {
+        let visual = VisualOptions {
+            text: Some(text),
+            ..VisualOptions::default()
+        };
+        add_text(Document::new(), &visual, &layout()).to_string()
+    }
+
+    #[test]
+    fn family_name_is_read_from_the_name_table() {
+        assert_eq!(font_family_name(&font("Cinzel")).as_deref(), Some("Cinzel"));
+        assert_eq!(font_family_name(b"wOFF\0\0\0\0"), None);
+    }
+
+    #[test]
+    fn title_lines_are_centred_tspans() {
+        let svg = render(Text {
+            title: vec!["Certificate".into(), "of Merit".into()],
+            font_size: Some(20.0),
+            ..Text::default()
+        });
+        assert!(svg.contains(r#"text-anchor="middle""#));
+        assert_eq!(svg.matches("<tspan").count(), 2);
+        // 1.25 lines apart, centred on y = 200 with the capitals' middle.
+        assert!(svg.contains(r#"x="200" y="194.5""#), "{svg}");
+        assert!(svg.contains(r#"x="200" y="219.5""#), "{svg}");
+    }
+
+    #[test]
+    fn ring_text_follows_the_chosen_rail() {
+        let svg = render(Text {
+            ring_text: Some("ΑΡΕΤΗ".into()),
+            rail: TextRail::Outer,
+            font_size: Some(20.0),
+            ..Text::default()
+        });
+        assert!(svg.contains(r##"<textPath href="#gm-ring-text-path" startOffset="50%">"##));
+        // Outer frame 150, half the stroke, then a quarter of the font size.
+        assert!(svg.contains("M200,356 A156,156"), "{svg}");
+    }
+
+    #[test]
+    fn embedded_font_comes_first_and_round_trips() {
+        let data = font("Cinzel");
+        let svg = render(Text {
+            title: vec!["Menu".into()],
+            font_data: Some(data.clone()),
+            ..Text::default()
+        });
+        assert!(
+            svg.contains("font-family=\"&apos;Cinzel&apos;, serif\""),
+            "{svg}"
+        );
+        assert!(svg.contains("@font-face{font-family:'Cinzel';src:url(data:font/ttf;base64,"));
+        #[cfg(feature = "native")]
+        assert_eq!(embedded_fonts(&svg), vec![data]);
+    }
+
+    #[test]
+    fn embedded_family_names_are_escaped() {
+        let svg = render(Text {
+            title: vec!["Menu".into()],
+            font_data: Some(font("A'}\\\nB")),
+            ..Text::default()
+        });
+        assert!(
+            svg.contains(r"@font-face{font-family:'A\'}\\\a B';src:"),
+            "{svg}"
+        );
+        assert!(svg.contains(r#"font-family="&apos;A\&apos;}\\\a B&apos;, serif""#));
+    }
+
+    #[test]
+    fn invalid_text_options_are_rejected() {
+        for (text, flag) in [
+            (
+                Text {
+                    font_size: Some(0.0),
+                    ..Text::default()
+                },
+                "--font-size",
+            ),
+            (
+                Text {
+                    color: Some("#GG0000".into()),
+                    ..Text::default()
+                },
+                "--text-color",
+            ),
+            (
+                Text {
+                    font_data: Some(b"not a font".to_vec()),
+                    ..Text::default()
+                },
+                "--font-file",
+            ),
+        ] {
+            assert!(text.validate().unwrap_err().to_string().contains(flag));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::str::FromStr;
+
+use crate::config::VisualOptions;
+use crate::effects::Effect;
+use crate::paint::{GradientStop, LinearGradient, Paint, RadialGradient};
+
+/// A built-in set of visual options for a traditional look.
+///
+/// Themes parse from the names `attic-red-figure`, `gold-leaf`, `marble` and
+/// `monochrome`. A theme only supplies defaults: options set on the command
+/// line or in a config file override it.
+#[cfg_attr(feature = "native", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "native", serde(rename_all = "kebab-case"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    /// Terracotta keys on black glaze, as on Attic red-figure pottery.
+    AtticRedFigure,
+    /// Burnished gold keys, embossed on a dark ground.
+    GoldLeaf,
+    /// Grey keys engraved into pale, softly lit stone.
+    Marble,
+    /// Solid black keys on white, for print.
+    Monochrome,
+}
+
+fn stop(offset: f64, color: &str) -> GradientStop {
+    GradientStop {
+        offset,
+        color: color.to_string(),
+    }
+}
+
+impl Theme {
+    /// Returns the visual options this theme stands for.
+    pub fn visual_options(self) -> VisualOptions {
+        match self {
+            Theme::AtticRedFigure => {
+                let mut visual = VisualOptions::new("#C8693A", 1.0);
+                visual.background_color = Some("#17110E".into());
+                visual
+            }
+            Theme::GoldLeaf => {
+                let mut visual = VisualOptions::new(
+                    Paint::Linear(LinearGradient {
+                        angle: 135.0,
+                        stops: vec![
+                            stop(0.0, "#8B6914"),
+                            stop(0.5, "#FFF1A8"),
+                            stop(1.0, "#8B6914"),
+                        ],
+                    }),
+                    1.0,
+                );
+                visual.background_color = Some("#1B1A17".into());
+                visual.effects = vec![Effect::Emboss];
+                visual
+            }
+            Theme::Marble => {
+                let mut visual = VisualOptions::new("#6B7078", 0.9);
+                visual.background_color = Some(Paint::Radial(RadialGradient {
+                    centre: (0.35, 0.3),
+                    radius: 1.4,
+                    stops: vec![stop(0.0, "#FFFFFF"), stop(1.0, "#DCD8D0")],
+                }));
+                visual.effects = vec![Effect::Engrave];
+                visual
+            }
+            Theme::Monochrome => {
+                let mut visual = VisualOptions::new("#000000", 1.0);
+                visual.background_color = Some("#FFFFFF".into());
+                visual
+            }
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "attic-red-figure" => Ok(Theme::AtticRedFigure),
+            "gold-leaf" => Ok(Theme::GoldLeaf),
+            "marble" => Ok(Theme::Marble),
+            "monochrome" => Ok(Theme::Monochrome),
+            _ => Err(format!(
+                "unknown theme '{s}'; expected 'attic-red-figure', 'gold-leaf', 'marble' or 'monochrome'"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THEMES: [Theme; 4] = [
+        Theme::AtticRedFigure,
+        Theme::GoldLeaf,
+        Theme::Marble,
+        Theme::Monochrome,
+    ];
+
+    #[test]
+    fn every_theme_is_valid() {
+        for theme in THEMES {
+            theme.visual_options().validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn theme_names_parse() {
+        assert_eq!("gold-leaf".parse(), Ok(Theme::GoldLeaf));
+        assert!("gold".parse::<Theme>().is_err());
+    }
+}
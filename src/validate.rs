@@ -0,0 +1,334 @@
+use std::error::Error;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::file_config::ConfigFormat;
+use crate::schema;
+
+/// Something wrong with a config file.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    /// Line and column, counted from 1, when the problem can be placed.
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            position: None,
+            message: message.into(),
+        }
+    }
+
+    /// The problem prefixed with the file and, if known, the line and column.
+    pub fn located(&self, path: &Path) -> String {
+        match self.position {
+            Some((line, column)) => {
+                format!("{}:{}:{}: {}", path.display(), line, column, self.message)
+            }
+            None => format!("{}: {}", path.display(), self.message),
+        }
+    }
+}
+
+/// Reads a config file and returns a problem for each key greek-meander does
+/// not know. See [`unknown_keys`].
+pub fn check_keys(
+    path: &Path,
+    format: Option<ConfigFormat>,
+) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read config file '{}': {}", path.display(), e))?;
+    let config = format
+        .unwrap_or_else(|| ConfigFormat::detect(path))
+        .parse(&content)
+        .map_err(|e| format!("invalid config file '{}': {}", path.display(), e))?;
+    Ok(unknown_keys(&content, &config))
+}
+
+/// Returns a problem for each key in `config` that names no option, with the
+/// nearest option name as a suggestion. `content` is the text `config` was
+/// parsed from, used to find the line of each key.
+///
+/// Keys are checked against the config schema, inside the sections and
+/// profiles it knows; an unknown section is reported but not looked into.
+pub fn unknown_keys(content: &str, config: &Value) -> Vec<Problem> {
+    if !config.is_object() {
+        return vec![Problem::new("a config file must be a table of options")];
+    }
+    let mut problems = Vec::new();
+    let schema = schema::config_schema();
+    check(
+        content,
+        &schema,
+        &schema,
+        config,
+        &mut Vec::new(),
+        &mut problems,
+    );
+    // Report in file order; keys that could not be placed come last.
+    problems.sort_by_key(|problem| problem.position.unwrap_or((usize::MAX, 0)));
+    problems
+}
+
+// Checks the keys of `value` against `schema`, the part of `root` for the
+// keys in `path`.
+fn check<'a>(
+    content: &str,
+    root: &Value,
+    schema: &Value,
+    value: &'a Value,
+    path: &mut Vec<&'a str>,
+    problems: &mut Vec<Problem>,
+) {
+    let known = properties(root, schema);
+    let known = known.as_ref();
+    // Profiles take keys of any name, each holding options.
+    let named = schema
+        .get("additionalProperties")
+        .filter(|any| any.is_object());
+    let (Some(value), true) = (value.as_object(), known.is_some() || named.is_some()) else {
+        return;
+    };
+    for (key, value) in value {
+        path.push(key);
+        match (known.and_then(|known| known.get(key)), named) {
+            (Some(option), _) | (None, Some(option)) => {
+                check(content, root, option, value, path, problems)
+            }
+            (None, None) => problems.push(unknown(content, root, path, known)),
+        }
+        path.pop();
+    }
+}
+
+// The keys `schema` names, with those of the definition its `$ref` points to.
+fn properties(root: &Value, schema: &Value) -> Option<Map<String, Value>> {
+    let referred = schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix('#'))
+        .and_then(|pointer| root.pointer(pointer))
+        .and_then(|definition| properties(root, definition));
+    let own = schema.get("properties").and_then(Value::as_object);
+    match (referred, own) {
+        (Some(mut keys), Some(own)) => {
+            keys.extend(own.clone());
+            Some(keys)
+        }
+        (keys, own) => keys.or_else(|| own.cloned()),
+    }
+}
+
+fn unknown(
+    content: &str,
+    root: &Value,
+    path: &[&str],
+    known: Option<&Map<String, Value>>,
+) -> Problem {
+    let (key, section) = path.split_last().expect("a key was checked");
+    let dotted = |name: &str| {
+        section
+            .iter()
+            .copied()
+            .chain([name])
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+    let mut message = format!("unknown key '{}'", dotted(key));
+    if let Some(suggestion) = known.and_then(|known| nearest(root, key, known)) {
+        message.push_str(&format!("; did you mean '{}'?", dotted(&suggestion)));
+    }
+    // Each key is looked for after the one holding it.
+    let offset = path
+        .iter()
+        .try_fold(0, |start, key| find_key(content, start, key));
+    Problem {
+        position: offset.map(|offset| line_and_column(content, offset)),
+        message,
+    }
+}
+
+// The key of `known` nearest to `key`, or failing that the nearest key of a
+// section of `known`, with the section's name: a top-level `patern_count`
+// belongs in a shape section.
+fn nearest(root: &Value, key: &str, known: &Map<String, Value>) -> Option<String> {
+    if let Some(name) = suggestion(key, known.keys()) {
+        return Some(name.to_string());
+    }
+    known
+        .iter()
+        .filter_map(|(section, option)| {
+            let keys = properties(root, option)?;
+            let name = suggestion(key, keys.keys())?;
+            Some((distance(key, name), format!("{section}.{name}")))
+        })
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, name)| name)
+}
+
+/// The known name nearest to `key`, if it is close enough to be a typo.
+pub fn suggestion<'a>(key: &str, names: impl Iterator<Item = &'a String>) -> Option<&'a str> {
+    let limit = (key.chars().count() / 3).max(1);
+    names
+        .map(|name| (distance(key, name), name))
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, name)| name.as_str())
+}
+
+// Edits, counting a swap of neighbouring characters as one, to turn `a` into
+// `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+// Finds `key` written as a key at or after `start`: a whole word, possibly
+// quoted, followed by `=`, `:`, `.` or the `]` of a TOML table header. This
+// reads TOML, JSON and YAML alike.
+fn find_key(content: &str, start: usize, key: &str) -> Option<usize> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let mut from = start;
+    while let Some(found) = content[from..].find(key) {
+        let offset = from + found;
+        from = offset + key.len();
+        if content[..offset].chars().next_back().is_some_and(is_word) {
+            continue;
+        }
+        let rest = content[from..].trim_start_matches(['"', '\'']).trim_start();
+        if rest.starts_with(['=', ':', '.', ']']) {
+            return Some(offset);
+        }
+    }
+    None
+}
+
+fn line_and_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(content: &str, format: ConfigFormat) -> Vec<Problem> {
+        unknown_keys(content, &format.parse(content).unwrap())
+    }
+
+    #[test]
+    fn typos_are_placed_and_corrected() {
+        let content = "stroke_width = 3\nstroke_colour = \"#112233\"\n\n[circle]\nradius = 100\npatern_count = 20\n";
+        assert_eq!(
+            problems(content, ConfigFormat::Toml),
+            [
+                Problem {
+                    position: Some((2, 1)),
+                    message: "unknown key 'stroke_colour'; did you mean 'stroke_color'?".into(),
+                },
+                Problem {
+                    position: Some((6, 1)),
+                    message:
+                        "unknown key 'circle.patern_count'; did you mean 'circle.pattern_count'?"
+                            .into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn json_and_yaml_keys_are_placed() {
+        let json = "{\n  \"$schema\": \"s.json\",\n  \"rect\": { \"size\": 10, \"widht\": 4 }\n}";
+        let problems_json = problems(json, ConfigFormat::Json);
+        assert_eq!(problems_json.len(), 1);
+        assert_eq!(problems_json[0].position, Some((3, 26)));
+        assert!(
+            problems_json[0]
+                .message
+                .ends_with("did you mean 'rect.width'?")
+        );
+
+        let yaml = "theme: marble\nspiral:\n  turns: 2\n  knid: logarithmic\n";
+        let problems_yaml = problems(yaml, ConfigFormat::Yaml);
+        assert_eq!(problems_yaml[0].position, Some((4, 3)));
+        assert!(
+            problems_yaml[0]
+                .message
+                .ends_with("did you mean 'spiral.kind'?")
+        );
+    }
+
+    #[test]
+    fn profiles_are_checked_like_the_base() {
+        let content = "[profile.print]\nscale = 4.0\n[profile.print.cirle]\nradius = 1\n\n[profile.web]\nstroke_widht = 2\n";
+        let problems = problems(content, ConfigFormat::Toml);
+        let messages: Vec<&str> = problems.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "unknown key 'profile.print.cirle'; did you mean 'profile.print.circle'?",
+                "unknown key 'profile.web.stroke_widht'; did you mean 'profile.web.stroke_width'?",
+            ]
+        );
+        assert_eq!(problems[0].position, Some((3, 16)));
+        assert_eq!(problems[1].position, Some((7, 1)));
+    }
+
+    #[test]
+    fn section_keys_at_the_top_are_suggested_with_their_section() {
+        let content = "patern_count = 20\n[profile.big]\nradius = 400\n";
+        let messages: Vec<String> = problems(content, ConfigFormat::Toml)
+            .into_iter()
+            .map(|p| p.message)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "unknown key 'patern_count'; did you mean 'circle.pattern_count'?",
+                "unknown key 'profile.big.radius'; did you mean 'profile.big.circle.radius'?",
+            ]
+        );
+    }
+
+    #[test]
+    fn distant_keys_get_no_suggestion() {
+        let problems = problems("[hexagon]\nsides = 6\n", ConfigFormat::Toml);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].message, "unknown key 'hexagon'");
+        assert_eq!(problems[0].position, Some((1, 2)));
+    }
+
+    #[test]
+    fn distance_counts_swaps_as_one_edit() {
+        assert_eq!(distance("stroke_colour", "stroke_color"), 1);
+        assert_eq!(distance("knid", "kind"), 1);
+        assert_eq!(distance("rx", "ry"), 1);
+        assert_eq!(distance("", "size"), 4);
+    }
+}
@@ -2,17 +2,239 @@ use wasm_bindgen::prelude::*;
 
 use crate::{
     circle,
-    config::{GreekKeyCircleConfig, GreekKeyEllipseConfig, GreekKeyRectConfig, VisualOptions},
-    ellipse, rect,
+    config::{
+        FrameShape, GreekKeyCircleConfig, GreekKeyEllipseConfig, GreekKeyFieldConfig,
+        GreekKeyFrameConfig, GreekKeyRectConfig, GreekKeySpiralConfig, GreekKeySuperellipseConfig,
+        Inlay, SpiralKind, VisualOptions,
+    },
+    ellipse, field,
+    frame::{self, Artwork},
+    metadata::Metadata,
+    paint::Paint,
+    rect, spiral, superellipse,
+    text::Text,
 };
 
-fn validate_stroke_opacity(stroke_opacity: f32) -> Result<(), JsValue> {
+const DEFAULT_BORDER_MARGIN: i32 = 1;
+const DEFAULT_STROKE_WIDTH: f32 = 6.0;
+const DEFAULT_STROKE_COLOR: &str = "#AB8E0E";
+const DEFAULT_STROKE_OPACITY: f32 = 0.7;
+const DEFAULT_INLAY_LINE_WIDTH: f64 = 2.0;
+
+/// Options shared by every `*_generate_svg` export, matching the command-line
+/// flags of the same names. Unset options take the command-line defaults, and
+/// options a shape does not draw, such as text on a spiral, are ignored.
+///
+/// ```js
+/// const options = new SvgOptions();
+/// options.stroke_color = "#7C3B2E";
+/// options.key_colors = ["#A3341F", "#111"];
+/// const svg = circle_generate_svg(300, 30, options);
+/// ```
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Default)]
+pub struct SvgOptions {
+    /// Padding outside the border (must be ≥ 0, default 1).
+    pub border_margin: Option<i32>,
+    /// Line width (must be a positive finite number, default 6).
+    pub stroke_width: Option<f32>,
+    /// CSS color string, e.g. `"#AB8E0E"` (the default), or a gradient such as
+    /// `"linear-gradient(90deg, #8B6914, #FFF1A8)"`.
+    pub stroke_color: Option<String>,
+    /// 0.0–1.0 (default 0.7).
+    pub stroke_opacity: Option<f32>,
+    /// Fill color or gradient for the pattern interior.
+    pub fill_color: Option<String>,
+    /// Canvas background color or gradient.
+    pub background_color: Option<String>,
+    /// SVG `stroke-dasharray` value, e.g. `"5,3"`.
+    pub stroke_dash: Option<String>,
+    /// Key lattice spacing for filling the inside of a rect, circle, ellipse
+    /// or superellipse border.
+    pub field_spacing: Option<f64>,
+    /// Maximum deviation in pixels when drawing the key runs of a circle,
+    /// ellipse or framed border as line segments instead of true arcs.
+    pub arc_tolerance: Option<f64>,
+    /// Writes compact SVG: rounded coordinates, relative paths, shared styles.
+    pub minify: bool,
+    /// Stroke color or gradient for the key pattern.
+    pub pattern_color: Option<String>,
+    /// Stroke for the inner frame; `"none"` leaves it out.
+    pub inner_frame_color: Option<String>,
+    /// Stroke for the outer frame; `"none"` leaves it out.
+    pub outer_frame_color: Option<String>,
+    /// Strokes cycled over successive keys, e.g. `["#A3341F", "#111"]`.
+    pub key_colors: Vec<String>,
+    /// Draws every line as two thin lines this far apart; `stroke_width`, if
+    /// set, must then equal the double line's width, `inlay_gap` plus twice
+    /// `inlay_line_width`.
+    pub inlay_gap: Option<f64>,
+    /// Width of each inlay line (default 2).
+    pub inlay_line_width: Option<f64>,
+    /// Color or gradient of the inlay lines.
+    pub inlay_line_color: Option<String>,
+    /// Color or gradient between the inlay lines.
+    pub inlay_color: Option<String>,
+    /// `"miter"`, `"round"` or `"bevel"` stroke corners.
+    pub line_join: Option<String>,
+    /// `"butt"`, `"round"` or `"square"` stroke ends.
+    pub line_cap: Option<String>,
+    /// SVG `stroke-miterlimit` (must be ≥ 1).
+    pub miter_limit: Option<f64>,
+    /// Keeps the stroke width fixed when the SVG is scaled.
+    pub non_scaling_stroke: bool,
+    /// Filter effects for the border, from `"shadow"`, `"emboss"`, `"engrave"`
+    /// and `"inner-glow"`.
+    pub effects: Vec<String>,
+    /// Lines of a title centred inside the border.
+    pub title: Vec<String>,
+    /// Text running around a circle or ellipse border.
+    pub ring_text: Option<String>,
+    /// Frame the ring text follows, `"inner"` (default) or `"outer"`.
+    pub ring_text_rail: Option<String>,
+    /// CSS font family for text (default serif).
+    pub font_family: Option<String>,
+    /// Font size; text is sized to fit when unset.
+    pub font_size: Option<f64>,
+    /// Color or gradient for text (default `stroke_color`).
+    pub text_color: Option<String>,
+    /// TrueType or OpenType font bytes to embed in the SVG.
+    pub font_data: Option<Vec<u8>>,
+    /// Accessible name stored as the SVG `<title>` (default: the `title` lines).
+    pub svg_title: Option<String>,
+    /// Description stored as the SVG `<desc>`.
+    pub description: Option<String>,
+    /// Author recorded in the SVG metadata.
+    pub author: Option<String>,
+    /// Licence URL or rights statement recorded in the SVG metadata.
+    pub license: Option<String>,
+    /// Start for element ids in place of `gm`, so several SVGs inlined in one
+    /// page do not clash.
+    pub id_prefix: Option<String>,
+}
+
+#[wasm_bindgen]
+impl SvgOptions {
+    /// Options with every value unset.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SvgOptions {
+        SvgOptions::default()
+    }
+}
+
+fn parse_paint(s: &str) -> Result<Paint, JsValue> {
+    s.parse().map_err(|e: String| JsValue::from_str(&e))
+}
+
+fn parse_optional_paint(s: &Option<String>) -> Result<Option<Paint>, JsValue> {
+    s.as_deref().map(parse_paint).transpose()
+}
+
+fn parse<T: std::str::FromStr<Err = String>>(s: &str) -> Result<T, JsValue> {
+    s.parse().map_err(|e: String| JsValue::from_str(&e))
+}
+
+fn to_js(e: Box<dyn std::error::Error>) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+// Builds the drawing options and returns them with the stroke width and
+// border margin the shape should leave room for.
+fn visual_options(options: &SvgOptions) -> Result<(VisualOptions, f32, i32), JsValue> {
+    let stroke_opacity = options.stroke_opacity.unwrap_or(DEFAULT_STROKE_OPACITY);
     if !(0.0..=1.0).contains(&stroke_opacity) || !stroke_opacity.is_finite() {
         return Err(JsValue::from_str(
             "--stroke-opacity must be a finite number between 0.0 and 1.0",
         ));
     }
-    Ok(())
+    let stroke_color = options
+        .stroke_color
+        .as_deref()
+        .unwrap_or(DEFAULT_STROKE_COLOR);
+    let mut visual = VisualOptions::new(parse_paint(stroke_color)?, stroke_opacity);
+    visual.fill_color = parse_optional_paint(&options.fill_color)?;
+    visual.background_color = parse_optional_paint(&options.background_color)?;
+    visual.stroke_dash = options.stroke_dash.clone();
+    visual.field_spacing = options.field_spacing;
+    visual.arc_tolerance = options.arc_tolerance;
+    visual.minify = options.minify;
+
+    // Per-part strokes; a part stroked "none" is not drawn.
+    visual.pattern_style.stroke_color = parse_optional_paint(&options.pattern_color)?;
+    visual.inner_frame_style.stroke_color = parse_optional_paint(&options.inner_frame_color)?;
+    visual.outer_frame_style.stroke_color = parse_optional_paint(&options.outer_frame_color)?;
+    for style in [
+        &mut visual.pattern_style,
+        &mut visual.inner_frame_style,
+        &mut visual.outer_frame_style,
+    ] {
+        style.hidden = style.stroke_color == Some(Paint::from("none"));
+    }
+    visual.key_colors = options
+        .key_colors
+        .iter()
+        .map(|c| parse_paint(c))
+        .collect::<Result<_, _>>()?;
+
+    visual.line_join = options.line_join.as_deref().map(parse).transpose()?;
+    visual.line_cap = options.line_cap.as_deref().map(parse).transpose()?;
+    visual.miter_limit = options.miter_limit;
+    visual.non_scaling_stroke = options.non_scaling_stroke;
+    visual.effects = options
+        .effects
+        .iter()
+        .map(|e| parse(e))
+        .collect::<Result<_, _>>()?;
+
+    if !options.title.is_empty() || options.ring_text.is_some() {
+        visual.text = Some(Text {
+            title: options.title.clone(),
+            ring_text: options.ring_text.clone(),
+            rail: options
+                .ring_text_rail
+                .as_deref()
+                .map(parse)
+                .transpose()?
+                .unwrap_or_default(),
+            font_family: options.font_family.clone(),
+            font_size: options.font_size,
+            color: parse_optional_paint(&options.text_color)?,
+            font_data: options.font_data.clone(),
+        });
+    }
+    visual.metadata = Metadata {
+        title: options.svg_title.clone(),
+        description: options.description.clone(),
+        author: options.author.clone(),
+        license: options.license.clone(),
+        config: None,
+    };
+    visual.id_prefix = options.id_prefix.clone();
+
+    // Double lines replace the single stroke. As on the command line, a
+    // `stroke_width` other than the width of the double line is an error
+    // rather than being replaced.
+    let mut stroke_width = options.stroke_width.unwrap_or(DEFAULT_STROKE_WIDTH);
+    if let Some(gap) = options.inlay_gap {
+        let line_width = options.inlay_line_width.unwrap_or(DEFAULT_INLAY_LINE_WIDTH);
+        let mut inlay = Inlay::new(gap, line_width);
+        inlay.line_color = parse_optional_paint(&options.inlay_line_color)?;
+        inlay.inlay_color = parse_optional_paint(&options.inlay_color)?;
+        stroke_width = inlay.width() as f32;
+        if options
+            .stroke_width
+            .is_some_and(|width| width != stroke_width)
+        {
+            return Err(JsValue::from_str(&format!(
+                "--stroke-width cannot be combined with --inlay-gap, \
+                 whose double line is {stroke_width} wide"
+            )));
+        }
+        visual.inlay = Some(inlay);
+    }
+    visual.validate().map_err(to_js)?;
+    let border_margin = options.border_margin.unwrap_or(DEFAULT_BORDER_MARGIN);
+    Ok((visual, stroke_width, border_margin))
 }
 
 /// Generate a rectangle Greek Key pattern and return SVG markup.
@@ -21,37 +243,24 @@ fn validate_stroke_opacity(stroke_opacity: f32) -> Result<(), JsValue> {
 /// - `size` — key unit length (must be > 0)
 /// - `width` — number of pattern units across (must be ≥ 3)
 /// - `height` — number of pattern units down (must be ≥ 3)
-/// - `border_margin` — padding outside the outer frame (must be ≥ 0)
-/// - `stroke_width` — line width (must be a positive finite number)
-/// - `stroke_color` — CSS color string, e.g. `"#AB8E0E"`
-/// - `stroke_opacity` — 0.0–1.0
-/// - `fill_color` — optional fill color for the pattern interior
-/// - `background_color` — optional canvas background color
-/// - `stroke_dash` — optional SVG `stroke-dasharray` value, e.g. `"5,3"`
+/// - `options` — the shared [`SvgOptions`]
 ///
 /// Returns an SVG string, or throws a JS error string on invalid input.
-#[allow(clippy::too_many_arguments)]
 #[wasm_bindgen]
 pub fn rect_generate_svg(
     size: i32,
     width: i32,
     height: i32,
-    border_margin: i32,
-    stroke_width: f32,
-    stroke_color: &str,
-    stroke_opacity: f32,
-    fill_color: Option<String>,
-    background_color: Option<String>,
-    stroke_dash: Option<String>,
+    options: &SvgOptions,
 ) -> Result<String, JsValue> {
-    validate_stroke_opacity(stroke_opacity)?;
-    let mut visual = VisualOptions::new(stroke_color, stroke_opacity);
-    visual.fill_color = fill_color;
-    visual.background_color = background_color;
-    visual.stroke_dash = stroke_dash;
+    let (visual, stroke_width, border_margin) = visual_options(options)?;
     GreekKeyRectConfig::new(size, width, height, border_margin, stroke_width)
-        .map(|c| rect::generate_svg_string(&c, &visual))
-        .map_err(|e| JsValue::from_str(&e.to_string()))
+        .and_then(|c| {
+            let (width, height) = c.get_canvas_size();
+            visual.validate_canvas(width, height)?;
+            Ok(rect::generate_svg_string(&c, &visual))
+        })
+        .map_err(to_js)
 }
 
 /// Generate a circle Greek Key pattern and return SVG markup.
@@ -59,36 +268,23 @@ pub fn rect_generate_svg(
 /// # Arguments
 /// - `radius` — outer radius in SVG units (must be > 0)
 /// - `pattern_count` — number of key units around the ring (must be ≥ 4)
-/// - `border_margin` — padding outside the outer circle (must be ≥ 0)
-/// - `stroke_width` — line width (must be a positive finite number)
-/// - `stroke_color` — CSS color string, e.g. `"#AB8E0E"`
-/// - `stroke_opacity` — 0.0–1.0
-/// - `fill_color` — optional fill color for the pattern interior
-/// - `background_color` — optional canvas background color
-/// - `stroke_dash` — optional SVG `stroke-dasharray` value, e.g. `"5,3"`
+/// - `options` — the shared [`SvgOptions`]
 ///
 /// Returns an SVG string, or throws a JS error string on invalid input.
-#[allow(clippy::too_many_arguments)]
 #[wasm_bindgen]
 pub fn circle_generate_svg(
     radius: f64,
     pattern_count: i32,
-    border_margin: i32,
-    stroke_width: f32,
-    stroke_color: &str,
-    stroke_opacity: f32,
-    fill_color: Option<String>,
-    background_color: Option<String>,
-    stroke_dash: Option<String>,
+    options: &SvgOptions,
 ) -> Result<String, JsValue> {
-    validate_stroke_opacity(stroke_opacity)?;
-    let mut visual = VisualOptions::new(stroke_color, stroke_opacity);
-    visual.fill_color = fill_color;
-    visual.background_color = background_color;
-    visual.stroke_dash = stroke_dash;
+    let (visual, stroke_width, border_margin) = visual_options(options)?;
     GreekKeyCircleConfig::new(radius, pattern_count, border_margin, stroke_width)
-        .map(|c| circle::generate_svg_string(&c, &visual))
-        .map_err(|e| JsValue::from_str(&e.to_string()))
+        .and_then(|c| {
+            let (width, height) = c.get_canvas_size();
+            visual.validate_canvas(width, height)?;
+            Ok(circle::generate_svg_string(&c, &visual))
+        })
+        .map_err(to_js)
 }
 
 /// Generate an ellipse Greek Key pattern and return SVG markup.
@@ -97,37 +293,138 @@ pub fn circle_generate_svg(
 /// - `rx` — horizontal outer semi-axis in SVG units (must be > 0)
 /// - `ry` — vertical outer semi-axis in SVG units (must be > 0)
 /// - `pattern_count` — number of key units around the ellipse (must be ≥ 4)
-/// - `border_margin` — padding outside the outer ellipse (must be ≥ 0)
-/// - `stroke_width` — line width (must be a positive finite number)
-/// - `stroke_color` — CSS color string, e.g. `"#AB8E0E"`
-/// - `stroke_opacity` — 0.0–1.0
-/// - `fill_color` — optional fill color for the pattern interior
-/// - `background_color` — optional canvas background color
-/// - `stroke_dash` — optional SVG `stroke-dasharray` value, e.g. `"5,3"`
+/// - `options` — the shared [`SvgOptions`]
 ///
 /// Returns an SVG string, or throws a JS error string on invalid input.
-#[allow(clippy::too_many_arguments)]
 #[wasm_bindgen]
 pub fn ellipse_generate_svg(
     rx: f64,
     ry: f64,
     pattern_count: i32,
-    border_margin: i32,
-    stroke_width: f32,
-    stroke_color: &str,
-    stroke_opacity: f32,
-    fill_color: Option<String>,
-    background_color: Option<String>,
-    stroke_dash: Option<String>,
+    options: &SvgOptions,
 ) -> Result<String, JsValue> {
-    validate_stroke_opacity(stroke_opacity)?;
-    let mut visual = VisualOptions::new(stroke_color, stroke_opacity);
-    visual.fill_color = fill_color;
-    visual.background_color = background_color;
-    visual.stroke_dash = stroke_dash;
+    let (visual, stroke_width, border_margin) = visual_options(options)?;
     GreekKeyEllipseConfig::new(rx, ry, pattern_count, border_margin, stroke_width)
-        .map(|c| ellipse::generate_svg_string(&c, &visual))
-        .map_err(|e| JsValue::from_str(&e.to_string()))
+        .and_then(|c| {
+            let (width, height) = c.get_canvas_size();
+            visual.validate_canvas(width, height)?;
+            Ok(ellipse::generate_svg_string(&c, &visual))
+        })
+        .map_err(to_js)
+}
+
+/// Generate a superellipse Greek Key pattern and return SVG markup.
+///
+/// # Arguments
+/// - `rx` — horizontal outer semi-axis in SVG units (must be > 0)
+/// - `ry` — vertical outer semi-axis in SVG units (must be > 0)
+/// - `exponent` — superellipse exponent; 2 is an ellipse, 4 a squircle (must be ≥ 2)
+/// - `pattern_count` — number of key units around the curve (must be ≥ 4)
+/// - `options` — the shared [`SvgOptions`]
+///
+/// Returns an SVG string, or throws a JS error string on invalid input.
+#[wasm_bindgen]
+pub fn superellipse_generate_svg(
+    rx: f64,
+    ry: f64,
+    exponent: f64,
+    pattern_count: i32,
+    options: &SvgOptions,
+) -> Result<String, JsValue> {
+    let (visual, stroke_width, border_margin) = visual_options(options)?;
+    GreekKeySuperellipseConfig::new(rx, ry, exponent, pattern_count, border_margin, stroke_width)
+        .and_then(|c| {
+            let (width, height) = c.get_canvas_size();
+            visual.validate_canvas(width, height)?;
+            Ok(superellipse::generate_svg_string(&c, &visual))
+        })
+        .map_err(to_js)
+}
+
+/// Generate a spiral Greek Key band and return SVG markup.
+///
+/// # Arguments
+/// - `kind` — `"archimedean"` or `"logarithmic"`
+/// - `turns` — number of full turns (must be > 0)
+/// - `start_radius` — radius where the band starts, at the top (must be > 0)
+/// - `end_radius` — radius where the band ends (must be > 0)
+/// - `size` — key unit length, constant along the path (must be > 0)
+/// - `options` — the shared [`SvgOptions`]
+///
+/// Returns an SVG string, or throws a JS error string on invalid input.
+#[wasm_bindgen]
+pub fn spiral_generate_svg(
+    kind: &str,
+    turns: f64,
+    start_radius: f64,
+    end_radius: f64,
+    size: f64,
+    options: &SvgOptions,
+) -> Result<String, JsValue> {
+    let kind: SpiralKind = parse(kind)?;
+    let (visual, stroke_width, border_margin) = visual_options(options)?;
+    GreekKeySpiralConfig::new(
+        kind,
+        turns,
+        start_radius,
+        end_radius,
+        size,
+        border_margin,
+        stroke_width,
+    )
+    .map(|c| spiral::generate_svg_string(&c, &visual))
+    .map_err(to_js)
+}
+
+/// Generate a rectangle filled with an all-over Greek Key lattice and return SVG markup.
+///
+/// # Arguments
+/// - `width` — width of the filled area in SVG units (must be > 0)
+/// - `height` — height of the filled area in SVG units (must be > 0)
+/// - `size` — key unit length of the lattice (must be > 0)
+/// - `options` — the shared [`SvgOptions`]
+///
+/// Returns an SVG string, or throws a JS error string on invalid input.
+#[wasm_bindgen]
+pub fn field_generate_svg(
+    width: f64,
+    height: f64,
+    size: f64,
+    options: &SvgOptions,
+) -> Result<String, JsValue> {
+    let (visual, stroke_width, border_margin) = visual_options(options)?;
+    GreekKeyFieldConfig::new(width, height, size, border_margin, stroke_width)
+        .map(|c| field::generate_svg_string(&c, &visual))
+        .map_err(to_js)
+}
+
+/// Frame an image with a Greek Key border and return SVG markup.
+///
+/// # Arguments
+/// - `image` — the bytes of a PNG, JPEG or SVG file; it is embedded in the output
+/// - `shape` — `"rect"`, `"circle"` or `"ellipse"`
+/// - `size` — key unit length of a rect border (must be > 0)
+/// - `pattern_count` — number of key units around a circle or ellipse border (must be ≥ 4)
+/// - `options` — the shared [`SvgOptions`]
+///
+/// Returns an SVG string, or throws a JS error string on invalid input.
+#[wasm_bindgen]
+pub fn frame_generate_svg(
+    image: Vec<u8>,
+    shape: &str,
+    size: i32,
+    pattern_count: i32,
+    options: &SvgOptions,
+) -> Result<String, JsValue> {
+    let shape: FrameShape = parse(shape)?;
+    let (visual, stroke_width, border_margin) = visual_options(options)?;
+    Artwork::from_bytes(image)
+        .and_then(|artwork| {
+            let config =
+                GreekKeyFrameConfig::new(shape, size, pattern_count, border_margin, stroke_width)?;
+            frame::generate_svg_string(&config, &artwork, &visual)
+        })
+        .map_err(to_js)
 }
 
 #[cfg(test)]
@@ -135,109 +432,526 @@ mod tests {
     use super::*;
     use wasm_bindgen_test::*;
 
+    // The options most tests start from.
+    fn options() -> SvgOptions {
+        SvgOptions {
+            border_margin: Some(10),
+            stroke_width: Some(3.0),
+            stroke_color: Some("#AB8E0E".to_string()),
+            stroke_opacity: Some(0.7),
+            ..SvgOptions::default()
+        }
+    }
+
     #[wasm_bindgen_test]
     fn rect_svg_contains_svg_element() {
-        let svg = rect_generate_svg(25, 16, 9, 10, 3.0, "#AB8E0E", 0.7, None, None, None).unwrap();
+        let svg = rect_generate_svg(25, 16, 9, &options()).unwrap();
         assert!(svg.contains("<svg"));
         assert!(svg.contains("<path"));
     }
 
     #[wasm_bindgen_test]
     fn rect_svg_contains_color() {
-        let svg = rect_generate_svg(25, 16, 9, 10, 3.0, "#AB8E0E", 0.7, None, None, None).unwrap();
+        let svg = rect_generate_svg(25, 16, 9, &options()).unwrap();
         assert!(svg.contains("#AB8E0E"));
     }
 
     #[wasm_bindgen_test]
     fn rect_invalid_size_returns_error() {
-        let err =
-            rect_generate_svg(0, 16, 9, 10, 3.0, "#AB8E0E", 0.7, None, None, None).unwrap_err();
+        let err = rect_generate_svg(0, 16, 9, &options()).unwrap_err();
         assert!(err.as_string().unwrap().contains("--size"));
     }
 
     #[wasm_bindgen_test]
     fn rect_invalid_opacity_returns_error() {
-        let err =
-            rect_generate_svg(25, 16, 9, 10, 3.0, "#AB8E0E", 1.1, None, None, None).unwrap_err();
+        let err = rect_generate_svg(
+            25,
+            16,
+            9,
+            &SvgOptions {
+                stroke_opacity: Some(1.1),
+                ..options()
+            },
+        )
+        .unwrap_err();
         assert!(err.as_string().unwrap().contains("--stroke-opacity"));
     }
 
     #[wasm_bindgen_test]
     fn circle_svg_contains_svg_element() {
-        let svg =
-            circle_generate_svg(300.0, 30, 10, 3.0, "#AB8E0E", 0.7, None, None, None).unwrap();
+        let svg = circle_generate_svg(300.0, 30, &options()).unwrap();
         assert!(svg.contains("<svg"));
         assert!(svg.contains("<path"));
     }
 
     #[wasm_bindgen_test]
     fn circle_svg_contains_color() {
-        let svg =
-            circle_generate_svg(300.0, 30, 10, 3.0, "#AB8E0E", 0.7, None, None, None).unwrap();
+        let svg = circle_generate_svg(300.0, 30, &options()).unwrap();
         assert!(svg.contains("#AB8E0E"));
     }
 
     #[wasm_bindgen_test]
     fn circle_invalid_radius_returns_error() {
-        let err =
-            circle_generate_svg(0.0, 30, 10, 3.0, "#AB8E0E", 0.7, None, None, None).unwrap_err();
+        let err = circle_generate_svg(0.0, 30, &options()).unwrap_err();
         assert!(err.as_string().unwrap().contains("--radius"));
     }
 
     #[wasm_bindgen_test]
     fn circle_nan_radius_returns_error() {
-        let err = circle_generate_svg(f64::NAN, 30, 10, 3.0, "#AB8E0E", 0.7, None, None, None)
-            .unwrap_err();
+        let err = circle_generate_svg(f64::NAN, 30, &options()).unwrap_err();
         assert!(err.as_string().unwrap().contains("--radius"));
     }
 
     #[wasm_bindgen_test]
     fn circle_infinite_radius_returns_error() {
-        let err = circle_generate_svg(f64::INFINITY, 30, 10, 3.0, "#AB8E0E", 0.7, None, None, None)
-            .unwrap_err();
+        let err = circle_generate_svg(f64::INFINITY, 30, &options()).unwrap_err();
         assert!(err.as_string().unwrap().contains("--radius"));
     }
 
     #[wasm_bindgen_test]
     fn circle_nan_opacity_returns_error() {
-        let err = circle_generate_svg(300.0, 30, 10, 3.0, "#AB8E0E", f32::NAN, None, None, None)
-            .unwrap_err();
+        let err = circle_generate_svg(
+            300.0,
+            30,
+            &SvgOptions {
+                stroke_opacity: Some(f32::NAN),
+                ..options()
+            },
+        )
+        .unwrap_err();
         assert!(err.as_string().unwrap().contains("--stroke-opacity"));
     }
 
     #[wasm_bindgen_test]
     fn ellipse_svg_contains_svg_element() {
-        let svg = ellipse_generate_svg(300.0, 200.0, 30, 10, 3.0, "#AB8E0E", 0.7, None, None, None)
-            .unwrap();
+        let svg = ellipse_generate_svg(300.0, 200.0, 30, &options()).unwrap();
         assert!(svg.contains("<svg"));
         assert!(svg.contains("<path"));
     }
 
     #[wasm_bindgen_test]
     fn ellipse_svg_contains_ellipse_element() {
-        let svg = ellipse_generate_svg(300.0, 200.0, 30, 10, 3.0, "#AB8E0E", 0.7, None, None, None)
-            .unwrap();
+        let svg = ellipse_generate_svg(300.0, 200.0, 30, &options()).unwrap();
         assert!(svg.contains("<ellipse"));
     }
 
     #[wasm_bindgen_test]
     fn ellipse_invalid_rx_returns_error() {
-        let err = ellipse_generate_svg(0.0, 200.0, 30, 10, 3.0, "#AB8E0E", 0.7, None, None, None)
-            .unwrap_err();
+        let err = ellipse_generate_svg(0.0, 200.0, 30, &options()).unwrap_err();
         assert!(err.as_string().unwrap().contains("--rx"));
     }
 
     #[wasm_bindgen_test]
     fn ellipse_invalid_ry_returns_error() {
-        let err = ellipse_generate_svg(300.0, -1.0, 30, 10, 3.0, "#AB8E0E", 0.7, None, None, None)
-            .unwrap_err();
+        let err = ellipse_generate_svg(300.0, -1.0, 30, &options()).unwrap_err();
         assert!(err.as_string().unwrap().contains("--ry"));
     }
 
     #[wasm_bindgen_test]
     fn ellipse_invalid_opacity_returns_error() {
-        let err = ellipse_generate_svg(300.0, 200.0, 30, 10, 3.0, "#AB8E0E", 1.5, None, None, None)
-            .unwrap_err();
+        let err = ellipse_generate_svg(
+            300.0,
+            200.0,
+            30,
+            &SvgOptions {
+                stroke_opacity: Some(1.5),
+                ..options()
+            },
+        )
+        .unwrap_err();
         assert!(err.as_string().unwrap().contains("--stroke-opacity"));
     }
+
+    #[wasm_bindgen_test]
+    fn superellipse_svg_contains_svg_element() {
+        let svg = superellipse_generate_svg(300.0, 200.0, 4.0, 30, &options()).unwrap();
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("<path"));
+    }
+
+    #[wasm_bindgen_test]
+    fn superellipse_invalid_exponent_returns_error() {
+        let err = superellipse_generate_svg(300.0, 200.0, 1.5, 30, &options()).unwrap_err();
+        assert!(err.as_string().unwrap().contains("--exponent"));
+    }
+
+    #[wasm_bindgen_test]
+    fn spiral_svg_contains_svg_element() {
+        let svg = spiral_generate_svg("archimedean", 3.0, 300.0, 60.0, 8.0, &options()).unwrap();
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("<path"));
+    }
+
+    #[wasm_bindgen_test]
+    fn spiral_unknown_kind_returns_error() {
+        let err = spiral_generate_svg("golden", 3.0, 300.0, 60.0, 8.0, &options()).unwrap_err();
+        assert!(err.as_string().unwrap().contains("spiral kind"));
+    }
+
+    #[wasm_bindgen_test]
+    fn field_svg_contains_clip_path() {
+        let svg = field_generate_svg(
+            400.0,
+            300.0,
+            10.0,
+            &SvgOptions {
+                border_margin: Some(5),
+                stroke_width: Some(2.0),
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains("<clipPath"));
+    }
+
+    #[wasm_bindgen_test]
+    fn circle_field_spacing_adds_lattice() {
+        let svg = circle_generate_svg(
+            300.0,
+            30,
+            &SvgOptions {
+                field_spacing: Some(10.0),
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains("<clipPath"));
+    }
+
+    #[wasm_bindgen_test]
+    fn ellipse_arc_tolerance_flattens_arcs() {
+        let arcs = ellipse_generate_svg(300.0, 200.0, 30, &options()).unwrap();
+        let lines = ellipse_generate_svg(
+            300.0,
+            200.0,
+            30,
+            &SvgOptions {
+                arc_tolerance: Some(0.1),
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(arcs.contains(" A"));
+        assert!(!lines.contains(" A"));
+    }
+
+    #[wasm_bindgen_test]
+    fn minify_shrinks_output() {
+        let full = spiral_generate_svg(
+            "archimedean",
+            2.0,
+            200.0,
+            80.0,
+            6.0,
+            &SvgOptions {
+                border_margin: Some(5),
+                stroke_width: Some(2.0),
+                ..options()
+            },
+        )
+        .unwrap();
+        let minified = spiral_generate_svg(
+            "archimedean",
+            2.0,
+            200.0,
+            80.0,
+            6.0,
+            &SvgOptions {
+                border_margin: Some(5),
+                stroke_width: Some(2.0),
+                minify: true,
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(minified.len() * 2 < full.len());
+    }
+
+    #[wasm_bindgen_test]
+    fn gradient_stroke_adds_defs() {
+        let svg = field_generate_svg(
+            400.0,
+            300.0,
+            10.0,
+            &SvgOptions {
+                border_margin: Some(5),
+                stroke_width: Some(2.0),
+                stroke_color: Some("linear-gradient(90deg, #8B6914, #FFF1A8)".to_string()),
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains("<linearGradient"));
+    }
+
+    #[wasm_bindgen_test]
+    fn invalid_gradient_returns_error() {
+        let err = field_generate_svg(
+            400.0,
+            300.0,
+            10.0,
+            &SvgOptions {
+                border_margin: Some(5),
+                stroke_width: Some(2.0),
+                stroke_color: Some("radial-gradient(red)".to_string()),
+                ..options()
+            },
+        )
+        .unwrap_err();
+        assert!(err.as_string().unwrap().contains("two colour stops"));
+    }
+
+    #[wasm_bindgen_test]
+    fn rect_invalid_field_spacing_returns_error() {
+        let err = rect_generate_svg(
+            25,
+            16,
+            9,
+            &SvgOptions {
+                field_spacing: Some(0.0),
+                ..options()
+            },
+        )
+        .unwrap_err();
+        assert!(err.as_string().unwrap().contains("--field-spacing"));
+    }
+
+    #[wasm_bindgen_test]
+    fn key_colors_split_pattern_and_none_hides_frame() {
+        let svg = superellipse_generate_svg(
+            300.0,
+            200.0,
+            4.0,
+            30,
+            &SvgOptions {
+                outer_frame_color: Some("none".to_string()),
+                key_colors: vec!["#A3341F".to_string(), "#111".to_string()],
+                ..options()
+            },
+        )
+        .unwrap();
+        assert_eq!(svg.matches("stroke=\"#A3341F\"").count(), 15);
+        assert_eq!(svg.matches("stroke=\"#111\"").count(), 15);
+        // 30 keys and the inner frame.
+        assert_eq!(svg.matches("<path").count(), 31);
+    }
+
+    #[wasm_bindgen_test]
+    fn inlay_draws_double_lines_and_rejects_bad_gap() {
+        let svg = field_generate_svg(
+            200.0,
+            100.0,
+            10.0,
+            &SvgOptions {
+                border_margin: Some(5),
+                stroke_width: Some(7.0),
+                inlay_gap: Some(4.0),
+                inlay_line_width: Some(1.5),
+                inlay_line_color: Some("#222".to_string()),
+                inlay_color: Some("#C0C0C0".to_string()),
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains("stroke=\"#C0C0C0\""));
+        assert!(svg.contains("stroke=\"#222\""));
+        assert!(svg.contains("stroke-width=\"1.5\""));
+        // Without a stroke width the double line sets it.
+        let svg = field_generate_svg(
+            200.0,
+            100.0,
+            10.0,
+            &SvgOptions {
+                stroke_width: None,
+                inlay_gap: Some(4.0),
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains("stroke-width=\"2\""));
+        let err = field_generate_svg(
+            200.0,
+            100.0,
+            10.0,
+            &SvgOptions {
+                stroke_width: None,
+                inlay_gap: Some(0.0),
+                ..options()
+            },
+        )
+        .unwrap_err();
+        assert!(err.as_string().unwrap().contains("--inlay-gap"));
+        // A stroke width other than the double line's is refused, not replaced.
+        let err = field_generate_svg(
+            200.0,
+            100.0,
+            10.0,
+            &SvgOptions {
+                border_margin: Some(5),
+                inlay_gap: Some(4.0),
+                inlay_line_width: Some(1.5),
+                inlay_line_color: Some("#222".to_string()),
+                inlay_color: Some("#C0C0C0".to_string()),
+                ..options()
+            },
+        )
+        .unwrap_err();
+        assert!(
+            err.as_string()
+                .unwrap()
+                .contains("--stroke-width cannot be combined with --inlay-gap")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn stroke_geometry_applies_and_rejects_unknown_join() {
+        let svg = rect_generate_svg(
+            25,
+            16,
+            9,
+            &SvgOptions {
+                line_join: Some("round".to_string()),
+                line_cap: Some("square".to_string()),
+                miter_limit: Some(2.0),
+                non_scaling_stroke: true,
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains("stroke-linejoin=\"round\""));
+        assert!(svg.contains("stroke-linecap=\"square\""));
+        assert!(svg.contains("stroke-miterlimit=\"2\""));
+        assert!(svg.contains("vector-effect=\"non-scaling-stroke\""));
+        assert!(
+            rect_generate_svg(
+                25,
+                16,
+                9,
+                &SvgOptions {
+                    line_join: Some("sharp".to_string()),
+                    ..options()
+                }
+            )
+            .is_err()
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn effects_wrap_border_in_filter() {
+        let svg = spiral_generate_svg(
+            "archimedean",
+            3.0,
+            300.0,
+            60.0,
+            8.0,
+            &SvgOptions {
+                effects: vec!["shadow".to_string(), "inner-glow".to_string()],
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains("filter=\"url(#gm-effects)\""));
+        assert!(svg.contains("<feOffset"));
+    }
+
+    #[wasm_bindgen_test]
+    fn frame_svg_embeds_the_image() {
+        let image = br#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 300 200"/>"#;
+        let svg = frame_generate_svg(
+            image.to_vec(),
+            "ellipse",
+            10,
+            30,
+            &SvgOptions {
+                border_margin: Some(1),
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains("data:image/svg+xml;base64,"));
+        assert!(svg.contains("<ellipse"));
+    }
+
+    #[wasm_bindgen_test]
+    fn frame_rejects_unknown_images() {
+        let err = frame_generate_svg(
+            b"not an image".to_vec(),
+            "rect",
+            10,
+            30,
+            &SvgOptions {
+                border_margin: Some(1),
+                ..options()
+            },
+        )
+        .unwrap_err();
+        assert!(err.as_string().unwrap().contains("PNG, JPEG or SVG"));
+    }
+
+    #[wasm_bindgen_test]
+    fn circle_draws_title_and_ring_text() {
+        let svg = circle_generate_svg(
+            300.0,
+            30,
+            &SvgOptions {
+                title: vec!["Menu".to_string()],
+                ring_text: Some("ΑΡΕΤΗ".to_string()),
+                ring_text_rail: Some("outer".to_string()),
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains("Menu</tspan>"));
+        assert!(svg.contains("ΑΡΕΤΗ</textPath>"));
+    }
+
+    #[wasm_bindgen_test]
+    fn rect_rejects_unknown_ring_text_rail() {
+        let err = rect_generate_svg(
+            25,
+            16,
+            9,
+            &SvgOptions {
+                ring_text: Some("ring".to_string()),
+                ring_text_rail: Some("middle".to_string()),
+                ..options()
+            },
+        )
+        .unwrap_err();
+        assert!(err.as_string().unwrap().contains("unknown text rail"));
+    }
+
+    #[wasm_bindgen_test]
+    fn rect_stores_title_and_licence() {
+        let svg = rect_generate_svg(
+            25,
+            16,
+            9,
+            &SvgOptions {
+                svg_title: Some("Border".to_string()),
+                license: Some("CC0".to_string()),
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains(r#"<title id="gm-title">Border</title>"#));
+        assert!(svg.contains("<dc:rights>CC0</dc:rights>"));
+        let config = crate::metadata::read_config(&svg).unwrap();
+        assert!(config.contains("[rect]\nsize = 25\nwidth = 16\nheight = 9\n"));
+    }
+
+    #[wasm_bindgen_test]
+    fn circle_id_prefix_replaces_gm() {
+        let svg = circle_generate_svg(
+            300.0,
+            30,
+            &SvgOptions {
+                svg_title: Some("Ring".to_string()),
+                id_prefix: Some("left".to_string()),
+                ..options()
+            },
+        )
+        .unwrap();
+        assert!(svg.contains(r#"<title id="left-title">Ring</title>"#));
+    }
 }
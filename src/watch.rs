@@ -0,0 +1,91 @@
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+/// How often the file is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long a changed file must stay unchanged before it is read, so an
+/// editor's burst of writes leads to one render.
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// Modification time and length of a file, or `None` while it is missing.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Polls a file for changes.
+///
+/// Polling needs no platform support and sees files replaced by editors that
+/// save through a rename, which would end a watch on the old file.
+pub struct Watcher {
+    path: PathBuf,
+    stamp: Stamp,
+}
+
+impl Watcher {
+    /// Starts watching `path` as it is now.
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            stamp: stamp(path),
+        }
+    }
+
+    /// Blocks until the file has changed and then stayed unchanged, and
+    /// present, for the settle time.
+    pub fn wait(&mut self) {
+        loop {
+            sleep(POLL_INTERVAL);
+            let mut current = stamp(&self.path);
+            if current == self.stamp {
+                continue;
+            }
+            let mut settled = Duration::ZERO;
+            while settled < SETTLE_TIME {
+                sleep(POLL_INTERVAL);
+                let next = stamp(&self.path);
+                if next == current {
+                    settled += POLL_INTERVAL;
+                } else {
+                    current = next;
+                    settled = Duration::ZERO;
+                }
+            }
+            self.stamp = current;
+            if current.is_some() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn a_burst_of_writes_is_one_change() {
+        let path = std::env::temp_dir().join(format!("gm_watch_{}.toml", std::process::id()));
+        std::fs::write(&path, "a").unwrap();
+        let mut watcher = Watcher::new(&path);
+        let writer = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                for content in ["ab", "abc", "abcd"] {
+                    sleep(Duration::from_millis(50));
+                    std::fs::write(&path, content).unwrap();
+                }
+            })
+        };
+        let start = Instant::now();
+        watcher.wait();
+        writer.join().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(150) + SETTLE_TIME);
+        assert_eq!(watcher.stamp, stamp(&path));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,7 +1,16 @@
 use greek_meander::{
-    circle,
-    config::{GreekKeyCircleConfig, GreekKeyEllipseConfig, GreekKeyRectConfig, VisualOptions},
-    ellipse, rect,
+    Theme, circle,
+    config::{
+        ElementStyle, FrameShape, GreekKeyCircleConfig, GreekKeyEllipseConfig, GreekKeyFieldConfig,
+        GreekKeyFrameConfig, GreekKeyRectConfig, GreekKeySpiralConfig, GreekKeySuperellipseConfig,
+        Inlay, LineCap, LineJoin, SpiralKind, VisualOptions,
+    },
+    effects::Effect,
+    ellipse, field,
+    frame::{self, Artwork},
+    metadata::{self, Metadata},
+    rect, spiral, superellipse,
+    text::Text,
 };
 #[cfg(feature = "native")]
 use std::path::PathBuf;
@@ -392,6 +401,101 @@ ry = 90.0
     );
 }
 
+#[cfg(feature = "native")]
+#[test]
+fn config_file_sets_superellipse_params() {
+    let out = temp_path("gm_test_cfg_superellipse");
+    let _guard = TempFiles::for_base(&out);
+    let cfg = write_temp_config(
+        "gm_test_cfg_superellipse",
+        &format!(
+            r#"
+file = "{out}"
+[superellipse]
+pattern_count = 24
+rx = 140.0
+ry = 90.0
+exponent = 3.0
+"#
+        ),
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--config",
+            cfg.to_str().unwrap(),
+            "--no-png",
+            "superellipse",
+        ])
+        .output()
+        .unwrap();
+
+    let _ = std::fs::remove_file(&cfg);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = std::fs::read_to_string(format!("{out}.svg")).unwrap();
+    assert!(
+        svg.contains(r#"viewBox="0 0 294 194""#),
+        "superellipse semi-axes should come from the TOML file"
+    );
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn config_file_sets_spiral_params() {
+    let out = temp_path("gm_test_cfg_spiral");
+    let _guard = TempFiles::for_base(&out);
+    let cfg = write_temp_config(
+        "gm_test_cfg_spiral",
+        &format!(
+            r#"
+file = "{out}"
+[spiral]
+kind = "logarithmic"
+turns = 2.0
+start_radius = 200.0
+end_radius = 30.0
+size = 4.0
+"#
+        ),
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--config", cfg.to_str().unwrap(), "--no-png", "spiral"])
+        .output()
+        .unwrap();
+
+    let _ = std::fs::remove_file(&cfg);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = std::fs::read_to_string(format!("{out}.svg")).unwrap();
+    assert!(
+        svg.contains(r#"viewBox="0 0 438 438""#),
+        "spiral radius and key size should come from the TOML file"
+    );
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_rejects_unknown_spiral_kind() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout", "--no-svg", "--no-png", "spiral", "--kind", "golden",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unknown spiral kind"));
+}
+
 #[cfg(feature = "native")]
 #[test]
 fn config_file_scale_changes_png_dimensions() {
@@ -646,173 +750,284 @@ fn ellipse_svg_string_is_valid_svg() {
     );
 }
 
-// --- VisualOptions: SVG structure ---
-
 #[test]
-fn default_output_has_no_fill_no_background_no_dash() {
-    let config = GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap();
-    let svg = rect::generate_svg_string(&config, &VisualOptions::default());
+fn superellipse_svg_string_is_valid_svg() {
+    let config = GreekKeySuperellipseConfig::new(140.0, 90.0, 4.0, 24, 5, 2.0).unwrap();
+    let svg = superellipse::generate_svg_string(&config, &VisualOptions::default());
     assert!(
-        svg.contains(r#"fill="none""#),
-        "default fill should be none"
+        svg.contains("<svg"),
+        "svg string should contain an <svg> element"
     );
-    assert!(!svg.contains("stroke-dasharray"), "no dasharray by default");
-    assert!(!svg.contains("<rect"), "no background rect by default");
+    assert!(
+        svg.contains("viewBox"),
+        "svg string should contain a viewBox attribute"
+    );
+    assert!(svg.contains("<path"), "svg string should contain path data");
 }
 
+#[cfg(feature = "native")]
 #[test]
-fn fill_color_appears_on_pattern_path() {
-    let config = GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap();
-    let visual = VisualOptions {
-        fill_color: Some("#FF0000".to_string()),
-        ..Default::default()
-    };
-    let svg = rect::generate_svg_string(&config, &visual);
-    assert!(
-        svg.contains("fill=\"#FF0000\""),
-        "fill color should appear on the path element"
-    );
+fn superellipse_creates_svg_and_png() {
+    let config = GreekKeySuperellipseConfig::new(100.0, 80.0, 4.0, 20, 5, 2.0).unwrap();
+    let path = temp_path("gm_test_superellipse");
+    let _guard = TempFiles::for_base(&path);
+    superellipse::generate_pattern_svg(&config, &VisualOptions::default(), &path).unwrap();
+    assert!(PathBuf::from(format!("{}.svg", path)).exists());
+    assert!(PathBuf::from(format!("{}.png", path)).exists());
 }
 
 #[test]
-fn background_color_adds_rect_element() {
-    let config = GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap();
-    let visual = VisualOptions {
-        background_color: Some("#000000".to_string()),
-        ..Default::default()
-    };
-    let svg = rect::generate_svg_string(&config, &visual);
+fn spiral_svg_string_is_valid_svg() {
+    let config =
+        GreekKeySpiralConfig::new(SpiralKind::Archimedean, 2.0, 150.0, 40.0, 5.0, 5, 2.0).unwrap();
+    let svg = spiral::generate_svg_string(&config, &VisualOptions::default());
     assert!(
-        svg.contains("<rect"),
-        "background color should add a rect element"
+        svg.contains("<svg"),
+        "svg string should contain an <svg> element"
     );
     assert!(
-        svg.contains("fill=\"#000000\""),
-        "background rect should have the specified fill color"
+        svg.contains("viewBox"),
+        "svg string should contain a viewBox attribute"
     );
+    assert!(svg.contains("<path"), "svg string should contain path data");
 }
 
+#[cfg(feature = "native")]
 #[test]
-fn stroke_dash_appears_on_path_and_frames() {
-    let config = GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap();
-    let visual = VisualOptions {
-        stroke_dash: Some("5,3".to_string()),
-        ..Default::default()
-    };
-    let svg = rect::generate_svg_string(&config, &visual);
-    let count = svg.matches("stroke-dasharray").count();
+fn spiral_creates_svg_and_png() {
+    let config =
+        GreekKeySpiralConfig::new(SpiralKind::Logarithmic, 2.0, 150.0, 30.0, 3.0, 5, 1.0).unwrap();
+    let path = temp_path("gm_test_spiral");
+    let _guard = TempFiles::for_base(&path);
+    spiral::generate_pattern_svg(&config, &VisualOptions::default(), &path).unwrap();
+    assert!(PathBuf::from(format!("{}.svg", path)).exists());
+    assert!(PathBuf::from(format!("{}.png", path)).exists());
+}
+
+#[test]
+fn field_svg_string_is_valid_svg() {
+    let config = GreekKeyFieldConfig::new(200.0, 120.0, 8.0, 5, 2.0).unwrap();
+    let svg = field::generate_svg_string(&config, &VisualOptions::default());
     assert!(
-        count >= 3,
-        "stroke-dasharray should appear on pattern path and both frames, got {count}"
+        svg.contains(r#"viewBox="0 0 214 134""#),
+        "field canvas should cover the requested area plus margins"
     );
+    assert!(svg.contains("<clipPath"), "field should be clipped");
 }
 
 #[test]
-fn circle_fill_color_appears_on_pattern_path() {
-    let config = GreekKeyCircleConfig::new(100.0, 10, 5, 2.0).unwrap();
+fn field_spacing_fills_each_closed_shape() {
     let visual = VisualOptions {
-        fill_color: Some("#00FF00".to_string()),
-        ..Default::default()
+        field_spacing: Some(8.0),
+        ..VisualOptions::default()
     };
-    let svg = circle::generate_svg_string(&config, &visual);
-    assert!(svg.contains("fill=\"#00FF00\""));
+    let svgs = [
+        rect::generate_svg_string(&GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap(), &visual),
+        circle::generate_svg_string(
+            &GreekKeyCircleConfig::new(100.0, 10, 5, 2.0).unwrap(),
+            &visual,
+        ),
+        ellipse::generate_svg_string(
+            &GreekKeyEllipseConfig::new(140.0, 90.0, 24, 5, 2.0).unwrap(),
+            &visual,
+        ),
+        superellipse::generate_svg_string(
+            &GreekKeySuperellipseConfig::new(140.0, 90.0, 4.0, 24, 5, 2.0).unwrap(),
+            &visual,
+        ),
+    ];
+    for svg in svgs {
+        assert!(svg.contains("<clipPath"), "field should clip to the shape");
+        assert!(svg.contains("clip-path=\"url(#gm-field-clip)\""));
+    }
 }
 
 #[test]
-fn circle_background_color_adds_rect_element() {
+fn no_field_by_default() {
     let config = GreekKeyCircleConfig::new(100.0, 10, 5, 2.0).unwrap();
-    let visual = VisualOptions {
-        background_color: Some("#111111".to_string()),
-        ..Default::default()
-    };
-    let svg = circle::generate_svg_string(&config, &visual);
-    assert!(svg.contains("<rect"));
-    assert!(svg.contains("fill=\"#111111\""));
+    let svg = circle::generate_svg_string(&config, &VisualOptions::default());
+    assert!(!svg.contains("<clipPath"));
 }
 
+#[cfg(feature = "native")]
 #[test]
-fn circle_stroke_dash_appears_on_path_and_frames() {
-    let config = GreekKeyCircleConfig::new(100.0, 10, 5, 2.0).unwrap();
-    let visual = VisualOptions {
-        stroke_dash: Some("8,4".to_string()),
-        ..Default::default()
-    };
-    let svg = circle::generate_svg_string(&config, &visual);
-    let count = svg.matches("stroke-dasharray").count();
+fn cli_field_spacing_fills_border() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "--field-spacing",
+            "6",
+            "circle",
+            "--radius",
+            "100",
+            "--pattern-count",
+            "10",
+        ])
+        .output()
+        .unwrap();
+
     assert!(
-        count >= 3,
-        "stroke-dasharray should appear on pattern path and both circle frames, got {count}"
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
     );
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert!(svg.contains("<clipPath"));
+    assert!(svg.contains("<circle"));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_arc_tolerance_replaces_circle_arcs() {
+    let run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(["--stdout", "--no-svg", "--no-png"])
+            .args(extra)
+            .args(["circle", "--radius", "150", "--pattern-count", "8"])
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(run(&[]).contains(" A"), "circle runs should be true arcs");
+    assert!(!run(&["--arc-tolerance", "0.2"]).contains(" A"));
 }
 
+#[cfg(feature = "native")]
 #[test]
-fn ellipse_stroke_dash_appears_on_path_and_frames() {
-    let config = GreekKeyEllipseConfig::new(140.0, 90.0, 24, 5, 2.0).unwrap();
-    let visual = VisualOptions {
-        stroke_dash: Some("8,4".to_string()),
-        ..Default::default()
+fn cli_minify_shrinks_svg_and_still_renders() {
+    let out = temp_path("gm_test_minify");
+    let _guard = TempFiles::for_base(&out);
+    let run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(["--file", &out])
+            .args(extra)
+            .args(["superellipse"])
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        std::fs::read_to_string(format!("{out}.svg")).unwrap()
     };
-    let svg = ellipse::generate_svg_string(&config, &visual);
-    let count = svg.matches("stroke-dasharray").count();
+
+    let full = run(&[]);
+    let minified = run(&["--minify"]);
     assert!(
-        count >= 3,
-        "stroke-dasharray should appear on pattern path and both ellipse frames, got {count}"
+        minified.len() * 3 < full.len(),
+        "minified {} bytes, full {} bytes",
+        minified.len(),
+        full.len()
     );
+    assert!(!minified.contains('\n'));
+    assert_eq!(minified.matches("stroke=").count(), 1);
+    assert!(std::path::Path::new(&format!("{out}.png")).exists());
+
+    // Shapes drawn with short runs and arcs shrink less, but still by half
+    // again.
+    for shape in ["rect", "circle", "ellipse", "field"] {
+        let svg = |extra: &[&str]| {
+            let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+                .args(["--isolated", "--stdout", "--no-svg", "--no-png"])
+                .args(extra)
+                .arg(shape)
+                .output()
+                .unwrap();
+            output.stdout.len()
+        };
+        let (full, minified) = (svg(&[]), svg(&["--minify"]));
+        assert!(
+            minified * 3 < full * 2,
+            "{shape}: minified {minified} bytes, full {full} bytes"
+        );
+    }
 }
 
 #[cfg(feature = "native")]
 #[test]
-fn cli_fill_color_appears_in_svg() {
-    let path = temp_path("gm_test_fill_color");
-    let _guard = TempFiles::for_base(&path);
+fn config_file_sets_precision() {
+    let cfg = write_temp_config(
+        "gm_test_cfg_precision",
+        r#"
+precision = 1
+relative_paths = true
+"#,
+    );
 
     let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
         .args([
-            "--fill-color",
-            "#FF0000",
+            "--config",
+            cfg.to_str().unwrap(),
+            "--stdout",
+            "--no-svg",
             "--no-png",
-            "--file",
-            &path,
-            "rect",
-            "--size",
-            "10",
-            "--width",
-            "4",
-            "--height",
-            "4",
+            "circle",
         ])
         .output()
         .unwrap();
 
-    assert!(
-        output.status.success(),
-        "{}",
-        String::from_utf8_lossy(&output.stderr)
-    );
-    let svg = std::fs::read_to_string(format!("{path}.svg")).unwrap();
-    assert!(svg.contains("fill=\"#FF0000\""));
+    let _ = std::fs::remove_file(&cfg);
+    assert!(output.status.success());
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert!(svg.contains(r#"r="233""#), "frame radius should be rounded");
+    assert!(svg.contains(" a"), "arcs should be relative");
+    assert!(!svg.contains(" A"));
 }
 
-#[cfg(feature = "native")]
 #[test]
-fn cli_background_color_adds_rect_to_svg() {
-    let path = temp_path("gm_test_background_color");
-    let _guard = TempFiles::for_base(&path);
+fn gradient_paints_add_defs_to_every_shape() {
+    let visual = VisualOptions {
+        stroke_color: "linear-gradient(90deg, #8B6914, #FFF1A8 50%, #8B6914)"
+            .parse()
+            .unwrap(),
+        background_color: Some("radial-gradient(white, black)".parse().unwrap()),
+        ..VisualOptions::default()
+    };
+    let svgs = [
+        rect::generate_svg_string(&GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap(), &visual),
+        circle::generate_svg_string(
+            &GreekKeyCircleConfig::new(100.0, 10, 5, 2.0).unwrap(),
+            &visual,
+        ),
+        spiral::generate_svg_string(
+            &GreekKeySpiralConfig::new(SpiralKind::Archimedean, 2.0, 200.0, 80.0, 6.0, 5, 2.0)
+                .unwrap(),
+            &visual,
+        ),
+        field::generate_svg_string(
+            &GreekKeyFieldConfig::new(100.0, 60.0, 5.0, 5, 2.0).unwrap(),
+            &visual,
+        ),
+    ];
+    for svg in svgs {
+        assert!(svg.contains("<linearGradient"));
+        assert!(svg.contains("<radialGradient"));
+        assert!(svg.contains(r#"stroke="url(#gm-stroke)""#));
+        assert!(svg.contains(r#"fill="url(#gm-background)""#));
+    }
+}
 
+#[cfg(feature = "native")]
+#[test]
+fn cli_gradient_stroke_renders() {
+    let out = temp_path("gm_test_gradient");
+    let _guard = TempFiles::for_base(&out);
     let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
         .args([
-            "--background-color",
-            "#000000",
-            "--no-png",
             "--file",
-            &path,
-            "rect",
-            "--size",
-            "10",
-            "--width",
-            "4",
-            "--height",
-            "4",
+            &out,
+            "--stroke-color",
+            "linear-gradient(135deg, #8B6914, #FFF1A8 50%, #8B6914)",
+            "circle",
         ])
         .output()
         .unwrap();
@@ -822,16 +1037,1227 @@ fn cli_background_color_adds_rect_to_svg() {
         "{}",
         String::from_utf8_lossy(&output.stderr)
     );
-    let svg = std::fs::read_to_string(format!("{path}.svg")).unwrap();
-    assert!(svg.contains("<rect"));
-    assert!(svg.contains("fill=\"#000000\""));
+    let svg = std::fs::read_to_string(format!("{out}.svg")).unwrap();
+    assert!(svg.contains("<linearGradient"));
+    assert!(std::path::Path::new(&format!("{out}.png")).exists());
 }
 
 #[cfg(feature = "native")]
 #[test]
-fn cli_stroke_dash_appears_in_svg() {
-    let path = temp_path("gm_test_stroke_dash");
-    let _guard = TempFiles::for_base(&path);
+fn cli_rejects_invalid_gradient() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "--fill-color",
+            "linear-gradient(45deg, red)",
+            "rect",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("two colour stops"), "{stderr}");
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn config_file_sets_gradient_background() {
+    let cfg = write_temp_config(
+        "gm_test_cfg_gradient",
+        r##"
+background_color = "radial-gradient(80% at 50% 50%, #3A2A08, #0B0B0B)"
+"##,
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--config",
+            cfg.to_str().unwrap(),
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "circle",
+        ])
+        .output()
+        .unwrap();
+
+    let _ = std::fs::remove_file(&cfg);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert!(svg.contains("<radialGradient"));
+    assert!(svg.contains(r#"fill="url(#gm-background)""#));
+}
+
+#[test]
+fn key_colors_give_one_stroked_path_per_key() {
+    let visual = VisualOptions {
+        key_colors: vec!["#A3341F".into(), "#111111".into()],
+        ..VisualOptions::default()
+    };
+    let keys = |svg: &str| {
+        (
+            svg.matches(r##"stroke="#A3341F""##).count(),
+            svg.matches(r##"stroke="#111111""##).count(),
+        )
+    };
+    // Three keys along each side of a 4x4 border.
+    let svg =
+        rect::generate_svg_string(&GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap(), &visual);
+    assert_eq!(keys(&svg), (6, 6));
+    let svg = circle::generate_svg_string(
+        &GreekKeyCircleConfig::new(100.0, 10, 5, 2.0).unwrap(),
+        &visual,
+    );
+    assert_eq!(keys(&svg), (5, 5));
+    let svg = superellipse::generate_svg_string(
+        &GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 30, 10, 3.0).unwrap(),
+        &visual,
+    );
+    assert_eq!(keys(&svg), (15, 15));
+}
+
+#[test]
+fn element_styles_override_and_hide_frames() {
+    let visual = VisualOptions {
+        pattern_style: ElementStyle {
+            stroke_opacity: Some(1.0),
+            ..ElementStyle::default()
+        },
+        inner_frame_style: ElementStyle {
+            stroke_color: Some("#445566".into()),
+            ..ElementStyle::default()
+        },
+        outer_frame_style: ElementStyle {
+            hidden: true,
+            ..ElementStyle::default()
+        },
+        ..VisualOptions::default()
+    };
+    let svg = ellipse::generate_svg_string(
+        &GreekKeyEllipseConfig::new(300.0, 200.0, 30, 10, 3.0).unwrap(),
+        &visual,
+    );
+    assert_eq!(svg.matches("<ellipse").count(), 1);
+    assert!(svg.contains(r##"stroke="#445566""##));
+    assert!(svg.contains(r#"stroke-opacity="1""#));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_key_colors_and_frame_flags() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "--key-color",
+            "#A3341F",
+            "--key-color",
+            "#111111",
+            "--inner-frame-color",
+            "#445566",
+            "--hide-outer-frame",
+            "circle",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(svg.matches(r##"stroke="#A3341F""##).count(), 15);
+    assert_eq!(svg.matches(r##"stroke="#111111""##).count(), 15);
+    assert_eq!(svg.matches("<circle").count(), 1);
+    assert!(svg.contains(r##"stroke="#445566""##));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_rejects_invalid_frame_opacity() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "--outer-frame-opacity",
+            "1.5",
+            "rect",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--outer-frame-opacity"), "{stderr}");
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn config_file_sets_element_styles() {
+    let cfg = write_temp_config(
+        "gm_test_cfg_elements",
+        r##"
+key_colors = ["#A3341F", "#111111"]
+
+[inner_frame]
+hidden = true
+
+[outer_frame]
+color = "#445566"
+opacity = 1.0
+"##,
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--config",
+            cfg.to_str().unwrap(),
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "rect",
+            "--width",
+            "4",
+            "--height",
+            "4",
+        ])
+        .output()
+        .unwrap();
+
+    let _ = std::fs::remove_file(&cfg);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(svg.matches(r##"stroke="#A3341F""##).count(), 6);
+    // The outer frame alone; the inner frame is hidden.
+    assert_eq!(svg.matches(r##"stroke="#445566""##).count(), 1);
+    assert_eq!(svg.matches("<path").count(), 13);
+}
+
+#[test]
+fn inlay_draws_bands_between_double_lines() {
+    let config = GreekKeyRectConfig::new(25, 4, 4, 1, 7.0).unwrap();
+    let visual = VisualOptions {
+        inlay: Some(Inlay {
+            inlay_color: Some("#C0C0C0".into()),
+            ..Inlay::new(4.0, 1.5)
+        }),
+        ..VisualOptions::default()
+    };
+    let svg = rect::generate_svg_string(&config, &visual);
+    // One band and one pair of lines each for the pattern and both frames.
+    assert_eq!(svg.matches(r##"stroke="#C0C0C0""##).count(), 3);
+    assert_eq!(svg.matches(r#"stroke-width="4""#).count(), 3);
+    assert_eq!(svg.matches(r#"stroke-width="1.5""#).count(), 3);
+}
+
+#[test]
+fn inlay_flattens_circle_arcs_and_frames() {
+    let config = GreekKeyCircleConfig::new(300.0, 30, 10, 7.0).unwrap();
+    let visual = VisualOptions {
+        inlay: Some(Inlay::new(4.0, 1.5)),
+        ..VisualOptions::default()
+    };
+    let svg = circle::generate_svg_string(&config, &visual);
+    assert!(!svg.contains(" A"));
+    assert!(!svg.contains("<circle"));
+    // Without an inlay colour only the lines are drawn.
+    assert_eq!(svg.matches("<path").count(), 3);
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_inlay_replaces_stroke_width() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "--inlay-gap",
+            "4",
+            "--inlay-line-color",
+            "#222222",
+            "--inlay-color",
+            "#C0C0C0",
+            "spiral",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert!(svg.contains(r##"stroke="#C0C0C0""##));
+    assert!(svg.contains(r##"stroke="#222222""##));
+    assert!(svg.contains(r#"stroke-width="2""#));
+    assert!(!svg.contains(r#"stroke-width="6""#));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_rejects_invalid_inlay_gap() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "--inlay-gap",
+            "0",
+            "rect",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--inlay-gap"));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn config_file_sets_inlay() {
+    let cfg = write_temp_config(
+        "gm_test_cfg_inlay",
+        r##"
+[inlay]
+gap = 5.0
+line_width = 1.0
+color = "#C0C0C0"
+"##,
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--config",
+            cfg.to_str().unwrap(),
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "ellipse",
+        ])
+        .output()
+        .unwrap();
+
+    let _ = std::fs::remove_file(&cfg);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(svg.matches(r##"stroke="#C0C0C0""##).count(), 3);
+    assert_eq!(svg.matches(r#"stroke-width="1""#).count(), 3);
+    assert!(!svg.contains("<ellipse"));
+}
+
+#[test]
+fn stroke_geometry_applies_to_every_stroked_shape() {
+    let visual = VisualOptions {
+        line_join: Some(LineJoin::Bevel),
+        line_cap: Some(LineCap::Round),
+        fill_color: Some("#FFEECC".into()),
+        key_colors: vec!["#A3341F".into(), "#111111".into()],
+        ..VisualOptions::default()
+    };
+    let svg = circle::generate_svg_string(
+        &GreekKeyCircleConfig::new(300.0, 30, 10, 3.0).unwrap(),
+        &visual,
+    );
+    // 30 key paths and two frames; the unstroked fill path is left alone.
+    assert_eq!(svg.matches(r#"stroke-linejoin="bevel""#).count(), 32);
+    assert_eq!(svg.matches(r#"stroke-linecap="round""#).count(), 32);
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_stroke_geometry_flags() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "--line-join",
+            "round",
+            "--miter-limit",
+            "2",
+            "--non-scaling-stroke",
+            "--shared-styles",
+            "rect",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = String::from_utf8(output.stdout).unwrap();
+    // Shared by all three strokes, so hoisted onto the group.
+    assert_eq!(svg.matches(r#"stroke-linejoin="round""#).count(), 1);
+    assert_eq!(svg.matches(r#"stroke-miterlimit="2""#).count(), 1);
+    assert_eq!(
+        svg.matches(r#"vector-effect="non-scaling-stroke""#).count(),
+        1
+    );
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_rejects_unknown_line_cap_and_low_miter_limit() {
+    for args in [["--line-cap", "flat"], ["--miter-limit", "0.5"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(["--stdout", "--no-svg", "--no-png"])
+            .args(args)
+            .arg("rect")
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains(args[0]));
+    }
+}
+
+#[test]
+fn effects_filter_the_border_but_not_the_background() {
+    let visual = VisualOptions {
+        background_color: Some("#F4EEDC".into()),
+        effects: vec![Effect::Shadow, Effect::Emboss],
+        ..VisualOptions::default()
+    };
+    let svg = ellipse::generate_svg_string(
+        &GreekKeyEllipseConfig::new(300.0, 200.0, 30, 10, 3.0).unwrap(),
+        &visual,
+    );
+    assert!(svg.contains(r#"<filter color-interpolation-filters="sRGB" id="gm-effects">"#));
+    let group = svg.find(r#"<g filter="url(#gm-effects)">"#).unwrap();
+    assert!(svg.find(r##"fill="#F4EEDC""##).unwrap() < group);
+    assert_eq!(svg[group..].matches("<ellipse").count(), 2);
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_effects_render_to_png() {
+    let render = |name: &str, effects: &[&str]| {
+        let path = temp_path(name);
+        let guard = TempFiles::for_base(&path);
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(["--file", &path, "--no-svg"])
+            .args(effects)
+            .args(["rect", "--width", "4", "--height", "4"])
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let png = std::fs::read(format!("{}.png", path)).unwrap();
+        drop(guard);
+        png
+    };
+    let plain = render("gm_test_cli_no_effects", &[]);
+    let filtered = render(
+        "gm_test_cli_effects",
+        &["--effect", "emboss", "--effect", "shadow"],
+    );
+    assert!(filtered.starts_with(&PNG_MAGIC));
+    assert_ne!(plain, filtered);
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_rejects_unknown_and_conflicting_effects() {
+    for effects in [
+        &["--effect", "sparkle"][..],
+        &["--effect", "emboss", "--effect", "engrave"][..],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(["--stdout", "--no-svg", "--no-png"])
+            .args(effects)
+            .arg("rect")
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("--effect"));
+    }
+}
+
+#[test]
+fn frame_sizes_border_to_svg_artwork() {
+    let artwork = Artwork::from_bytes(
+        br#"<svg xmlns="http://www.w3.org/2000/svg" width="400" height="300"/>"#.to_vec(),
+    )
+    .unwrap();
+    let config = GreekKeyFrameConfig::new(FrameShape::Rect, 10, 30, 1, 3.0).unwrap();
+    let svg = frame::generate_svg_string(&config, &artwork, &VisualOptions::default()).unwrap();
+    assert!(svg.contains(r#"preserveAspectRatio="xMidYMid slice""#));
+    assert!(svg.contains("data:image/svg+xml;base64,"));
+    // The inner frame is 8 by 6 keys of 50 pixels, the size of the artwork.
+    assert!(svg.contains(r#"height="300" width="400""#), "{svg}");
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_frame_wraps_png_artwork() {
+    let out = temp_path("gm_test_cli_frame");
+    let _guard = TempFiles::for_base(&out);
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("images/meander_circle.png");
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--file", &out, "frame", "--shape", "circle"])
+        .arg(&input)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = std::fs::read_to_string(format!("{out}.svg")).unwrap();
+    assert!(svg.contains("data:image/png;base64,"));
+    assert!(svg.contains(r#"<clipPath id="gm-artwork-clip">"#));
+    assert!(
+        std::fs::read(format!("{out}.png"))
+            .unwrap()
+            .starts_with(&PNG_MAGIC)
+    );
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_frame_requires_an_image() {
+    for args in [&["frame"][..], &["frame", "Cargo.toml"][..]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(["--stdout", "--no-svg", "--no-png"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("image"), "{stderr}");
+    }
+}
+
+#[test]
+fn malformed_colours_fail_validation() {
+    let visual = VisualOptions {
+        fill_color: Some("#12345".into()),
+        ..VisualOptions::default()
+    };
+    let e = visual.validate().unwrap_err().to_string();
+    assert!(e.contains("--fill-color"), "{e}");
+    for color in ["rgb(171, 142, 14)", "hsl(49, 85%, 36%)", "goldenrod"] {
+        VisualOptions::new(color, 1.0).validate().unwrap();
+    }
+}
+
+#[test]
+fn theme_supplies_visual_options() {
+    let svg = rect::generate_svg_string(
+        &GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap(),
+        &Theme::Monochrome.visual_options(),
+    );
+    assert!(svg.contains(r##"stroke="#000000""##));
+    assert!(svg.contains(r##"fill="#FFFFFF""##));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_rejects_malformed_stroke_color() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "--stroke-color",
+            "#GG0000",
+            "rect",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--stroke-color"));
+    assert!(stderr.contains("#GG0000"));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_flags_override_theme() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "--theme",
+            "attic-red-figure",
+            "--stroke-color",
+            "#224466",
+            "rect",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert!(svg.contains(r##"stroke="#224466""##));
+    assert!(!svg.contains("#C8693A"));
+    assert!(svg.contains(r##"fill="#17110E""##));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn config_file_theme_applies() {
+    let out = temp_path("gm_test_cfg_theme");
+    let _guard = TempFiles::for_base(&out);
+    let cfg = write_temp_config(
+        "gm_test_cfg_theme",
+        &format!(
+            r#"
+file = "{out}"
+theme = "gold-leaf"
+[rect]
+width = 4
+height = 4
+"#
+        ),
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--config", cfg.to_str().unwrap(), "--no-png", "rect"])
+        .output()
+        .unwrap();
+
+    let _ = std::fs::remove_file(&cfg);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = std::fs::read_to_string(format!("{out}.svg")).unwrap();
+    assert!(svg.contains("<linearGradient"));
+    assert!(svg.contains(r#"filter="url(#gm-effects)""#));
+}
+
+fn titled(title: &str) -> VisualOptions {
+    VisualOptions {
+        text: Some(Text {
+            title: vec![title.to_string()],
+            ring_text: Some("ring".to_string()),
+            ..Text::default()
+        }),
+        ..VisualOptions::default()
+    }
+}
+
+#[test]
+fn title_is_centred_inside_every_closed_shape() {
+    let visual = titled("Menu");
+    let rect =
+        rect::generate_svg_string(&GreekKeyRectConfig::new(10, 6, 4, 5, 2.0).unwrap(), &visual);
+    // The inner frame of this rect spans x 67..267.
+    assert!(rect.contains(r#"<tspan x="167" "#), "{rect}");
+    let superellipse = superellipse::generate_svg_string(
+        &GreekKeySuperellipseConfig::new(300.0, 200.0, 4.0, 30, 10, 3.0).unwrap(),
+        &visual,
+    );
+    let field = field::generate_svg_string(
+        &GreekKeyFieldConfig::new(400.0, 300.0, 10.0, 5, 2.0).unwrap(),
+        &visual,
+    );
+    for svg in [&rect, &superellipse, &field] {
+        assert!(svg.contains("Menu</tspan>"));
+        // Ring text needs a circle or ellipse.
+        assert!(!svg.contains("<textPath"));
+    }
+    let spiral = spiral::generate_svg_string(
+        &GreekKeySpiralConfig::new(SpiralKind::Archimedean, 3.0, 300.0, 60.0, 8.0, 10, 3.0)
+            .unwrap(),
+        &visual,
+    );
+    assert!(!spiral.contains("<text"));
+}
+
+#[test]
+fn ring_text_runs_around_circles_and_ellipses() {
+    let visual = titled("Menu");
+    let circle = circle::generate_svg_string(
+        &GreekKeyCircleConfig::new(300.0, 30, 10, 3.0).unwrap(),
+        &visual,
+    );
+    let ellipse = ellipse::generate_svg_string(
+        &GreekKeyEllipseConfig::new(300.0, 200.0, 30, 10, 3.0).unwrap(),
+        &visual,
+    );
+    for svg in [circle, ellipse] {
+        assert!(svg.contains(r#"id="gm-ring-text-path""#));
+        assert!(svg.contains(r##"href="#gm-ring-text-path""##), "{svg}");
+        assert!(svg.contains("ring</textPath>"));
+    }
+}
+
+#[test]
+fn text_color_gets_its_own_gradient() {
+    let mut visual = titled("Menu");
+    if let Some(text) = &mut visual.text {
+        text.color = Some("linear-gradient(90deg, #8B6914, #FFF1A8)".parse().unwrap());
+    }
+    let svg =
+        rect::generate_svg_string(&GreekKeyRectConfig::new(10, 6, 4, 5, 2.0).unwrap(), &visual);
+    assert!(svg.contains(r#"id="gm-text""#));
+    assert!(svg.contains(r#"fill="url(#gm-text)""#), "{svg}");
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn config_file_text_applies() {
+    let out = temp_path("gm_test_cfg_text");
+    let _guard = TempFiles::for_base(&out);
+    let cfg = write_temp_config(
+        "gm_test_cfg_text",
+        &format!(
+            r#"
+file = "{out}"
+[text]
+title = ["Certificate", "of Merit"]
+ring_text = "ΑΡΕΤΗ"
+rail = "outer"
+font_size = 18.0
+"#
+        ),
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--config", cfg.to_str().unwrap(), "circle"])
+        .output()
+        .unwrap();
+
+    let _ = std::fs::remove_file(&cfg);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = std::fs::read_to_string(format!("{out}.svg")).unwrap();
+    assert_eq!(svg.matches("<tspan").count(), 2);
+    assert!(svg.contains("ΑΡΕΤΗ</textPath>"));
+    assert!(
+        std::fs::read(format!("{out}.png"))
+            .unwrap()
+            .starts_with(&PNG_MAGIC)
+    );
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_rejects_a_font_file_that_is_not_a_font() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--stdout", "--no-svg", "--no-png"])
+        .args(["--title", "Menu", "--font-file", "Cargo.toml", "rect"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--font-file"), "{stderr}");
+}
+
+#[test]
+fn metadata_labels_the_svg() {
+    let visual = VisualOptions {
+        metadata: Metadata {
+            title: Some("Greek key border".to_string()),
+            description: Some("Sixteen keys by nine".to_string()),
+            author: Some("A. Potter".to_string()),
+            license: Some("https://creativecommons.org/licenses/by/4.0/".to_string()),
+            config: None,
+        },
+        ..VisualOptions::default()
+    };
+    let svg =
+        rect::generate_svg_string(&GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap(), &visual);
+    assert!(svg.contains(r#"role="img""#));
+    assert!(svg.contains(r#"<title id="gm-title">Greek key border</title>"#));
+    assert!(svg.contains("<dc:title>A. Potter</dc:title>"));
+    assert!(svg.contains(r#"rdf:resource="https://creativecommons.org/licenses/by/4.0/""#));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_stamped_config_rebuilds_the_same_svg() {
+    let out = temp_path("gm_test_stamped_config");
+    let _guard = TempFiles::for_base(&out);
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(["--no-png"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        std::fs::read_to_string(format!("{out}.svg")).unwrap()
+    };
+
+    let first = run(&[
+        "--file",
+        &out,
+        "--theme",
+        "gold-leaf",
+        "--title",
+        "Menu",
+        "--license",
+        "CC0",
+        "--inlay-gap",
+        "3",
+        "circle",
+        "--radius",
+        "120",
+    ]);
+    let start = first.find("<gm:config").unwrap();
+    let start = start + first[start..].find('>').unwrap() + 1;
+    let end = first.find("</gm:config>").unwrap();
+    let config = first[start..end]
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&");
+    assert!(config.contains("[circle]"), "{config}");
+    assert!(!config.contains("theme"));
+
+    let cfg = write_temp_config("gm_test_stamped_config", &config);
+    let second = run(&["--config", cfg.to_str().unwrap(), "circle"]);
+    let _ = std::fs::remove_file(&cfg);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn stored_config_reads_back_from_the_svg() {
+    let visual = VisualOptions {
+        minify: true,
+        metadata: Metadata {
+            config: Some("[circle]\nradius = 120.0\n".to_string()),
+            ..Metadata::default()
+        },
+        ..VisualOptions::default()
+    };
+    let svg = circle::generate_svg_string(
+        &GreekKeyCircleConfig::new(120.0, 30, 10, 3.0).unwrap(),
+        &visual,
+    );
+    assert_eq!(
+        metadata::read_config(&svg).as_deref(),
+        Some("[circle]\nradius = 120.0\n")
+    );
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn library_svgs_store_a_config_the_cli_reproduces() {
+    let visual = VisualOptions {
+        stroke_opacity: 0.9,
+        key_colors: vec!["#A3341F".into(), "#111111".into()],
+        inlay: Some(Inlay::new(3.0, 1.5)),
+        line_join: Some(LineJoin::Round),
+        effects: vec![Effect::Shadow],
+        outer_frame_style: ElementStyle {
+            hidden: true,
+            ..ElementStyle::default()
+        },
+        text: Some(Text {
+            title: vec!["Say \"hi\"".to_string()],
+            ..Text::default()
+        }),
+        ..VisualOptions::default()
+    };
+    let designs = [
+        circle::generate_svg_string(
+            &GreekKeyCircleConfig::new(120.0, 30, 10, Inlay::new(3.0, 1.5).width() as f32).unwrap(),
+            &visual,
+        ),
+        spiral::generate_svg_string(
+            &GreekKeySpiralConfig::new(SpiralKind::Logarithmic, 2.0, 40.0, 300.0, 6.0, 10, 3.0)
+                .unwrap(),
+            &VisualOptions::default(),
+        ),
+    ];
+    // The command line stores its own output options in the config.
+    let without_metadata = |svg: &str| {
+        let start = svg.find("<metadata>").unwrap();
+        let end = svg.find("</metadata>").unwrap();
+        format!("{}{}", &svg[..start], svg[end..].trim_end())
+    };
+    for (i, svg) in designs.iter().enumerate() {
+        let path = write_temp_config(&format!("gm_test_library_config_{i}"), svg);
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args([
+                "--isolated",
+                "--stdout",
+                "--no-svg",
+                "--no-png",
+                "reproduce",
+            ])
+            .arg(&path)
+            .output()
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let reproduced = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(without_metadata(&reproduced), without_metadata(svg));
+    }
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_reproduce_rebuilds_with_overrides() {
+    let out = temp_path("gm_test_reproduce_source");
+    let _guard = TempFiles::for_base(&out);
+    let copy = temp_path("gm_test_reproduce_copy");
+    let _copy_guard = TempFiles::for_base(&copy);
+    let exported = temp_path("gm_test_reproduce_export.toml");
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    };
+
+    run(&[
+        "--file", &out, "--no-png", "ellipse", "--rx", "150", "--ry", "90",
+    ]);
+    let source = format!("{out}.svg");
+    run(&[
+        "--file",
+        &copy,
+        "--stroke-color",
+        "#123456",
+        "reproduce",
+        &source,
+    ]);
+    let svg = std::fs::read_to_string(format!("{copy}.svg")).unwrap();
+    let original = std::fs::read_to_string(&source).unwrap();
+    assert!(svg.contains("#123456"));
+    assert_eq!(
+        svg.split('>').next().unwrap(),
+        original.split('>').next().unwrap(),
+        "same viewBox"
+    );
+    assert!(
+        std::fs::read(format!("{copy}.png"))
+            .unwrap()
+            .starts_with(&PNG_MAGIC)
+    );
+
+    run(&[
+        "--stroke-opacity",
+        "0.5",
+        "reproduce",
+        &source,
+        "--export",
+        &exported,
+    ]);
+    let toml = std::fs::read_to_string(&exported).unwrap();
+    let refused = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--no-clobber", "reproduce", &source, "--export", &exported])
+        .output()
+        .unwrap();
+    let kept = std::fs::read_to_string(&exported).unwrap();
+    let _ = std::fs::remove_file(&exported);
+    assert!(toml.contains("stroke_opacity = 0.5"), "{toml}");
+    // The stored file name, not the one a plain reproduce would write to.
+    let table: toml::Table = toml.parse().unwrap();
+    assert_eq!(table["file"].as_str(), Some(out.as_str()), "{toml}");
+    assert!(!refused.status.success());
+    assert!(String::from_utf8_lossy(&refused.stderr).contains("already exists"));
+    assert_eq!(kept, toml);
+    assert!(toml.contains("[ellipse]\npattern_count = 30\nrx = 150.0\nry = 90.0"));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_reproduce_needs_a_stored_config() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["reproduce", "Cargo.toml"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no stored config"), "{stderr}");
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_rejects_invalid_field_spacing() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "--field-spacing",
+            "0",
+            "rect",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--field-spacing"));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn config_file_sets_field_params() {
+    let out = temp_path("gm_test_cfg_field");
+    let _guard = TempFiles::for_base(&out);
+    let cfg = write_temp_config(
+        "gm_test_cfg_field",
+        &format!(
+            r#"
+file = "{out}"
+border_margin = 0
+stroke_width = 1.0
+[field]
+width = 100.0
+height = 60.0
+size = 5.0
+"#
+        ),
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--config", cfg.to_str().unwrap(), "--no-png", "field"])
+        .output()
+        .unwrap();
+
+    let _ = std::fs::remove_file(&cfg);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = std::fs::read_to_string(format!("{out}.svg")).unwrap();
+    assert!(
+        svg.contains(r#"viewBox="0 0 102 62""#),
+        "field area should come from the TOML file"
+    );
+}
+
+// --- VisualOptions: SVG structure ---
+
+#[test]
+fn default_output_has_no_fill_no_background_no_dash() {
+    let config = GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap();
+    let svg = rect::generate_svg_string(&config, &VisualOptions::default());
+    assert!(
+        svg.contains(r#"fill="none""#),
+        "default fill should be none"
+    );
+    assert!(!svg.contains("stroke-dasharray"), "no dasharray by default");
+    assert!(!svg.contains("<rect"), "no background rect by default");
+}
+
+#[test]
+fn fill_color_appears_on_pattern_path() {
+    let config = GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap();
+    let visual = VisualOptions {
+        fill_color: Some("#FF0000".into()),
+        ..Default::default()
+    };
+    let svg = rect::generate_svg_string(&config, &visual);
+    assert!(
+        svg.contains("fill=\"#FF0000\""),
+        "fill color should appear on the path element"
+    );
+}
+
+#[test]
+fn background_color_adds_rect_element() {
+    let config = GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap();
+    let visual = VisualOptions {
+        background_color: Some("#000000".into()),
+        ..Default::default()
+    };
+    let svg = rect::generate_svg_string(&config, &visual);
+    assert!(
+        svg.contains("<rect"),
+        "background color should add a rect element"
+    );
+    assert!(
+        svg.contains("fill=\"#000000\""),
+        "background rect should have the specified fill color"
+    );
+}
+
+#[test]
+fn stroke_dash_appears_on_path_and_frames() {
+    let config = GreekKeyRectConfig::new(10, 4, 4, 5, 2.0).unwrap();
+    let visual = VisualOptions {
+        stroke_dash: Some("5,3".to_string()),
+        ..Default::default()
+    };
+    let svg = rect::generate_svg_string(&config, &visual);
+    let count = svg.matches("stroke-dasharray").count();
+    assert!(
+        count >= 3,
+        "stroke-dasharray should appear on pattern path and both frames, got {count}"
+    );
+}
+
+#[test]
+fn circle_fill_color_appears_on_pattern_path() {
+    let config = GreekKeyCircleConfig::new(100.0, 10, 5, 2.0).unwrap();
+    let visual = VisualOptions {
+        fill_color: Some("#00FF00".into()),
+        ..Default::default()
+    };
+    let svg = circle::generate_svg_string(&config, &visual);
+    assert!(svg.contains("fill=\"#00FF00\""));
+}
+
+#[test]
+fn circle_background_color_adds_rect_element() {
+    let config = GreekKeyCircleConfig::new(100.0, 10, 5, 2.0).unwrap();
+    let visual = VisualOptions {
+        background_color: Some("#111111".into()),
+        ..Default::default()
+    };
+    let svg = circle::generate_svg_string(&config, &visual);
+    assert!(svg.contains("<rect"));
+    assert!(svg.contains("fill=\"#111111\""));
+}
+
+#[test]
+fn circle_stroke_dash_appears_on_path_and_frames() {
+    let config = GreekKeyCircleConfig::new(100.0, 10, 5, 2.0).unwrap();
+    let visual = VisualOptions {
+        stroke_dash: Some("8,4".to_string()),
+        ..Default::default()
+    };
+    let svg = circle::generate_svg_string(&config, &visual);
+    let count = svg.matches("stroke-dasharray").count();
+    assert!(
+        count >= 3,
+        "stroke-dasharray should appear on pattern path and both circle frames, got {count}"
+    );
+}
+
+#[test]
+fn ellipse_stroke_dash_appears_on_path_and_frames() {
+    let config = GreekKeyEllipseConfig::new(140.0, 90.0, 24, 5, 2.0).unwrap();
+    let visual = VisualOptions {
+        stroke_dash: Some("8,4".to_string()),
+        ..Default::default()
+    };
+    let svg = ellipse::generate_svg_string(&config, &visual);
+    let count = svg.matches("stroke-dasharray").count();
+    assert!(
+        count >= 3,
+        "stroke-dasharray should appear on pattern path and both ellipse frames, got {count}"
+    );
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_fill_color_appears_in_svg() {
+    let path = temp_path("gm_test_fill_color");
+    let _guard = TempFiles::for_base(&path);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--fill-color",
+            "#FF0000",
+            "--no-png",
+            "--file",
+            &path,
+            "rect",
+            "--size",
+            "10",
+            "--width",
+            "4",
+            "--height",
+            "4",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = std::fs::read_to_string(format!("{path}.svg")).unwrap();
+    assert!(svg.contains("fill=\"#FF0000\""));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_background_color_adds_rect_to_svg() {
+    let path = temp_path("gm_test_background_color");
+    let _guard = TempFiles::for_base(&path);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--background-color",
+            "#000000",
+            "--no-png",
+            "--file",
+            &path,
+            "rect",
+            "--size",
+            "10",
+            "--width",
+            "4",
+            "--height",
+            "4",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let svg = std::fs::read_to_string(format!("{path}.svg")).unwrap();
+    assert!(svg.contains("<rect"));
+    assert!(svg.contains("fill=\"#000000\""));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_stroke_dash_appears_in_svg() {
+    let path = temp_path("gm_test_stroke_dash");
+    let _guard = TempFiles::for_base(&path);
 
     let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
         .args([
@@ -942,3 +2368,792 @@ fn ellipse_radii_type_is_nameable_at_crate_root() {
     let _r: greek_meander::EllipseRadii = config.ellipse_radii;
     assert!((_r.rx_e / _r.ry_e - 1.5).abs() < f64::EPSILON);
 }
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_batch_renders_every_job_and_reports_failures() {
+    let first = temp_path("gm_test_batch_first");
+    let _first_guard = TempFiles::for_base(&first);
+    let second = temp_path("gm_test_batch_second");
+    let _second_guard = TempFiles::for_base(&second);
+    let manifest = temp_path("gm_test_batch_manifest.json");
+    let _manifest_guard = TempFiles(vec![manifest.clone()]);
+    std::fs::write(
+        &manifest,
+        format!(
+            r##"{{
+                "defaults": {{ "stroke_color": "#224466", "circle": {{ "pattern_count": 20 }} }},
+                "jobs": [
+                    {{ "shape": "circle", "output": "{first}", "circle": {{ "radius": 150 }} }},
+                    {{ "shape": "rect", "output": "{second}", "stroke_opacity": 0.4 }},
+                    {{ "name": "broken", "shape": "rect", "output": "unused", "rect": {{ "size": 0 }} }}
+                ]
+            }}"##
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--no-png", "--stroke-width", "3", "batch", &manifest])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("ok      {first}")), "{stdout}");
+    assert!(stdout.contains("FAILED  broken: --size must be greater than 0"));
+    assert!(stdout.contains("2 of 3 jobs rendered"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 of 3 jobs failed"));
+
+    let circle = std::fs::read_to_string(format!("{first}.svg")).unwrap();
+    assert!(circle.contains("#224466"));
+    assert!(circle.contains("stroke_width = 3.0"));
+    assert!(circle.contains("[circle]\npattern_count = 20\nradius = 150.0"));
+    let rect = std::fs::read_to_string(format!("{second}.svg")).unwrap();
+    assert!(rect.contains("stroke_opacity = 0.4"));
+    assert!(!std::path::Path::new(&format!("{first}.png")).exists());
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_batch_rejects_single_output_flags() {
+    for flag in ["--stdout", "--file=x"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args([flag, "batch", "manifest.toml"])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("remove --"), "{stderr}");
+    }
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_sweep_renders_every_variant_and_a_contact_sheet() {
+    let base = temp_path("gm_test_sweep");
+    let _guards: Vec<TempFiles> = ["01", "02", "03", "04", "sheet"]
+        .iter()
+        .map(|suffix| TempFiles::for_base(&format!("{base}-{suffix}")))
+        .collect();
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--file",
+            &base,
+            "--no-png",
+            "sweep",
+            "circle",
+            "--vary",
+            "pattern_count=20..30:10",
+            "--vary",
+            "stroke_width=2,4",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("4 of 4 jobs rendered"));
+
+    let third = std::fs::read_to_string(format!("{base}-03.svg")).unwrap();
+    assert!(third.contains("[circle]\npattern_count = 30"), "{third}");
+    assert!(third.contains("stroke_width = 2.0"));
+    let sheet = std::fs::read_to_string(format!("{base}-sheet.svg")).unwrap();
+    assert_eq!(sheet.matches("data:image/svg+xml;base64,").count(), 4);
+    assert!(sheet.contains("circle.pattern_count = 30\n</text>"));
+    assert!(sheet.contains("stroke_width = 4\n</text>"));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_watch_renders_again_when_the_config_changes() {
+    use std::time::{Duration, Instant};
+
+    let out = temp_path("gm_test_watch");
+    let _guard = TempFiles::for_base(&out);
+    let config = temp_path("gm_test_watch.toml");
+    let _config_guard = TempFiles(vec![config.clone()]);
+    std::fs::write(&config, "[circle]\nradius = 100.0\n").unwrap();
+    let svg = format!("{out}.svg");
+    let _ = std::fs::remove_file(&svg);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--config", &config, "--file", &out, "--no-png", "--watch"])
+        .arg("circle")
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let wait_for = |check: &dyn Fn(&str) -> bool| {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(20) {
+            if std::fs::read_to_string(&svg).is_ok_and(|s| check(&s)) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        false
+    };
+
+    let first = wait_for(&|s| s.contains("radius = 100.0"));
+    // An invalid edit is reported and the watch carries on.
+    std::fs::write(&config, "[circle]\npattern_count = 0\n").unwrap();
+    std::thread::sleep(Duration::from_millis(1000));
+    std::fs::write(&config, "[circle]\nradius = 150.0\n").unwrap();
+    let second = wait_for(&|s| s.contains("radius = 150.0"));
+    child.kill().unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(first && second, "{stderr}");
+    assert!(stderr.contains("Error: "), "{stderr}");
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_serve_renders_designs_over_http() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--stroke-opacity",
+            "0.4",
+            "serve",
+            "--port",
+            "0",
+            "--jobs",
+            "2",
+        ])
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut banner = String::new();
+    BufReader::new(child.stderr.take().unwrap())
+        .read_line(&mut banner)
+        .unwrap();
+    let address = banner
+        .split("http://")
+        .nth(1)
+        .and_then(|rest| rest.split(';').next())
+        .unwrap_or_else(|| panic!("{banner}"))
+        .to_string();
+    let get = |target: &str, extra: &str| {
+        let mut stream = std::net::TcpStream::connect(&address).unwrap();
+        write!(stream, "GET {target} HTTP/1.1\r\nHost: test\r\n{extra}\r\n").unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8_lossy(&response[..split]).into_owned();
+        (head, response[split + 4..].to_vec())
+    };
+
+    let (head, body) = get("/circle.svg?radius=120&pattern_count=20", "");
+    assert!(head.starts_with("HTTP/1.1 200 OK"), "{head}");
+    assert!(head.contains("Content-Type: image/svg+xml"));
+    assert!(head.contains("Cache-Control: public, max-age=86400"));
+    assert!(head.contains("X-Cache: MISS"));
+    let svg = String::from_utf8(body).unwrap();
+    assert!(svg.contains("[circle]\npattern_count = 20\nradius = 120.0"));
+    assert!(svg.contains("stroke_opacity = 0.4"));
+
+    let (again, _) = get("/circle.svg?pattern_count=20&radius=120.0", "");
+    assert!(again.contains("X-Cache: HIT"), "{again}");
+    let etag = head
+        .lines()
+        .find_map(|line| line.strip_prefix("ETag: "))
+        .unwrap();
+    let (revalidated, body) = get(
+        "/circle.svg?radius=120&pattern_count=20",
+        &format!("If-None-Match: {etag}\r\n"),
+    );
+    assert!(revalidated.starts_with("HTTP/1.1 304"), "{revalidated}");
+    assert!(body.is_empty());
+
+    let (head, body) = get("/rect.png?size=10&width=6&height=4", "");
+    assert!(head.contains("Content-Type: image/png"), "{head}");
+    assert!(body.starts_with(&PNG_MAGIC));
+
+    let (head, body) = get("/rect.svg?size=0", "");
+    assert!(head.starts_with("HTTP/1.1 400"), "{head}");
+    assert!(head.contains("Content-Type: application/json"));
+    assert_eq!(
+        String::from_utf8(body).unwrap(),
+        r#"{"error":"--size must be greater than 0"}"#
+    );
+    let (head, _) = get("/hexagon.svg", "");
+    assert!(head.starts_with("HTTP/1.1 404"), "{head}");
+
+    // Designs beyond the server's limits are refused before drawing.
+    for target in [
+        "/rect.svg?size=60000&width=60000",
+        "/circle.svg?radius=10000",
+        "/circle.svg?pattern_count=50000",
+        "/circle.png?radius=1000&scale=4",
+    ] {
+        let (head, _) = get(target, "");
+        assert!(head.starts_with("HTTP/1.1 400"), "{target}: {head}");
+    }
+    let (head, _) = get("/rect.svg?size=10", "");
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+
+    child.kill().unwrap();
+    let _ = child.wait();
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_reads_json_and_yaml_configs() {
+    let yaml_out = temp_path("gm_test_yaml_config");
+    let _yaml_guard = TempFiles::for_base(&yaml_out);
+    let json_out = temp_path("gm_test_json_config");
+    let _json_guard = TempFiles::for_base(&json_out);
+    let yaml = temp_path("gm_test_config.yml");
+    let json = temp_path("gm_test_config.conf");
+    let _config_guard = TempFiles(vec![yaml.clone(), json.clone()]);
+    std::fs::write(
+        &yaml,
+        format!("file: {yaml_out}\nstroke_width: 3\ncircle:\n  pattern_count: 20\n  radius: 150\n"),
+    )
+    .unwrap();
+    std::fs::write(
+        &json,
+        format!(r#"{{ "file": "{json_out}", "rect": {{ "size": 10, "width": 4, "height": 4 }} }}"#),
+    )
+    .unwrap();
+
+    for args in [
+        vec!["--config", &yaml, "--no-png", "circle"],
+        vec![
+            "--config",
+            &json,
+            "--config-format",
+            "json",
+            "--no-png",
+            "rect",
+        ],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(&args)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let circle = std::fs::read_to_string(format!("{yaml_out}.svg")).unwrap();
+    assert!(circle.contains("stroke_width = 3.0"));
+    assert!(circle.contains("[circle]\npattern_count = 20\nradius = 150.0"));
+    let rect = std::fs::read_to_string(format!("{json_out}.svg")).unwrap();
+    assert!(
+        rect.contains("[rect]\nsize = 10\nwidth = 4\nheight = 4"),
+        "{rect}"
+    );
+
+    // Without --config-format the .conf file is read as TOML.
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--config", &json, "rect"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid config file"));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_config_schema_matches_the_shipped_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["config", "schema"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let shipped = std::fs::read_to_string(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("schema/greek-meander.schema.json"),
+    )
+    .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), shipped);
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_validate_reports_unknown_keys_and_bad_values() {
+    let cfg = temp_path("gm_test_validate.toml");
+    let _guard = TempFiles(vec![cfg.clone()]);
+    std::fs::write(
+        &cfg,
+        "stroke_colour = \"#112233\"\n[circle]\nradius = 100\npattern_count = 2\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["validate", &cfg])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!(
+            "{cfg}:1:1: unknown key 'stroke_colour'; did you mean 'stroke_color'?"
+        )),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("as a circle: --pattern-count must be at least 4"),
+        "{stdout}"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2 problems in"));
+
+    // Options given before `validate` are merged in, as for a render.
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--pattern-count",
+            "20",
+            "validate",
+            &cfg,
+            "--shape",
+            "circle",
+        ])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("pattern-count"), "{stdout}");
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_strict_fails_on_unknown_keys_that_otherwise_warn() {
+    let out = temp_path("gm_test_strict");
+    let _out_guard = TempFiles::for_base(&out);
+    let cfg = temp_path("gm_test_strict.yaml");
+    let _cfg_guard = TempFiles(vec![cfg.clone()]);
+    std::fs::write(
+        &cfg,
+        format!("file: {out}\nrect:\n  size: 10\n  widht: 4\n"),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--config", &cfg, "--no-png", "rect"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!(
+            "warning: {cfg}:4:3: unknown key 'rect.widht'; did you mean 'rect.width'? (ignored)"
+        )),
+        "{stderr}"
+    );
+    std::fs::remove_file(format!("{out}.svg")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--strict", "--config", &cfg, "--no-png", "rect"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("(--strict)"));
+    assert!(!std::path::Path::new(&format!("{out}.svg")).exists());
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_presets_reproduce_the_sample_images() {
+    for (preset, command, image) in [
+        ("plain-rect", "rect", "meander_rect"),
+        ("plain-circle", "circle", "meander_circle"),
+        ("styled-rect", "rect", "meander_rect_styled"),
+        ("styled-circle", "circle", "meander_circle_styled"),
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args([
+                "--preset", preset, "--stdout", "--no-svg", "--no-png", command,
+            ])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{preset}");
+        let svg = String::from_utf8(output.stdout).unwrap();
+        // The samples predate the stored config.
+        let start = svg.find("<metadata>").unwrap();
+        let end = svg.find("</metadata>").unwrap() + "</metadata>\n".len();
+        let svg = format!("{}{}", &svg[..start], &svg[end..]);
+        let sample = std::fs::read_to_string(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("images")
+                .join(format!("{image}.svg")),
+        )
+        .unwrap();
+        assert_eq!(svg.trim_end(), sample.trim_end(), "{preset}");
+    }
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_profile_is_laid_over_the_config() {
+    let cfg = write_temp_config(
+        "gm_test_profile",
+        r##"
+stroke_color = "#112233"
+stroke_width = 4.0
+[circle]
+radius = 100.0
+
+[profile.dark]
+background_color = "#101010"
+[profile.dark.circle]
+pattern_count = 20
+"##,
+    );
+    let run = |extra: &[&str]| {
+        let cfg = cfg.to_str().unwrap();
+        let mut args = vec!["--config", cfg, "--stdout", "--no-svg", "--no-png"];
+        args.extend_from_slice(extra);
+        args.push("circle");
+        Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let output = run(&["--profile", "dark"]);
+    let missing = run(&["--profile", "light"]);
+    let _ = std::fs::remove_file(&cfg);
+
+    assert!(output.status.success());
+    let svg = String::from_utf8_lossy(&output.stdout);
+    assert!(svg.contains(r##"fill="#101010""##), "{svg}");
+    assert!(svg.contains("stroke_width = 4.0"));
+    assert!(svg.contains("[circle]\npattern_count = 20\nradius = 100.0"));
+    assert!(!missing.status.success());
+    assert!(
+        String::from_utf8_lossy(&missing.stderr)
+            .contains("no profile named 'light'; the config defines dark")
+    );
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_layers_user_project_env_and_flags() {
+    let root = std::env::temp_dir().join(format!("gm_test_layers_{}", std::process::id()));
+    let user = root.join("xdg").join("greek-meander");
+    let project = root.join("project");
+    let nested = project.join("designs");
+    std::fs::create_dir_all(&user).unwrap();
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(
+        user.join("config.toml"),
+        "stroke_width = 2.0\nstroke_opacity = 0.4\nscale = 2.0\n",
+    )
+    .unwrap();
+    std::fs::write(
+        project.join("greek-meander.toml"),
+        "stroke_width = 3.0\n[circle]\nradius = 120.0\n",
+    )
+    .unwrap();
+    let run = |extra: &[&str]| {
+        let mut args = vec!["--stroke-opacity", "0.5"];
+        args.extend_from_slice(extra);
+        Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(args)
+            .current_dir(&nested)
+            .env("XDG_CONFIG_HOME", root.join("xdg"))
+            .env("GREEK_MEANDER_STROKE_COLOR", "#112233")
+            .env("GREEK_MEANDER_CIRCLE_PATERN_COUNT", "12")
+            .output()
+            .unwrap()
+    };
+    let shown = run(&["config", "show", "--resolved"]);
+    let rendered = run(&["--stdout", "--no-svg", "--no-png", "circle"]);
+    let isolated = run(&["--isolated", "config", "show"]);
+    let _ = std::fs::remove_dir_all(&root);
+
+    assert!(shown.status.success(), "{shown:?}");
+    let shown = String::from_utf8_lossy(&shown.stdout);
+    let project_config = format!(
+        "project config {}",
+        project.join("greek-meander.toml").display()
+    );
+    let source_of = |option: &str| {
+        shown
+            .lines()
+            .find(|line| line.starts_with(&format!("{option} = ")))
+            .and_then(|line| line.split_once("  # "))
+            .map(|(_, source)| source.to_string())
+    };
+    assert_eq!(source_of("stroke_width"), Some(project_config.clone()));
+    assert_eq!(
+        source_of("stroke_color").as_deref(),
+        Some("GREEK_MEANDER_STROKE_COLOR")
+    );
+    assert_eq!(source_of("stroke_opacity").as_deref(), Some("command line"));
+    assert_eq!(source_of("border_margin").as_deref(), Some("default"));
+    assert!(source_of("scale").is_some_and(|source| source.starts_with("user config ")));
+    assert_eq!(source_of("radius"), Some(project_config));
+    assert!(shown.contains("[circle]"), "{shown}");
+
+    assert!(rendered.status.success());
+    let svg = String::from_utf8_lossy(&rendered.stdout);
+    assert!(svg.contains("stroke=\"#112233\""), "{svg}");
+    assert!(svg.contains("stroke_width = 3.0"));
+    assert!(
+        String::from_utf8_lossy(&rendered.stderr).contains(
+            "warning: GREEK_MEANDER_CIRCLE_PATERN_COUNT names no option; did you mean GREEK_MEANDER_CIRCLE_PATTERN_COUNT? (ignored)"
+        )
+    );
+
+    assert!(isolated.status.success());
+    assert!(isolated.stdout.is_empty(), "{isolated:?}");
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_config_init_writes_a_template_of_the_defaults() {
+    let dir = std::env::temp_dir().join(format!("gm_test_init_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .arg("--isolated")
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+    let first = run(&["config", "init"]);
+    let again = run(&["config", "init"]);
+    let forced = run(&["config", "init", "--force"]);
+    let template = std::fs::read_to_string(dir.join("greek-meander.toml")).unwrap();
+    let printed = run(&["config", "init", "-"]);
+    let dumps: Vec<_> = [
+        "rect",
+        "circle",
+        "ellipse",
+        "superellipse",
+        "spiral",
+        "field",
+    ]
+    .into_iter()
+    .map(|shape| run(&["config", "dump", shape]))
+    .collect();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(first.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&first.stdout),
+        "wrote greek-meander.toml\n"
+    );
+    assert!(!again.status.success());
+    assert!(String::from_utf8_lossy(&again.stderr).contains("pass --force to overwrite it"));
+    assert!(forced.status.success());
+    assert_eq!(String::from_utf8_lossy(&printed.stdout), template);
+
+    // Uncommenting the template's values gives what a bare command renders with.
+    let uncommented: String = template
+        .lines()
+        .filter_map(|line| line.strip_prefix("# "))
+        .filter(|line| {
+            let is_key = |key: &str| key.chars().all(|c| c.is_alphanumeric() || c == '_');
+            match line.split_once(" = ") {
+                Some((key, _)) => is_key(key),
+                None => line
+                    .strip_prefix('[')
+                    .and_then(|line| line.strip_suffix(']'))
+                    .is_some_and(is_key),
+            }
+        })
+        .map(|line| format!("{line}\n"))
+        .collect();
+    let defaults: toml::Table = uncommented.parse().unwrap();
+    let same = |a: &toml::Value, b: &toml::Value| match (a.as_float(), b.as_float()) {
+        (Some(a), Some(b)) => (a - b).abs() < 1e-6,
+        _ => a == b,
+    };
+    for dump in dumps {
+        assert!(dump.status.success(), "{dump:?}");
+        let dumped: toml::Table = String::from_utf8_lossy(&dump.stdout).parse().unwrap();
+        for (key, value) in &dumped {
+            match value.as_table() {
+                Some(section) => {
+                    for (name, value) in section {
+                        assert!(same(value, &defaults[key][name]), "{key}.{name}");
+                    }
+                }
+                None => assert!(same(value, &defaults[key]), "{key}"),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_config_dump_prints_the_effective_config() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--isolated",
+            "--stroke-width",
+            "3",
+            "--pattern-color",
+            "#224466",
+            "config",
+            "dump",
+            "circle",
+            "--radius",
+            "120",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let dumped: toml::Table = String::from_utf8_lossy(&output.stdout).parse().unwrap();
+    assert_eq!(dumped["stroke_width"].as_float(), Some(3.0));
+    assert_eq!(dumped["pattern"]["color"].as_str(), Some("#224466"));
+    assert_eq!(dumped["circle"]["radius"].as_float(), Some(120.0));
+    assert_eq!(dumped["circle"]["pattern_count"].as_integer(), Some(30));
+    assert_eq!(dumped["file"].as_str(), Some("meander"));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_out_dir_and_file_templates_name_the_outputs() {
+    let dir = std::env::temp_dir().join(format!("gm_test_out_dir_{}", std::process::id()));
+    let run = |extra: &[&str]| {
+        let out_dir = dir.join("renders");
+        let mut args = vec![
+            "--isolated",
+            "--out-dir",
+            out_dir.to_str().unwrap(),
+            "--file",
+            "{shape}-{pattern_count}-{stroke_color}",
+            "--stroke-color",
+            "#224466",
+            "--no-png",
+        ];
+        args.extend_from_slice(extra);
+        args.extend_from_slice(&["circle", "--pattern-count", "12"]);
+        Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let first = run(&[]);
+    let svg = dir.join("renders").join("circle-12-#224466.svg");
+    let written = std::fs::read_to_string(&svg).unwrap_or_default();
+    let refused = run(&["--no-clobber"]);
+    let forced = run(&["--no-clobber", "--force"]);
+    let entries: Vec<_> = std::fs::read_dir(dir.join("renders"))
+        .map(|entries| entries.map(|entry| entry.unwrap().file_name()).collect())
+        .unwrap_or_default();
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(first.status.success(), "{first:?}");
+    // The stored config keeps the template, so a reproduction names itself alike.
+    assert!(written.contains("file = \"{shape}-{pattern_count}-{stroke_color}\""));
+    assert!(!refused.status.success());
+    assert!(
+        String::from_utf8_lossy(&refused.stderr).contains("already exists; pass --force"),
+        "{refused:?}"
+    );
+    assert!(forced.status.success(), "{forced:?}");
+    // Nothing is left behind but the output itself.
+    assert_eq!(entries, ["circle-12-#224466.svg"]);
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_failed_png_leaves_no_svg_behind() {
+    let base = temp_path("gm_test_failed_png");
+    let _files = TempFiles::for_base(&base);
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--isolated", "--file", &base, "--scale", "0.0001", "rect"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(!PathBuf::from(format!("{base}.svg")).exists());
+    assert!(!PathBuf::from(format!("{base}.png")).exists());
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_unknown_file_placeholder_is_an_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args(["--isolated", "--file", "m-{radius}", "--no-png", "rect"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("output name 'm-{radius}': 'radius' names no option")
+    );
+    assert!(!PathBuf::from("m-{radius}.svg").exists());
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_field_spacing_too_small_for_the_canvas_is_an_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--isolated",
+            "--field-spacing",
+            "0.01",
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "circle",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--field-spacing is too small"));
+    assert!(output.stdout.is_empty());
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_id_prefix_keeps_inlined_svgs_apart() {
+    let run = |prefix: &str| {
+        Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+            .args([
+                "--isolated",
+                "--id-prefix",
+                prefix,
+                "--stroke-color",
+                "linear-gradient(90deg, #8B6914, #FFF1A8)",
+                "--stdout",
+                "--no-svg",
+                "--no-png",
+                "circle",
+            ])
+            .output()
+            .unwrap()
+    };
+    let output = run("left");
+    assert!(output.status.success());
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert!(svg.contains(r#"id="left-stroke""#));
+    assert!(svg.contains(r#"stroke="url(#left-stroke)""#));
+    assert!(!svg.contains("gm-stroke"));
+
+    let output = run("1st");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--id-prefix must start with"));
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn cli_stroke_width_with_an_inlay_is_an_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_greek-meander"))
+        .args([
+            "--isolated",
+            "--stroke-width",
+            "3",
+            "--inlay-gap",
+            "4",
+            "--stdout",
+            "--no-svg",
+            "--no-png",
+            "circle",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("--stroke-width cannot be combined with --inlay-gap")
+    );
+}